@@ -1,4 +1,6 @@
-use self::commands::locate_failure_log;
+use self::commands::{analyze_buildstats, cluster_failures, history, locate_failure_log, replay};
+use crate::{commands::ResultFormat, issue::similarity::Similarity, notify::RunReport};
+use std::io::Write;
 
 use super::*;
 
@@ -41,24 +43,454 @@ impl CIProvider {
         use commands::Command;
         match command {
             // This is a command that is not specific to a CI provider
-            Command::LocateFailureLog { kind, input_file } => {
-                locate_failure_log::locate_failure_log(*kind, input_file.as_ref())
+            Command::LocateFailureLog {
+                kind,
+                input_file,
+                format,
+                all,
+                null,
+                search_root,
+                path_map,
+            } => locate_failure_log::locate_failure_log(
+                *kind,
+                input_file.as_ref(),
+                Config::global().failure_log_format(*format),
+                *all,
+                *null,
+                &locate_failure_log::PathResolver::new(search_root.clone(), path_map.clone()),
+            ),
+            Command::Replay {
+                input,
+                kind,
+                title,
+                label,
+            } => replay::replay(input, *kind, title, label),
+            Command::ClusterFailures {
+                repo,
+                archives,
+                label,
+                kind,
+                similarity_algo,
+                similarity_threshold,
+                top,
+                format,
+            } => {
+                let similarity = Similarity::new(*similarity_algo, *similarity_threshold)?;
+                match (repo, self) {
+                    (None, _) => cluster_failures::cluster_archives(
+                        archives,
+                        *kind,
+                        similarity,
+                        *top,
+                        Config::global().list_format(*format),
+                    ),
+                    (Some(repo), Self::GitHub) => {
+                        github::GitHub::get()
+                            .cluster_issue_failures(
+                                repo,
+                                label,
+                                similarity,
+                                *top,
+                                Config::global().list_format(*format),
+                            )
+                            .await
+                    }
+                    (Some(_), Self::GitLab) => {
+                        bail!("`cluster-failures --repo` is not supported for GitLab yet")
+                    }
+                }
             }
-            Command::CreateIssueFromRun {
+            Command::History { days, top, format } => {
+                let Some(path) = Config::global().history_db() else {
+                    bail!("`history` needs the database to read, use --history-db <PATH>");
+                };
+                history::history(path, *days, *top, Config::global().list_format(*format))
+            }
+            Command::AnalyzeBuildstats {
+                buildstats,
+                previous,
+                top,
+                format,
+            } => analyze_buildstats::analyze_buildstats(
+                buildstats,
+                previous.as_deref(),
+                *top,
+                Config::global().list_format(*format),
+            ),
+            Command::CreateIssueFromRun(args) => match self {
+                Self::GitHub => {
+                    let report = github::GitHub::get().create_issue_from_run(args).await?;
+                    exit_code::set_outcome(exit_code::Exit::of_report(report.as_ref()));
+                    if let Some(report) = &report {
+                        if let Err(e) = github::step_outputs::write_step_outputs(report) {
+                            log::warn!("Failed to write the step outputs: {e:#}");
+                        }
+                    }
+                    let config = Config::global();
+                    if let Some(output) =
+                        run_report_output(report.as_ref(), config.output_format(), config.quiet())?
+                    {
+                        pipe_println!("{output}")?;
+                    }
+                    Ok(())
+                }
+                Self::GitLab if args.issue.pr_comment => {
+                    gitlab::GitLab::get().comment_merge_request(args).await
+                }
+                Self::GitLab => gitlab::GitLab::get().handle(command),
+            },
+            Command::CreateIssuesFromRuns(args) => match self {
+                Self::GitHub => github::GitHub::get().create_issues_from_runs(args).await,
+                Self::GitLab => bail!("`create-issues-from-runs` is not supported for GitLab yet"),
+            },
+            Command::CloseResolvedIssues {
                 repo,
                 run_id,
                 label,
                 kind,
-                title,
-                no_duplicate,
             } => match self {
                 Self::GitHub => {
                     github::GitHub::get()
-                        .create_issue_from_run(repo, run_id, label, kind, *no_duplicate, title)
+                        .close_resolved_issues(repo, run_id.as_deref(), label, *kind)
                         .await
                 }
-                Self::GitLab => gitlab::GitLab::get().handle(command),
+                Self::GitLab => bail!("`close-resolved-issues` is not supported for GitLab yet"),
+            },
+            Command::IssueGc {
+                repo,
+                stale_after,
+                label,
+            } => match self {
+                Self::GitHub => {
+                    github::GitHub::get()
+                        .close_stale_issues(repo, *stale_after, label)
+                        .await
+                }
+                Self::GitLab => bail!("`issue-gc` is not supported for GitLab yet"),
+            },
+            Command::ListFailedRuns {
+                repo,
+                filter,
+                limit,
+                format,
+            } => match self {
+                Self::GitHub => {
+                    github::GitHub::get()
+                        .list_failed_runs(
+                            repo,
+                            filter,
+                            *limit,
+                            Config::global().list_format(*format),
+                        )
+                        .await
+                }
+                Self::GitLab => bail!("`list-failed-runs` is not supported for GitLab yet"),
+            },
+            Command::Stats(args) => match self {
+                Self::GitHub => github::GitHub::get().workflow_stats(args).await,
+                Self::GitLab => bail!("`stats` is not supported for GitLab yet"),
+            },
+            Command::WatchRun {
+                repo,
+                run_id,
+                wait,
+                then,
+            } => match self {
+                Self::GitHub => {
+                    github::GitHub::get()
+                        .watch_run(repo, run_id, wait, then.as_ref())
+                        .await
+                }
+                Self::GitLab => bail!("`watch-run` is not supported for GitLab yet"),
             },
+            Command::BisectRun {
+                repo,
+                run_id,
+                wait,
+                then,
+            } => match self {
+                Self::GitHub => {
+                    github::GitHub::get()
+                        .bisect_run(repo, run_id, wait, then.as_ref())
+                        .await
+                }
+                Self::GitLab => bail!("`bisect-run` is not supported for GitLab yet"),
+            },
+            Command::SummarizeRun {
+                repo,
+                run_id,
+                kind,
+                step_summary,
+                report,
+                report_format,
+            } => match self {
+                Self::GitHub => {
+                    let report = report.as_deref().map(|path| (*report_format, path));
+                    github::GitHub::get()
+                        .summarize_run(repo, run_id, *kind, *step_summary, report)
+                        .await
+                }
+                Self::GitLab => bail!("`summarize-run` is not supported for GitLab yet"),
+            },
+            Command::DownloadLogs {
+                repo,
+                run_id,
+                job_id,
+                output_dir,
+            } => match self {
+                Self::GitHub => {
+                    github::GitHub::get()
+                        .download_logs(repo, run_id, *job_id, output_dir.as_deref())
+                        .await
+                }
+                Self::GitLab => bail!("`download-logs` is not supported for GitLab yet"),
+            },
+            Command::ExportRun {
+                repo,
+                run_id,
+                kind,
+                output,
+            } => match self {
+                Self::GitHub => {
+                    github::GitHub::get()
+                        .export_run(repo, run_id, *kind, output.as_deref())
+                        .await
+                }
+                Self::GitLab => bail!("`export-run` is not supported for GitLab yet"),
+            },
+            Command::CompareRuns {
+                repo,
+                old_run_id,
+                new_run_id,
+                kind,
+            } => match self {
+                Self::GitHub => {
+                    github::GitHub::get()
+                        .compare_runs(repo, old_run_id, new_run_id, *kind)
+                        .await
+                }
+                Self::GitLab => bail!("`compare-runs` is not supported for GitLab yet"),
+            },
+            Command::CancelStaleRuns {
+                repo,
+                workflow,
+                older_than,
+                superseded,
+            } => match self {
+                Self::GitHub => {
+                    github::GitHub::get()
+                        .cancel_stale_runs(repo, workflow.as_deref(), *older_than, *superseded)
+                        .await
+                }
+                Self::GitLab => bail!("`cancel-stale-runs` is not supported for GitLab yet"),
+            },
+            Command::CleanupArtifacts {
+                repo,
+                org,
+                older_than,
+                larger_than,
+            } => match self {
+                Self::GitHub => {
+                    github::GitHub::get()
+                        .cleanup_artifacts(
+                            repo.as_deref(),
+                            org.as_deref(),
+                            *older_than,
+                            *larger_than,
+                        )
+                        .await
+                }
+                Self::GitLab => bail!("`cleanup-artifacts` is not supported for GitLab yet"),
+            },
+            Command::Doctor { repo } => match self {
+                Self::GitHub => github::GitHub::get().doctor(repo).await,
+                Self::GitLab => bail!("`doctor` is not supported for GitLab yet"),
+            },
+            Command::Serve { listen, issue } => match self {
+                Self::GitHub => github::GitHub::get().serve(*listen, issue).await,
+                Self::GitLab => bail!("`serve` is not supported for GitLab yet"),
+            },
+            Command::ScanOrg {
+                org,
+                topic,
+                since,
+                event,
+                issue,
+            } => match self {
+                Self::GitHub => {
+                    github::GitHub::get()
+                        .scan_org(org, topic.as_deref(), since, event, issue)
+                        .await
+                }
+                Self::GitLab => bail!("`scan-org` is not supported for GitLab yet"),
+            },
+            Command::Digest {
+                repo,
+                period,
+                workflow,
+                branch,
+                kind,
+                label,
+            } => match self {
+                Self::GitHub => {
+                    github::GitHub::get()
+                        .digest(
+                            repo,
+                            *period,
+                            workflow.as_deref(),
+                            branch.as_deref(),
+                            *kind,
+                            label,
+                        )
+                        .await
+                }
+                Self::GitLab => bail!("`digest` is not supported for GitLab yet"),
+            },
+            Command::Monitor { config } => match self {
+                Self::GitHub => github::GitHub::get().monitor(config).await,
+                Self::GitLab => bail!("`monitor` is not supported for GitLab yet"),
+            },
+            Command::RerunFailedJobs {
+                repo,
+                run_id,
+                if_classified_as,
+            } => match self {
+                Self::GitHub => {
+                    github::GitHub::get()
+                        .rerun_failed_jobs(repo, run_id, *if_classified_as)
+                        .await
+                }
+                Self::GitLab => {
+                    gitlab::GitLab::get().retry_pipeline(repo, run_id, *if_classified_as)
+                }
+            },
+        }
+    }
+}
+
+/// What `create-issue-from-run` prints on stdout: the report as JSON with `--output-format json`,
+/// `null` if the run had no failed jobs, or with `--quiet` only the URL of where the failure is
+/// tracked. `None` if nothing is printed.
+fn run_report_output(
+    report: Option<&RunReport>,
+    output_format: Option<ResultFormat>,
+    quiet: bool,
+) -> Result<Option<String>> {
+    match output_format {
+        Some(ResultFormat::Json) => Ok(Some(serde_json::to_string_pretty(&report)?)),
+        None if quiet => Ok(report
+            .and_then(|report| report.outcome.url())
+            .map(str::to_owned)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notify::{IssueOutcome, JobReport};
+    use pretty_assertions::assert_eq;
+
+    fn report(outcome: IssueOutcome) -> RunReport {
+        RunReport {
+            run_id: "1".to_owned(),
+            run_url: "https://github.com/luftkode/ci-manager/actions/runs/1".to_owned(),
+            title: "Build failed".to_owned(),
+            fingerprint: "4f1c7a2b9e3d5c60".to_owned(),
+            labels: vec!["CI".to_owned()],
+            failed_jobs: vec![JobReport {
+                name: "Build".to_owned(),
+                url: "https://github.com/luftkode/ci-manager/actions/runs/1/job/2".to_owned(),
+                failed_step: "Build".to_owned(),
+                failure_label: Some("compiler-error".to_owned()),
+                fingerprint: "d0c5e0ac7e8c2a1b".to_owned(),
+                summary: "error[E0308]: mismatched types".to_owned(),
+            }],
+            outcome,
         }
     }
+
+    fn created() -> IssueOutcome {
+        IssueOutcome::Created {
+            url: "https://github.com/luftkode/ci-manager/issues/42".to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_run_report_output_json() {
+        let output = run_report_output(Some(&report(created())), Some(ResultFormat::Json), false)
+            .unwrap()
+            .unwrap();
+        let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(
+            output,
+            serde_json::json!({
+                "run_id": "1",
+                "run_url": "https://github.com/luftkode/ci-manager/actions/runs/1",
+                "title": "Build failed",
+                "fingerprint": "4f1c7a2b9e3d5c60",
+                "labels": ["CI"],
+                "failed_jobs": [{
+                    "name": "Build",
+                    "url": "https://github.com/luftkode/ci-manager/actions/runs/1/job/2",
+                    "failed_step": "Build",
+                    "failure_label": "compiler-error",
+                    "fingerprint": "d0c5e0ac7e8c2a1b",
+                    "summary": "error[E0308]: mismatched types",
+                }],
+                "outcome": {
+                    "decision": "created",
+                    "url": "https://github.com/luftkode/ci-manager/issues/42",
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn test_run_report_output_json_without_failed_jobs_is_null() {
+        assert_eq!(
+            run_report_output(None, Some(ResultFormat::Json), false).unwrap(),
+            Some("null".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_run_report_output_json_takes_precedence_over_quiet() {
+        let output = run_report_output(Some(&report(created())), Some(ResultFormat::Json), true)
+            .unwrap()
+            .unwrap();
+        assert!(serde_json::from_str::<serde_json::Value>(&output).is_ok());
+    }
+
+    #[test]
+    fn test_run_report_output_quiet_prints_only_url() {
+        assert_eq!(
+            run_report_output(Some(&report(created())), None, true).unwrap(),
+            Some("https://github.com/luftkode/ci-manager/issues/42".to_owned())
+        );
+        let pr_comment = IssueOutcome::PrComment {
+            url: "https://github.com/luftkode/ci-manager/pull/7#issuecomment-1".to_owned(),
+        };
+        assert_eq!(
+            run_report_output(Some(&report(pr_comment)), None, true).unwrap(),
+            Some("https://github.com/luftkode/ci-manager/pull/7#issuecomment-1".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_run_report_output_quiet_without_url_prints_nothing() {
+        assert_eq!(
+            run_report_output(Some(&report(IssueOutcome::DryRun)), None, true).unwrap(),
+            None
+        );
+        assert_eq!(run_report_output(None, None, true).unwrap(), None);
+    }
+
+    #[test]
+    fn test_run_report_output_nothing_by_default() {
+        assert_eq!(
+            run_report_output(Some(&report(created())), None, false).unwrap(),
+            None
+        );
+    }
 }
@@ -1,14 +1,47 @@
 use std::io::Read;
 
+pub mod archive;
+mod artifacts;
+pub mod batch;
+mod bisect;
+pub mod check_run;
+pub mod commit_status;
+mod compare;
+pub mod cross_repo;
+mod digest;
+mod doctor;
+pub mod export;
+mod gists;
+mod issue_lifecycle;
+mod logs;
+mod matrix;
+pub mod monitor;
+pub mod owners;
+pub mod pr_comment;
+pub mod projects;
+pub mod run_context;
+pub mod runs;
+mod scan_org;
+pub mod serve;
+pub mod stats;
+pub mod step_outputs;
 pub mod util;
 
 use crate::{
+    ci_provider::github::cross_repo::CrossRepoScope,
     ci_provider::github::util::{
-        distance_to_other_issues, job_error_logs_from_log_and_failed_jobs_and_steps,
-        repo_url_to_run_url, run_url_to_job_url, JobErrorLog,
+        duplicate_issue, failed_jobs, failed_jobs_from_job_error_logs, issues_tracking_jobs,
+        job_error_logs_from_log_and_failed_jobs_and_steps, repo_url_to_run_url, DedupExplanation,
+        DuplicateReason, JobErrorLog,
     },
-    err_parse::parse_error_message,
-    issue::{FailedJob, FirstFailedStep},
+    err_parse::{network::INFRA_FLAKE_LABEL, FailureClass},
+    history::History,
+    issue::{
+        occurrence::{OccurrenceMarker, RECURRING_LABEL},
+        similarity::Similarity,
+    },
+    notify::{self, alert::Alert, IssueOutcome, RunReport},
+    storage::StorageConfig,
     *,
 };
 use hyper::body;
@@ -16,7 +49,7 @@ use octocrab::{
     models::{
         issues::Issue,
         workflows::{Conclusion, Job, Run},
-        Label, RunId,
+        IssueId, IssueState, Label, Repository, RunId,
     },
     params::{workflows::Filter, State},
     Octocrab, *,
@@ -31,6 +64,21 @@ pub struct GitHub {
     client: Octocrab,
 }
 
+/// A failed run that an issue is filed for, and the options of `create-issue-from-run` it is filed
+/// with, shared by the steps of filing it
+struct RunFiling<'a> {
+    args: &'a commands::CreateIssueArgs,
+    owner: &'a str,
+    repo: &'a str,
+    run_id: u64,
+    run_url: &'a str,
+    similarity: Similarity,
+    /// Where issues of the same failure are searched in other repositories
+    cross_repo_scope: Option<CrossRepoScope>,
+    /// Where shortened logs are uploaded with `--long-logs storage`
+    storage: Option<&'a StorageConfig>,
+}
+
 impl GitHub {
     /// Get a reference to the global config
     pub fn get() -> &'static GitHub {
@@ -60,27 +108,132 @@ impl GitHub {
 
     pub async fn create_issue_from_run(
         &self,
-        repo: &String,
-        run_id: &String,
-        label: &String,
-        kind: &commands::WorkflowKind,
-        no_duplicate: bool,
-        title: &String,
-    ) -> Result<()> {
-        log::debug!(
-            "Creating issue from:\n\
-            \trepo: {repo}\n\
-            \trun_id: {run_id}\n\
-            \tlabel: {label}\n\
-            \tkind: {kind}\n\
-            \tno_duplicate: {no_duplicate}\n\
-            \ttitle: {title}",
-        );
+        args: &commands::CreateIssueFromRunArgs,
+    ) -> Result<Option<RunReport>> {
+        self.create_issue_from_run_with_sections(args, Vec::new())
+            .await
+    }
+
+    /// Create an issue from a failed run, with additional `(title, content)` sections in the issue
+    /// body, see [Issue::add_section](issue::Issue::add_section), send the notifications of
+    /// `--notify` about it and set the commit status of `--commit-status`. Returns the report of the
+    /// run, `None` if the run had no failed jobs or succeeded when its flaky jobs were re-run.
+    pub async fn create_issue_from_run_with_sections(
+        &self,
+        args: &commands::CreateIssueFromRunArgs,
+        sections: Vec<(String, String)>,
+    ) -> Result<Option<RunReport>> {
+        let report = self.file_issue_from_run(args, sections).await?;
+        if let Some(report) = &report {
+            telemetry::record_report(report);
+            if let Some(path) = Config::global().history_db() {
+                if let Err(e) = History::open(path).and_then(|mut h| h.record(&args.repo, report)) {
+                    log::warn!("Failed to record the run in the history database: {e:#}");
+                }
+            }
+            notify::notify(&args.issue.notify, report).await;
+            if args.issue.commit_status {
+                if let Err(e) = self.set_commit_status(&args.repo, report).await {
+                    log::warn!("Failed to set the commit status: {e:#}");
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Create an issue from a failed run unless the failure is already tracked, and report what was
+    /// done about it. `None` if the run had no failed jobs or succeeded when its flaky jobs were
+    /// re-run.
+    #[tracing::instrument(skip_all, fields(repo = %args.repo, run_id = %args.run_id))]
+    async fn file_issue_from_run(
+        &self,
+        args: &commands::CreateIssueFromRunArgs,
+        mut sections: Vec<(String, String)>,
+    ) -> Result<Option<RunReport>> {
+        log::debug!("Creating issue from: {args:#?}");
+        let commands::CreateIssueFromRunArgs {
+            repo,
+            run_id,
+            wait_for_completion,
+            wait,
+            issue:
+                commands::CreateIssueArgs {
+                    label,
+                    kind,
+                    title,
+                    no_duplicate,
+                    similarity_algo,
+                    similarity_threshold,
+                    central_repo,
+                    org_wide_dedup,
+                    reopen_closed_within,
+                    dedup_state,
+                    retry_flakes,
+                    diff_against_last_success,
+                    commits_since_last_success,
+                    recipe_labels,
+                    task_log_artifact,
+                    excerpt,
+                    template,
+                    assignees,
+                    milestone,
+                    auto_assign,
+                    max_body_len,
+                    max_job_len,
+                    body_format,
+                    tracker,
+                    check_run,
+                    pr_comment,
+                    jira,
+                    linear,
+                    redmine,
+                    alert,
+                    long_logs,
+                    ..
+                },
+        } = args;
+        // Read the template before anything is fetched so an invalid template fails early
+        let body_format = tracker.body_format(*body_format);
+        let template = match template {
+            Some(path) => issue::template::IssueTemplate::from_file(path, body_format)?,
+            None => issue::template::IssueTemplate::default_for(body_format),
+        };
+        let similarity = Similarity::new(*similarity_algo, *similarity_threshold)?;
+        let storage = match long_logs {
+            commands::LongLogs::Storage => {
+                Some(Config::global().config_file().storage.as_ref().context(
+                    "--long-logs storage needs a [storage] section in the --config-file",
+                )?)
+            }
+            _ => None,
+        };
+        if *dedup_state == commands::DedupState::All && reopen_closed_within.is_some() {
+            bail!("--reopen-closed-within can't be combined with --dedup-state all, which treats closed issues as duplicates instead of reopening them");
+        }
         let (owner, repo) = repo_to_owner_repo_fragments(repo)?;
+        let cross_repo_scope = match (central_repo, org_wide_dedup) {
+            (Some(central_repo), _) => {
+                let (owner, repo) = repo_to_owner_repo_fragments(central_repo)?;
+                Some(CrossRepoScope::Repo { owner, repo })
+            }
+            (None, true) => Some(CrossRepoScope::Org(owner.clone())),
+            (None, false) => None,
+        };
         let run_url = repo_url_to_run_url(&format!("github.com/{owner}/{repo}"), run_id);
         let run_id: u64 = run_id.parse()?;
 
-        let workflow_run = self.workflow_run(&owner, &repo, RunId(run_id)).await?;
+        let workflow_run = if *wait_for_completion {
+            self.wait_for_run_completion(
+                &owner,
+                &repo,
+                RunId(run_id),
+                wait.poll_interval(),
+                wait.wait_timeout(),
+            )
+            .await?
+        } else {
+            self.workflow_run(&owner, &repo, RunId(run_id)).await?
+        };
         log::debug!("{workflow_run:?}");
 
         if workflow_run.conclusion != Some("failure".to_string()) {
@@ -90,26 +243,788 @@ impl GitHub {
             );
         }
 
-        let mut jobs = self.workflow_run_jobs(&owner, &repo, RunId(run_id)).await?;
-        log::info!("Got {} job(s) for the workflow run", jobs.len());
-        if jobs.is_empty() {
-            bail!("No jobs found for the workflow run");
+        let pull_request = if *pr_comment {
+            let pull_request = self
+                .pull_request_of_run(&owner, &repo, &workflow_run)
+                .await?;
+            if pull_request.is_none() {
+                log::info!("Run {run_id} wasn't triggered by a pull request, creating an issue instead of a comment");
+            }
+            pull_request
+        } else {
+            None
+        };
+
+        let mut job_error_logs = self
+            .failed_job_error_logs(&owner, &repo, RunId(run_id))
+            .await?;
+        if job_error_logs.is_empty() {
+            log::info!("Run {run_id} has no failed jobs, no issue will be created");
+            return Ok(None);
+        }
+
+        for retry in 1..=*retry_flakes {
+            let logs: Vec<(String, commands::WorkflowKind)> = job_error_logs
+                .iter()
+                .map(|j| (j.logs_as_str(), kind.for_job(&j.job_name)))
+                .collect();
+            let logs = logs.iter().map(|(log, kind)| (log.as_str(), *kind));
+            if FailureClass::classify_all(logs) != FailureClass::Flake {
+                break;
+            }
+            log::warn!(
+                "Failure is classified as {flake}, re-running failed jobs ({retry}/{retry_flakes})",
+                flake = FailureClass::Flake
+            );
+            if Config::global().dry_run() {
+                println!("DRY RUN MODE! The failed jobs of run {run_id} would be re-run");
+                break;
+            }
+            let rerun = self
+                .rerun_failed_jobs_to_completion(
+                    &owner,
+                    &repo,
+                    RunId(run_id),
+                    wait.poll_interval(),
+                    wait.wait_timeout(),
+                )
+                .await?;
+            if rerun.conclusion.as_deref() == Some("success") {
+                log::info!(
+                    "Run {run_id} succeeded after re-running failed jobs, no issue will be created"
+                );
+                return Ok(None);
+            }
+            job_error_logs = self
+                .failed_job_error_logs(&owner, &repo, RunId(run_id))
+                .await?;
+        }
+
+        if *diff_against_last_success {
+            match self
+                .last_successful_run(&owner, &repo, &workflow_run)
+                .await?
+            {
+                Some(last_success) => {
+                    log::info!(
+                        "Comparing logs to the last successful run: {}",
+                        last_success.html_url
+                    );
+                    let baseline_logs = self
+                        .download_workflow_run_logs(&owner, &repo, last_success.id)
+                        .await?;
+                    util::remove_lines_present_in_baseline(&mut job_error_logs, &baseline_logs);
+                }
+                None => log::warn!(
+                    "No successful run of workflow {workflow} before run {run_id}, keeping the full logs",
+                    workflow = workflow_run.name
+                ),
+            }
+        }
+
+        match self
+            .run_context_markdown(&owner, &repo, &workflow_run, &job_error_logs)
+            .await
+        {
+            Ok(context) => sections.insert(0, ("Run context".to_owned(), context)),
+            Err(e) => log::warn!("Failed to get the context of run {run_id}: {e:#}"),
+        }
+        if *commits_since_last_success {
+            if let Some(commits) = self
+                .commits_since_last_success_markdown(&owner, &repo, &workflow_run)
+                .await?
+            {
+                sections.push(("Commits since last success".to_owned(), commits));
+            }
         }
 
-        // Take only jobs from the most recent attempt
-        let max_attempt = jobs
+        // Parse to a github issue
+        let mut failed_jobs = failed_jobs_from_job_error_logs(&job_error_logs, &run_url, *kind);
+        if let Some(pattern) = task_log_artifact {
+            if let Err(e) = self
+                .attach_task_logs_from_artifacts(
+                    &owner,
+                    &repo,
+                    RunId(run_id),
+                    pattern,
+                    &mut failed_jobs,
+                )
+                .await
+            {
+                log::warn!("Failed to attach task logs from artifacts: {e:#}");
+            }
+        }
+
+        if let Err(e) = self
+            .name_matrix_params(&owner, &repo, &workflow_run, &mut failed_jobs)
+            .await
+        {
+            log::warn!("Failed to get the matrix keys of the failed jobs: {e:#}");
+        }
+        let label_styles = &Config::global().config_file().labels;
+        let mut issue = issue::Issue::new(
+            title.to_owned(),
+            run_id.to_string(),
+            run_url.clone(),
+            failed_jobs,
+            label[0].to_owned(),
+        );
+        issue.add_labels(&label[1..]);
+        for (section_title, content) in sections {
+            issue.add_section(section_title, content);
+        }
+        if *recipe_labels {
+            issue.add_recipe_labels();
+        }
+        issue.rename_labels(label_styles);
+        if let Some(excerpt) = excerpt.context_excerpt() {
+            issue.set_excerpt(excerpt);
+        }
+        issue.set_template(template);
+        if max_body_len.is_some()
+            || max_job_len.is_some()
+            || *tracker != commands::Tracker::GitHub
+            || pull_request.is_some()
+        {
+            let tracker_max_len = match pull_request {
+                Some(_) => issue::BodyLimits::GITHUB.max_len - pr_comment::COMMENT_OVERHEAD_LEN,
+                None => tracker.body_limits().max_len,
+            };
+            let max_len = match *max_body_len {
+                Some(len) if len > tracker_max_len => {
+                    log::warn!("The maximum length of a {tracker} issue body is {tracker_max_len} bytes, ignoring --max-body-len {len}");
+                    tracker_max_len
+                }
+                Some(len) => len,
+                None => tracker_max_len,
+            };
+            issue.set_body_limits(issue::BodyLimits {
+                max_len,
+                max_job_len: *max_job_len,
+            });
+        }
+        issue.add_assignees(assignees);
+        let command: &'static str = Config::global().subcmd().into();
+        let kind_name = kind
+            .to_possible_value()
+            .map_or_else(|| kind.to_string(), |value| value.get_name().to_owned());
+        issue.set_created_by(command, kind_name);
+        if let Some(strategy) = auto_assign {
+            if let Err(e) = self
+                .auto_assign(&owner, &repo, &workflow_run, *strategy, &mut issue)
+                .await
+            {
+                log::warn!("Failed to determine the owners of the failure: {e:#}");
+            }
+        }
+        if let Some(milestone) = milestone {
+            issue.set_milestone(milestone);
+        }
+        log::debug!("generic issue instance: {issue:?}");
+        if let Some(service) = alert.service {
+            let alerts = Alert::from_issue(&issue);
+            if !alerts.is_empty() {
+                let alerted = notify::alert::trigger(service, &alerts).await;
+                if alert.alert_instead_of_issue && !alerted.is_empty() {
+                    if alerted.len() == issue.failed_jobs().len() {
+                        log::info!("Alerted about every failed job, no issue will be created");
+                        let dedup_keys = alerts.iter().map(Alert::dedup_key).collect();
+                        return Ok(Some(RunReport::new(
+                            &issue,
+                            IssueOutcome::Alerted { dedup_keys },
+                        )));
+                    }
+                    issue.remove_failed_jobs(&alerted);
+                }
+            }
+        }
+        if *tracker != commands::Tracker::GitHub || *check_run || pull_request.is_some() {
+            if !assignees.is_empty() || milestone.is_some() || args.issue.project.is_some() {
+                log::warn!("--assignee, --milestone and --project only apply to GitHub issues, ignoring them");
+            }
+            match storage {
+                Some(storage) => storage::upload_shortened_logs(storage, &mut issue).await?,
+                None if *long_logs == commands::LongLogs::Gist => {
+                    self.upload_shortened_logs_to_gists(&mut issue).await?;
+                }
+                None => (),
+            }
+        }
+        if *check_run {
+            let job_names: Vec<&str> = issue.failed_jobs().iter().map(|j| j.name()).collect();
+            let annotations: Vec<_> = job_error_logs
+                .iter()
+                .filter(|job| job_names.contains(&job.job_name.as_str()))
+                .flat_map(|job| {
+                    let log = job.logs_as_str();
+                    let log = Config::global().config_file().redact.apply(&log);
+                    check_run::check_annotations(&job.job_name, &log, &repo)
+                })
+                .collect();
+            let outcome = self
+                .publish_check_run(&owner, &repo, &workflow_run, &mut issue, &annotations)
+                .await?;
+            return Ok(Some(RunReport::new(&issue, outcome)));
+        }
+        if let Some(number) = pull_request {
+            let outcome = self
+                .post_pr_comment(&owner, &repo, number, &workflow_run, &mut issue)
+                .await?;
+            return Ok(Some(RunReport::new(&issue, outcome)));
+        }
+        let outcome = match tracker {
+            commands::Tracker::GitHub => None,
+            commands::Tracker::Jira => Some(
+                crate::tracker::jira::file_issue(
+                    jira,
+                    &mut issue,
+                    *no_duplicate,
+                    similarity,
+                    &run_url,
+                )
+                .await?,
+            ),
+            commands::Tracker::Linear => Some(
+                crate::tracker::linear::file_issue(
+                    linear,
+                    &mut issue,
+                    *no_duplicate,
+                    similarity,
+                    &run_url,
+                )
+                .await?,
+            ),
+            commands::Tracker::Redmine => Some(
+                crate::tracker::redmine::file_issue(
+                    redmine,
+                    &mut issue,
+                    *no_duplicate,
+                    similarity,
+                    &run_url,
+                )
+                .await?,
+            ),
+        };
+        if let Some(outcome) = outcome {
+            return Ok(Some(RunReport::new(&issue, outcome)));
+        }
+        let filing = RunFiling {
+            args: &args.issue,
+            owner: &owner,
+            repo: &repo,
+            run_id,
+            run_url: &run_url,
+            similarity,
+            cross_repo_scope,
+            storage,
+        };
+        if *no_duplicate {
+            if let Some(outcome) = self.handle_duplicate(&filing, &mut issue).await? {
+                return Ok(Some(RunReport::new(&issue, outcome)));
+            }
+        }
+
+        let report = self.create_github_issue(&filing, issue).await?;
+        Ok(Some(report))
+    }
+
+    /// Create the GitHub issue of a run, and the labels of the issue that don't exist yet, or print
+    /// it in a dry run
+    async fn create_github_issue(
+        &self,
+        filing: &RunFiling<'_>,
+        mut issue: issue::Issue,
+    ) -> Result<RunReport> {
+        let RunFiling {
+            args,
+            owner,
+            repo,
+            storage,
+            ..
+        } = *filing;
+        // Get all labels for the repo, and create the ones that don't exist
+        let all_labels = self.get_all_labels(owner, repo).await?;
+        log::info!("Got {num_labels} label(s)", num_labels = all_labels.len());
+        let labels_to_create: Vec<String> = issue
+            .labels()
             .iter()
-            .max_by_key(|job| job.run_attempt)
-            .unwrap()
-            .run_attempt;
-        jobs.retain(|job| job.run_attempt == max_attempt);
+            .filter(|label| !all_labels.iter().any(|l| l.name.eq(*label)))
+            .cloned()
+            .collect();
+        if !labels_to_create.is_empty() {
+            log::info!(
+                "{} label(s) determined for the issue-to-be-created do not yet exist on the repo, and will be created: {labels_to_create:?}",
+                labels_to_create.len()
+            );
+        }
 
-        let jobs = jobs; // Make immutable again
+        let overflow_comments = match args.long_logs {
+            commands::LongLogs::Truncate => Vec::new(),
+            commands::LongLogs::Comments => issue.overflow_comments(),
+            commands::LongLogs::Gist => {
+                self.upload_shortened_logs_to_gists(&mut issue).await?;
+                Vec::new()
+            }
+            commands::LongLogs::Storage => {
+                if let Some(storage) = storage {
+                    storage::upload_shortened_logs(storage, &mut issue).await?;
+                }
+                Vec::new()
+            }
+        };
 
-        let failed_jobs = jobs
+        let mut report = RunReport::new(&issue, IssueOutcome::DryRun);
+        // Check if dry-run is set
+        if Config::global().dry_run() {
+            // Then print the issue to be created instead of creating it
+            println!("####################################");
+            println!("DRY RUN MODE! The following issue would be created:");
+            println!("==== ISSUE TITLE ==== \n{}", issue.title());
+            println!("==== ISSUE LABEL(S) ==== \n{}", issue.labels().join(","));
+            if !issue.assignees().is_empty() {
+                println!(
+                    "==== ISSUE ASSIGNEE(S) ==== \n{}",
+                    issue.assignees().join(",")
+                );
+            }
+            if let Some(milestone) = issue.milestone() {
+                println!("==== ISSUE MILESTONE ==== \n{milestone}");
+            }
+            if let Some(project) = &args.project {
+                println!("==== ISSUE PROJECT ==== \n{project}");
+            }
+            println!("==== START OF ISSUE BODY ==== \n{}", issue.body());
+            println!("==== END OF ISSUE BODY ====");
+            for comment in &overflow_comments {
+                println!("==== START OF COMMENT ==== \n{comment}");
+                println!("==== END OF COMMENT ====");
+            }
+        } else {
+            // Create the labels that don't exist
+            for issue_label in labels_to_create {
+                log::info!("Creating label: {issue_label}");
+                let (color, description) =
+                    Config::global().config_file().labels.style_of(&issue_label);
+                self.client
+                    .issues(owner, repo)
+                    .create_label(issue_label, color, description)
+                    .await?; // Await the completion of the create_label future
+            }
+            let created_issue = self.create_issue(owner, repo, issue).await?;
+            report.outcome = IssueOutcome::Created {
+                url: created_issue.html_url.to_string(),
+            };
+            if let Some(project) = &args.project {
+                log::info!(
+                    "Adding issue #{number} to project {project}",
+                    number = created_issue.number
+                );
+                self.add_issue_to_project(project, &created_issue.node_id)
+                    .await?;
+            }
+            for comment in overflow_comments {
+                log::info!(
+                    "Posting the shortened content as a comment on issue #{number}",
+                    number = created_issue.number
+                );
+                self.client
+                    .issues(owner, repo)
+                    .create_comment(created_issue.number, comment)
+                    .await?;
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Look for an issue that already tracks the failure of a run with `--no-duplicate`, among the
+    /// open issues and the issues closed within `--reopen-closed-within` days, and record the
+    /// occurrence in it.
+    ///
+    /// Returns what was done about the failure instead of creating an issue, `None` if no issue
+    /// tracks it.
+    async fn handle_duplicate(
+        &self,
+        filing: &RunFiling<'_>,
+        issue: &mut issue::Issue,
+    ) -> Result<Option<IssueOutcome>> {
+        let RunFiling {
+            args,
+            owner,
+            repo,
+            similarity,
+            ..
+        } = *filing;
+        log::info!("No-duplicate flag is set, checking for similar issues");
+        let mut explanation =
+            DedupExplanation::new(args.explain_dedup || Config::global().dry_run());
+        let label_styles = &Config::global().config_file().labels;
+        // The labels as they are named on GitHub that existing issues of the same failure are found
+        // by, the labels of the issue unless others are given
+        let dedup_labels: Vec<&str> = if args.dedup_labels.is_empty() {
+            &args.label
+        } else {
+            &args.dedup_labels
+        }
+        .iter()
+        .map(|l| label_styles.name_of(l))
+        .collect();
+        let flake_label = label_styles.name_of(INFRA_FLAKE_LABEL);
+        let dedup_date = args.dedup_window.map_or(DateFilter::None, |days| {
+            DateFilter::UpdatedSince(Date::days_ago(days))
+        });
+        // Then check if a similar issue exists
+        let (state, state_name) = match args.dedup_state {
+            commands::DedupState::Open => (State::Open, "open "),
+            commands::DedupState::All => (State::All, ""),
+        };
+        let existing_issues = self
+            .issues_at(
+                owner,
+                repo,
+                dedup_date.clone(),
+                state,
+                LabelFilter::All(&dedup_labels),
+            )
+            .await?;
+        let window = args.dedup_window.map_or_else(String::new, |days| {
+            format!(" updated in the last {days} day(s)")
+        });
+        log::info!(
+            "Found {num_issues} {state_name}issue(s) with label(s) {dedup_labels:?}{window}",
+            num_issues = existing_issues.len()
+        );
+        explanation.add(format!(
+            "Searched {num_issues} {state_name}issue(s) with label(s) {dedup_labels:?}{window}",
+            num_issues = existing_issues.len()
+        ));
+        // Network errors differ in the details (hosts, addresses, status codes), so an open
+        // issue about network errors tracks all of them
+        let flake_issue = if issue.labels().iter().any(|l| l == flake_label) {
+            existing_issues
+                .iter()
+                .filter(|existing_issue| existing_issue.state == IssueState::Open)
+                .find(|open_issue| open_issue.labels.iter().any(|l| l.name == flake_label))
+                .map(|open_issue| {
+                    (
+                        open_issue,
+                        DuplicateReason::FlakeLabel(flake_label.to_owned()),
+                    )
+                })
+        } else {
+            None
+        };
+        // The issues the occurrence of the run is recorded on, once per issue
+        let mut recorded_on = Vec::new();
+        if flake_issue.is_none() {
+            if let Some(outcome) = self
+                .leave_out_tracked_jobs(
+                    filing,
+                    &existing_issues,
+                    issue,
+                    &mut recorded_on,
+                    &mut explanation,
+                )
+                .await?
+            {
+                return Ok(Some(outcome));
+            }
+        }
+        let fingerprint = issue.fingerprint();
+        let body = issue.body();
+        explanation.add(format!("Fingerprint of the failure: {fingerprint}"));
+        let normalizations = issue.applied_normalizations();
+        explanation.add(if normalizations.is_empty() {
+            "No normalization rules applied to the errors".to_owned()
+        } else {
+            format!(
+                "Normalization rules applied to the errors: {}",
+                normalizations.join(", ")
+            )
+        });
+        if let Some((similar_issue, reason)) = flake_issue
+            .or_else(|| duplicate_issue(&fingerprint, &body, &existing_issues, similarity))
+        {
+            let state_name = match similar_issue.state {
+                IssueState::Closed => "Closed",
+                _ => "Open",
+            };
+            log::warn!(
+                "{state_name} issue #{number} already tracks this failure. Exiting...",
+                number = similar_issue.number
+            );
+            explanation.add(format!(
+                "{state_name} issue #{number} matches: {reason}",
+                number = similar_issue.number
+            ));
+            explanation.print(&format!(
+                "not creating an issue, recording the occurrence on #{}",
+                similar_issue.number
+            ))?;
+            // The issue may already track other jobs of the run
+            if !recorded_on.contains(&similar_issue.id) {
+                self.record_occurrence(
+                    owner,
+                    repo,
+                    similar_issue,
+                    &fingerprint,
+                    args.recurring_threshold,
+                )
+                .await?;
+            }
+            let urls = vec![similar_issue.html_url.to_string()];
+            return Ok(Some(IssueOutcome::Duplicate { urls }));
+        }
+
+        if let Some(days) = args.reopen_closed_within {
+            if let Some(outcome) = self
+                .reopen_closed_duplicate(
+                    filing,
+                    &dedup_labels,
+                    days,
+                    &fingerprint,
+                    &body,
+                    &mut explanation,
+                )
+                .await?
+            {
+                return Ok(Some(outcome));
+            }
+        }
+        if let Some(outcome) = self
+            .comment_on_cross_repo_duplicate(
+                filing,
+                &dedup_labels,
+                dedup_date,
+                &fingerprint,
+                &body,
+                &mut explanation,
+            )
+            .await?
+        {
+            return Ok(Some(outcome));
+        }
+        log::info!("No similar issue found. Continuing...");
+        explanation.add(format!(
+            "No issue has the fingerprint {fingerprint} or is within {algo} distance {threshold}",
+            algo = similarity.algo,
+            threshold = similarity.threshold
+        ));
+        explanation.print("creating a new issue")?;
+        Ok(None)
+    }
+
+    /// Comment on the issue in the repository of `--central-repo` or the organization with
+    /// `--org-wide-dedup` that tracks the failure of `fingerprint` and `body`. Returns the outcome
+    /// if there is one.
+    async fn comment_on_cross_repo_duplicate(
+        &self,
+        filing: &RunFiling<'_>,
+        dedup_labels: &[&str],
+        dedup_date: DateFilter,
+        fingerprint: &str,
+        body: &str,
+        explanation: &mut DedupExplanation,
+    ) -> Result<Option<IssueOutcome>> {
+        let Some(scope) = &filing.cross_repo_scope else {
+            return Ok(None);
+        };
+        let RunFiling {
+            owner,
+            repo,
+            run_id,
+            run_url,
+            similarity,
+            ..
+        } = *filing;
+        explanation.add(format!("Searched the open issues in {scope}"));
+        if let Some((other_issue, reason)) = self
+            .cross_repo_duplicate(
+                scope,
+                owner,
+                repo,
+                dedup_labels,
+                dedup_date,
+                fingerprint,
+                body,
+                similarity,
+            )
+            .await?
+        {
+            log::warn!(
+                "Issue {url} in {scope} already tracks this failure. Commenting on it instead. Exiting...",
+                url = other_issue.html_url
+            );
+            explanation.add(format!(
+                "Issue {url} matches: {reason}",
+                url = other_issue.html_url
+            ));
+            explanation.print(&format!("commenting on {}", other_issue.html_url))?;
+            self.comment_also_seen(&other_issue, owner, repo, run_id, run_url)
+                .await?;
+            let urls = vec![other_issue.html_url.to_string()];
+            return Ok(Some(IssueOutcome::Duplicate { urls }));
+        }
+        Ok(None)
+    }
+
+    /// Leave the failed jobs that `existing_issues` already track out of `issue`, so a run with an
+    /// old and a new failure only gets an issue for the new one, and record the occurrence in the
+    /// issues tracking them, which are added to `recorded_on`. Returns the outcome if every failed
+    /// job is already tracked.
+    async fn leave_out_tracked_jobs(
+        &self,
+        filing: &RunFiling<'_>,
+        existing_issues: &[Issue],
+        issue: &mut issue::Issue,
+        recorded_on: &mut Vec<IssueId>,
+        explanation: &mut DedupExplanation,
+    ) -> Result<Option<IssueOutcome>> {
+        let job_fingerprints = issue.job_fingerprints();
+        let tracking = issues_tracking_jobs(&job_fingerprints, existing_issues);
+        let mut tracking_issues: Vec<&Issue> = Vec::new();
+        let mut tracked_fingerprints = Vec::new();
+        let mut tracked_lines = Vec::new();
+        for ((job, job_fingerprint), tracking_issue) in issue
+            .failed_jobs()
             .iter()
-            .filter(|job| job.conclusion == Some(Conclusion::Failure))
-            .collect::<Vec<_>>();
+            .zip(job_fingerprints)
+            .zip(tracking)
+        {
+            let Some(tracking_issue) = tracking_issue else {
+                continue;
+            };
+            log::info!(
+                "Issue #{number} already tracks the failure of job {name}",
+                number = tracking_issue.number,
+                name = job.name()
+            );
+            tracked_lines.push(format!(
+                "- `{name}` in #{number}",
+                name = job.name(),
+                number = tracking_issue.number
+            ));
+            explanation.add(format!(
+                "Job {name} (fingerprint {job_fingerprint}) is tracked by issue #{number}",
+                name = job.name(),
+                number = tracking_issue.number
+            ));
+            tracked_fingerprints.push(job_fingerprint);
+            if !tracking_issues.iter().any(|i| i.id == tracking_issue.id) {
+                tracking_issues.push(tracking_issue);
+            }
+        }
+        for tracking_issue in &tracking_issues {
+            let fingerprint = issue::fingerprint_from_issue_body(
+                tracking_issue.body.as_deref().unwrap_or_default(),
+            )
+            .unwrap_or_default();
+            self.record_occurrence(
+                filing.owner,
+                filing.repo,
+                tracking_issue,
+                &fingerprint,
+                filing.args.recurring_threshold,
+            )
+            .await?;
+            recorded_on.push(tracking_issue.id);
+        }
+        if tracked_fingerprints.is_empty() {
+            return Ok(None);
+        }
+        if tracked_fingerprints.len() == issue.failed_jobs().len() {
+            log::warn!("Existing issues already track every failed job. Exiting...");
+            explanation.print("not creating an issue, every failed job is tracked")?;
+            let urls = tracking_issues
+                .iter()
+                .map(|i| i.html_url.to_string())
+                .collect();
+            return Ok(Some(IssueOutcome::Duplicate { urls }));
+        }
+        issue.remove_failed_jobs(&tracked_fingerprints);
+        issue.add_section("Already tracked", tracked_lines.join("\n"));
+        Ok(None)
+    }
+
+    /// Reopen the issue closed within the last `days` days that tracks the failure of `fingerprint`
+    /// and `body`, with `--reopen-closed-within`. Returns the outcome if there is one.
+    async fn reopen_closed_duplicate(
+        &self,
+        filing: &RunFiling<'_>,
+        dedup_labels: &[&str],
+        days: u32,
+        fingerprint: &str,
+        body: &str,
+        explanation: &mut DedupExplanation,
+    ) -> Result<Option<IssueOutcome>> {
+        let RunFiling {
+            args,
+            owner,
+            repo,
+            run_url,
+            similarity,
+            ..
+        } = *filing;
+        let closed_since = Date::days_ago(days);
+        let closed_issues = self
+            .issues_at(
+                owner,
+                repo,
+                DateFilter::ClosedSince(closed_since.clone()),
+                State::Closed,
+                LabelFilter::All(dedup_labels),
+            )
+            .await?;
+        log::info!(
+            "Found {num_issues} issue(s) with label(s) {dedup_labels:?} closed since {closed_since}",
+            num_issues = closed_issues.len()
+        );
+        explanation.add(format!(
+            "Searched {num_issues} issue(s) closed since {closed_since}",
+            num_issues = closed_issues.len()
+        ));
+        if let Some((similar_issue, reason)) =
+            duplicate_issue(fingerprint, body, &closed_issues, similarity)
+        {
+            log::warn!(
+                "Closed issue #{number} tracks this failure. Reopening it...",
+                number = similar_issue.number
+            );
+            explanation.add(format!(
+                "Closed issue #{number} matches: {reason}",
+                number = similar_issue.number
+            ));
+            explanation.print(&format!("reopening #{}", similar_issue.number))?;
+            self.reopen_issue(owner, repo, similar_issue, run_url)
+                .await?;
+            self.record_occurrence(
+                owner,
+                repo,
+                similar_issue,
+                fingerprint,
+                args.recurring_threshold,
+            )
+            .await?;
+            let url = similar_issue.html_url.to_string();
+            return Ok(Some(IssueOutcome::Reopened { url }));
+        }
+        Ok(None)
+    }
+
+    /// Get the error logs of the failed jobs and steps in the most recent attempt of a workflow run
+    #[tracing::instrument(skip(self))]
+    pub async fn failed_job_error_logs(
+        &self,
+        owner: &str,
+        repo: &str,
+        run_id: RunId,
+    ) -> Result<Vec<JobErrorLog>> {
+        let jobs = self.latest_attempt_jobs(owner, repo, run_id).await?;
+        if jobs.is_empty() {
+            bail!("No jobs found for the workflow run");
+        }
+
+        let failed_jobs = failed_jobs(&jobs);
 
         log::info!(
             "Found {} failed job(s): {}",
@@ -124,7 +1039,13 @@ impl GitHub {
         let failed_steps = failed_jobs
             .iter()
             .flat_map(|job| job.steps.iter())
-            .filter(|step| step.conclusion == Some(Conclusion::Failure))
+            // The step that was running when a job timed out or was cancelled is cancelled
+            .filter(|step| {
+                matches!(
+                    step.conclusion,
+                    Some(Conclusion::Failure | Conclusion::Cancelled | Conclusion::TimedOut)
+                )
+            })
             .collect::<Vec<_>>();
         log::info!(
             "Found {} failed step(s): {}",
@@ -139,9 +1060,7 @@ impl GitHub {
             log::debug!("{step:?}");
         });
 
-        let logs = self
-            .download_workflow_run_logs(&owner, &repo, RunId(run_id))
-            .await?;
+        let logs = self.download_workflow_run_logs(owner, repo, run_id).await?;
         log::info!("Downloaded {} logs", logs.len());
         log::info!(
             "Log names sorted by timestamp:\n{logs}",
@@ -163,110 +1082,101 @@ impl GitHub {
 
         util::log_info_downloaded_job_error_logs(&job_error_logs);
 
-        // Parse to a github issue
-        // Map the GitHub Job to a `FailedJob`
-        let failed_jobs = job_error_logs
-            .iter()
-            .map(|job| {
-                let job_id_str = job.job_id.to_string();
-                let job_url = run_url_to_job_url(&run_url, &job_id_str);
-                let continuous_errorlog_msgs = job.logs_as_str();
-                let first_failed_step: FirstFailedStep = match job.failed_step_logs.first() {
-                    Some(first_failed_step_log) => {
-                        FirstFailedStep::StepName(first_failed_step_log.step_name.to_owned())
-                    }
-                    // This can happen if the job times out while waiting for a runner to pick it up
-                    // Relevant issue: https://github.com/luftkode/ci-manager/issues/4
-                    None => FirstFailedStep::NoStepsExecuted,
-                };
-                let parsed_msg = parse_error_message(&continuous_errorlog_msgs, *kind).unwrap();
-                FailedJob::new(
-                    job.job_name.to_owned(),
-                    job_id_str,
-                    job_url,
-                    first_failed_step,
-                    parsed_msg,
-                )
-            })
-            .collect();
+        Ok(job_error_logs)
+    }
 
-        let mut issue = issue::Issue::new(
-            title.to_owned(),
-            run_id.to_string(),
-            run_url,
-            failed_jobs,
-            label.to_owned(),
-        );
-        log::debug!("generic issue instance: {issue:?}");
-        // Check if-no-duplicate is set
-        if no_duplicate {
-            log::info!("No-duplicate flag is set, checking for similar issues");
-            // Then check if a similar issue exists
-            let open_issues = self
-                .issues_at(
-                    &owner,
-                    &repo,
-                    DateFilter::None,
-                    State::Open,
-                    LabelFilter::All([label]),
-                )
-                .await?;
-            log::info!(
-                "Found {num_issues} open issue(s) with label {label}",
-                num_issues = open_issues.len()
+    /// Reopen a closed issue with a comment pointing to the run where the failure reoccurred
+    async fn reopen_issue(
+        &self,
+        owner: &str,
+        repo: &str,
+        closed_issue: &Issue,
+        run_url: &str,
+    ) -> Result<()> {
+        let mut run_url = run_url.to_owned();
+        ensure_https_prefix(&mut run_url);
+        let comment = format!("The failure reoccurred in {run_url}");
+        if Config::global().dry_run() {
+            println!(
+                "DRY RUN MODE! Issue #{number} would be reopened with the comment: {comment}",
+                number = closed_issue.number
             );
-            let min_distance = distance_to_other_issues(&issue.body(), &open_issues);
-            log::info!("Minimum distance to similar issue: {min_distance}");
-            match min_distance {
-                0 => {
-                    log::warn!("An issue with the exact same body already exists. Exiting...");
-                    return Ok(());
-                }
-                _ if min_distance < issue::similarity::LEVENSHTEIN_THRESHOLD => {
-                    log::warn!("An issue with a similar body already exists. Exiting...");
-                    return Ok(());
-                }
-                _ => log::info!("No similar issue found. Continuing..."),
-            }
+            return Ok(());
         }
+        log::info!("Reopening issue #{number}", number = closed_issue.number);
+        self.client
+            .issues(owner, repo)
+            .update(closed_issue.number)
+            .state(IssueState::Open)
+            .send()
+            .await?;
+        self.client
+            .issues(owner, repo)
+            .create_comment(closed_issue.number, comment)
+            .await?;
+        Ok(())
+    }
 
-        // Get all labels for the repo, and create the ones that don't exist
-        let all_labels = self.get_all_labels(&owner, &repo).await?;
-        log::info!("Got {num_labels} label(s)", num_labels = all_labels.len());
-        let labels_to_create: Vec<String> = issue
-            .labels()
-            .iter()
-            .filter(|label| !all_labels.iter().any(|l| l.name.eq(*label)))
-            .cloned()
-            .collect();
-        if !labels_to_create.is_empty() {
-            log::info!(
-                "{} label(s) determined for the issue-to-be-created do not yet exist on the repo, and will be created: {labels_to_create:?}",
-                labels_to_create.len()
-            );
-        }
+    /// Record another occurrence of a failure in an existing issue by updating the hidden
+    /// [OccurrenceMarker] in its body, and add the [RECURRING_LABEL], renamed as configured in
+    /// `labels`, once the failure has been seen `recurring_threshold` times.
+    async fn record_occurrence(
+        &self,
+        owner: &str,
+        repo: &str,
+        existing_issue: &Issue,
+        fingerprint: &str,
+        recurring_threshold: u32,
+    ) -> Result<()> {
+        let body = existing_issue.body.as_deref().unwrap_or_default();
+        // Issues created before occurrences were tracked count as the first occurrence
+        let mut marker = OccurrenceMarker::from_issue_body(body)
+            .unwrap_or_else(|| OccurrenceMarker::new(fingerprint.to_owned()));
+        marker.increment();
+        log::info!(
+            "Failure has now occurred {count} time(s) according to issue #{number}",
+            count = marker.count(),
+            number = existing_issue.number
+        );
+        let recurring_label = Config::global()
+            .config_file()
+            .labels
+            .name_of(RECURRING_LABEL);
+        let add_recurring_label = marker.count() >= recurring_threshold
+            && !existing_issue
+                .labels
+                .iter()
+                .any(|l| l.name == recurring_label);
 
-        // Check if dry-run is set
         if Config::global().dry_run() {
-            // Then print the issue to be created instead of creating it
-            println!("####################################");
-            println!("DRY RUN MODE! The following issue would be created:");
-            println!("==== ISSUE TITLE ==== \n{}", issue.title());
-            println!("==== ISSUE LABEL(S) ==== \n{}", issue.labels().join(","));
-            println!("==== START OF ISSUE BODY ==== \n{}", issue.body());
-            println!("==== END OF ISSUE BODY ====");
-        } else {
-            // Create the labels that don't exist
-            for issue_label in labels_to_create {
-                log::info!("Creating label: {issue_label}");
-                self.client
-                    .issues(&owner, &repo)
-                    .create_label(issue_label, "FF0000", "")
-                    .await?; // Await the completion of the create_label future
+            println!(
+                "DRY RUN MODE! Issue #{number} would be updated with occurrence count {count}",
+                number = existing_issue.number,
+                count = marker.count()
+            );
+            if add_recurring_label {
+                println!("DRY RUN MODE! The label `{recurring_label}` would be added to the issue");
             }
-            self.create_issue(&owner, &repo, issue).await?;
+            return Ok(());
         }
 
+        let updated_body = marker.update_issue_body(body);
+        self.client
+            .issues(owner, repo)
+            .update(existing_issue.number)
+            .body(&updated_body)
+            .send()
+            .await?;
+        if add_recurring_label {
+            log::info!(
+                "Adding label {recurring_label} to issue #{number}",
+                number = existing_issue.number
+            );
+            self.client
+                .issues(owner, repo)
+                .add_labels(existing_issue.number, &[recurring_label.to_owned()])
+                .await?;
+        }
         Ok(())
     }
 
@@ -298,12 +1208,13 @@ impl GitHub {
     }
 
     /// Create an issue
+    #[tracing::instrument(skip(self, issue))]
     pub async fn create_issue(
         &self,
         owner: &str,
         repo: &str,
         mut issue: issue::Issue,
-    ) -> Result<()> {
+    ) -> Result<Issue> {
         let body_str = issue.body();
         log::debug!(
             "Creating issue for {owner}/{repo} with\n\
@@ -323,17 +1234,44 @@ impl GitHub {
             bail!("Issue body is too long");
         }
 
-        self.client
+        let milestone = match issue.milestone() {
+            Some(milestone) => Some(self.milestone_number(owner, repo, milestone).await?),
+            None => None,
+        };
+        let created_issue = self
+            .client
             .issues(owner, repo)
             .create(issue.title())
             .body(issue.body())
             .labels(issue.labels().to_vec())
+            .assignees(issue.assignees().to_vec())
+            .milestone(milestone)
             .send()
             .await?;
-        Ok(())
+        Ok(created_issue)
+    }
+
+    /// The number of an open milestone, by its number or its title
+    async fn milestone_number(&self, owner: &str, repo: &str, milestone: &str) -> Result<u64> {
+        if let Ok(number) = milestone.parse() {
+            return Ok(number);
+        }
+        // route: https://docs.github.com/en/rest/issues/milestones?apiVersion=2022-11-28#list-milestones
+        let milestones: Vec<models::Milestone> = self
+            .client
+            .get(
+                format!("/repos/{owner}/{repo}/milestones"),
+                Some(&[("state", "open"), ("per_page", "100")]),
+            )
+            .await?;
+        match milestones.iter().find(|m| m.title == milestone) {
+            Some(m) => Ok(m.number as u64),
+            None => bail!("No open milestone titled {milestone} in {owner}/{repo}"),
+        }
     }
 
     // Utility function to get issues
+    #[tracing::instrument(skip(self, labels))]
     async fn issues<I, S>(
         &self,
         owner: &str,
@@ -342,6 +1280,23 @@ impl GitHub {
         date: DateFilter,
         labels: LabelFilter<I, S>,
     ) -> Result<Vec<Issue>>
+    where
+        S: AsRef<str> + fmt::Display + fmt::Debug,
+        I: IntoIterator<Item = S> + Clone,
+    {
+        self.issues_in_scope(&format!("repo:{owner}/{repo}"), state, date, labels)
+            .await
+    }
+
+    /// Search for issues in a scope of the search syntax, e.g. `repo:luftkode/ci-manager` or
+    /// `org:luftkode`
+    pub(crate) async fn issues_in_scope<I, S>(
+        &self,
+        scope: &str,
+        state: State,
+        date: DateFilter,
+        labels: LabelFilter<I, S>,
+    ) -> Result<Vec<Issue>>
     where
         S: AsRef<str> + fmt::Display + fmt::Debug,
         I: IntoIterator<Item = S> + Clone,
@@ -357,8 +1312,7 @@ impl GitHub {
             _ => bail!("Invalid state"),
         };
 
-        let query_str =
-            format!("repo:{owner}/{repo} is:issue {issue_state} {date_filter} {label_filter}");
+        let query_str = format!("{scope} is:issue {issue_state} {date_filter} {label_filter}");
         log::debug!("Query string={query_str}");
         let issues = self
             .client
@@ -380,12 +1334,41 @@ impl GitHub {
         Ok(label_page.items)
     }
 
+    /// Get all repositories of an organization, excluding archived repositories
+    pub async fn org_repos(&self, org: &str) -> Result<Vec<Repository>> {
+        const PER_PAGE: u8 = 100;
+        let orgs = self.client.orgs(org);
+        let mut repos = Vec::new();
+        for page in 1u32.. {
+            let page_repos = orgs
+                .list_repos()
+                .per_page(PER_PAGE)
+                .page(page)
+                .send()
+                .await?
+                .items;
+            let num_repos = page_repos.len();
+            repos.extend(
+                page_repos
+                    .into_iter()
+                    .filter(|repo| repo.archived != Some(true)),
+            );
+            if num_repos < usize::from(PER_PAGE) {
+                break;
+            }
+        }
+        log::info!("Found {} repositories in {org}", repos.len());
+        Ok(repos)
+    }
+
+    #[tracing::instrument(skip(self))]
     pub async fn workflow_run(&self, owner: &str, repo: &str, run_id: RunId) -> Result<Run> {
         log::debug!("Getting workflow run {run_id} for {owner}/{repo}");
         let run = self.client.workflows(owner, repo).get(run_id).await?;
         Ok(run)
     }
 
+    #[tracing::instrument(skip(self))]
     pub async fn workflow_run_jobs(
         &self,
         owner: &str,
@@ -404,12 +1387,28 @@ impl GitHub {
         Ok(jobs.items)
     }
 
+    /// Get the jobs of the most recent attempt of a workflow run
+    pub async fn latest_attempt_jobs(
+        &self,
+        owner: &str,
+        repo: &str,
+        run_id: RunId,
+    ) -> Result<Vec<Job>> {
+        let mut jobs = self.workflow_run_jobs(owner, repo, run_id).await?;
+        log::info!("Got {} job(s) for the workflow run", jobs.len());
+        if let Some(max_attempt) = jobs.iter().map(|job| job.run_attempt).max() {
+            jobs.retain(|job| job.run_attempt == max_attempt);
+        }
+        Ok(jobs)
+    }
+
     /// Get the entire raw log for a job
     ///
     /// # Note
     /// The log does not contain the name of the workflow steps, only the output of the steps. It is
     /// therefore not feasible to parse the log to find the step that failed.
     /// Instead use [`download_workflow_run_logs`][GitHub::download_workflow_run_logs] to get the logs for the entire workflow run.
+    #[tracing::instrument(skip(self))]
     pub async fn download_job_logs(&self, owner: &str, repo: &str, job_id: u64) -> Result<String> {
         use http_body_util::BodyExt;
         use hyper::Uri;
@@ -438,6 +1437,7 @@ impl GitHub {
     ///
     /// # Note
     /// The logs are from the entire workflow run and all attempts, not just the most recent attempt.
+    #[tracing::instrument(skip(self))]
     pub async fn download_workflow_run_logs(
         &self,
         owner: &str,
@@ -0,0 +1,136 @@
+//! Archiving of everything ci-manager gathers about a failed workflow run, so it can be analyzed
+//! later without access to the GitHub API.
+use super::{
+    util::{repo_url_to_run_url, JobErrorLog, StepErrorLog},
+    GitHub,
+};
+use crate::{commands::WorkflowKind, err_parse::parse_error_message, *};
+use octocrab::models::{workflows::Run, JobId, RunId};
+use std::io::Write;
+
+/// Everything ci-manager gathered about a workflow run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunArchive {
+    /// Version of ci-manager that created the archive
+    pub ci_manager_version: String,
+    /// The repository in the form `owner/repo`
+    pub repo: String,
+    pub run_id: u64,
+    pub run_url: String,
+    /// The workflow run as returned by the GitHub API
+    pub run: Run,
+    /// The failed jobs in the most recent attempt of the run
+    pub failed_jobs: Vec<ArchivedJob>,
+}
+
+/// A failed job with the raw logs of its failed steps and the parsed error summary
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivedJob {
+    pub id: u64,
+    pub name: String,
+    pub failed_steps: Vec<ArchivedStep>,
+    /// The error summary parsed from the logs of the failed steps
+    pub error_summary: String,
+    /// Name of the log file of the failed task, if the parser found one (e.g. for Yocto)
+    pub logfile_name: Option<String>,
+    pub failure_label: Option<String>,
+    /// Name of the runner that ran the job, missing in archives of older versions
+    #[serde(default)]
+    pub runner_name: Option<String>,
+    /// Labels of the runner that ran the job, missing in archives of older versions
+    #[serde(default)]
+    pub runner_labels: Vec<String>,
+}
+
+/// A failed step and its raw log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchivedStep {
+    pub name: String,
+    pub log: String,
+}
+
+impl RunArchive {
+    /// The failed jobs as [JobErrorLog]s, as if they were downloaded from GitHub
+    pub fn job_error_logs(&self) -> Vec<JobErrorLog> {
+        self.failed_jobs
+            .iter()
+            .map(|job| {
+                let step_logs = job
+                    .failed_steps
+                    .iter()
+                    .map(|step| StepErrorLog::new(step.name.clone(), step.log.clone()))
+                    .collect();
+                JobErrorLog::new(JobId(job.id), job.name.clone(), step_logs)
+                    .with_runner_name(job.runner_name.clone())
+                    .with_runner_labels(job.runner_labels.clone())
+            })
+            .collect()
+    }
+}
+
+impl ArchivedJob {
+    fn from_job_error_log(job: &JobErrorLog, kind: WorkflowKind) -> Result<Self> {
+        let error_message = parse_error_message(&job.logs_as_str(), kind.for_job(&job.job_name))?;
+        Ok(Self {
+            id: *job.job_id,
+            name: job.job_name.clone(),
+            failed_steps: job
+                .failed_step_logs
+                .iter()
+                .map(|step| ArchivedStep {
+                    name: step.step_name.clone(),
+                    log: step.contents.clone(),
+                })
+                .collect(),
+            error_summary: error_message.summary().to_owned(),
+            logfile_name: error_message.logfile_name().map(str::to_owned),
+            failure_label: error_message.failure_label(),
+            runner_name: job.runner_name.clone(),
+            runner_labels: job.runner_labels.clone(),
+        })
+    }
+}
+
+impl GitHub {
+    /// Gather the metadata, failed jobs, raw logs and parsed error summaries of a workflow run
+    /// into a [RunArchive] and write it as JSON to `output`, or stdout if `output` is `None`
+    pub async fn export_run(
+        &self,
+        repo: &str,
+        run_id: &str,
+        kind: WorkflowKind,
+        output: Option<&Path>,
+    ) -> Result<()> {
+        let (owner, repo) = repo_to_owner_repo_fragments(repo)?;
+        let mut run_url = repo_url_to_run_url(&format!("github.com/{owner}/{repo}"), run_id);
+        ensure_https_prefix(&mut run_url);
+        let run_id = RunId(run_id.parse()?);
+
+        let run = self.workflow_run(&owner, &repo, run_id).await?;
+        let failed_jobs = self
+            .failed_job_error_logs(&owner, &repo, run_id)
+            .await?
+            .iter()
+            .map(|job| ArchivedJob::from_job_error_log(job, kind))
+            .collect::<Result<Vec<_>>>()?;
+        let archive = RunArchive {
+            ci_manager_version: env!("CARGO_PKG_VERSION").to_owned(),
+            repo: format!("{owner}/{repo}"),
+            run_id: *run_id,
+            run_url,
+            run,
+            failed_jobs,
+        };
+        let json = serde_json::to_string_pretty(&archive)?;
+
+        match output {
+            Some(path) => {
+                fs::write(path, json)
+                    .with_context(|| format!("Failed to write archive: {}", path.display()))?;
+                log::info!("Exported run {run_id} to {}", path.display());
+            }
+            None => pipe_println!("{json}")?,
+        }
+        Ok(())
+    }
+}
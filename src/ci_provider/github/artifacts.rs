@@ -0,0 +1,285 @@
+//! Subcommands that manage the workflow artifacts of repositories.
+use super::GitHub;
+use crate::{
+    ci_provider::util::Date,
+    err_parse::{
+        yocto::{YoctoError, YoctoFailureLog},
+        LOGFILE_MAX_LEN,
+    },
+    issue::FailedJob,
+    *,
+};
+use octocrab::{
+    models::{workflows::WorkflowListArtifact, RunId},
+    params::actions::ArchiveFormat,
+};
+
+/// Maximum number of lines at the end of a task log that are attached to an issue
+const TASK_LOG_TAIL_LINES: usize = 200;
+
+/// A page of the response of the list artifacts endpoint
+#[derive(Debug, Deserialize)]
+struct ArtifactsPage {
+    artifacts: Vec<WorkflowListArtifact>,
+}
+
+impl GitHub {
+    /// Delete the artifacts of a repository, or of every repository in an organization if `repo`
+    /// is `None`, that are older than `older_than_days` or larger than `larger_than_mb` megabytes
+    pub async fn cleanup_artifacts(
+        &self,
+        repo: Option<&str>,
+        org: Option<&str>,
+        older_than_days: Option<u32>,
+        larger_than_mb: Option<u64>,
+    ) -> Result<()> {
+        if older_than_days.is_none() && larger_than_mb.is_none() {
+            bail!("Nothing to delete, specify `--older-than` and/or `--larger-than`");
+        }
+        let repos = match (repo, org) {
+            (Some(repo), _) => vec![repo_to_owner_repo_fragments(repo)?],
+            (None, Some(org)) => self
+                .org_repos(org)
+                .await?
+                .into_iter()
+                .map(|repo| (org.to_owned(), repo.name))
+                .collect(),
+            (None, None) => bail!("Specify either `--repo` or `--org`"),
+        };
+        let created_before = older_than_days.map(Date::days_ago);
+        let larger_than_bytes = larger_than_mb.map(|mb| mb * 1024 * 1024);
+
+        let mut num_deleted = 0;
+        let mut reclaimed_bytes: u64 = 0;
+        for (owner, repo) in &repos {
+            for artifact in self.artifacts(owner, repo).await? {
+                if artifact.expired {
+                    continue;
+                }
+                let created = Date::from_timestamp(artifact.created_at.timestamp())?;
+                let size = artifact.size_in_bytes as u64;
+                let is_old = created_before.as_ref().is_some_and(|date| created < *date);
+                let is_large = larger_than_bytes.is_some_and(|max| size > max);
+                if !is_old && !is_large {
+                    continue;
+                }
+                if Config::global().dry_run() {
+                    println!(
+                        "DRY RUN MODE! Artifact {name} ({size}, created {created}) in {owner}/{repo} would be deleted",
+                        name = artifact.name,
+                        size = format_bytes(size)
+                    );
+                } else {
+                    log::info!(
+                        "Deleting artifact {name} ({size}, created {created}) in {owner}/{repo}",
+                        name = artifact.name,
+                        size = format_bytes(size)
+                    );
+                    self.delete_artifact(owner, repo, *artifact.id).await?;
+                }
+                num_deleted += 1;
+                reclaimed_bytes += size;
+            }
+        }
+
+        let verb = if Config::global().dry_run() {
+            "Would delete"
+        } else {
+            "Deleted"
+        };
+        println!(
+            "{verb} {num_deleted} artifact(s) in {num_repos} repo(s), reclaiming {reclaimed}",
+            num_repos = repos.len(),
+            reclaimed = format_bytes(reclaimed_bytes)
+        );
+        Ok(())
+    }
+
+    /// Attach the last lines of the failure logs of failed Yocto tasks that were not found locally,
+    /// from the artifacts of the run with a name matching `name_pattern` (`*` matches anything)
+    ///
+    /// In GitHub Actions the task logs stay on the runner, unless the workflow uploads them.
+    pub async fn attach_task_logs_from_artifacts(
+        &self,
+        owner: &str,
+        repo: &str,
+        run_id: RunId,
+        name_pattern: &str,
+        failed_jobs: &mut [FailedJob],
+    ) -> Result<()> {
+        let mut missing: Vec<&mut YoctoError> = failed_jobs
+            .iter_mut()
+            .filter_map(FailedJob::yocto_error_without_logfile)
+            .filter(|err| err.logfile_path().is_some())
+            .collect();
+        if missing.is_empty() {
+            log::debug!("No failed Yocto task is missing its failure log");
+            return Ok(());
+        }
+        let name_re = wildcard_regex(name_pattern);
+        let artifacts: Vec<WorkflowListArtifact> = self
+            .run_artifacts(owner, repo, run_id)
+            .await?
+            .into_iter()
+            .filter(|artifact| !artifact.expired && name_re.is_match(&artifact.name))
+            .collect();
+        if artifacts.is_empty() {
+            log::warn!("No artifact of run {run_id} matches {name_pattern}");
+        }
+        for artifact in artifacts {
+            log::info!("Looking for task logs in artifact {}", artifact.name);
+            let zip = self
+                .client
+                .actions()
+                .download_artifact(owner, repo, artifact.id, ArchiveFormat::Zip)
+                .await?;
+            let mut archive = zip::ZipArchive::new(io::Cursor::new(zip))?;
+            for err in missing.iter_mut().filter(|err| err.logfile().is_none()) {
+                if let Some(logfile) = task_log_from_archive(&mut archive, err)? {
+                    log::info!("Attaching {} from artifact {}", logfile.name, artifact.name);
+                    err.attach_logfile(logfile);
+                }
+            }
+            if missing.iter().all(|err| err.logfile().is_some()) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Get all artifacts of a repository
+    async fn artifacts(&self, owner: &str, repo: &str) -> Result<Vec<WorkflowListArtifact>> {
+        // route: https://docs.github.com/en/rest/actions/artifacts?apiVersion=2022-11-28#list-artifacts-for-a-repository
+        let artifacts = self
+            .artifacts_at(&format!("/repos/{owner}/{repo}/actions/artifacts"))
+            .await?;
+        log::debug!("Found {} artifact(s) in {owner}/{repo}", artifacts.len());
+        Ok(artifacts)
+    }
+
+    /// Get the artifacts uploaded by a workflow run
+    async fn run_artifacts(
+        &self,
+        owner: &str,
+        repo: &str,
+        run_id: RunId,
+    ) -> Result<Vec<WorkflowListArtifact>> {
+        // route: https://docs.github.com/en/rest/actions/artifacts?apiVersion=2022-11-28#list-workflow-run-artifacts
+        let artifacts = self
+            .artifacts_at(&format!(
+                "/repos/{owner}/{repo}/actions/runs/{run_id}/artifacts"
+            ))
+            .await?;
+        log::debug!("Found {} artifact(s) of run {run_id}", artifacts.len());
+        Ok(artifacts)
+    }
+
+    /// Get all pages of an endpoint listing artifacts
+    async fn artifacts_at(&self, route: &str) -> Result<Vec<WorkflowListArtifact>> {
+        const PER_PAGE: usize = 100;
+        let mut artifacts = Vec::new();
+        for page in 1u32.. {
+            let page: ArtifactsPage = self
+                .client
+                .get(
+                    route,
+                    Some(&[("per_page", PER_PAGE as u32), ("page", page)]),
+                )
+                .await?;
+            let num_artifacts = page.artifacts.len();
+            artifacts.extend(page.artifacts);
+            if num_artifacts < PER_PAGE {
+                break;
+            }
+        }
+        Ok(artifacts)
+    }
+
+    async fn delete_artifact(&self, owner: &str, repo: &str, artifact_id: u64) -> Result<()> {
+        // route: https://docs.github.com/en/rest/actions/artifacts?apiVersion=2022-11-28#delete-an-artifact
+        let route = format!("/repos/{owner}/{repo}/actions/artifacts/{artifact_id}");
+        let response = self.client._delete(route.as_str(), None::<&()>).await?;
+        octocrab::map_github_error(response).await?;
+        Ok(())
+    }
+}
+
+/// Find the failure log of a failed Yocto task in an artifact, by the name of the log file or else
+/// by the path of the log in the work directory of the recipe, e.g. `foo/1.0/temp/log.do_compile`.
+/// Returns the last lines of the log that fit in an issue.
+fn task_log_from_archive<R: io::Read + io::Seek>(
+    archive: &mut zip::ZipArchive<R>,
+    err: &YoctoError,
+) -> Result<Option<YoctoFailureLog>> {
+    let Some(fname) = err
+        .logfile_path()
+        .and_then(Path::file_name)
+        .and_then(|name| name.to_str())
+    else {
+        return Ok(None);
+    };
+    let work_dir_suffix = match (err.recipe(), err.version(), err.task()) {
+        (Some(recipe), Some(version), Some(task)) => {
+            Some(format!("{recipe}/{version}/temp/log.{task}"))
+        }
+        _ => None,
+    };
+    let names: Vec<String> = archive.file_names().map(str::to_owned).collect();
+    let found = names
+        .iter()
+        .find(|name| {
+            Path::new(name)
+                .file_name()
+                .is_some_and(|name| name == fname)
+        })
+        .or_else(|| {
+            let suffix = work_dir_suffix.as_deref()?;
+            names.iter().find(|name| name.ends_with(suffix))
+        });
+    let Some(found) = found else {
+        return Ok(None);
+    };
+    let mut contents = String::new();
+    io::Read::read_to_string(&mut archive.by_name(found)?, &mut contents)?;
+    let contents = Config::global()
+        .config_file()
+        .redact
+        .apply(&contents)
+        .into_owned();
+    let tail = log_tail(&contents, TASK_LOG_TAIL_LINES, LOGFILE_MAX_LEN);
+    let name = if tail.len() < contents.len() {
+        format!("{fname} (last {} lines)", tail.lines().count())
+    } else {
+        fname.to_owned()
+    };
+    Ok(Some(YoctoFailureLog {
+        name,
+        contents: tail.to_owned(),
+    }))
+}
+
+/// The last lines of a log, at most `max_lines` lines and `max_len` bytes
+fn log_tail(log: &str, max_lines: usize, max_len: usize) -> &str {
+    let mut start = log.len();
+    for line in log.split_inclusive('\n').rev().take(max_lines) {
+        if log.len() - start + line.len() > max_len {
+            break;
+        }
+        start -= line.len();
+    }
+    &log[start..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_log_tail() {
+        let log = "a\nbb\nccc\n";
+        assert_eq!(log_tail(log, 2, 100), "bb\nccc\n");
+        assert_eq!(log_tail(log, 10, 6), "ccc\n");
+        assert_eq!(log_tail(log, 10, 100), log);
+    }
+}
@@ -0,0 +1,277 @@
+//! Creating issues from many failed runs in one invocation, see `create-issues-from-runs`
+//!
+//! The runs share the GitHub client and are processed concurrently, pausing while the rate limit
+//! budget is nearly spent.
+use super::GitHub;
+use crate::{
+    commands::{CreateIssueFromRunArgs, CreateIssuesFromRunsArgs},
+    notify::RunReport,
+    *,
+};
+use std::{io::Write, sync::Arc, time::Duration};
+use tokio::{sync::Semaphore, task::JoinSet};
+
+/// Number of remaining API requests below which no more runs are started until the rate limit resets
+const MIN_REMAINING_REQUESTS: usize = 200;
+/// Longest pause for the rate limit to reset, in case the reset time is off
+const MAX_RATE_LIMIT_PAUSE: Duration = Duration::from_secs(3600);
+
+/// A run to create an issue from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchItem {
+    pub repo: String,
+    pub run_id: String,
+}
+
+/// The result of a run in the output of `--output-format json`
+#[derive(Debug, Serialize)]
+struct BatchResult<'a> {
+    repo: &'a str,
+    run_id: &'a str,
+    /// `None` if the issue couldn't be created or the run succeeded when its flaky jobs were re-run
+    report: Option<RunReport>,
+    error: Option<String>,
+}
+
+/// The runs of `--repo` and `--run-id` followed by the runs in the manifest
+///
+/// The repositories and run IDs are paired in order, a single repository is used for every run ID.
+/// The manifest has a `REPO RUN_ID` pair per line, empty lines and lines starting with `#` are
+/// skipped.
+///
+/// # Example
+/// ```
+/// # use ci_manager::ci_provider::github::batch::batch_items;
+/// let manifest = "# nightly builds\nluftkode/ci-manager 8302026485\n";
+/// let repos = ["docker/buildx".to_owned()];
+/// let run_ids = ["1".to_owned(), "2".to_owned()];
+/// let items = batch_items(&repos, &run_ids, Some(manifest)).unwrap();
+/// assert_eq!(items.len(), 3);
+/// assert_eq!(items[1].repo, "docker/buildx");
+/// assert_eq!(items[2].run_id, "8302026485");
+/// ```
+pub fn batch_items(
+    repos: &[String],
+    run_ids: &[String],
+    manifest: Option<&str>,
+) -> Result<Vec<BatchItem>> {
+    let mut items: Vec<BatchItem> = match repos {
+        [] if run_ids.is_empty() => Vec::new(),
+        [repo] => run_ids
+            .iter()
+            .map(|run_id| BatchItem {
+                repo: repo.to_owned(),
+                run_id: run_id.to_owned(),
+            })
+            .collect(),
+        repos if repos.len() == run_ids.len() => repos
+            .iter()
+            .zip(run_ids)
+            .map(|(repo, run_id)| BatchItem {
+                repo: repo.to_owned(),
+                run_id: run_id.to_owned(),
+            })
+            .collect(),
+        repos => bail!(
+            "Got {num_repos} --repo and {num_run_ids} --run-id, give either one --repo for all run IDs or one --repo per --run-id",
+            num_repos = repos.len(),
+            num_run_ids = run_ids.len()
+        ),
+    };
+    for (idx, line) in manifest.unwrap_or_default().lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split_whitespace();
+        let (Some(repo), Some(run_id), None) = (fields.next(), fields.next(), fields.next()) else {
+            bail!(
+                "Line {num} of the manifest is not a `REPO RUN_ID` pair: {line}",
+                num = idx + 1
+            );
+        };
+        items.push(BatchItem {
+            repo: repo.to_owned(),
+            run_id: run_id.to_owned(),
+        });
+    }
+    if items.is_empty() {
+        bail!("No runs given, use --repo and --run-id or --manifest");
+    }
+    Ok(items)
+}
+
+impl GitHub {
+    /// Create an issue from every run of `args`, at most `--max-concurrent` at a time, and print
+    /// whether it succeeded for each run, or the reports of the runs with `--output-format json`
+    ///
+    /// # Errors
+    /// Returns an error if the issue couldn't be created from any of the runs
+    pub async fn create_issues_from_runs(
+        &'static self,
+        args: &CreateIssuesFromRunsArgs,
+    ) -> Result<()> {
+        let manifest = match &args.manifest {
+            Some(path) => Some(
+                fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read manifest {}", path.display()))?,
+            ),
+            None => None,
+        };
+        let items = batch_items(&args.repo, &args.run_id, manifest.as_deref())?;
+        log::info!(
+            "Creating issues from {num} run(s), {max} at a time",
+            num = items.len(),
+            max = args.max_concurrent
+        );
+
+        let permits = Arc::new(Semaphore::new(args.max_concurrent.max(1)));
+        let mut tasks = JoinSet::new();
+        for (idx, item) in items.iter().enumerate() {
+            let permit = Arc::clone(&permits).acquire_owned().await?;
+            self.wait_for_rate_limit().await;
+            let run_args = CreateIssueFromRunArgs {
+                repo: item.repo.clone(),
+                run_id: item.run_id.clone(),
+                wait_for_completion: args.wait_for_completion,
+                wait: args.wait.clone(),
+                issue: args.issue.clone(),
+            };
+            tasks.spawn(async move {
+                let result = self.create_issue_from_run(&run_args).await;
+                drop(permit);
+                (idx, result)
+            });
+        }
+        let mut results: Vec<Option<Result<Option<RunReport>>>> =
+            items.iter().map(|_| None).collect();
+        while let Some(joined) = tasks.join_next().await {
+            let (idx, result) = joined?;
+            results[idx] = Some(result);
+        }
+
+        let mut num_failed = 0;
+        let mut batch_results = Vec::with_capacity(items.len());
+        for (item, result) in items.iter().zip(results) {
+            let (report, error) =
+                match result.unwrap_or_else(|| Err(anyhow::anyhow!("Not processed"))) {
+                    Ok(report) => (report, None),
+                    Err(e) => {
+                        num_failed += 1;
+                        (None, Some(format!("{e:#}")))
+                    }
+                };
+            batch_results.push(BatchResult {
+                repo: &item.repo,
+                run_id: &item.run_id,
+                report,
+                error,
+            });
+        }
+        if Config::global().output_format().is_some() {
+            pipe_println!("{}", serde_json::to_string_pretty(&batch_results)?)?;
+        } else {
+            for result in &batch_results {
+                match &result.error {
+                    None => pipe_println!("[ OK ] {} run {}", result.repo, result.run_id)?,
+                    Some(e) => pipe_println!("[FAIL] {} run {}: {e}", result.repo, result.run_id)?,
+                }
+            }
+        }
+        if num_failed > 0 {
+            bail!("{num_failed} of {num} run(s) failed", num = items.len());
+        }
+        Ok(())
+    }
+
+    /// Pause until the rate limit resets if the budget is nearly spent. The budget is not checked
+    /// if it can't be retrieved.
+    async fn wait_for_rate_limit(&self) {
+        let core = match self.client.ratelimit().get().await {
+            Ok(rate_limit) => rate_limit.resources.core,
+            Err(e) => {
+                log::debug!("Could not get the rate limit: {e}");
+                return;
+            }
+        };
+        if core.remaining >= MIN_REMAINING_REQUESTS {
+            return;
+        }
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let pause = Duration::from_secs(core.reset.saturating_sub(now)).min(MAX_RATE_LIMIT_PAUSE);
+        log::warn!(
+            "Only {remaining} API requests left, pausing {secs}s until the rate limit resets",
+            remaining = core.remaining,
+            secs = pause.as_secs()
+        );
+        tokio::time::sleep(pause).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn item(repo: &str, run_id: &str) -> BatchItem {
+        BatchItem {
+            repo: repo.to_owned(),
+            run_id: run_id.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_batch_items() {
+        let repos = ["luftkode/ci-manager".to_owned(), "docker/buildx".to_owned()];
+        let run_ids = ["1".to_owned(), "2".to_owned()];
+        assert_eq!(
+            batch_items(&repos, &run_ids, None).unwrap(),
+            [item("luftkode/ci-manager", "1"), item("docker/buildx", "2")]
+        );
+        assert_eq!(
+            batch_items(&[], &[], Some("\n  # comment\nluftkode/ci-manager  3\n")).unwrap(),
+            [item("luftkode/ci-manager", "3")]
+        );
+        assert!(batch_items(&repos, &run_ids[..1], None).is_err());
+        assert!(batch_items(&[], &[], Some("luftkode/ci-manager")).is_err());
+        assert!(batch_items(&[], &[], None).is_err());
+    }
+
+    #[test]
+    fn test_batch_result_json() {
+        let results = [
+            BatchResult {
+                repo: "luftkode/ci-manager",
+                run_id: "1",
+                report: None,
+                error: None,
+            },
+            BatchResult {
+                repo: "docker/buildx",
+                run_id: "2",
+                report: None,
+                error: Some("Run 2 not found".to_owned()),
+            },
+        ];
+        assert_eq!(
+            serde_json::to_value(results).unwrap(),
+            serde_json::json!([
+                {
+                    "repo": "luftkode/ci-manager",
+                    "run_id": "1",
+                    "report": null,
+                    "error": null,
+                },
+                {
+                    "repo": "docker/buildx",
+                    "run_id": "2",
+                    "report": null,
+                    "error": "Run 2 not found",
+                },
+            ])
+        );
+    }
+}
@@ -0,0 +1,319 @@
+//! Bisection of the commits between the last successful run of a workflow and a failed run.
+use super::GitHub;
+use crate::{
+    commands::{CreateIssueFromRunArgs, WaitArgs, WatchRunThen},
+    *,
+};
+use octocrab::{
+    models::{commits::Commit, workflows::Run, RunId},
+    params::repos::Reference,
+};
+use std::io::Write;
+
+/// Prefix of the temporary branches the workflow is dispatched on while bisecting
+const BISECT_BRANCH_PREFIX: &str = "ci-manager/bisect";
+
+impl GitHub {
+    /// Find the commit that broke a workflow by bisecting the commits between the last successful
+    /// run on the same branch and the failed run `run_id`.
+    ///
+    /// The workflow is dispatched (it must have a `workflow_dispatch` trigger) on a temporary
+    /// branch pointing at the commit in the middle of the remaining range, until the first failing
+    /// commit is found. If `then` is set, an issue is created from the failed run with the result
+    /// of the bisection.
+    pub async fn bisect_run(
+        &self,
+        repo: &str,
+        run_id: &str,
+        wait: &WaitArgs,
+        then: Option<&WatchRunThen>,
+    ) -> Result<()> {
+        let (owner, repo_name) = repo_to_owner_repo_fragments(repo)?;
+        let failed_run = self
+            .workflow_run(&owner, &repo_name, RunId(run_id.parse()?))
+            .await?;
+        if failed_run.conclusion.as_deref() != Some("failure") {
+            bail!(
+                "Run {run_id} did not fail, it has conclusion: {:?}",
+                failed_run.conclusion
+            );
+        }
+        let last_success = self
+            .last_successful_run(&owner, &repo_name, &failed_run)
+            .await?
+            .with_context(|| {
+                format!(
+                    "No successful run of workflow {workflow} on branch {branch} before run {run_id}",
+                    workflow = failed_run.name,
+                    branch = failed_run.head_branch
+                )
+            })?;
+        let commits = self
+            .commits_between(
+                &owner,
+                &repo_name,
+                &last_success.head_sha,
+                &failed_run.head_sha,
+            )
+            .await?;
+        log::info!(
+            "{num_commits} commit(s) since the last successful run {last_success_id}",
+            num_commits = commits.len(),
+            last_success_id = last_success.id
+        );
+        if commits.is_empty() {
+            bail!(
+                "The failed run is on the same commit as the last successful run {}, the failure is not caused by a commit",
+                last_success.id
+            );
+        }
+
+        let mut bisection = Bisection::new(commits.len());
+        while let Some(mid) = bisection.next_commit() {
+            let sha = &commits[mid].sha;
+            if Config::global().dry_run() {
+                println!(
+                    "DRY RUN MODE! {remaining} commit(s) left to bisect, the workflow would be dispatched on commit {sha}",
+                    remaining = bisection.remaining()
+                );
+                return Ok(());
+            }
+            let run = self
+                .run_workflow_on_commit(&owner, &repo_name, &failed_run, sha, wait)
+                .await?;
+            let passed = run.conclusion.as_deref() == Some("success");
+            log::info!(
+                "Commit {sha} {result} in run {run_url}",
+                result = if passed { "passed" } else { "failed" },
+                run_url = run.html_url
+            );
+            bisection.record(mid, passed);
+        }
+
+        let culprit = &commits[bisection.first_bad()];
+        let summary = bisect_summary(culprit, &last_success, commits.len());
+        pipe_println!("{summary}")?;
+
+        if let Some(WatchRunThen::CreateIssue(issue)) = then {
+            let report = self
+                .create_issue_from_run_with_sections(
+                    &CreateIssueFromRunArgs {
+                        repo: repo.to_owned(),
+                        run_id: run_id.to_owned(),
+                        wait_for_completion: false,
+                        wait: wait.clone(),
+                        issue: issue.clone(),
+                    },
+                    vec![("Bisect result".to_owned(), summary)],
+                )
+                .await?;
+            exit_code::set_outcome(exit_code::Exit::of_report(report.as_ref()));
+        }
+        Ok(())
+    }
+
+    /// Dispatch the workflow of `run` on a temporary branch pointing at `sha` and wait for the
+    /// dispatched run to complete. The temporary branch is deleted afterwards.
+    async fn run_workflow_on_commit(
+        &self,
+        owner: &str,
+        repo: &str,
+        run: &Run,
+        sha: &str,
+        wait: &WaitArgs,
+    ) -> Result<Run> {
+        let branch = format!("{BISECT_BRANCH_PREFIX}/{sha}");
+        let reference = Reference::Branch(branch.clone());
+        log::info!("Creating branch {branch}");
+        self.client
+            .repos(owner, repo)
+            .create_ref(&reference, sha)
+            .await
+            .with_context(|| format!("Failed to create branch {branch}"))?;
+
+        let result = self
+            .dispatch_and_wait(owner, repo, run, &branch, wait)
+            .await;
+
+        log::info!("Deleting branch {branch}");
+        if let Err(e) = self.client.repos(owner, repo).delete_ref(&reference).await {
+            log::error!("Failed to delete branch {branch}: {e}");
+        }
+        result
+    }
+
+    async fn dispatch_and_wait(
+        &self,
+        owner: &str,
+        repo: &str,
+        run: &Run,
+        branch: &str,
+        wait: &WaitArgs,
+    ) -> Result<Run> {
+        let workflow_id = run.workflow_id.to_string();
+        log::info!(
+            "Dispatching workflow {workflow} on {branch}",
+            workflow = run.name
+        );
+        self.client
+            .actions()
+            .create_workflow_dispatch(owner, repo, workflow_id.as_str(), branch)
+            .send()
+            .await?;
+
+        // The dispatched run shows up after a short delay
+        let start = std::time::Instant::now();
+        let dispatched_run = loop {
+            tokio::time::sleep(wait.poll_interval()).await;
+            let runs = self
+                .client
+                .workflows(owner, repo)
+                .list_runs(workflow_id.as_str())
+                .branch(branch)
+                .event("workflow_dispatch")
+                .per_page(1u8)
+                .send()
+                .await?;
+            if let Some(dispatched_run) = runs.items.into_iter().next() {
+                break dispatched_run;
+            }
+            if start.elapsed() >= wait.wait_timeout() {
+                bail!("Timed out waiting for the dispatched run on {branch} to start");
+            }
+        };
+        log::info!("Dispatched run: {}", dispatched_run.html_url);
+
+        let remaining_timeout = wait.wait_timeout().saturating_sub(start.elapsed());
+        self.wait_for_run_completion(
+            owner,
+            repo,
+            dispatched_run.id,
+            wait.poll_interval(),
+            remaining_timeout,
+        )
+        .await
+    }
+}
+
+/// The state of a bisection of a range of commits, oldest first, where the commit before the range
+/// passed and the last commit of the range failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Bisection {
+    /// Index of the first commit that is not known to pass
+    good: usize,
+    /// Index of the first commit known to fail
+    bad: usize,
+}
+
+impl Bisection {
+    /// Bisect a non-empty range of `num_commits` commits
+    fn new(num_commits: usize) -> Self {
+        debug_assert!(num_commits > 0, "Nothing to bisect");
+        Self {
+            good: 0,
+            bad: num_commits.saturating_sub(1),
+        }
+    }
+
+    /// The index of the commit to test next, the middle of the remaining range. `None` once the
+    /// first failing commit is found
+    fn next_commit(&self) -> Option<usize> {
+        (self.good < self.bad).then(|| self.good + (self.bad - self.good) / 2)
+    }
+
+    /// Narrow the range down with the result of testing the commit at `index`
+    fn record(&mut self, index: usize, passed: bool) {
+        if passed {
+            self.good = index + 1;
+        } else {
+            self.bad = index;
+        }
+    }
+
+    /// Number of commits that may still be the first failing commit
+    fn remaining(&self) -> usize {
+        self.bad - self.good + 1
+    }
+
+    /// Index of the first failing commit, once [Bisection::next_commit] returns `None`
+    fn first_bad(&self) -> usize {
+        self.bad
+    }
+}
+
+fn bisect_summary(culprit: &Commit, last_success: &Run, num_commits: usize) -> String {
+    let title = culprit.commit.message.lines().next().unwrap_or_default();
+    format!(
+        "First failing commit: [{short_sha}]({url}) {title}\n\
+        Bisected {num_commits} commit(s) since the last successful run: {last_success_url}",
+        short_sha = &culprit.sha[..culprit.sha.len().min(7)],
+        url = culprit.html_url,
+        last_success_url = last_success.html_url
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Bisect `num_commits` commits where every commit from `first_bad` on fails, returning the
+    /// found commit and the tested commits
+    fn bisect(num_commits: usize, first_bad: usize) -> (usize, Vec<usize>) {
+        let mut bisection = Bisection::new(num_commits);
+        let mut tested = Vec::new();
+        while let Some(commit) = bisection.next_commit() {
+            tested.push(commit);
+            bisection.record(commit, commit < first_bad);
+        }
+        (bisection.first_bad(), tested)
+    }
+
+    #[test]
+    fn test_single_commit_is_first_bad_without_testing() {
+        assert_eq!(bisect(1, 0), (0, vec![]));
+    }
+
+    #[test]
+    fn test_two_commits_first_bad() {
+        assert_eq!(bisect(2, 0), (0, vec![0]));
+    }
+
+    #[test]
+    fn test_two_commits_last_bad() {
+        assert_eq!(bisect(2, 1), (1, vec![0]));
+    }
+
+    #[test]
+    fn test_first_bad_at_start_of_range() {
+        assert_eq!(bisect(8, 0), (0, vec![3, 1, 0]));
+    }
+
+    #[test]
+    fn test_first_bad_at_end_of_range() {
+        assert_eq!(bisect(8, 7), (7, vec![3, 5, 6]));
+    }
+
+    #[test]
+    fn test_finds_every_first_bad_commit() {
+        for num_commits in 1..20 {
+            for first_bad in 0..num_commits {
+                let (found, tested) = bisect(num_commits, first_bad);
+                assert_eq!(found, first_bad, "{num_commits} commits");
+                assert!(tested.len() <= num_commits.ilog2() as usize + 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_remaining_narrows_down() {
+        let mut bisection = Bisection::new(5);
+        assert_eq!(bisection.remaining(), 5);
+        bisection.record(2, true);
+        assert_eq!(bisection.remaining(), 2);
+        bisection.record(3, false);
+        assert_eq!(bisection.remaining(), 1);
+        assert_eq!(bisection.next_commit(), None);
+        assert_eq!(bisection.first_bad(), 3);
+    }
+}
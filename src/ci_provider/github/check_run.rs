@@ -0,0 +1,218 @@
+//! Check runs with the analysis of a failed run, published on the head commit of the run instead of
+//! an issue, with annotations at the lines of the errors found in the logs, see `--check-run`
+use super::GitHub;
+use crate::{
+    err_parse::{
+        annotations::error_annotations, compiler::compiler_errors, lint::lint_findings,
+        pytest::pytest_failures,
+    },
+    issue::Issue,
+    notify::IssueOutcome,
+    *,
+};
+use octocrab::models::workflows::Run;
+
+/// Name of the check runs on the commits
+const CHECK_RUN_NAME: &str = "ci-manager";
+/// Maximum number of annotations GitHub takes per request, the rest are added by updating the check
+/// run
+const ANNOTATIONS_PER_REQUEST: usize = 50;
+
+/// An annotation of a check run at the line of a file where an error was found
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CheckAnnotation {
+    /// Path relative to the root of the repository
+    pub path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub annotation_level: &'static str,
+    /// Name of the job the error was found in
+    pub title: String,
+    pub message: String,
+}
+
+/// The annotations at the errors with a file and line in the log of a job: compiler errors, linter
+/// findings, failed pytest tests and the `::error file=...` annotations of actions. Errors in files
+/// outside of `repo` are left out.
+///
+/// # Example
+/// ```
+/// # use ci_manager::ci_provider::github::check_run::check_annotations;
+/// let log = "2024-03-04T10:00:01.0000000Z /home/runner/work/ci-manager/ci-manager/src/main.c:5:3: error: use of undeclared identifier 'y'
+/// 2024-03-04T10:00:01.0000000Z /usr/include/stdio.h:1:1: error: unknown type name 'x'";
+/// let annotations = check_annotations("Build", log, "ci-manager");
+/// assert_eq!(annotations.len(), 1);
+/// assert_eq!(annotations[0].path, "src/main.c");
+/// assert_eq!(annotations[0].start_line, 5);
+/// assert_eq!(annotations[0].message, "use of undeclared identifier 'y'");
+/// ```
+pub fn check_annotations(job_name: &str, log: &str, repo: &str) -> Vec<CheckAnnotation> {
+    let log = remove_timestamp_prefixes(log);
+    let log = remove_ansi_codes(&log);
+    let locations = compiler_errors(&log)
+        .into_iter()
+        .map(|diagnostic| {
+            let mut message = diagnostic.message;
+            for line in diagnostic.context {
+                message.push('\n');
+                message.push_str(&line);
+            }
+            (diagnostic.file, diagnostic.line, message)
+        })
+        .chain(lint_findings(&log).into_iter().map(|finding| {
+            let message = format!("{} {}", finding.code, finding.message);
+            (finding.file, finding.line, message)
+        }))
+        .chain(
+            pytest_failures(&log)
+                .into_iter()
+                .map(|failure| (failure.file, failure.line, failure.message)),
+        )
+        .chain(
+            error_annotations(&log)
+                .into_iter()
+                .filter_map(|annotation| {
+                    let message = match annotation.title {
+                        Some(title) => format!("{title}: {}", annotation.message),
+                        None => annotation.message,
+                    };
+                    Some((annotation.file?, annotation.line.unwrap_or(1), message))
+                }),
+        );
+
+    let mut annotations: Vec<CheckAnnotation> = Vec::new();
+    for (file, line, message) in locations {
+        let Some(path) = repo_relative_path(&file, repo) else {
+            continue;
+        };
+        let annotation = CheckAnnotation {
+            path,
+            start_line: line.max(1),
+            end_line: line.max(1),
+            annotation_level: "failure",
+            title: job_name.to_owned(),
+            message,
+        };
+        if !annotations.contains(&annotation) {
+            annotations.push(annotation);
+        }
+    }
+    annotations
+}
+
+/// The path of `file` relative to the root of the repository, where annotations have to be. An
+/// absolute path is only known to be in the repository if it is in the checkout of the runner, e.g.
+/// `/home/runner/work/ci-manager/ci-manager/src/main.rs`
+fn repo_relative_path(file: &str, repo: &str) -> Option<String> {
+    let file = file.strip_prefix("./").unwrap_or(file);
+    if !file.starts_with('/') {
+        return Some(file.to_owned());
+    }
+    let checkout = format!("/{repo}/{repo}/");
+    file.find(&checkout)
+        .map(|start| file[start + checkout.len()..].to_owned())
+}
+
+impl GitHub {
+    /// Publish the body of `issue` as a failed check run on the head commit of `run`, with the
+    /// annotations, or print it in a dry run
+    #[tracing::instrument(skip_all, fields(run_id = %run.id, annotations = annotations.len()))]
+    pub async fn publish_check_run(
+        &self,
+        owner: &str,
+        repo: &str,
+        run: &Run,
+        issue: &mut Issue,
+        annotations: &[CheckAnnotation],
+    ) -> Result<IssueOutcome> {
+        let summary = issue.body();
+        let output = |annotations: &[CheckAnnotation]| {
+            serde_json::json!({
+                "title": issue.title(),
+                "summary": summary,
+                "annotations": annotations,
+            })
+        };
+        let mut chunks = annotations.chunks(ANNOTATIONS_PER_REQUEST);
+        let check_run = serde_json::json!({
+            "name": CHECK_RUN_NAME,
+            "head_sha": run.head_sha,
+            "status": "completed",
+            "conclusion": "failure",
+            "details_url": issue.run_url(),
+            "external_id": issue.fingerprint(),
+            "output": output(chunks.next().unwrap_or_default()),
+        });
+        if Config::global().dry_run() {
+            println!(
+                "DRY RUN MODE! The following check run would be created on commit {sha} with {num} annotation(s):\n{}",
+                serde_json::to_string_pretty(&check_run)?,
+                sha = run.head_sha,
+                num = annotations.len()
+            );
+            return Ok(IssueOutcome::DryRun);
+        }
+
+        // route: https://docs.github.com/en/rest/checks/runs?apiVersion=2022-11-28#create-a-check-run
+        let route = format!("/repos/{owner}/{repo}/check-runs");
+        let created: serde_json::Value = self.client.post(route, Some(&check_run)).await.context(
+            "Failed to create the check run, the token needs the `checks: write` permission",
+        )?;
+        let id = created["id"]
+            .as_u64()
+            .context("The created check run has no ID")?;
+        for chunk in chunks {
+            let route = format!("/repos/{owner}/{repo}/check-runs/{id}");
+            let _: serde_json::Value = self
+                .client
+                .patch(route, Some(&serde_json::json!({ "output": output(chunk) })))
+                .await?;
+        }
+        let url = created["html_url"].as_str().unwrap_or_default().to_owned();
+        log::info!(
+            "Created check run {url} with {} annotation(s)",
+            annotations.len()
+        );
+        Ok(IssueOutcome::CheckRun { url })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_check_annotations() {
+        let log = "\
+tests/test_math.py:12: AssertionError
+scripts/build.sh:3:6: note: Double quote to prevent globbing and word splitting. [SC2086]
+::error file=./src/lib.rs,line=7,title=Clippy::unused variable `x`
+::error file=src/lib.rs,line=7,title=Clippy::unused variable `x`
+::error::Tests failed";
+        let annotations: Vec<(String, u32, String)> = check_annotations("Test", log, "ci-manager")
+            .into_iter()
+            .map(|a| (a.path, a.start_line, a.message))
+            .collect();
+        assert_eq!(
+            annotations,
+            [
+                (
+                    "scripts/build.sh".to_owned(),
+                    3,
+                    "SC2086 Double quote to prevent globbing and word splitting.".to_owned()
+                ),
+                (
+                    "tests/test_math.py".to_owned(),
+                    12,
+                    "AssertionError".to_owned()
+                ),
+                (
+                    "src/lib.rs".to_owned(),
+                    7,
+                    "Clippy: unused variable `x`".to_owned()
+                ),
+            ]
+        );
+    }
+}
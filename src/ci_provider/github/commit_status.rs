@@ -0,0 +1,131 @@
+//! The `ci-manager/analysis` commit status on the head commit of an analyzed run, so the triage
+//! state of the failure shows up next to the checks of the commit, see `--commit-status`
+use super::GitHub;
+use crate::{
+    notify::{IssueOutcome, RunReport},
+    *,
+};
+use octocrab::models::RunId;
+
+/// Context of the commit status
+pub const STATUS_CONTEXT: &str = "ci-manager/analysis";
+/// Longest description of a commit status
+const MAX_DESCRIPTION_LEN: usize = 140;
+
+/// The commit status of an analyzed run: `error` if every failed job was an infrastructure failure
+/// that was alerted about, otherwise `failure`
+pub fn status_state(report: &RunReport) -> &'static str {
+    match report.outcome {
+        IssueOutcome::Alerted { .. } => "error",
+        _ => "failure",
+    }
+}
+
+/// What was done about the failure and the failure labels of the failed jobs, e.g.
+/// `Issue created: compiler-error, lint`
+pub fn status_description(report: &RunReport) -> String {
+    let decision = match report.outcome {
+        IssueOutcome::Created { .. } => "Issue created",
+        IssueOutcome::Duplicate { .. } => "Already tracked",
+        IssueOutcome::Reopened { .. } => "Issue reopened",
+        IssueOutcome::DryRun => "Dry run",
+        IssueOutcome::Alerted { .. } => "Infrastructure failure alerted",
+        IssueOutcome::CheckRun { .. } => "Check run published",
+        IssueOutcome::PrComment { .. } => "Commented on the pull request",
+    };
+    let labels = report.failure_labels();
+    let description = if labels.is_empty() {
+        decision.to_owned()
+    } else {
+        format!("{decision}: {}", labels.join(", "))
+    };
+    if description.chars().count() <= MAX_DESCRIPTION_LEN {
+        return description;
+    }
+    let mut description: String = description.chars().take(MAX_DESCRIPTION_LEN - 1).collect();
+    description.push('…');
+    description
+}
+
+impl GitHub {
+    /// Set the commit status of the analyzed run on its head commit, linking to where the failure
+    /// is tracked, or print it in a dry run
+    #[tracing::instrument(skip_all, fields(run_id = %report.run_id))]
+    pub async fn set_commit_status(&self, repo: &str, report: &RunReport) -> Result<()> {
+        let (owner, repo) = repo_to_owner_repo_fragments(repo)?;
+        let run = self
+            .workflow_run(&owner, &repo, RunId(report.run_id.parse()?))
+            .await?;
+        let status = serde_json::json!({
+            "state": status_state(report),
+            "target_url": report.outcome.url().unwrap_or(&report.run_url),
+            "description": status_description(report),
+            "context": STATUS_CONTEXT,
+        });
+        if Config::global().dry_run() {
+            println!(
+                "DRY RUN MODE! The following commit status would be set on commit {sha}:\n{}",
+                serde_json::to_string_pretty(&status)?,
+                sha = run.head_sha
+            );
+            return Ok(());
+        }
+        // route: https://docs.github.com/en/rest/commits/statuses?apiVersion=2022-11-28#create-a-commit-status
+        let route = format!("/repos/{owner}/{repo}/statuses/{sha}", sha = run.head_sha);
+        let _: serde_json::Value = self.client.post(route, Some(&status)).await?;
+        log::info!(
+            "Set the {STATUS_CONTEXT} status of commit {sha}",
+            sha = run.head_sha
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notify::JobReport;
+    use pretty_assertions::assert_eq;
+
+    fn job(name: &str, failure_label: Option<&str>) -> JobReport {
+        JobReport {
+            name: name.to_owned(),
+            url: "https://github.com/luftkode/ci-manager/actions/runs/1/job/2".to_owned(),
+            failed_step: "Build".to_owned(),
+            failure_label: failure_label.map(str::to_owned),
+            fingerprint: "d0c5e0ac7e8c2a1b".to_owned(),
+            summary: "error".to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_status_description() {
+        let mut report = RunReport {
+            run_id: "1".to_owned(),
+            run_url: "https://github.com/luftkode/ci-manager/actions/runs/1".to_owned(),
+            title: "Build failed".to_owned(),
+            fingerprint: "4f1c7a2b9e3d5c60".to_owned(),
+            labels: vec!["CI".to_owned()],
+            failed_jobs: vec![
+                job("Build", Some("compiler-error")),
+                job("Lint", None),
+                job("Build (arm64)", Some("compiler-error")),
+            ],
+            outcome: IssueOutcome::Created {
+                url: "https://github.com/luftkode/ci-manager/issues/42".to_owned(),
+            },
+        };
+        assert_eq!(status_state(&report), "failure");
+        assert_eq!(
+            status_description(&report),
+            "Issue created: compiler-error, unclassified"
+        );
+
+        report.failed_jobs = (0..20)
+            .map(|i| job("Test", Some(&format!("flaky-test-{i}"))))
+            .collect();
+        let description = status_description(&report);
+        assert_eq!(description.chars().count(), MAX_DESCRIPTION_LEN);
+        assert!(description.ends_with('…'));
+    }
+}
@@ -0,0 +1,103 @@
+//! Comparison of the failures in two workflow runs.
+use super::{util::failed_jobs, GitHub};
+use crate::{commands::WorkflowKind, err_parse::parse_error_message, *};
+use octocrab::models::RunId;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::Write,
+};
+
+impl GitHub {
+    /// Print which jobs newly failed in run `new_run_id` compared to run `old_run_id`, which were
+    /// fixed, and a diff of the parsed error summaries of the jobs that failed in both runs
+    pub async fn compare_runs(
+        &self,
+        repo: &str,
+        old_run_id: &str,
+        new_run_id: &str,
+        kind: WorkflowKind,
+    ) -> Result<()> {
+        let (owner, repo) = repo_to_owner_repo_fragments(repo)?;
+        let old_run_id = RunId(old_run_id.parse()?);
+        let new_run_id = RunId(new_run_id.parse()?);
+
+        let old_failed = self.failed_job_names(&owner, &repo, old_run_id).await?;
+        let new_failed = self.failed_job_names(&owner, &repo, new_run_id).await?;
+
+        pipe_println!("Comparing run {old_run_id} to run {new_run_id}")?;
+        pipe_println!("\nNewly failed jobs:")?;
+        print_job_names(new_failed.difference(&old_failed))?;
+        pipe_println!("\nFixed jobs:")?;
+        print_job_names(old_failed.difference(&new_failed))?;
+
+        let failed_in_both: BTreeSet<&String> = old_failed.intersection(&new_failed).collect();
+        if failed_in_both.is_empty() {
+            return Ok(());
+        }
+        let old_summaries = self
+            .error_summaries(&owner, &repo, old_run_id, kind)
+            .await?;
+        let new_summaries = self
+            .error_summaries(&owner, &repo, new_run_id, kind)
+            .await?;
+        for job_name in failed_in_both {
+            let old_summary = old_summaries.get(job_name).map_or("", String::as_str);
+            let new_summary = new_summaries.get(job_name).map_or("", String::as_str);
+            pipe_println!("\nFailed in both runs: {job_name}")?;
+            if old_summary == new_summary {
+                pipe_println!("Error summary is unchanged")?;
+                continue;
+            }
+            pipe_println!("```diff")?;
+            for line in diff_lines(old_summary, new_summary) {
+                pipe_println!("{line}")?;
+            }
+            pipe_println!("```")?;
+        }
+        Ok(())
+    }
+
+    /// Names of the failed jobs in the most recent attempt of a workflow run
+    async fn failed_job_names(
+        &self,
+        owner: &str,
+        repo: &str,
+        run_id: RunId,
+    ) -> Result<BTreeSet<String>> {
+        let jobs = self.latest_attempt_jobs(owner, repo, run_id).await?;
+        Ok(failed_jobs(&jobs)
+            .into_iter()
+            .map(|job| job.name.clone())
+            .collect())
+    }
+
+    /// Parsed error summaries of the failed jobs in a workflow run by job name
+    async fn error_summaries(
+        &self,
+        owner: &str,
+        repo: &str,
+        run_id: RunId,
+        kind: WorkflowKind,
+    ) -> Result<BTreeMap<String, String>> {
+        self.failed_job_error_logs(owner, repo, run_id)
+            .await?
+            .into_iter()
+            .map(|job| {
+                let error_message =
+                    parse_error_message(&job.logs_as_str(), kind.for_job(&job.job_name))?;
+                Ok((job.job_name, error_message.summary().to_owned()))
+            })
+            .collect()
+    }
+}
+
+fn print_job_names<'n>(names: impl Iterator<Item = &'n String>) -> Result<()> {
+    let mut names = names.peekable();
+    if names.peek().is_none() {
+        pipe_println!("  (none)")?;
+    }
+    for name in names {
+        pipe_println!("  {name}")?;
+    }
+    Ok(())
+}
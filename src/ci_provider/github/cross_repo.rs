@@ -0,0 +1,100 @@
+//! Duplicate detection across repositories, for failures that hit many repositories at once, e.g.
+//! a broken upstream that every repository made from the same template depends on
+use super::{
+    util::{duplicate_issue, DuplicateReason},
+    GitHub,
+};
+use crate::{
+    ci_provider::util::{DateFilter, LabelFilter},
+    issue::similarity::Similarity,
+    *,
+};
+use octocrab::{models::issues::Issue, params::State};
+
+/// Where to look for issues of the same failure in other repositories
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CrossRepoScope {
+    /// A central repository that tracks the failures of many repositories
+    Repo { owner: String, repo: String },
+    /// Every repository of the organization
+    Org(String),
+}
+
+impl fmt::Display for CrossRepoScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CrossRepoScope::Repo { owner, repo } => write!(f, "{owner}/{repo}"),
+            CrossRepoScope::Org(org) => write!(f, "the {org} organization"),
+        }
+    }
+}
+
+impl GitHub {
+    /// Find an open issue with `labels` matching `date` in `scope` outside `owner/repo` that tracks
+    /// the same failure, see [duplicate_issue], and why it is a duplicate
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) async fn cross_repo_duplicate(
+        &self,
+        scope: &CrossRepoScope,
+        owner: &str,
+        repo: &str,
+        labels: &[&str],
+        date: DateFilter,
+        fingerprint: &str,
+        issue_body: &str,
+        similarity: Similarity,
+    ) -> Result<Option<(Issue, DuplicateReason)>> {
+        let search_scope = match scope {
+            CrossRepoScope::Repo {
+                owner: central_owner,
+                repo: central_repo,
+            } => format!("repo:{central_owner}/{central_repo}"),
+            CrossRepoScope::Org(org) => format!("org:{org}"),
+        };
+        let issues = self
+            .issues_in_scope(&search_scope, State::Open, date, LabelFilter::All(labels))
+            .await?;
+        let this_repo = format!("/repos/{owner}/{repo}");
+        let other_issues: Vec<Issue> = issues
+            .into_iter()
+            .filter(|issue| !issue.repository_url.path().ends_with(&this_repo))
+            .collect();
+        log::info!(
+            "Found {num_issues} open issue(s) in {scope} with label(s) {labels:?}",
+            num_issues = other_issues.len()
+        );
+        Ok(
+            duplicate_issue(fingerprint, issue_body, &other_issues, similarity)
+                .map(|(issue, reason)| (issue.clone(), reason)),
+        )
+    }
+
+    /// Comment on an issue in another repository that the failure it tracks was also seen in a
+    /// run of `owner/repo`
+    pub(crate) async fn comment_also_seen(
+        &self,
+        other_issue: &Issue,
+        owner: &str,
+        repo: &str,
+        run_id: u64,
+        run_url: &str,
+    ) -> Result<()> {
+        let (other_owner, other_repo) =
+            repo_to_owner_repo_fragments(other_issue.repository_url.as_str())?;
+        let mut run_url = run_url.to_owned();
+        ensure_https_prefix(&mut run_url);
+        let comment = format!("Also seen in {owner}/{repo} run [{run_id}]({run_url})");
+        if Config::global().dry_run() {
+            println!(
+                "DRY RUN MODE! Issue {other_owner}/{other_repo}#{number} would get the comment: {comment}",
+                number = other_issue.number
+            );
+            return Ok(());
+        }
+        self.client
+            .issues(&other_owner, &other_repo)
+            .create_comment(other_issue.number, comment)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,235 @@
+//! Aggregation of the failed runs in a time window into a single digest issue.
+use super::GitHub;
+use crate::{
+    ci_provider::util::{Date, DateFilter, LabelFilter},
+    commands::{DigestPeriod, RunFilterArgs, WorkflowKind},
+    err_parse::parse_error_message,
+    *,
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use octocrab::{models::workflows::Run, params::State};
+
+/// A failed run listed in a digest
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DigestRow {
+    run_id: u64,
+    run_url: String,
+    workflow: String,
+    branch: String,
+    created_at: String,
+    failed_jobs: Vec<String>,
+    failure_kinds: Vec<String>,
+}
+
+impl GitHub {
+    /// Collect the runs that failed in the last day or week, optionally only of `workflow` and on
+    /// `branch`, into a single digest issue with a table of the runs, their failed jobs and failure
+    /// kinds.
+    ///
+    /// The digest issue is titled after the period and date, an open digest issue with the same
+    /// title and label is updated instead of creating a new one.
+    pub async fn digest(
+        &self,
+        repo: &str,
+        period: DigestPeriod,
+        workflow: Option<&str>,
+        branch: Option<&str>,
+        kind: WorkflowKind,
+        label: &str,
+    ) -> Result<()> {
+        let (owner, repo) = repo_to_owner_repo_fragments(repo)?;
+        let end = Utc::now();
+        let start = end - TimeDelta::days(i64::from(period.days()));
+        let filter = RunFilterArgs {
+            workflow: workflow.map(str::to_owned),
+            branch: branch.map(str::to_owned),
+            event: None,
+            since: Some(Date::from_timestamp(start.timestamp())?),
+            until: None,
+        };
+        let failed_runs: Vec<Run> = self
+            .failed_runs(&owner, &repo, &filter, usize::MAX)
+            .await?
+            .into_iter()
+            .filter(|run| run.created_at >= start)
+            .collect();
+        log::info!("Found {} failed run(s) since {start}", failed_runs.len());
+
+        let mut rows = Vec::with_capacity(failed_runs.len());
+        for run in &failed_runs {
+            let mut failed_jobs = Vec::new();
+            let mut failure_kinds = Vec::new();
+            for job in self.failed_job_error_logs(&owner, &repo, run.id).await? {
+                let error_message =
+                    parse_error_message(&job.logs_as_str(), kind.for_job(&job.job_name))?;
+                let failure_kind = error_message
+                    .failure_label()
+                    .unwrap_or_else(|| "unknown".to_owned());
+                if !failure_kinds.contains(&failure_kind) {
+                    failure_kinds.push(failure_kind);
+                }
+                failed_jobs.push(job.job_name);
+            }
+            rows.push(DigestRow {
+                run_id: *run.id,
+                run_url: run.html_url.to_string(),
+                workflow: run.name.clone(),
+                branch: run.head_branch.clone(),
+                created_at: run.created_at.to_rfc3339(),
+                failed_jobs,
+                failure_kinds,
+            });
+        }
+
+        let title = digest_title(period, &start, &end);
+        let body = digest_body(&rows, &start, &end);
+        self.create_or_update_digest_issue(&owner, &repo, &title, &body, label)
+            .await
+    }
+
+    async fn create_or_update_digest_issue(
+        &self,
+        owner: &str,
+        repo: &str,
+        title: &str,
+        body: &str,
+        label: &str,
+    ) -> Result<()> {
+        let open_issues = self
+            .issues_at(
+                owner,
+                repo,
+                DateFilter::None,
+                State::Open,
+                LabelFilter::All([label]),
+            )
+            .await?;
+        let existing_issue = open_issues.iter().find(|issue| issue.title == title);
+
+        if Config::global().dry_run() {
+            match existing_issue {
+                Some(issue) => println!(
+                    "DRY RUN MODE! Digest issue #{number} would be updated with the body:",
+                    number = issue.number
+                ),
+                None => println!(
+                    "DRY RUN MODE! Digest issue {title:?} with label {label} would be created with the body:"
+                ),
+            }
+            println!("{body}");
+            return Ok(());
+        }
+
+        let issues = self.client.issues(owner, repo);
+        if let Some(issue) = existing_issue {
+            log::info!("Updating digest issue #{number}", number = issue.number);
+            issues.update(issue.number).body(body).send().await?;
+            return Ok(());
+        }
+        if !self
+            .get_all_labels(owner, repo)
+            .await?
+            .iter()
+            .any(|l| l.name == label)
+        {
+            log::info!("Creating label: {label}");
+            let (color, description) = Config::global().config_file().labels.style_of(label);
+            issues.create_label(label, color, description).await?;
+        }
+        log::info!("Creating digest issue {title:?}");
+        issues
+            .create(title)
+            .body(body)
+            .labels(vec![label.to_owned()])
+            .send()
+            .await?;
+        Ok(())
+    }
+}
+
+fn digest_title(period: DigestPeriod, start: &DateTime<Utc>, end: &DateTime<Utc>) -> String {
+    let end_date = end.format("%Y-%m-%d");
+    match period {
+        DigestPeriod::Daily => format!("Daily CI digest {end_date}"),
+        DigestPeriod::Weekly => format!(
+            "Weekly CI digest {start_date} to {end_date}",
+            start_date = start.format("%Y-%m-%d")
+        ),
+    }
+}
+
+fn digest_body(rows: &[DigestRow], start: &DateTime<Utc>, end: &DateTime<Utc>) -> String {
+    let mut body = format!(
+        "**{num_runs} failed run(s)** between {start} and {end} (UTC)\n",
+        num_runs = rows.len(),
+        start = start.format("%Y-%m-%d %H:%M"),
+        end = end.format("%Y-%m-%d %H:%M")
+    );
+    if rows.is_empty() {
+        return body;
+    }
+    body.push_str("\n| Run | Workflow | Branch | Created | Failed jobs | Failure kind |\n");
+    body.push_str("|---|---|---|---|---|---|\n");
+    for row in rows {
+        body.push_str(&format!(
+            "| [{run_id}]({run_url}) | {workflow} | {branch} | {created_at} | {failed_jobs} | {failure_kinds} |\n",
+            run_id = row.run_id,
+            run_url = row.run_url,
+            workflow = escape_table_cell(&row.workflow),
+            branch = escape_table_cell(&row.branch),
+            created_at = row.created_at,
+            failed_jobs = escape_table_cell(&row.failed_jobs.join(", ")),
+            failure_kinds = escape_table_cell(&row.failure_kinds.join(", ")),
+        ));
+    }
+    body
+}
+
+/// Escape the characters that would break a Markdown table cell
+fn escape_table_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_digest_title() {
+        let end = Utc.with_ymd_and_hms(2024, 3, 8, 6, 0, 0).unwrap();
+        let start = end - TimeDelta::days(7);
+        assert_eq!(
+            digest_title(DigestPeriod::Daily, &start, &end),
+            "Daily CI digest 2024-03-08"
+        );
+        assert_eq!(
+            digest_title(DigestPeriod::Weekly, &start, &end),
+            "Weekly CI digest 2024-03-01 to 2024-03-08"
+        );
+    }
+
+    #[test]
+    fn test_digest_body() {
+        let end = Utc.with_ymd_and_hms(2024, 3, 8, 6, 0, 0).unwrap();
+        let start = end - TimeDelta::days(1);
+        let rows = vec![DigestRow {
+            run_id: 8172799334,
+            run_url: "https://github.com/luftkode/distro-template/actions/runs/8172799334"
+                .to_owned(),
+            workflow: "Yocto Build".to_owned(),
+            branch: "main".to_owned(),
+            created_at: "2024-03-07T23:00:00+00:00".to_owned(),
+            failed_jobs: vec!["build (imx8)".to_owned(), "build (qemu|x86)".to_owned()],
+            failure_kinds: vec!["yocto-do_fetch".to_owned()],
+        }];
+        assert_eq!(
+            digest_body(&rows, &start, &end),
+            "**1 failed run(s)** between 2024-03-07 06:00 and 2024-03-08 06:00 (UTC)\n\
+            \n| Run | Workflow | Branch | Created | Failed jobs | Failure kind |\n\
+            |---|---|---|---|---|---|\n\
+            | [8172799334](https://github.com/luftkode/distro-template/actions/runs/8172799334) | Yocto Build | main | 2024-03-07T23:00:00+00:00 | build (imx8), build (qemu\\|x86) | yocto-do_fetch |\n"
+        );
+    }
+}
@@ -0,0 +1,175 @@
+//! Validation of the GitHub credentials and permissions ci-manager needs.
+use super::GitHub;
+use crate::*;
+use std::io::Write;
+
+/// Minimum number of remaining API requests before the rate limit is reported as a problem
+const LOW_RATE_LIMIT: usize = 100;
+
+/// Result of a single `doctor` check
+enum Check {
+    Ok(String),
+    Warn(String),
+    Fail(String),
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Check::Ok(msg) => write!(f, "[ OK ] {msg}"),
+            Check::Warn(msg) => write!(f, "[WARN] {msg}"),
+            Check::Fail(msg) => write!(f, "[FAIL] {msg}"),
+        }
+    }
+}
+
+impl GitHub {
+    /// Check the token, its scopes, the permissions in `repo` and the rate limit budget, and print
+    /// the result of each check.
+    ///
+    /// # Errors
+    /// Returns an error if any of the checks failed
+    pub async fn doctor(&self, repo: &str) -> Result<()> {
+        let (owner, repo) = repo_to_owner_repo_fragments(repo)?;
+        let checks = vec![
+            check_token(),
+            self.check_scopes().await,
+            self.check_rate_limit().await,
+            self.check_issue_permissions(&owner, &repo).await,
+            self.check_log_access(&owner, &repo).await,
+        ];
+
+        let mut num_failed = 0;
+        for check in &checks {
+            if matches!(check, Check::Fail(_)) {
+                num_failed += 1;
+            }
+            pipe_println!("{check}")?;
+        }
+        if num_failed > 0 {
+            bail!("{num_failed} check(s) failed");
+        }
+        Ok(())
+    }
+
+    async fn check_scopes(&self) -> Check {
+        let response = match self.client._get("/rate_limit").await {
+            Ok(response) => response,
+            Err(e) => return Check::Fail(format!("Could not reach the GitHub API: {e}")),
+        };
+        // Only classic personal access tokens report their scopes, fine-grained tokens and
+        // GitHub App tokens (like the GITHUB_TOKEN in workflows) have permissions instead
+        match response
+            .headers()
+            .get("x-oauth-scopes")
+            .and_then(|scopes| scopes.to_str().ok())
+        {
+            Some(scopes) if scopes.split(',').any(|s| s.trim() == "repo") => {
+                Check::Ok(format!("Token scopes: {scopes}"))
+            }
+            Some(scopes) => Check::Warn(format!(
+                "Token scopes: {scopes:?}. The `repo` scope is needed to create issues and download logs in private repositories"
+            )),
+            None => Check::Ok(
+                "Token does not report scopes (fine-grained or GitHub App token), see the checks below for its permissions"
+                    .to_owned(),
+            ),
+        }
+    }
+
+    async fn check_rate_limit(&self) -> Check {
+        match self.client.ratelimit().get().await {
+            Ok(rate_limit) => {
+                let core = rate_limit.resources.core;
+                let msg = format!(
+                    "Rate limit: {remaining}/{limit} requests remaining, resets at unix time {reset}",
+                    remaining = core.remaining,
+                    limit = core.limit,
+                    reset = core.reset
+                );
+                if core.remaining < LOW_RATE_LIMIT {
+                    Check::Warn(msg)
+                } else {
+                    Check::Ok(msg)
+                }
+            }
+            Err(e) => Check::Fail(format!("Could not get the rate limit: {e}")),
+        }
+    }
+
+    async fn check_issue_permissions(&self, owner: &str, repo: &str) -> Check {
+        let repository = match self.client.repos(owner, repo).get().await {
+            Ok(repository) => repository,
+            Err(e) => {
+                return Check::Fail(format!(
+                    "Cannot access {owner}/{repo}: {e}. Check the repository name and that the token has access to it"
+                ))
+            }
+        };
+        if repository.has_issues == Some(false) {
+            return Check::Fail(format!(
+                "Issues are disabled in {owner}/{repo}, enable them in the repository settings"
+            ));
+        }
+        match repository.permissions {
+            Some(permissions) if permissions.push || permissions.triage => Check::Ok(format!(
+                "Token can create issues and labels in {owner}/{repo}"
+            )),
+            Some(_) => Check::Fail(format!(
+                "Token has read-only access to {owner}/{repo}, it needs write (or triage) access to create issues and labels"
+            )),
+            None => Check::Warn(format!(
+                "Could not determine the permissions of the token in {owner}/{repo}. In a workflow, grant `issues: write`"
+            )),
+        }
+    }
+
+    async fn check_log_access(&self, owner: &str, repo: &str) -> Check {
+        let latest_run = match self
+            .client
+            .workflows(owner, repo)
+            .list_all_runs()
+            .status("completed")
+            .per_page(1u8)
+            .send()
+            .await
+        {
+            Ok(runs) => runs.items.into_iter().next(),
+            Err(e) => {
+                return Check::Fail(format!(
+                    "Cannot list workflow runs in {owner}/{repo}: {e}. In a workflow, grant `actions: read`"
+                ))
+            }
+        };
+        let Some(run) = latest_run else {
+            return Check::Warn(format!(
+                "No completed workflow runs in {owner}/{repo}, could not check access to logs"
+            ));
+        };
+        // The endpoint redirects to the log archive if the token is allowed to download it
+        let route = format!("/repos/{owner}/{repo}/actions/runs/{}/logs", run.id);
+        match self.client._get(route.as_str()).await {
+            Ok(response) if response.status().is_redirection() || response.status().is_success() => {
+                Check::Ok(format!("Token can download workflow logs in {owner}/{repo}"))
+            }
+            Ok(response) => Check::Fail(format!(
+                "Cannot download workflow logs in {owner}/{repo} (HTTP {status}). In a workflow, grant `actions: read`",
+                status = response.status()
+            )),
+            Err(e) => Check::Fail(format!(
+                "Cannot download workflow logs in {owner}/{repo}: {e}"
+            )),
+        }
+    }
+}
+
+fn check_token() -> Check {
+    match env::var("GITHUB_TOKEN") {
+        Ok(token) if !token.trim().is_empty() => Check::Ok("GITHUB_TOKEN is set".to_owned()),
+        _ => Check::Fail(
+            "GITHUB_TOKEN is not set, requests are unauthenticated and heavily rate limited. \
+            Set it to a token with access to the repository"
+                .to_owned(),
+        ),
+    }
+}
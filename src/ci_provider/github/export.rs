@@ -0,0 +1,113 @@
+//! Export of the failed jobs of workflow runs, one row per failed job, e.g. to analyze failures in a
+//! spreadsheet, see `--output csv`
+use super::GitHub;
+use crate::{
+    ci_provider::util::{DateFilter, LabelFilter},
+    commands::WorkflowKind,
+    err_parse::parse_error_message,
+    issue::run_id_from_issue_body,
+    *,
+};
+use octocrab::{models::workflows::Run, params::State};
+use std::{borrow::Cow, collections::HashMap, io::Write};
+
+/// A failed job of a workflow run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailedJobRow {
+    pub run_id: u64,
+    /// When the run was created, in RFC 3339
+    pub date: String,
+    pub workflow: String,
+    pub job: String,
+    pub failure_label: Option<String>,
+    /// The issue that was created from the run, if any
+    pub issue_url: Option<String>,
+}
+
+impl GitHub {
+    /// The failed jobs of `runs`, with the failure label parsed from their logs as `kind`, and the
+    /// issue that was created from their run
+    pub async fn failed_job_rows(
+        &self,
+        owner: &str,
+        repo: &str,
+        runs: &[&Run],
+        kind: WorkflowKind,
+    ) -> Result<Vec<FailedJobRow>> {
+        let issue_urls: HashMap<u64, String> = self
+            .issues_at(
+                owner,
+                repo,
+                DateFilter::None,
+                State::All,
+                LabelFilter::none(),
+            )
+            .await?
+            .into_iter()
+            .filter_map(|issue| {
+                let run_id = run_id_from_issue_body(issue.body.as_deref()?)?;
+                Some((run_id, issue.html_url.to_string()))
+            })
+            .collect();
+
+        let mut rows = Vec::new();
+        for run in runs {
+            for job in self.failed_job_error_logs(owner, repo, run.id).await? {
+                let failure_label =
+                    parse_error_message(&job.logs_as_str(), kind.for_job(&job.job_name))?
+                        .failure_label();
+                rows.push(FailedJobRow {
+                    run_id: *run.id,
+                    date: run.created_at.to_rfc3339(),
+                    workflow: run.name.clone(),
+                    job: job.job_name.clone(),
+                    failure_label,
+                    issue_url: issue_urls.get(&*run.id).cloned(),
+                });
+            }
+        }
+        Ok(rows)
+    }
+}
+
+/// Print the rows as CSV with a header row
+pub fn print_csv(rows: &[FailedJobRow]) -> Result<()> {
+    pipe_println!("run_id,date,workflow,job,failure_label,issue_url")?;
+    for row in rows {
+        pipe_println!(
+            "{},{},{},{},{},{}",
+            row.run_id,
+            csv_field(&row.date),
+            csv_field(&row.workflow),
+            csv_field(&row.job),
+            csv_field(row.failure_label.as_deref().unwrap_or_default()),
+            csv_field(row.issue_url.as_deref().unwrap_or_default())
+        )?;
+    }
+    Ok(())
+}
+
+/// Quote a field that contains a separator, quote or line break, as in RFC 4180
+fn csv_field(field: &str) -> Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("Build (ubuntu-latest)"), "Build (ubuntu-latest)");
+        assert_eq!(csv_field("Build, test"), "\"Build, test\"");
+        assert_eq!(
+            csv_field("Deploy \"staging\""),
+            "\"Deploy \"\"staging\"\"\""
+        );
+    }
+}
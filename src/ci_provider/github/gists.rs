@@ -0,0 +1,37 @@
+//! Upload of job logs that are too long for an issue to secret gists
+use super::GitHub;
+use crate::{issue::Issue, *};
+
+impl GitHub {
+    /// Upload the full error summary and log of every job that is shortened in the issue body to
+    /// a secret gist, and link the gist in the issue body instead
+    pub async fn upload_shortened_logs_to_gists(&self, issue: &mut Issue) -> Result<()> {
+        let title = issue.title().to_owned();
+        for job in issue.shortened_jobs_mut() {
+            let file_name = format!("{}.log", job.name().replace(['/', '\\'], "-"));
+            if Config::global().dry_run() {
+                println!(
+                    "DRY RUN MODE! The log of job {name} would be uploaded to a secret gist",
+                    name = job.name()
+                );
+                continue;
+            }
+            log::info!(
+                "Uploading the log of job {name} to a secret gist",
+                name = job.name()
+            );
+            let gist = self
+                .client
+                .gists()
+                .create()
+                .description(format!("{title}: {}", job.name()))
+                .public(false)
+                .file(file_name, job.full_log())
+                .send()
+                .await
+                .with_context(|| format!("Failed to upload the log of job {}", job.name()))?;
+            job.set_full_log_url(gist.html_url.to_string());
+        }
+        Ok(())
+    }
+}
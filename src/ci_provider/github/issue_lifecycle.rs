@@ -0,0 +1,452 @@
+//! Subcommands that manage the lifecycle of issues previously created by ci-manager.
+use super::util::failed_jobs_from_job_error_logs;
+use super::GitHub;
+use crate::{
+    ci_provider::util::{Date, DateFilter, LabelFilter},
+    commands::{
+        cluster_failures::{cluster, print_clusters, FailureSample},
+        OutputFormat, WorkflowKind,
+    },
+    issue::{
+        footer::IssueMetadata, occurrence::OccurrenceMarker, run_id_from_issue_body,
+        similarity::Similarity,
+    },
+    *,
+};
+use octocrab::{
+    models::{issues::Issue, workflows::Run, IssueState, RunId},
+    params::State,
+};
+use std::collections::HashMap;
+
+impl GitHub {
+    /// Close open issues created by ci-manager whose failure no longer reproduces.
+    ///
+    /// An issue is considered resolved by a run of the same workflow on the same branch that
+    /// completed after the run the issue was created from, if none of the failed jobs in the
+    /// issue (see [IssueMetadata] and [OccurrenceMarker]) fails the same way in that run. If
+    /// `green_run_id` is given, that successful run is used, otherwise the most recent completed
+    /// run of the workflow, which is parsed as `kind` if it failed.
+    pub async fn close_resolved_issues(
+        &self,
+        repo: &str,
+        green_run_id: Option<&str>,
+        label: &str,
+        kind: WorkflowKind,
+    ) -> Result<()> {
+        let (owner, repo) = repo_to_owner_repo_fragments(repo)?;
+        let green_run = match green_run_id {
+            Some(run_id) => {
+                let run = self
+                    .workflow_run(&owner, &repo, RunId(run_id.parse()?))
+                    .await?;
+                if run.conclusion.as_deref() != Some("success") {
+                    bail!(
+                        "Run {run_id} did not succeed, it has conclusion: {:?}",
+                        run.conclusion
+                    );
+                }
+                Some(run)
+            }
+            None => None,
+        };
+
+        let open_issues = self
+            .issues_at(
+                &owner,
+                &repo,
+                DateFilter::None,
+                State::Open,
+                LabelFilter::All([label]),
+            )
+            .await?;
+        log::info!(
+            "Found {num_issues} open issue(s) with label {label}",
+            num_issues = open_issues.len()
+        );
+
+        // Several issues are often resolved by the same run, whose failures are only fetched once
+        let mut run_fingerprints = HashMap::new();
+        for issue in &open_issues {
+            let body = issue.body.as_deref().unwrap_or_default();
+            // Issues created before the footer was added only have the fingerprint in the marker,
+            // and issues created before the jobs were fingerprinted only the fingerprint of the run
+            let fingerprints = match IssueMetadata::from_issue_body(body) {
+                Some(metadata) if !metadata.job_fingerprints.is_empty() => {
+                    metadata.job_fingerprints
+                }
+                Some(metadata) => vec![metadata.fingerprint],
+                None => match OccurrenceMarker::from_issue_body(body) {
+                    Some(marker) => vec![marker.fingerprint().to_owned()],
+                    None => continue,
+                },
+            };
+            if let Err(e) = self
+                .close_if_resolved(
+                    &owner,
+                    &repo,
+                    issue,
+                    &fingerprints,
+                    green_run.as_ref(),
+                    kind,
+                    &mut run_fingerprints,
+                )
+                .await
+            {
+                log::warn!(
+                    "Failed to check if the failure of issue #{number} is resolved: {e:#}",
+                    number = issue.number
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Close `issue` if none of the failures of `fingerprints` reproduces in `green_run` or the
+    /// most recent completed run of its workflow, see [GitHub::close_resolved_issues]
+    #[allow(clippy::too_many_arguments)]
+    async fn close_if_resolved(
+        &self,
+        owner: &str,
+        repo: &str,
+        issue: &Issue,
+        fingerprints: &[String],
+        green_run: Option<&Run>,
+        kind: WorkflowKind,
+        run_fingerprints: &mut HashMap<u64, Option<RunFingerprints>>,
+    ) -> Result<()> {
+        let failed_run_id = run_id_from_issue_body(issue.body.as_deref().unwrap_or_default())
+            .with_context(|| format!("no link to a run in issue #{}", issue.number))?;
+        let failed_run = self.workflow_run(owner, repo, RunId(failed_run_id)).await?;
+        let later_run = match green_run {
+            Some(run) => run.clone(),
+            None => match self.latest_completed_run(owner, repo, &failed_run).await? {
+                Some(run) => run,
+                None => {
+                    log::info!(
+                        "No completed run of workflow {workflow} after run {failed_run_id}, keeping issue #{number} open",
+                        workflow = failed_run.name,
+                        number = issue.number
+                    );
+                    return Ok(());
+                }
+            },
+        };
+        if !is_later_run_of_same_workflow(&failed_run, &later_run) {
+            log::info!(
+                "Run {run_id} is not a later run of the workflow of run {failed_run_id}, keeping issue #{number} open",
+                run_id = later_run.id,
+                number = issue.number
+            );
+            return Ok(());
+        }
+        let later_fingerprints = match run_fingerprints.get(&later_run.id.0) {
+            Some(later_fingerprints) => later_fingerprints.clone(),
+            None => {
+                let later_fingerprints = self
+                    .failure_fingerprint(owner, repo, &later_run, kind)
+                    .await?;
+                run_fingerprints.insert(later_run.id.0, later_fingerprints.clone());
+                later_fingerprints
+            }
+        };
+        if later_fingerprints.is_some_and(|later| later.reproduces(fingerprints)) {
+            log::info!(
+                "The failure of issue #{number} reproduces in run {run_id}, keeping it open",
+                run_id = later_run.id,
+                number = issue.number
+            );
+            return Ok(());
+        }
+        self.close_issue_with_comment(
+            owner,
+            repo,
+            issue,
+            &format!(
+                "The failure no longer reproduces in run {run_id}: {run_url}",
+                run_id = later_run.id,
+                run_url = later_run.html_url
+            ),
+        )
+        .await
+    }
+
+    /// Fingerprints of the failure in a run as they would be in an issue created from the run with
+    /// `create-issue-from-run`, `None` if the run succeeded
+    async fn failure_fingerprint(
+        &self,
+        owner: &str,
+        repo: &str,
+        run: &Run,
+        kind: WorkflowKind,
+    ) -> Result<Option<RunFingerprints>> {
+        if run.conclusion.as_deref() == Some("success") {
+            return Ok(None);
+        }
+        let job_error_logs = self.failed_job_error_logs(owner, repo, run.id).await?;
+        if job_error_logs.is_empty() {
+            return Ok(None);
+        }
+        let run_url = run.html_url.to_string();
+        let failed_jobs = failed_jobs_from_job_error_logs(&job_error_logs, &run_url, kind);
+        let issue = issue::Issue::new(
+            String::new(),
+            run.id.to_string(),
+            run_url,
+            failed_jobs,
+            String::new(),
+        );
+        Ok(Some(RunFingerprints::of(&issue)))
+    }
+
+    /// Close open issues created by ci-manager that have had no activity for `stale_after_days` days.
+    ///
+    /// If `labels` is not empty, only issues with all of the labels are considered.
+    pub async fn close_stale_issues(
+        &self,
+        repo: &str,
+        stale_after_days: u32,
+        labels: &[String],
+    ) -> Result<()> {
+        let (owner, repo) = repo_to_owner_repo_fragments(repo)?;
+        let label_filter = if labels.is_empty() {
+            LabelFilter::none()
+        } else {
+            LabelFilter::All(labels.to_vec())
+        };
+        let stale_issues = self
+            .issues_at(
+                &owner,
+                &repo,
+                DateFilter::UpdatedBefore(Date::days_ago(stale_after_days)),
+                State::Open,
+                label_filter,
+            )
+            .await?;
+        log::info!(
+            "Found {num_issues} open issue(s) without activity for {stale_after_days} days",
+            num_issues = stale_issues.len()
+        );
+
+        for issue in stale_issues
+            .iter()
+            .filter(|issue| is_created_by_ci_manager(issue))
+        {
+            self.close_issue_with_comment(
+                &owner,
+                &repo,
+                issue,
+                &format!(
+                    "Closing as stale, there has been no activity for {stale_after_days} days. \
+                    Reopen the issue if the failure reoccurs."
+                ),
+            )
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Group the failures of the open and closed issues created by ci-manager in a repository and
+    /// print the largest groups, see [cluster]
+    pub async fn cluster_issue_failures(
+        &self,
+        repo: &str,
+        labels: &[String],
+        similarity: Similarity,
+        top: usize,
+        format: OutputFormat,
+    ) -> Result<()> {
+        let (owner, repo) = repo_to_owner_repo_fragments(repo)?;
+        let label_filter = if labels.is_empty() {
+            LabelFilter::none()
+        } else {
+            LabelFilter::All(labels.to_vec())
+        };
+        let issues = self
+            .issues_at(&owner, &repo, DateFilter::None, State::All, label_filter)
+            .await?;
+        let samples: Vec<FailureSample> = issues
+            .iter()
+            .filter(|issue| is_created_by_ci_manager(issue))
+            .map(|issue| {
+                FailureSample::from_issue(
+                    format!("#{}", issue.number),
+                    &issue.title,
+                    issue.body.as_deref().unwrap_or_default(),
+                )
+            })
+            .collect();
+        log::info!(
+            "Clustering the failures of {num_issues} issue(s) created by ci-manager",
+            num_issues = samples.len()
+        );
+        print_clusters(&cluster(samples, similarity), top, format)
+    }
+
+    /// The most recent completed run of the same workflow and branch as `run`
+    async fn latest_completed_run(
+        &self,
+        owner: &str,
+        repo: &str,
+        run: &Run,
+    ) -> Result<Option<Run>> {
+        let runs = self
+            .client
+            .workflows(owner, repo)
+            .list_runs(run.workflow_id.to_string())
+            .branch(run.head_branch.as_str())
+            .status("completed")
+            .per_page(1u8)
+            .send()
+            .await?;
+        Ok(runs.items.into_iter().next())
+    }
+
+    /// Close an issue and leave a comment explaining why
+    async fn close_issue_with_comment(
+        &self,
+        owner: &str,
+        repo: &str,
+        issue: &Issue,
+        comment: &str,
+    ) -> Result<()> {
+        if Config::global().dry_run() {
+            println!(
+                "DRY RUN MODE! Issue #{number} would be closed with the comment: {comment}",
+                number = issue.number
+            );
+            return Ok(());
+        }
+        log::info!("Closing issue #{number}", number = issue.number);
+        self.client
+            .issues(owner, repo)
+            .create_comment(issue.number, comment)
+            .await?;
+        self.client
+            .issues(owner, repo)
+            .update(issue.number)
+            .state(IssueState::Closed)
+            .send()
+            .await?;
+        Ok(())
+    }
+}
+
+/// Fingerprints of the failure in a later run, see [issue::Issue::fingerprint] and
+/// [issue::Issue::job_fingerprints]
+#[derive(Debug, Clone)]
+struct RunFingerprints {
+    fingerprint: String,
+    job_fingerprints: Vec<String>,
+}
+
+impl RunFingerprints {
+    fn of(issue: &issue::Issue) -> Self {
+        Self {
+            fingerprint: issue.fingerprint(),
+            job_fingerprints: issue.job_fingerprints(),
+        }
+    }
+
+    /// Whether the failure of an issue with the failed jobs of `fingerprints` reproduces in the
+    /// run, i.e. any of the jobs fails the same way again, whatever else failed in the run. The
+    /// fingerprint of an issue that predates the job fingerprints is compared with the whole run.
+    fn reproduces(&self, fingerprints: &[String]) -> bool {
+        fingerprints.iter().any(|fingerprint| {
+            *fingerprint == self.fingerprint || self.job_fingerprints.contains(fingerprint)
+        })
+    }
+}
+
+/// Issues created by ci-manager carry an [OccurrenceMarker] and, since the footer was added,
+/// [IssueMetadata] in their body
+fn is_created_by_ci_manager(issue: &Issue) -> bool {
+    issue.body.as_deref().is_some_and(|body| {
+        OccurrenceMarker::from_issue_body(body).is_some()
+            || IssueMetadata::from_issue_body(body).is_some()
+    })
+}
+
+/// A run of the same workflow on the same branch that started after the failed run, which shows
+/// whether the failure still reproduces
+fn is_later_run_of_same_workflow(failed_run: &Run, later_run: &Run) -> bool {
+    later_run.workflow_id == failed_run.workflow_id
+        && later_run.head_branch == failed_run.head_branch
+        && later_run.created_at > failed_run.created_at
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        err_parse::ErrorMessageSummary,
+        issue::{FailedJob, FirstFailedStep},
+    };
+
+    fn run_failure(jobs: &[(&str, &str)]) -> issue::Issue {
+        let failed_jobs = jobs
+            .iter()
+            .map(|(name, summary)| {
+                FailedJob::new(
+                    name.to_string(),
+                    "2".to_string(),
+                    "https://github.com/luftkode/ci-manager/actions/runs/1/job/2".to_string(),
+                    FirstFailedStep::StepName("Build".to_owned()),
+                    ErrorMessageSummary::Other(summary.to_string()),
+                )
+            })
+            .collect();
+        issue::Issue::new(
+            "Scheduled run failed".to_string(),
+            "1".to_string(),
+            "https://github.com/luftkode/ci-manager/actions/runs/1".to_string(),
+            failed_jobs,
+            "CI".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_reproduces_when_other_jobs_fail_too() {
+        let tracked = run_failure(&[("build", "error: `x` undeclared")]).job_fingerprints();
+        let later = run_failure(&[
+            ("build", "error: `x` undeclared"),
+            ("test", "test foo ... FAILED"),
+        ]);
+        assert!(RunFingerprints::of(&later).reproduces(&tracked));
+    }
+
+    #[test]
+    fn test_reproduces_when_other_jobs_succeed() {
+        let tracked = run_failure(&[
+            ("build", "error: `x` undeclared"),
+            ("test", "test foo ... FAILED"),
+        ])
+        .job_fingerprints();
+        let later = run_failure(&[("test", "test foo ... FAILED")]);
+        assert!(RunFingerprints::of(&later).reproduces(&tracked));
+    }
+
+    #[test]
+    fn test_resolved_when_only_a_different_job_fails() {
+        let tracked = run_failure(&[("build", "error: `x` undeclared")]).job_fingerprints();
+        let later = run_failure(&[("test", "test foo ... FAILED")]);
+        assert!(!RunFingerprints::of(&later).reproduces(&tracked));
+        // The same job failing differently is a different failure
+        let later = run_failure(&[("build", "error: `y` undeclared")]);
+        assert!(!RunFingerprints::of(&later).reproduces(&tracked));
+    }
+
+    #[test]
+    fn test_reproduces_fingerprint_of_whole_run() {
+        // Issues created before the jobs were fingerprinted
+        let issue = run_failure(&[
+            ("build", "error: `x` undeclared"),
+            ("test", "test foo ... FAILED"),
+        ]);
+        let later = RunFingerprints::of(&issue);
+        assert!(later.reproduces(&[issue.fingerprint()]));
+        assert!(!later.reproduces(&["0123456789abcdef".to_owned()]));
+    }
+}
@@ -0,0 +1,76 @@
+//! Subcommands that download the raw logs of workflow runs.
+use super::GitHub;
+use crate::{ci_provider::util::JobLog, *};
+use octocrab::models::RunId;
+use std::io::Write;
+
+impl GitHub {
+    /// Download the logs of a workflow run, or of a single job in the run, into `output_dir`.
+    ///
+    /// The directory structure of the log archive is kept, but every path component is sanitized
+    /// with [sanitize_filename]. If `output_dir` is `None`, the logs are written to `logs-<RUN_ID>`.
+    ///
+    /// Timestamps and ANSI codes are removed from the logs if `--trim-timestamp` and
+    /// `--trim-ansi-codes` are set.
+    pub async fn download_logs(
+        &self,
+        repo: &str,
+        run_id: &str,
+        job_id: Option<u64>,
+        output_dir: Option<&Path>,
+    ) -> Result<()> {
+        let (owner, repo) = repo_to_owner_repo_fragments(repo)?;
+        let output_dir = output_dir.map_or_else(
+            || PathBuf::from(format!("logs-{run_id}")),
+            Path::to_path_buf,
+        );
+        let run_id = RunId(run_id.parse()?);
+
+        let logs = match job_id {
+            Some(job_id) => {
+                let jobs = self.workflow_run_jobs(&owner, &repo, run_id).await?;
+                let job = jobs
+                    .iter()
+                    .find(|job| *job.id == job_id)
+                    .with_context(|| format!("Job {job_id} not found in run {run_id}"))?;
+                let content = self.download_job_logs(&owner, &repo, job_id).await?;
+                vec![JobLog::new(format!("{}.txt", job.name), content)]
+            }
+            None => {
+                self.download_workflow_run_logs(&owner, &repo, run_id)
+                    .await?
+            }
+        };
+
+        fs::create_dir_all(&output_dir)
+            .with_context(|| format!("Failed to create directory: {}", output_dir.display()))?;
+        for log in &logs {
+            let path = log
+                .name
+                .split('/')
+                .filter(|component| !component.is_empty())
+                .fold(output_dir.clone(), |path, component| {
+                    path.join(sanitize_filename(component))
+                });
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut content = log.content.clone();
+            if Config::global().trim_timestamp() {
+                content = remove_timestamp_prefixes(&content).into_owned();
+            }
+            if Config::global().trim_ansi_codes() {
+                content = remove_ansi_codes(&content).into_owned();
+            }
+            fs::write(&path, content.as_bytes())
+                .with_context(|| format!("Failed to write log: {}", path.display()))?;
+            pipe_println!("{}", path.display())?;
+        }
+        log::info!(
+            "Downloaded {num_logs} log(s) to {dir}",
+            num_logs = logs.len(),
+            dir = output_dir.display()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+//! Keys of the matrix parameters of failed jobs, from the workflow file of the run
+use super::GitHub;
+use crate::{issue::matrix::matrix_keys_of_workflow, issue::FailedJob, *};
+use octocrab::models::workflows::Run;
+
+/// The path of a workflow, which is missing in the [Run] model
+#[derive(Debug, Deserialize)]
+struct WorkflowPath {
+    path: String,
+}
+
+impl GitHub {
+    /// Name the matrix parameters of the failed jobs with the keys of the matrix in the workflow
+    /// file, as of the commit of the run
+    pub(crate) async fn name_matrix_params(
+        &self,
+        owner: &str,
+        repo: &str,
+        run: &Run,
+        failed_jobs: &mut [FailedJob],
+    ) -> Result<()> {
+        if failed_jobs.iter().all(|job| job.matrix().is_empty()) {
+            return Ok(());
+        }
+        // route: https://docs.github.com/en/rest/actions/workflows?apiVersion=2022-11-28#get-a-workflow
+        let workflow: WorkflowPath = self
+            .client
+            .get(
+                format!(
+                    "/repos/{owner}/{repo}/actions/workflows/{workflow_id}",
+                    workflow_id = run.workflow_id
+                ),
+                None::<&()>,
+            )
+            .await?;
+        let content = self
+            .client
+            .repos(owner, repo)
+            .get_content()
+            .path(&workflow.path)
+            .r#ref(&run.head_sha)
+            .send()
+            .await?;
+        let Some(contents) = content.items.first().and_then(|c| c.decoded_content()) else {
+            bail!("Workflow file {} is empty", workflow.path);
+        };
+        let keys = matrix_keys_of_workflow(&contents)
+            .with_context(|| format!("Invalid workflow file {}", workflow.path))?;
+        log::debug!("Matrix keys of the jobs in {}: {keys:?}", workflow.path);
+        for job in failed_jobs {
+            job.set_matrix_keys(&keys);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,291 @@
+//! Scheduled scanning of repositories for failed workflow runs.
+use super::GitHub;
+use crate::{
+    ci_provider::util::Date,
+    commands::{CreateIssueArgs, CreateIssueFromRunArgs, RunFilterArgs, WaitArgs},
+    *,
+};
+use chrono::{DateTime, TimeDelta, Utc};
+use std::{collections::HashSet, str::FromStr};
+
+/// Configuration file of the `monitor` subcommand
+///
+/// # Example
+/// ```toml
+/// # sec min hour day-of-month month day-of-week
+/// schedule = "0 */15 * * * *"
+///
+/// [[repos]]
+/// repo = "luftkode/distro-template"
+/// workflows = ["Yocto Build"]
+/// branch = "main"
+/// label = ["CI scheduled build"]
+/// kind = "yocto"
+/// title = "Scheduled Yocto build failed"
+/// issue_args = ["--recurring-threshold", "5"]
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MonitorConfig {
+    /// Cron expression with a seconds field, e.g. `0 0 6 * * *` for every day at 06:00 UTC
+    schedule: String,
+    /// How many hours back to look for failed runs on the first scan
+    #[serde(default = "default_lookback_hours")]
+    lookback_hours: u32,
+    repos: Vec<MonitoredRepo>,
+}
+
+/// A repository scanned by the `monitor` subcommand
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MonitoredRepo {
+    repo: String,
+    /// Names of the workflows to scan, all workflows if empty
+    #[serde(default)]
+    workflows: Vec<String>,
+    branch: Option<String>,
+    event: Option<String>,
+    /// Labels of the issues, at least one
+    label: LabelList,
+    /// The kind of workflow, `auto` if omitted
+    kind: Option<String>,
+    title: String,
+    /// Additional `create-issue-from-run` options, e.g. `["--retry-flakes", "1"]`
+    #[serde(default)]
+    issue_args: Vec<String>,
+}
+
+/// A single label, as in configurations written before more labels were allowed, or a list
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum LabelList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl LabelList {
+    fn as_slice(&self) -> &[String] {
+        match self {
+            LabelList::One(label) => std::slice::from_ref(label),
+            LabelList::Many(labels) => labels,
+        }
+    }
+}
+
+fn default_lookback_hours() -> u32 {
+    24
+}
+
+/// Parser for the issue options of a monitored repository, reusing the options (and defaults) of
+/// `create-issue-from-run`
+#[derive(Debug, Parser)]
+#[command(no_binary_name = true)]
+struct IssueArgsParser {
+    #[command(flatten)]
+    issue: CreateIssueArgs,
+}
+
+impl MonitorConfig {
+    /// Read and validate a `monitor` configuration file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let config: Self = toml::from_str(&contents)
+            .with_context(|| format!("Invalid monitor configuration in {}", path.display()))?;
+        config.schedule()?;
+        if config.repos.is_empty() {
+            bail!("No repositories to monitor in {}", path.display());
+        }
+        for repo in &config.repos {
+            repo_to_owner_repo_fragments(&repo.repo)?;
+            repo.issue_args()?;
+        }
+        Ok(config)
+    }
+
+    fn schedule(&self) -> Result<cron::Schedule> {
+        cron::Schedule::from_str(&self.schedule)
+            .with_context(|| format!("Invalid cron schedule {:?}", self.schedule))
+    }
+}
+
+impl MonitoredRepo {
+    fn issue_args(&self) -> Result<CreateIssueArgs> {
+        let labels = self
+            .label
+            .as_slice()
+            .iter()
+            .flat_map(|label| ["--label", label.as_str()]);
+        let kind = self.kind.as_deref().map(|kind| ["--kind", kind]);
+        let args = labels
+            .chain(["--title", self.title.as_str()])
+            .chain(kind.into_iter().flatten())
+            .chain(self.issue_args.iter().map(String::as_str));
+        let parsed = IssueArgsParser::try_parse_from(args)
+            .with_context(|| format!("Invalid issue options for {}", self.repo))?;
+        Ok(parsed.issue)
+    }
+}
+
+impl GitHub {
+    /// Scan the repositories in the configuration file at `config_path` on its cron schedule, and
+    /// create an issue for every run that failed since the previous scan.
+    ///
+    /// Runs until interrupted, a failed scan of a repository is logged and retried on the next
+    /// scheduled scan, and a run an issue could not be created for is logged and skipped.
+    pub async fn monitor(&self, config_path: &Path) -> Result<()> {
+        let config = MonitorConfig::from_file(config_path)?;
+        let schedule = config.schedule()?;
+        let first_scan_since = Utc::now() - TimeDelta::hours(i64::from(config.lookback_hours));
+        let mut last_scans = vec![first_scan_since; config.repos.len()];
+        let mut handled_runs = HashSet::new();
+
+        for next_scan in schedule.upcoming(Utc) {
+            log::info!("Next scan at {next_scan}");
+            if let Ok(delay) = (next_scan - Utc::now()).to_std() {
+                tokio::time::sleep(delay).await;
+            }
+            for (repo, last_scan) in config.repos.iter().zip(last_scans.iter_mut()) {
+                let scan_start = Utc::now();
+                match self
+                    .create_issues_for_failed_runs_since(repo, *last_scan, &mut handled_runs)
+                    .await
+                {
+                    Ok(()) => *last_scan = scan_start,
+                    Err(e) => log::error!("Failed to scan {}: {e:?}", repo.repo),
+                }
+            }
+        }
+        bail!(
+            "The cron schedule {:?} has no upcoming times",
+            config.schedule
+        )
+    }
+
+    /// Create an issue for every run in `monitored` that failed after `since` and is not already
+    /// in `handled_runs`
+    async fn create_issues_for_failed_runs_since(
+        &self,
+        monitored: &MonitoredRepo,
+        since: DateTime<Utc>,
+        handled_runs: &mut HashSet<u64>,
+    ) -> Result<()> {
+        let (owner, repo) = repo_to_owner_repo_fragments(&monitored.repo)?;
+        let issue = monitored.issue_args()?;
+        // Runs are filtered by creation date, but a long running workflow can fail well after it was created
+        let filter = RunFilterArgs {
+            workflow: None,
+            branch: monitored.branch.clone(),
+            event: monitored.event.clone(),
+            since: Some(Date::from_timestamp(
+                (since - TimeDelta::days(1)).timestamp(),
+            )?),
+            until: None,
+        };
+        let failed_runs = self.failed_runs(&owner, &repo, &filter, usize::MAX).await?;
+        let new_failed_runs: Vec<_> = failed_runs
+            .iter()
+            .filter(|run| {
+                run.updated_at > since
+                    && !handled_runs.contains(&*run.id)
+                    && (monitored.workflows.is_empty() || monitored.workflows.contains(&run.name))
+            })
+            .collect();
+        for run in new_failed_runs {
+            log::info!(
+                "Run {run_id} of {workflow} in {repo} failed, creating issue",
+                run_id = run.id,
+                workflow = run.name,
+                repo = monitored.repo
+            );
+            let created = self
+                .create_issue_from_run(&CreateIssueFromRunArgs {
+                    repo: monitored.repo.clone(),
+                    run_id: run.id.to_string(),
+                    wait_for_completion: false,
+                    wait: WaitArgs {
+                        poll_interval: super::runs::DEFAULT_POLL_INTERVAL.as_secs(),
+                        wait_timeout: super::runs::DEFAULT_WAIT_TIMEOUT.as_secs(),
+                    },
+                    issue: issue.clone(),
+                })
+                .await;
+            match created {
+                Ok(_) => {
+                    handled_runs.insert(*run.id);
+                }
+                Err(e) => log::error!(
+                    "Failed to create issue for run {run_id} in {repo}: {e:?}",
+                    run_id = run.id,
+                    repo = monitored.repo
+                ),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::WorkflowKind;
+    use pretty_assertions::assert_eq;
+
+    const EXAMPLE_CONFIG: &str = r#"
+schedule = "0 */15 * * * *"
+
+[[repos]]
+repo = "luftkode/distro-template"
+workflows = ["Yocto Build"]
+label = ["CI scheduled build", "yocto"]
+kind = "yocto"
+title = "Scheduled Yocto build failed"
+issue_args = ["--recurring-threshold", "5"]
+"#;
+
+    #[test]
+    fn test_parse_monitor_config() {
+        let config: MonitorConfig = toml::from_str(EXAMPLE_CONFIG).unwrap();
+        assert!(config.schedule().is_ok());
+        assert_eq!(config.lookback_hours, 24);
+        let issue = config.repos[0].issue_args().unwrap();
+        assert_eq!(issue.label, ["CI scheduled build", "yocto"]);
+        assert_eq!(issue.kind, WorkflowKind::Yocto);
+        assert_eq!(issue.recurring_threshold, 5);
+        assert!(issue.no_duplicate);
+    }
+
+    #[test]
+    fn test_parse_monitor_config_with_single_label() {
+        // A configuration written before a repository could have more than one label
+        let config: MonitorConfig = toml::from_str(
+            r#"
+schedule = "0 0 6 * * *"
+
+[[repos]]
+repo = "luftkode/distro-template"
+label = "CI scheduled build"
+title = "Scheduled build failed"
+"#,
+        )
+        .unwrap();
+        let issue = config.repos[0].issue_args().unwrap();
+        assert_eq!(issue.label, ["CI scheduled build"]);
+        assert_eq!(issue.title, "Scheduled build failed");
+    }
+
+    #[test]
+    fn test_kind_defaults_to_auto() {
+        let mut config: MonitorConfig = toml::from_str(EXAMPLE_CONFIG).unwrap();
+        config.repos[0].kind = None;
+        let issue = config.repos[0].issue_args().unwrap();
+        assert_eq!(issue.kind, WorkflowKind::Auto);
+    }
+
+    #[test]
+    fn test_invalid_issue_args() {
+        let mut config: MonitorConfig = toml::from_str(EXAMPLE_CONFIG).unwrap();
+        config.repos[0].kind = Some("not-a-kind".to_owned());
+        assert!(config.repos[0].issue_args().is_err());
+    }
+}
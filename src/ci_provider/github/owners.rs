@@ -0,0 +1,259 @@
+//! Likely owners of a failure, from the CODEOWNERS of the paths in the error summaries or from the
+//! authors of the commits since the last successful run
+use super::GitHub;
+use crate::{commands::AutoAssign, issue::Issue, *};
+use octocrab::models::workflows::Run;
+
+/// Locations GitHub looks for a CODEOWNERS file, in order
+const CODEOWNERS_PATHS: [&str; 3] = [".github/CODEOWNERS", "CODEOWNERS", "docs/CODEOWNERS"];
+
+/// GitHub ignores assignees beyond the first 10
+const MAX_ASSIGNEES: usize = 10;
+
+impl GitHub {
+    /// Assign the likely owners of the failure to the issue. Teams can't be assigned, so code
+    /// owners that are teams are mentioned in a section of the issue instead.
+    pub(crate) async fn auto_assign(
+        &self,
+        owner: &str,
+        repo: &str,
+        run: &Run,
+        strategy: AutoAssign,
+        issue: &mut Issue,
+    ) -> Result<()> {
+        let owners = match strategy {
+            AutoAssign::Codeowners => {
+                let Some(codeowners) = self.codeowners(owner, repo, &run.head_sha).await? else {
+                    log::warn!("No CODEOWNERS file in {owner}/{repo}");
+                    return Ok(());
+                };
+                let logs: Vec<String> = issue.failed_jobs().iter().map(|j| j.full_log()).collect();
+                let mut owners: Vec<String> = Vec::new();
+                for path in logs.iter().flat_map(|log| mentioned_paths(log)) {
+                    for code_owner in codeowners.owners_of(path) {
+                        if !owners.contains(code_owner) {
+                            owners.push(code_owner.to_owned());
+                        }
+                    }
+                }
+                owners
+            }
+            AutoAssign::LastCommitters => {
+                let Some(last_success) = self.last_successful_run(owner, repo, run).await? else {
+                    log::warn!("No successful run before run {run_id}", run_id = run.id);
+                    return Ok(());
+                };
+                let commits = self
+                    .commits_between(owner, repo, &last_success.head_sha, &run.head_sha)
+                    .await?;
+                let mut authors: Vec<String> = Vec::new();
+                // Most recent commit first
+                for author in commits.iter().rev().filter_map(|c| c.author.as_ref()) {
+                    let login = format!("@{}", author.login);
+                    if !author.login.ends_with("[bot]") && !authors.contains(&login) {
+                        authors.push(login);
+                    }
+                }
+                authors
+            }
+        };
+        log::info!("Likely owner(s) of the failure: {owners:?}");
+
+        let (teams, users): (Vec<String>, Vec<String>) =
+            owners.into_iter().partition(|o| o.contains('/'));
+        issue.add_assignees(
+            users
+                .iter()
+                .map(|user| user.trim_start_matches('@'))
+                .take(MAX_ASSIGNEES),
+        );
+        if !teams.is_empty() {
+            issue.add_section("Code owners", teams.join(" "));
+        }
+        Ok(())
+    }
+
+    /// The CODEOWNERS file of the repository at `git_ref`, if there is one
+    async fn codeowners(
+        &self,
+        owner: &str,
+        repo: &str,
+        git_ref: &str,
+    ) -> Result<Option<CodeOwners>> {
+        for path in CODEOWNERS_PATHS {
+            let Ok(content) = self
+                .client
+                .repos(owner, repo)
+                .get_content()
+                .path(path)
+                .r#ref(git_ref)
+                .send()
+                .await
+            else {
+                continue;
+            };
+            if let Some(contents) = content.items.first().and_then(|c| c.decoded_content()) {
+                log::debug!("Using {path} of {owner}/{repo}");
+                return Ok(Some(CodeOwners::parse(&contents)));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// The rules of a CODEOWNERS file, see
+/// <https://docs.github.com/en/repositories/managing-your-repositorys-settings-and-features/customizing-your-repository/about-code-owners>
+#[derive(Debug, Default)]
+pub struct CodeOwners {
+    rules: Vec<CodeOwnersRule>,
+}
+
+#[derive(Debug)]
+struct CodeOwnersRule {
+    pattern: Regex,
+    /// Users like `@octocat` and teams like `@luftkode/yocto`, email addresses are left out as
+    /// they can't be assigned or mentioned
+    owners: Vec<String>,
+}
+
+impl CodeOwners {
+    /// Parse the contents of a CODEOWNERS file, rules with an invalid pattern are skipped
+    pub fn parse(contents: &str) -> Self {
+        let rules = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let pattern = fields.next()?;
+                let owners = fields
+                    .take_while(|f| !f.starts_with('#'))
+                    .filter(|f| f.starts_with('@'))
+                    .map(str::to_owned)
+                    .collect();
+                match codeowners_pattern_regex(pattern) {
+                    Ok(pattern) => Some(CodeOwnersRule { pattern, owners }),
+                    Err(e) => {
+                        log::warn!("Skipping CODEOWNERS pattern {pattern}: {e}");
+                        None
+                    }
+                }
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// The owners of a path mentioned in a log, which can be relative to the repository or
+    /// absolute, e.g. `/home/runner/work/repo/repo/src/main.rs`
+    ///
+    /// The path and every suffix of it is matched, and like in CODEOWNERS the last matching rule
+    /// takes precedence.
+    ///
+    /// # Example
+    /// ```
+    /// # use ci_manager::ci_provider::github::owners::CodeOwners;
+    /// let codeowners = CodeOwners::parse(
+    ///     "*       @luftkode/maintainers\n\
+    ///     /src/   @octocat\n\
+    ///     *.bb    @luftkode/yocto # recipes\n",
+    /// );
+    /// assert_eq!(codeowners.owners_of("/home/runner/work/repo/repo/src/main.rs"), ["@octocat"]);
+    /// assert_eq!(codeowners.owners_of("meta-app/recipes/app/app_1.0.bb"), ["@luftkode/yocto"]);
+    /// assert_eq!(codeowners.owners_of("README.md"), ["@luftkode/maintainers"]);
+    /// ```
+    pub fn owners_of(&self, path: &str) -> &[String] {
+        let path = path.trim_start_matches('/');
+        let suffixes =
+            std::iter::once(path).chain(path.match_indices('/').map(|(i, _)| &path[i + 1..]));
+        suffixes
+            .filter_map(|suffix| {
+                self.rules
+                    .iter()
+                    .rposition(|rule| rule.pattern.is_match(suffix))
+            })
+            .max()
+            .map(|i| self.rules[i].owners.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+/// Convert a CODEOWNERS (gitignore style) pattern to a regex matching the paths it applies to
+fn codeowners_pattern_regex(pattern: &str) -> Result<Regex> {
+    // A slash at the start or in the middle anchors the pattern at the root of the repository
+    let anchored = pattern.trim_end_matches('/').contains('/');
+    let glob = pattern.trim_start_matches('/');
+    let mut regex = String::from(if anchored { "^" } else { "^(?:.*/)?" });
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    // A pattern also applies to everything in the directories it matches
+    regex.push_str(if glob.ends_with('/') {
+        ".*$"
+    } else {
+        "(?:/.*)?$"
+    });
+    Ok(Regex::new(&regex)?)
+}
+
+/// The paths mentioned in a log, URLs excluded
+fn mentioned_paths(log: &str) -> Vec<&str> {
+    static PATH_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"(?:\w+://\S+)|(?P<path>[\w.+-]*(?:/[\w.+-]+)+)").unwrap());
+    let mut paths = Vec::new();
+    for path in PATH_RE
+        .captures_iter(log)
+        .filter_map(|caps| caps.name("path"))
+        .map(|m| m.as_str())
+    {
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_owners_of_paths_mentioned_in_log() {
+        let log = "error[E0425]: cannot find value `x` in this scope\n \
+            --> /home/runner/work/app/app/crates/parser/src/lib.rs:12:5\n\
+            see https://doc.rust-lang.org/error_codes/E0425.html\n\
+            ERROR: Task (/app/meta-app/recipes-core/app/app_1.0.bb:do_compile) failed\n";
+        let codeowners = CodeOwners::parse(
+            "# Everything else\n\
+            * @luftkode/maintainers\n\
+            crates/parser/ @octocat @hubot octo@example.com\n\
+            /meta-app/**/*.bb @luftkode/yocto\n",
+        );
+
+        let owners: Vec<&[String]> = mentioned_paths(log)
+            .into_iter()
+            .map(|path| codeowners.owners_of(path))
+            .collect();
+        assert_eq!(
+            owners,
+            [
+                ["@octocat".to_owned(), "@hubot".to_owned()].as_slice(),
+                ["@luftkode/yocto".to_owned()].as_slice(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,200 @@
+//! Sticky comments with the failure summary on the pull request of a failed run, instead of an
+//! issue, see `--pr-comment`
+//!
+//! Every workflow has its own comment on the pull request. The comment is updated when another
+//! attempt of the same run fails, and the comments about earlier runs of the workflow are collapsed
+//! as outdated when a new run fails.
+use super::{projects::graphql_data, GitHub};
+use crate::{issue::Issue, notify::IssueOutcome, *};
+use octocrab::models::workflows::Run;
+
+/// Length of the issue body that is left for the heading and marker of a pull request comment
+pub const COMMENT_OVERHEAD_LEN: usize = 1024;
+
+/// Hidden marker in a pull request comment naming the workflow and run it is about
+///
+/// # Example
+/// ```
+/// # use ci_manager::ci_provider::github::pr_comment::CommentMarker;
+/// # use pretty_assertions::assert_eq;
+/// let marker = CommentMarker {
+///     workflow_id: 161335,
+///     run_id: 30433642,
+/// };
+/// assert_eq!(
+///     marker.to_string(),
+///     "<!-- ci-manager pr-comment workflow=161335 run=30433642 -->"
+/// );
+/// let body = format!("### Build failed\n\nerror\n\n{marker}");
+/// assert_eq!(CommentMarker::from_comment_body(&body), Some(marker));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommentMarker {
+    pub workflow_id: u64,
+    pub run_id: u64,
+}
+
+impl CommentMarker {
+    /// Parse the marker from a comment, returns `None` if it is not a comment of ci-manager
+    pub fn from_comment_body(body: &str) -> Option<Self> {
+        static MARKER_RE: Lazy<Regex> = Lazy::new(|| {
+            Regex::new(
+                r"<!-- ci-manager pr-comment workflow=(?<WORKFLOW>[0-9]+) run=(?<RUN>[0-9]+) -->",
+            )
+            .unwrap()
+        });
+        let captures = MARKER_RE.captures(body)?;
+        Some(Self {
+            workflow_id: captures.name("WORKFLOW")?.as_str().parse().ok()?,
+            run_id: captures.name("RUN")?.as_str().parse().ok()?,
+        })
+    }
+}
+
+impl fmt::Display for CommentMarker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "<!-- ci-manager pr-comment workflow={} run={} -->",
+            self.workflow_id, self.run_id
+        )
+    }
+}
+
+impl GitHub {
+    /// The number of the pull request `run` was triggered by, `None` if it wasn't triggered by a
+    /// pull request
+    pub async fn pull_request_of_run(
+        &self,
+        owner: &str,
+        repo: &str,
+        run: &Run,
+    ) -> Result<Option<u64>> {
+        if !run.event.starts_with("pull_request") {
+            return Ok(None);
+        }
+        // The pull requests of a run are not listed if they are from a fork, so they are looked up
+        // by the head commit
+        // route: https://docs.github.com/en/rest/commits/commits?apiVersion=2022-11-28#list-pull-requests-associated-with-a-commit
+        let route = format!("/repos/{owner}/{repo}/commits/{}/pulls", run.head_sha);
+        let pulls: Vec<serde_json::Value> = self.client.get(route, None::<&()>).await?;
+        Ok(pulls.first().and_then(|pull| pull["number"].as_u64()))
+    }
+
+    /// Post the body of `issue` as the comment of `run` on pull request `number`, or update it if an
+    /// earlier attempt of the run already did, and collapse the comments about earlier runs of the
+    /// workflow. In a dry run the comment is printed instead.
+    #[tracing::instrument(skip_all, fields(run_id = %run.id, number = number))]
+    pub async fn post_pr_comment(
+        &self,
+        owner: &str,
+        repo: &str,
+        number: u64,
+        run: &Run,
+        issue: &mut Issue,
+    ) -> Result<IssueOutcome> {
+        let marker = CommentMarker {
+            workflow_id: *run.workflow_id,
+            run_id: *run.id,
+        };
+        let issue_body = issue.body();
+        let body = format!(
+            "### {title}\n\n{issue_body}\n\n{marker}",
+            title = issue.title()
+        );
+        let page = self
+            .client
+            .issues(owner, repo)
+            .list_comments(number)
+            .per_page(100)
+            .send()
+            .await?;
+        let mut sticky_comment = None;
+        let mut outdated_comments = Vec::new();
+        for comment in self.client.all_pages(page).await? {
+            let Some(comment_marker) = comment
+                .body
+                .as_deref()
+                .and_then(CommentMarker::from_comment_body)
+            else {
+                continue;
+            };
+            if comment_marker.workflow_id != marker.workflow_id {
+                continue;
+            }
+            if comment_marker.run_id == marker.run_id {
+                sticky_comment = Some(comment);
+            } else {
+                outdated_comments.push(comment);
+            }
+        }
+
+        if Config::global().dry_run() {
+            match &sticky_comment {
+                Some(comment) => println!(
+                    "DRY RUN MODE! Comment {url} would be updated with the body:",
+                    url = comment.html_url
+                ),
+                None => println!(
+                    "DRY RUN MODE! The following comment would be posted on pull request #{number}:"
+                ),
+            }
+            println!("{body}");
+            if !outdated_comments.is_empty() {
+                println!(
+                    "DRY RUN MODE! {num} comment(s) about earlier runs would be collapsed",
+                    num = outdated_comments.len()
+                );
+            }
+            return Ok(IssueOutcome::DryRun);
+        }
+
+        let comment = match sticky_comment {
+            Some(comment) => {
+                log::info!(
+                    "Updating the comment of run {run_id} on pull request #{number}",
+                    run_id = run.id
+                );
+                self.client
+                    .issues(owner, repo)
+                    .update_comment(comment.id, body)
+                    .await?
+            }
+            None => {
+                log::info!("Commenting on pull request #{number}");
+                self.client
+                    .issues(owner, repo)
+                    .create_comment(number, body)
+                    .await?
+            }
+        };
+        for outdated in &outdated_comments {
+            if let Err(e) = self.minimize_comment(&outdated.node_id).await {
+                log::warn!(
+                    "Failed to collapse the outdated comment {url}: {e:#}",
+                    url = outdated.html_url
+                );
+            }
+        }
+        Ok(IssueOutcome::PrComment {
+            url: comment.html_url.to_string(),
+        })
+    }
+
+    /// Collapse a comment as outdated, by its node ID
+    async fn minimize_comment(&self, node_id: &str) -> Result<()> {
+        let response: serde_json::Value = self
+            .client
+            .graphql(&serde_json::json!({
+                "query": "mutation($id: ID!) {
+                    minimizeComment(input: {subjectId: $id, classifier: OUTDATED}) {
+                        minimizedComment { isMinimized }
+                    }
+                }",
+                "variables": { "id": node_id },
+            }))
+            .await?;
+        graphql_data(response, "minimizeComment")?;
+        Ok(())
+    }
+}
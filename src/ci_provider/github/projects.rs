@@ -0,0 +1,110 @@
+//! Adding created issues to GitHub Projects (the GraphQL only `ProjectV2`)
+use super::GitHub;
+use crate::*;
+
+/// A GitHub project of a user or an organization, e.g. `luftkode/3` for
+/// `https://github.com/orgs/luftkode/projects/3`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectRef {
+    pub owner: String,
+    pub number: u64,
+}
+
+impl std::str::FromStr for ProjectRef {
+    type Err = anyhow::Error;
+
+    /// Parse a project like `OWNER/NUMBER`
+    ///
+    /// # Example
+    /// ```
+    /// # use ci_manager::ci_provider::github::projects::ProjectRef;
+    /// let project: ProjectRef = "luftkode/3".parse().unwrap();
+    /// assert_eq!(project.owner, "luftkode");
+    /// assert_eq!(project.number, 3);
+    /// assert!("luftkode".parse::<ProjectRef>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self> {
+        let Some((owner, number)) = s.split_once('/') else {
+            bail!("Expected a project like OWNER/NUMBER, got: {s}");
+        };
+        let Ok(number) = number.parse() else {
+            bail!("Expected a project like OWNER/NUMBER, got: {s}");
+        };
+        if owner.is_empty() {
+            bail!("Expected a project like OWNER/NUMBER, got: {s}");
+        }
+        Ok(Self {
+            owner: owner.to_owned(),
+            number,
+        })
+    }
+}
+
+impl fmt::Display for ProjectRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.owner, self.number)
+    }
+}
+
+impl GitHub {
+    /// Add an issue to a project, by the node ID of the issue
+    pub async fn add_issue_to_project(
+        &self,
+        project: &ProjectRef,
+        issue_node_id: &str,
+    ) -> Result<()> {
+        let project_id = self.project_node_id(project).await?;
+        let response: serde_json::Value = self
+            .client
+            .graphql(&serde_json::json!({
+                "query": "mutation($project: ID!, $content: ID!) {
+                    addProjectV2ItemById(input: {projectId: $project, contentId: $content}) {
+                        item { id }
+                    }
+                }",
+                "variables": { "project": project_id, "content": issue_node_id },
+            }))
+            .await?;
+        graphql_data(response, "addProjectV2ItemById")
+            .with_context(|| format!("Failed to add the issue to project {project}"))?;
+        Ok(())
+    }
+
+    async fn project_node_id(&self, project: &ProjectRef) -> Result<String> {
+        let response: serde_json::Value = self
+            .client
+            .graphql(&serde_json::json!({
+                "query": "query($owner: String!, $number: Int!) {
+                    repositoryOwner(login: $owner) {
+                        ... on ProjectV2Owner { projectV2(number: $number) { id } }
+                    }
+                }",
+                "variables": { "owner": project.owner, "number": project.number },
+            }))
+            .await?;
+        let owner = graphql_data(response, "repositoryOwner")
+            .with_context(|| format!("Failed to look up project {project}"))?;
+        match owner["projectV2"]["id"].as_str() {
+            Some(id) => Ok(id.to_owned()),
+            None => bail!("Project {project} not found"),
+        }
+    }
+}
+
+/// The `field` of the data of a GraphQL response, which reports errors in the response body
+pub(crate) fn graphql_data(
+    mut response: serde_json::Value,
+    field: &str,
+) -> Result<serde_json::Value> {
+    if let Some(errors) = response.get("errors").and_then(|e| e.as_array()) {
+        let messages: Vec<&str> = errors
+            .iter()
+            .filter_map(|e| e["message"].as_str())
+            .collect();
+        bail!("GraphQL error: {}", messages.join(", "));
+    }
+    match response["data"][field].take() {
+        serde_json::Value::Null => bail!("GraphQL response has no {field}"),
+        data => Ok(data),
+    }
+}
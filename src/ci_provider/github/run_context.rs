@@ -0,0 +1,130 @@
+//! The context of a failed run in the issue body: what triggered it, on which branch and commit, by
+//! whom, on which runners and how long it took
+use super::{util::JobErrorLog, GitHub};
+use crate::*;
+use chrono::{DateTime, Utc};
+use octocrab::models::{workflows::Run, Author};
+
+/// Fields of a run that are missing in the [Run] model
+#[derive(Debug, Deserialize)]
+struct RunActors {
+    actor: Option<Author>,
+    triggering_actor: Option<Author>,
+    run_started_at: Option<DateTime<Utc>>,
+}
+
+impl GitHub {
+    /// The [RunContext] of a failed run as a Markdown table
+    pub(crate) async fn run_context_markdown(
+        &self,
+        owner: &str,
+        repo: &str,
+        run: &Run,
+        job_error_logs: &[JobErrorLog],
+    ) -> Result<String> {
+        // route: https://docs.github.com/en/rest/actions/workflow-runs?apiVersion=2022-11-28#get-a-workflow-run
+        let actors: RunActors = self
+            .client
+            .get(
+                format!(
+                    "/repos/{owner}/{repo}/actions/runs/{run_id}",
+                    run_id = run.id
+                ),
+                None::<&()>,
+            )
+            .await?;
+        let started_at = actors.run_started_at.unwrap_or(run.created_at);
+        let mut runners: Vec<String> = Vec::new();
+        for job in job_error_logs {
+            let runner = match (job.runner_name.as_deref(), job.runner_labels.is_empty()) {
+                (Some(name), true) => format!("`{name}`"),
+                (Some(name), false) => format!("`{name}` ({})", job.runner_labels.join(", ")),
+                (None, false) => job.runner_labels.join(", "),
+                (None, true) => continue,
+            };
+            if !runners.contains(&runner) {
+                runners.push(runner);
+            }
+        }
+        let context = RunContext {
+            event: &run.event,
+            branch: &run.head_branch,
+            sha: &run.head_sha,
+            commit_url: format!("https://github.com/{owner}/{repo}/commit/{}", run.head_sha),
+            actor: actors.actor.as_ref().map(|a| a.login.as_str()),
+            triggering_actor: actors.triggering_actor.as_ref().map(|a| a.login.as_str()),
+            runners,
+            duration_seconds: (run.updated_at - started_at).num_seconds() as f64,
+        };
+        Ok(context.to_markdown())
+    }
+}
+
+/// What triggered a run, on which branch and commit, by whom, on which runners and how long it
+/// took
+#[derive(Debug)]
+pub struct RunContext<'a> {
+    /// The event that triggered the run, e.g. `push` or `schedule`
+    pub event: &'a str,
+    pub branch: &'a str,
+    pub sha: &'a str,
+    pub commit_url: String,
+    /// The user that triggered the first attempt of the run
+    pub actor: Option<&'a str>,
+    /// The user that triggered the latest attempt of the run, if it was re-run
+    pub triggering_actor: Option<&'a str>,
+    /// The runners of the failed jobs with their labels
+    pub runners: Vec<String>,
+    pub duration_seconds: f64,
+}
+
+impl RunContext<'_> {
+    /// The context as a Markdown table
+    ///
+    /// # Example
+    /// ```
+    /// # use ci_manager::ci_provider::github::run_context::RunContext;
+    /// # use pretty_assertions::assert_eq;
+    /// let context = RunContext {
+    ///     event: "schedule",
+    ///     branch: "main",
+    ///     sha: "8c7f4e2b1d9a6c3e5f0b8a7d6c5e4f3a2b1c0d9e",
+    ///     commit_url: "https://github.com/luftkode/distro-template/commit/8c7f4e2b1d9a6c3e5f0b8a7d6c5e4f3a2b1c0d9e".to_owned(),
+    ///     actor: Some("octocat"),
+    ///     triggering_actor: Some("hubot"),
+    ///     runners: vec!["`runner-7` (self-hosted, yocto)".to_owned()],
+    ///     duration_seconds: 3723.0,
+    /// };
+    /// assert_eq!(
+    ///     context.to_markdown(),
+    ///     "| Event | Branch | Commit | Actor | Runner | Duration |\n\
+    ///     |-------|--------|--------|-------|--------|----------|\n\
+    ///     | `schedule` | `main` | [`8c7f4e2`](https://github.com/luftkode/distro-template/commit/8c7f4e2b1d9a6c3e5f0b8a7d6c5e4f3a2b1c0d9e) \
+    ///     | @octocat (re-run by @hubot) | `runner-7` (self-hosted, yocto) | 1h 02m 03s |\n"
+    /// );
+    /// ```
+    pub fn to_markdown(&self) -> String {
+        let actor = match (self.actor, self.triggering_actor) {
+            (Some(actor), Some(triggering)) if actor != triggering => {
+                format!("@{actor} (re-run by @{triggering})")
+            }
+            (Some(actor), _) | (None, Some(actor)) => format!("@{actor}"),
+            (None, None) => "-".to_owned(),
+        };
+        let runners = if self.runners.is_empty() {
+            "-".to_owned()
+        } else {
+            self.runners.join("<br>")
+        };
+        format!(
+            "| Event | Branch | Commit | Actor | Runner | Duration |\n\
+            |-------|--------|--------|-------|--------|----------|\n\
+            | `{event}` | `{branch}` | [`{short_sha}`]({commit_url}) | {actor} | {runners} | {duration} |\n",
+            event = self.event,
+            branch = self.branch,
+            short_sha = &self.sha[..self.sha.len().min(7)],
+            commit_url = self.commit_url,
+            duration = format_duration(self.duration_seconds),
+        )
+    }
+}
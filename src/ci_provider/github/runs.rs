@@ -0,0 +1,860 @@
+//! Subcommands and queries that operate on the workflow runs of a repository.
+use super::export::print_csv;
+use super::util::{failed_jobs_from_job_error_logs, repo_url_to_run_url};
+use super::GitHub;
+use crate::{
+    ci_provider::util::Date,
+    commands::{
+        CreateIssueFromRunArgs, OutputFormat, ReportFormat, RunFilterArgs, WaitArgs, WatchRunThen,
+        WorkflowKind,
+    },
+    err_parse::FailureClass,
+    issue::{junit::junit_xml, IssueBody},
+    *,
+};
+use octocrab::models::{commits::Commit, workflows::Run, RunId};
+use std::{cmp::Reverse, collections::HashSet, io::Write, time::Duration};
+
+/// Default interval between polls of a workflow run's status
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// Default maximum time to wait for a workflow run to complete
+pub const DEFAULT_WAIT_TIMEOUT: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// The status of a workflow run and its attempt, which [Run] leaves out
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct RunAttempt {
+    pub status: String,
+    pub conclusion: Option<String>,
+    pub run_attempt: u32,
+}
+
+/// How far the re-run of the failed jobs of a run has come
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RerunProgress {
+    /// GitHub hasn't queued the re-run yet, the run is still the completed previous attempt
+    NotStarted,
+    Running,
+    Completed,
+}
+
+impl RerunProgress {
+    /// The progress of the re-run of a run that was at `previous_attempt` when it was requested
+    fn of(run: &RunAttempt, previous_attempt: u32) -> Self {
+        if run.status != "completed" {
+            Self::Running
+        } else if run.run_attempt > previous_attempt {
+            Self::Completed
+        } else {
+            Self::NotStarted
+        }
+    }
+}
+
+/// Summary of a failed workflow run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FailedRun {
+    pub id: u64,
+    pub workflow: String,
+    pub branch: String,
+    pub event: String,
+    pub created_at: String,
+    pub url: String,
+}
+
+impl From<&Run> for FailedRun {
+    fn from(run: &Run) -> Self {
+        Self {
+            id: *run.id,
+            workflow: run.name.clone(),
+            branch: run.head_branch.clone(),
+            event: run.event.clone(),
+            created_at: run.created_at.to_rfc3339(),
+            url: run.html_url.to_string(),
+        }
+    }
+}
+
+impl GitHub {
+    /// Print the failed runs of a repository matching the filter
+    pub async fn list_failed_runs(
+        &self,
+        repo: &str,
+        filter: &RunFilterArgs,
+        limit: usize,
+        format: OutputFormat,
+    ) -> Result<()> {
+        let (owner, repo) = repo_to_owner_repo_fragments(repo)?;
+        let runs = self.failed_runs(&owner, &repo, filter, limit).await?;
+        log::info!("Found {} failed run(s)", runs.len());
+        let failed_runs: Vec<FailedRun> = runs.iter().map(FailedRun::from).collect();
+
+        match format {
+            OutputFormat::Table => {
+                pipe_println!(
+                    "{:<12} {:<25} {:<30} {:<20} {:<12} URL",
+                    "RUN ID",
+                    "CREATED",
+                    "WORKFLOW",
+                    "BRANCH",
+                    "EVENT"
+                )?;
+                for run in &failed_runs {
+                    pipe_println!(
+                        "{:<12} {:<25} {:<30} {:<20} {:<12} {}",
+                        run.id,
+                        run.created_at,
+                        run.workflow,
+                        run.branch,
+                        run.event,
+                        run.url
+                    )?;
+                }
+            }
+            OutputFormat::Json => {
+                pipe_println!("{}", serde_json::to_string_pretty(&failed_runs)?)?;
+            }
+            OutputFormat::Csv => {
+                let runs: Vec<&Run> = runs.iter().collect();
+                let rows = self
+                    .failed_job_rows(&owner, &repo, &runs, WorkflowKind::Auto)
+                    .await?;
+                print_csv(&rows)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Wait for a workflow run to complete and print its conclusion, then optionally create an
+    /// issue if the run failed
+    pub async fn watch_run(
+        &self,
+        repo: &str,
+        run_id: &str,
+        wait: &WaitArgs,
+        then: Option<&WatchRunThen>,
+    ) -> Result<()> {
+        let (owner, repo_name) = repo_to_owner_repo_fragments(repo)?;
+        let run = self
+            .wait_for_run_completion(
+                &owner,
+                &repo_name,
+                RunId(run_id.parse()?),
+                wait.poll_interval(),
+                wait.wait_timeout(),
+            )
+            .await?;
+        let conclusion = run.conclusion.as_deref().unwrap_or("none");
+        log::info!("Run {run_id} completed with conclusion: {conclusion}");
+        pipe_println!("{conclusion}")?;
+
+        if let Some(WatchRunThen::CreateIssue(issue)) = then {
+            if conclusion == "failure" {
+                let report = self
+                    .create_issue_from_run(&CreateIssueFromRunArgs {
+                        repo: repo.to_owned(),
+                        run_id: run_id.to_owned(),
+                        // The run is already completed
+                        wait_for_completion: false,
+                        wait: wait.clone(),
+                        issue: issue.clone(),
+                    })
+                    .await?;
+                exit_code::set_outcome(exit_code::Exit::of_report(report.as_ref()));
+            } else {
+                log::info!("Run {run_id} did not fail, no issue will be created");
+            }
+        }
+        Ok(())
+    }
+
+    /// Write a Markdown summary of the failures in a workflow run, formatted like the body of the
+    /// issue `create-issue-from-run` would create.
+    ///
+    /// The summary is printed to stdout, or appended to the file in `$GITHUB_STEP_SUMMARY` if
+    /// `step_summary` is set.
+    pub async fn summarize_run(
+        &self,
+        repo: &str,
+        run_id: &str,
+        kind: WorkflowKind,
+        step_summary: bool,
+        report: Option<(ReportFormat, &Path)>,
+    ) -> Result<()> {
+        let (owner, repo) = repo_to_owner_repo_fragments(repo)?;
+        let mut run_url = repo_url_to_run_url(&format!("github.com/{owner}/{repo}"), run_id);
+        ensure_https_prefix(&mut run_url);
+        let job_error_logs = self
+            .failed_job_error_logs(&owner, &repo, RunId(run_id.parse()?))
+            .await?;
+
+        let failed_jobs = failed_jobs_from_job_error_logs(&job_error_logs, &run_url, kind);
+        if let Some((ReportFormat::Junit, path)) = report {
+            fs::write(path, junit_xml(run_id, &run_url, &failed_jobs))
+                .with_context(|| format!("Failed to write the JUnit report: {path:?}"))?;
+            log::info!("Wrote the JUnit report to {path:?}");
+        }
+        let summary = if failed_jobs.is_empty() {
+            format!("**Run ID**: {run_id} [LINK TO RUN]({run_url})\n\nNo jobs failed")
+        } else {
+            IssueBody::new(run_id.to_owned(), run_url, failed_jobs).to_markdown_string()
+        };
+
+        if step_summary {
+            let path = env::var("GITHUB_STEP_SUMMARY").context(
+                "`--step-summary` requires the GITHUB_STEP_SUMMARY environment variable to be set",
+            )?;
+            log::info!("Appending run summary to {path}");
+            let mut file = fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .with_context(|| format!("Failed to open step summary file: {path}"))?;
+            writeln!(file, "{summary}")?;
+        } else {
+            pipe_println!("{summary}")?;
+        }
+        Ok(())
+    }
+
+    /// Re-run the failed jobs of a completed workflow run.
+    ///
+    /// If `if_classified_as` is set, the failed jobs are only re-run if the failure of the run is
+    /// classified as that [FailureClass].
+    pub async fn rerun_failed_jobs(
+        &self,
+        repo: &str,
+        run_id: &str,
+        if_classified_as: Option<FailureClass>,
+    ) -> Result<()> {
+        let (owner, repo) = repo_to_owner_repo_fragments(repo)?;
+        let run_id = RunId(run_id.parse()?);
+        // GitHub only re-runs the failed jobs of completed runs
+        let run = self.workflow_run(&owner, &repo, run_id).await?;
+        if run.status != "completed" {
+            bail!(
+                "Run {run_id} is still in progress with status: {status}, its failed jobs can only be re-run once it has completed",
+                status = run.status
+            );
+        }
+
+        if let Some(expected_class) = if_classified_as {
+            let job_error_logs = self.failed_job_error_logs(&owner, &repo, run_id).await?;
+            let logs: Vec<(String, WorkflowKind)> = job_error_logs
+                .iter()
+                .map(|j| (j.logs_as_str(), WorkflowKind::Auto.for_job(&j.job_name)))
+                .collect();
+            let class =
+                FailureClass::classify_all(logs.iter().map(|(log, kind)| (log.as_str(), *kind)));
+            log::info!("Failure of run {run_id} is classified as: {class}");
+            if class != expected_class {
+                log::warn!(
+                    "Failure is not classified as {expected_class}, not re-running failed jobs"
+                );
+                return Ok(());
+            }
+        }
+
+        if Config::global().dry_run() {
+            println!("DRY RUN MODE! The failed jobs of run {run_id} would be re-run");
+            return Ok(());
+        }
+        log::info!("Re-running failed jobs of run {run_id}");
+        self.request_rerun_failed_jobs(&owner, &repo, run_id).await
+    }
+
+    /// Cancel queued and in-progress runs that started more than `older_than_minutes` ago, or that
+    /// are superseded by a newer queued or in-progress run of the same workflow on the same branch
+    pub async fn cancel_stale_runs(
+        &self,
+        repo: &str,
+        workflow: Option<&str>,
+        older_than_minutes: Option<u64>,
+        superseded: bool,
+    ) -> Result<()> {
+        if older_than_minutes.is_none() && !superseded {
+            bail!("Nothing to cancel, specify `--older-than` and/or `--superseded`");
+        }
+        let (owner, repo) = repo_to_owner_repo_fragments(repo)?;
+        let mut active_runs = Vec::new();
+        for status in ["queued", "in_progress"] {
+            active_runs.extend(
+                self.runs_with_status(&owner, &repo, workflow, status)
+                    .await?,
+            );
+        }
+        log::info!("Found {} queued or in-progress run(s)", active_runs.len());
+
+        let now = time::OffsetDateTime::now_utc().unix_timestamp();
+        let runs: Vec<ActiveRun> = active_runs.iter().map(ActiveRun::from).collect();
+        let mut num_cancelled = 0;
+        for (index, reason) in stale_runs(&runs, now, older_than_minutes, superseded) {
+            let run = &active_runs[index];
+            if Config::global().dry_run() {
+                println!(
+                    "DRY RUN MODE! Run {id} of {workflow} on {branch} would be cancelled, it is {reason}",
+                    id = run.id,
+                    workflow = run.name,
+                    branch = run.head_branch
+                );
+                continue;
+            }
+            log::info!(
+                "Cancelling run {id} of {workflow} on {branch}, it is {reason}",
+                id = run.id,
+                workflow = run.name,
+                branch = run.head_branch
+            );
+            self.client
+                .actions()
+                .cancel_workflow_run(&owner, &repo, run.id)
+                .await?;
+            num_cancelled += 1;
+        }
+        log::info!("Cancelled {num_cancelled} run(s)");
+        Ok(())
+    }
+
+    /// Trigger GitHub's "Re-run failed jobs" for a workflow run
+    pub(crate) async fn request_rerun_failed_jobs(
+        &self,
+        owner: &str,
+        repo: &str,
+        run_id: RunId,
+    ) -> Result<()> {
+        // route: https://docs.github.com/en/rest/actions/workflow-runs?apiVersion=2022-11-28#re-run-failed-jobs-from-a-workflow-run
+        let route = format!("/repos/{owner}/{repo}/actions/runs/{run_id}/rerun-failed-jobs");
+        let response = self.client._post(route.as_str(), None::<&()>).await?;
+        octocrab::map_github_error(response).await?;
+        Ok(())
+    }
+
+    /// The status and attempt of a workflow run
+    pub(crate) async fn run_attempt(
+        &self,
+        owner: &str,
+        repo: &str,
+        run_id: RunId,
+    ) -> Result<RunAttempt> {
+        // route: https://docs.github.com/en/rest/actions/workflow-runs?apiVersion=2022-11-28#get-a-workflow-run
+        let route = format!("/repos/{owner}/{repo}/actions/runs/{run_id}");
+        let run = self.client.get(route, None::<&()>).await?;
+        Ok(run)
+    }
+
+    /// Re-run the failed jobs of a completed workflow run, poll it every `poll_interval` until the
+    /// new attempt is completed, and return it. Until GitHub queues the re-run, the run is still
+    /// the completed previous attempt, which is not mistaken for the result of the re-run.
+    ///
+    /// # Errors
+    /// Returns an error if the re-run is not completed within `timeout`
+    pub(crate) async fn rerun_failed_jobs_to_completion(
+        &self,
+        owner: &str,
+        repo: &str,
+        run_id: RunId,
+        poll_interval: Duration,
+        timeout: Duration,
+    ) -> Result<RunAttempt> {
+        let previous_attempt = self.run_attempt(owner, repo, run_id).await?.run_attempt;
+        self.request_rerun_failed_jobs(owner, repo, run_id).await?;
+        let start = std::time::Instant::now();
+        loop {
+            tokio::time::sleep(poll_interval).await;
+            let run = self.run_attempt(owner, repo, run_id).await?;
+            let progress = RerunProgress::of(&run, previous_attempt);
+            if progress == RerunProgress::Completed {
+                return Ok(run);
+            }
+            if start.elapsed() >= timeout {
+                bail!(
+                    "Timed out after {timeout:?} waiting for the re-run of run {run_id} to complete, it has status: {}",
+                    run.status
+                );
+            }
+            match progress {
+                RerunProgress::NotStarted => log::info!(
+                    "The re-run of run {run_id} hasn't started yet, checking again in {poll_interval:?}"
+                ),
+                _ => log::info!(
+                    "Attempt {attempt} of run {run_id} has status: {status}, checking again in {poll_interval:?}",
+                    attempt = run.run_attempt,
+                    status = run.status
+                ),
+            }
+        }
+    }
+
+    /// Poll a workflow run every `poll_interval` until it is completed, and return the completed run.
+    ///
+    /// # Errors
+    /// Returns an error if the run is not completed within `timeout`
+    pub async fn wait_for_run_completion(
+        &self,
+        owner: &str,
+        repo: &str,
+        run_id: RunId,
+        poll_interval: Duration,
+        timeout: Duration,
+    ) -> Result<Run> {
+        let start = std::time::Instant::now();
+        loop {
+            let run = self.workflow_run(owner, repo, run_id).await?;
+            if run.status == "completed" {
+                return Ok(run);
+            }
+            if start.elapsed() >= timeout {
+                bail!(
+                    "Timed out after {timeout:?} waiting for run {run_id} to complete, it has status: {}",
+                    run.status
+                );
+            }
+            log::info!(
+                "Run {run_id} has status: {status}, checking again in {poll_interval:?}",
+                status = run.status
+            );
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+
+    /// The most recent successful run of the same workflow and branch as `run` that started before it
+    pub(crate) async fn last_successful_run(
+        &self,
+        owner: &str,
+        repo: &str,
+        run: &Run,
+    ) -> Result<Option<Run>> {
+        let runs = self
+            .client
+            .workflows(owner, repo)
+            .list_runs(run.workflow_id.to_string())
+            .branch(run.head_branch.as_str())
+            .status("success")
+            .per_page(100u8)
+            .send()
+            .await?;
+        Ok(runs
+            .items
+            .into_iter()
+            .find(|success| success.created_at < run.created_at))
+    }
+
+    /// The commits after `base` up to and including `head`, oldest first
+    pub(crate) async fn commits_between(
+        &self,
+        owner: &str,
+        repo: &str,
+        base: &str,
+        head: &str,
+    ) -> Result<Vec<Commit>> {
+        const PER_PAGE: u8 = 100;
+        let commits_handler = self.client.commits(owner, repo);
+        let mut commits = Vec::new();
+        for page in 1u32.. {
+            let comparison = commits_handler
+                .compare(base, head)
+                .per_page(PER_PAGE)
+                .page(page)
+                .send()
+                .await?;
+            let num_commits = comparison.commits.len();
+            commits.extend(comparison.commits);
+            if num_commits < usize::from(PER_PAGE) {
+                break;
+            }
+        }
+        Ok(commits)
+    }
+
+    /// Markdown list of the commits since the last successful run of the same workflow and branch
+    /// as `run`, with a link to the comparison on GitHub.
+    ///
+    /// Returns `None` if there is no earlier successful run.
+    pub(crate) async fn commits_since_last_success_markdown(
+        &self,
+        owner: &str,
+        repo: &str,
+        run: &Run,
+    ) -> Result<Option<String>> {
+        /// Maximum number of commits to list, the rest can be seen in the comparison on GitHub
+        const MAX_LISTED_COMMITS: usize = 20;
+        let Some(last_success) = self.last_successful_run(owner, repo, run).await? else {
+            log::warn!(
+                "No successful run of workflow {workflow} on branch {branch} before run {run_id}",
+                workflow = run.name,
+                branch = run.head_branch,
+                run_id = run.id
+            );
+            return Ok(None);
+        };
+        let commits = self
+            .commits_between(owner, repo, &last_success.head_sha, &run.head_sha)
+            .await?;
+        log::info!(
+            "{num_commits} commit(s) since the last successful run {last_success_id}",
+            num_commits = commits.len(),
+            last_success_id = last_success.id
+        );
+
+        let mut markdown = format!(
+            "Last successful run: {last_success_url}\n\
+            Compare: https://github.com/{owner}/{repo}/compare/{base}...{head}\n",
+            last_success_url = last_success.html_url,
+            base = last_success.head_sha,
+            head = run.head_sha
+        );
+        // Most recent commit first
+        for commit in commits.iter().rev().take(MAX_LISTED_COMMITS) {
+            let title = commit.commit.message.lines().next().unwrap_or_default();
+            let author = commit
+                .author
+                .as_ref()
+                .map_or(String::new(), |author| format!(" (@{})", author.login));
+            markdown.push_str(&format!(
+                "- [`{short_sha}`]({url}) {title}{author}\n",
+                short_sha = &commit.sha[..commit.sha.len().min(7)],
+                url = commit.html_url
+            ));
+        }
+        if commits.len() > MAX_LISTED_COMMITS {
+            markdown.push_str(&format!(
+                "- ... and {} more\n",
+                commits.len() - MAX_LISTED_COMMITS
+            ));
+        }
+        Ok(Some(markdown))
+    }
+
+    /// Get all runs with `status`, of `workflow` if given or of every workflow otherwise
+    async fn runs_with_status(
+        &self,
+        owner: &str,
+        repo: &str,
+        workflow: Option<&str>,
+        status: &str,
+    ) -> Result<Vec<Run>> {
+        const PER_PAGE: u8 = 100;
+        let workflows = self.client.workflows(owner, repo);
+        let mut runs = Vec::new();
+        for page in 1u32.. {
+            let request = match workflow {
+                Some(workflow) => workflows.list_runs(workflow),
+                None => workflows.list_all_runs(),
+            };
+            let page_runs = request
+                .status(status)
+                .per_page(PER_PAGE)
+                .page(page)
+                .send()
+                .await?
+                .items;
+            let num_runs = page_runs.len();
+            runs.extend(page_runs);
+            if num_runs < usize::from(PER_PAGE) {
+                break;
+            }
+        }
+        Ok(runs)
+    }
+
+    /// Get up to `limit` failed runs matching the filter, most recent first
+    pub async fn failed_runs(
+        &self,
+        owner: &str,
+        repo: &str,
+        filter: &RunFilterArgs,
+        limit: usize,
+    ) -> Result<Vec<Run>> {
+        const PER_PAGE: u8 = 100;
+        let workflows = self.client.workflows(owner, repo);
+        let mut failed_runs = Vec::new();
+        for page in 1u32.. {
+            let mut request = workflows
+                .list_all_runs()
+                .status("failure")
+                .per_page(PER_PAGE)
+                .page(page);
+            if let Some(branch) = &filter.branch {
+                request = request.branch(branch.as_str());
+            }
+            if let Some(event) = &filter.event {
+                request = request.event(event.as_str());
+            }
+            let runs = request.send().await?.items;
+            let num_runs = runs.len();
+
+            for run in runs {
+                let created = Date::from_timestamp(run.created_at.timestamp())?;
+                match filter_run(filter, &run.name, &created) {
+                    RunFilterMatch::Match => (),
+                    RunFilterMatch::Skip => continue,
+                    RunFilterMatch::Done => return Ok(failed_runs),
+                }
+                failed_runs.push(run);
+                if failed_runs.len() >= limit {
+                    return Ok(failed_runs);
+                }
+            }
+
+            if num_runs < usize::from(PER_PAGE) {
+                break;
+            }
+        }
+        Ok(failed_runs)
+    }
+}
+
+/// How a run relates to a [RunFilterArgs], the filters that the API doesn't apply itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RunFilterMatch {
+    Match,
+    Skip,
+    /// Created before `--since`, and as runs are sorted by creation date, newest first, so are all
+    /// the runs after it
+    Done,
+}
+
+/// The fields of a queued or in-progress run that decide whether it is stale
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ActiveRun<'r> {
+    id: u64,
+    workflow_id: u64,
+    branch: &'r str,
+    /// Unix timestamp of when the run was created
+    created_at: i64,
+}
+
+impl<'r> From<&'r Run> for ActiveRun<'r> {
+    fn from(run: &'r Run) -> Self {
+        Self {
+            id: run.id.0,
+            workflow_id: run.workflow_id.0,
+            branch: &run.head_branch,
+            created_at: run.created_at.timestamp(),
+        }
+    }
+}
+
+/// Why a run is cancelled by `cancel-stale-runs`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StaleReason {
+    /// A newer run of the same workflow on the same branch is queued or in progress
+    Superseded,
+    /// The run was created `age_minutes` ago
+    Old { age_minutes: i64 },
+}
+
+impl fmt::Display for StaleReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Superseded => write!(f, "superseded by a newer run on the same branch"),
+            Self::Old { age_minutes } => write!(f, "started {age_minutes} minutes ago"),
+        }
+    }
+}
+
+/// Select the runs to cancel at the Unix timestamp `now`: those created at least
+/// `older_than_minutes` ago and, if `superseded` is set, all but the newest run of each workflow
+/// and branch. The newest of runs created at the same time is the one with the highest ID.
+///
+/// Returns the indices of the selected runs in `runs` with the reason, newest run first.
+fn stale_runs(
+    runs: &[ActiveRun],
+    now: i64,
+    older_than_minutes: Option<u64>,
+    superseded: bool,
+) -> Vec<(usize, StaleReason)> {
+    // Newest first, so the first run seen of a workflow and branch is the one superseding the rest
+    let mut newest_first: Vec<usize> = (0..runs.len()).collect();
+    newest_first.sort_by_key(|&index| Reverse((runs[index].created_at, runs[index].id)));
+
+    let mut newest_runs = HashSet::new();
+    newest_first
+        .into_iter()
+        .filter_map(|index| {
+            let run = &runs[index];
+            let is_newest = newest_runs.insert((run.workflow_id, run.branch));
+            let age_minutes = (now - run.created_at) / 60;
+            if superseded && !is_newest {
+                Some((index, StaleReason::Superseded))
+            } else if older_than_minutes.is_some_and(|minutes| age_minutes >= minutes as i64) {
+                Some((index, StaleReason::Old { age_minutes }))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Match a run of the workflow `workflow` created on `created` against the filter
+fn filter_run(filter: &RunFilterArgs, workflow: &str, created: &Date) -> RunFilterMatch {
+    if filter.since.as_ref().is_some_and(|since| created < since) {
+        return RunFilterMatch::Done;
+    }
+    if filter.until.as_ref().is_some_and(|until| created > until)
+        || filter.workflow.as_deref().is_some_and(|w| w != workflow)
+    {
+        return RunFilterMatch::Skip;
+    }
+    RunFilterMatch::Match
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn filter(workflow: Option<&str>, since: Option<&str>, until: Option<&str>) -> RunFilterArgs {
+        RunFilterArgs {
+            workflow: workflow.map(str::to_owned),
+            branch: None,
+            event: None,
+            since: since.map(|date| date.parse().unwrap()),
+            until: until.map(|date| date.parse().unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_filter_run_without_filters_matches() {
+        let created = "2024-03-01".parse().unwrap();
+        assert_eq!(
+            filter_run(&filter(None, None, None), "Build", &created),
+            RunFilterMatch::Match
+        );
+    }
+
+    #[test]
+    fn test_filter_run_by_workflow() {
+        let filter = filter(Some("Build"), None, None);
+        let created = "2024-03-01".parse().unwrap();
+        assert_eq!(
+            filter_run(&filter, "Build", &created),
+            RunFilterMatch::Match
+        );
+        assert_eq!(filter_run(&filter, "Test", &created), RunFilterMatch::Skip);
+    }
+
+    #[test]
+    fn test_filter_run_by_date_range() {
+        let filter = filter(None, Some("2024-03-01"), Some("2024-03-10"));
+        let on = |date: &str| filter_run(&filter, "Build", &date.parse().unwrap());
+        assert_eq!(on("2024-03-11"), RunFilterMatch::Skip);
+        assert_eq!(on("2024-03-10"), RunFilterMatch::Match);
+        assert_eq!(on("2024-03-01"), RunFilterMatch::Match);
+        assert_eq!(on("2024-02-29"), RunFilterMatch::Done);
+    }
+
+    fn run_attempt(status: &str, conclusion: Option<&str>, run_attempt: u32) -> RunAttempt {
+        // The fields of a response of the API
+        serde_json::from_value(serde_json::json!({
+            "id": 7945016152u64,
+            "status": status,
+            "conclusion": conclusion,
+            "run_attempt": run_attempt,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rerun_not_started_while_previous_attempt_is_completed() {
+        let run = run_attempt("completed", Some("failure"), 1);
+        assert_eq!(RerunProgress::of(&run, 1), RerunProgress::NotStarted);
+    }
+
+    #[test]
+    fn test_rerun_running_once_queued() {
+        // GitHub may queue the run before it counts the new attempt
+        for attempt in [1, 2] {
+            let run = run_attempt("queued", None, attempt);
+            assert_eq!(RerunProgress::of(&run, 1), RerunProgress::Running);
+        }
+        let run = run_attempt("in_progress", None, 2);
+        assert_eq!(RerunProgress::of(&run, 1), RerunProgress::Running);
+    }
+
+    #[test]
+    fn test_rerun_completed_in_new_attempt() {
+        let run = run_attempt("completed", Some("success"), 2);
+        assert_eq!(RerunProgress::of(&run, 1), RerunProgress::Completed);
+        assert_eq!(run.conclusion.as_deref(), Some("success"));
+        // The re-run of a run that was already re-run
+        let run = run_attempt("completed", Some("failure"), 3);
+        assert_eq!(RerunProgress::of(&run, 2), RerunProgress::Completed);
+        assert_eq!(RerunProgress::of(&run, 3), RerunProgress::NotStarted);
+    }
+
+    fn active_run(id: u64, branch: &str, created_at: i64) -> ActiveRun<'_> {
+        ActiveRun {
+            id,
+            workflow_id: 1,
+            branch,
+            created_at,
+        }
+    }
+
+    #[test]
+    fn test_stale_runs_superseded_keeps_newest_per_branch() {
+        let runs = [
+            active_run(1, "main", 0),
+            active_run(2, "main", 60),
+            active_run(3, "feature", 30),
+            active_run(4, "feature", 90),
+        ];
+        assert_eq!(
+            stale_runs(&runs, 120, None, true),
+            vec![(2, StaleReason::Superseded), (0, StaleReason::Superseded)]
+        );
+    }
+
+    #[test]
+    fn test_stale_runs_different_branches_are_not_superseded() {
+        let runs = [active_run(1, "main", 0), active_run(2, "feature", 60)];
+        assert_eq!(stale_runs(&runs, 120, None, true), vec![]);
+    }
+
+    #[test]
+    fn test_stale_runs_different_workflows_are_not_superseded() {
+        let runs = [
+            active_run(1, "main", 0),
+            ActiveRun {
+                workflow_id: 2,
+                ..active_run(2, "main", 60)
+            },
+        ];
+        assert_eq!(stale_runs(&runs, 120, None, true), vec![]);
+    }
+
+    #[test]
+    fn test_stale_runs_tie_keeps_highest_id() {
+        let runs = [active_run(7, "main", 60), active_run(8, "main", 60)];
+        assert_eq!(
+            stale_runs(&runs, 120, None, true),
+            vec![(0, StaleReason::Superseded)]
+        );
+        let reversed = [runs[1], runs[0]];
+        assert_eq!(
+            stale_runs(&reversed, 120, None, true),
+            vec![(1, StaleReason::Superseded)]
+        );
+    }
+
+    #[test]
+    fn test_stale_runs_older_than() {
+        let runs = [active_run(1, "main", 0), active_run(2, "feature", 3000)];
+        assert_eq!(
+            stale_runs(&runs, 3600, Some(60), false),
+            vec![(0, StaleReason::Old { age_minutes: 60 })]
+        );
+    }
+
+    #[test]
+    fn test_stale_runs_newest_run_cancelled_only_if_old() {
+        let runs = [active_run(1, "main", 0), active_run(2, "main", 60)];
+        assert_eq!(
+            stale_runs(&runs, 7200, Some(60), true),
+            vec![
+                (1, StaleReason::Old { age_minutes: 119 }),
+                (0, StaleReason::Superseded)
+            ]
+        );
+    }
+}
@@ -0,0 +1,103 @@
+//! Scanning of every repository in an organization for failed workflow runs.
+use super::GitHub;
+use crate::{
+    ci_provider::util::Date,
+    commands::{CreateIssueArgs, CreateIssueFromRunArgs, RunFilterArgs, WaitArgs},
+    *,
+};
+use octocrab::models::Repository;
+
+impl GitHub {
+    /// Create an issue for every run triggered by `event` that failed since `since` in the
+    /// repositories of `org`, optionally only the repositories with the topic `topic`
+    ///
+    /// # Errors
+    /// Returns an error if the repositories could not be listed, or if any repository could not be
+    /// scanned or any issue could not be created. The remaining runs are still processed.
+    pub async fn scan_org(
+        &self,
+        org: &str,
+        topic: Option<&str>,
+        since: &Date,
+        event: &str,
+        issue: &CreateIssueArgs,
+    ) -> Result<()> {
+        let repos: Vec<Repository> = self
+            .org_repos(org)
+            .await?
+            .into_iter()
+            .filter(|repo| match topic {
+                Some(topic) => repo
+                    .topics
+                    .as_ref()
+                    .is_some_and(|topics| topics.iter().any(|t| t == topic)),
+                None => true,
+            })
+            .collect();
+        if let Some(topic) = topic {
+            log::info!(
+                "{} repositories in {org} have the topic {topic}",
+                repos.len()
+            );
+        }
+        let filter = RunFilterArgs {
+            workflow: None,
+            branch: None,
+            event: Some(event.to_owned()),
+            since: Some(since.clone()),
+            until: None,
+        };
+
+        let mut num_failed_runs = 0;
+        let mut num_errors = 0;
+        for repo in &repos {
+            let failed_runs = match self.failed_runs(org, &repo.name, &filter, usize::MAX).await {
+                Ok(failed_runs) => failed_runs,
+                Err(e) => {
+                    log::error!(
+                        "Failed to list the failed runs in {org}/{}: {e:?}",
+                        repo.name
+                    );
+                    num_errors += 1;
+                    continue;
+                }
+            };
+            for run in &failed_runs {
+                log::info!(
+                    "Run {run_id} of {workflow} in {org}/{repo} failed",
+                    run_id = run.id,
+                    workflow = run.name,
+                    repo = repo.name
+                );
+                num_failed_runs += 1;
+                let args = CreateIssueFromRunArgs {
+                    repo: format!("{org}/{}", repo.name),
+                    run_id: run.id.to_string(),
+                    wait_for_completion: false,
+                    wait: WaitArgs {
+                        poll_interval: super::runs::DEFAULT_POLL_INTERVAL.as_secs(),
+                        wait_timeout: super::runs::DEFAULT_WAIT_TIMEOUT.as_secs(),
+                    },
+                    issue: issue.clone(),
+                };
+                if let Err(e) = self.create_issue_from_run(&args).await {
+                    log::error!(
+                        "Failed to create issue for run {run_id} in {repo}: {e:?}",
+                        run_id = args.run_id,
+                        repo = args.repo
+                    );
+                    num_errors += 1;
+                }
+            }
+        }
+
+        println!(
+            "Found {num_failed_runs} failed {event} run(s) since {since} in {num_repos} repo(s)",
+            num_repos = repos.len()
+        );
+        if num_errors > 0 {
+            bail!("{num_errors} error(s) while scanning {org}");
+        }
+        Ok(())
+    }
+}
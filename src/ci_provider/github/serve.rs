@@ -0,0 +1,231 @@
+//! Webhook server that creates issues for failed workflow runs as GitHub reports them.
+use super::GitHub;
+use crate::{
+    commands::{CreateIssueArgs, CreateIssueFromRunArgs, WaitArgs},
+    *,
+};
+use hmac::{Hmac, Mac};
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::{
+    body::{Bytes, Incoming},
+    server::conn::http1,
+    service::service_fn,
+    Method, Request, Response, StatusCode,
+};
+use hyper_util::rt::TokioIo;
+use sha2::Sha256;
+use std::{convert::Infallible, net::SocketAddr, sync::Arc};
+
+/// Environment variable holding the secret configured for the webhook on GitHub
+pub const WEBHOOK_SECRET_ENV: &str = "GITHUB_WEBHOOK_SECRET";
+/// GitHub caps webhook payloads at 25 MB
+const MAX_PAYLOAD_SIZE: usize = 25 * 1024 * 1024;
+
+struct ServeState {
+    secret: String,
+    issue: CreateIssueArgs,
+}
+
+/// The parts of a `workflow_run` webhook payload we need
+#[derive(Debug, Deserialize)]
+struct WorkflowRunEvent {
+    action: String,
+    workflow_run: WorkflowRunPayload,
+    repository: RepositoryPayload,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkflowRunPayload {
+    id: u64,
+    name: String,
+    conclusion: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RepositoryPayload {
+    full_name: String,
+}
+
+impl GitHub {
+    /// Listen for `workflow_run` webhooks on `addr` and create an issue for every failed run.
+    ///
+    /// Every request must be signed with the secret in the `GITHUB_WEBHOOK_SECRET` environment variable.
+    pub async fn serve(&'static self, addr: SocketAddr, issue: &CreateIssueArgs) -> Result<()> {
+        let secret = env::var(WEBHOOK_SECRET_ENV).with_context(|| {
+            format!("`serve` requires the webhook secret in the {WEBHOOK_SECRET_ENV} environment variable")
+        })?;
+        if secret.is_empty() {
+            bail!("The webhook secret in the {WEBHOOK_SECRET_ENV} environment variable is empty, refusing to accept unsigned webhooks");
+        }
+        let state = Arc::new(ServeState {
+            secret,
+            issue: issue.clone(),
+        });
+        let listener = tokio::net::TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("Failed to listen on {addr}"))?;
+        log::info!("Listening for workflow_run webhooks on {addr}");
+
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            log::debug!("Connection from {peer}");
+            let state = Arc::clone(&state);
+            tokio::spawn(async move {
+                let service = service_fn(move |req| self.handle_webhook(req, Arc::clone(&state)));
+                if let Err(e) = http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await
+                {
+                    log::error!("Error serving connection from {peer}: {e}");
+                }
+            });
+        }
+    }
+
+    async fn handle_webhook(
+        &'static self,
+        req: Request<Incoming>,
+        state: Arc<ServeState>,
+    ) -> Result<Response<Full<Bytes>>, Infallible> {
+        if req.method() != Method::POST {
+            return Ok(respond(
+                StatusCode::METHOD_NOT_ALLOWED,
+                "Only POST is supported",
+            ));
+        }
+        let event = header_str(&req, "x-github-event").unwrap_or_default();
+        let signature = header_str(&req, "x-hub-signature-256").unwrap_or_default();
+        let body = match Limited::new(req.into_body(), MAX_PAYLOAD_SIZE)
+            .collect()
+            .await
+        {
+            Ok(body) => body.to_bytes(),
+            Err(e) => {
+                log::warn!("Failed to read webhook payload: {e}");
+                return Ok(respond(StatusCode::BAD_REQUEST, "Failed to read payload"));
+            }
+        };
+        if !is_valid_signature(state.secret.as_bytes(), &body, &signature) {
+            log::warn!("Rejected webhook with invalid signature");
+            return Ok(respond(StatusCode::UNAUTHORIZED, "Invalid signature"));
+        }
+
+        match event.as_str() {
+            "ping" => Ok(respond(StatusCode::OK, "pong")),
+            "workflow_run" => {
+                let event: WorkflowRunEvent = match serde_json::from_slice(&body) {
+                    Ok(event) => event,
+                    Err(e) => {
+                        log::warn!("Invalid workflow_run payload: {e}");
+                        return Ok(respond(StatusCode::BAD_REQUEST, "Invalid payload"));
+                    }
+                };
+                if event.action != "completed"
+                    || event.workflow_run.conclusion.as_deref() != Some("failure")
+                {
+                    return Ok(respond(StatusCode::OK, "Ignored, the run did not fail"));
+                }
+                log::info!(
+                    "Run {run_id} of {workflow} in {repo} failed, creating issue",
+                    run_id = event.workflow_run.id,
+                    workflow = event.workflow_run.name,
+                    repo = event.repository.full_name
+                );
+                let args = CreateIssueFromRunArgs {
+                    repo: event.repository.full_name,
+                    run_id: event.workflow_run.id.to_string(),
+                    wait_for_completion: false,
+                    wait: WaitArgs {
+                        poll_interval: super::runs::DEFAULT_POLL_INTERVAL.as_secs(),
+                        wait_timeout: super::runs::DEFAULT_WAIT_TIMEOUT.as_secs(),
+                    },
+                    issue: state.issue.clone(),
+                };
+                // GitHub times out webhook deliveries after 10 seconds, so respond before creating the issue
+                tokio::spawn(async move {
+                    if let Err(e) = self.create_issue_from_run(&args).await {
+                        log::error!(
+                            "Failed to create issue for run {run_id} in {repo}: {e:?}",
+                            run_id = args.run_id,
+                            repo = args.repo
+                        );
+                    }
+                });
+                Ok(respond(StatusCode::ACCEPTED, "Creating issue"))
+            }
+            _ => Ok(respond(StatusCode::OK, "Ignored event")),
+        }
+    }
+}
+
+fn respond(status: StatusCode, body: &'static str) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(Bytes::from_static(body.as_bytes())));
+    *response.status_mut() = status;
+    response
+}
+
+fn header_str(req: &Request<Incoming>, name: &str) -> Option<String> {
+    req.headers()
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_owned)
+}
+
+/// Check the `X-Hub-Signature-256` header of a webhook, which is the HMAC-SHA256 of the payload
+/// with the webhook secret, formatted as `sha256=<hex digest>`
+fn is_valid_signature(secret: &[u8], payload: &[u8], signature_header: &str) -> bool {
+    let Some(signature) = signature_header
+        .strip_prefix("sha256=")
+        .and_then(decode_hex)
+    else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret) else {
+        return false;
+    };
+    mac.update(payload);
+    // Constant time comparison
+    mac.verify_slice(&signature).is_ok()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    // Example from https://docs.github.com/en/webhooks/using-webhooks/validating-webhook-deliveries#testing-the-webhook-payload-validation
+    const SECRET: &[u8] = b"It's a Secret to Everybody";
+    const PAYLOAD: &[u8] = b"Hello, World!";
+    const SIGNATURE: &str =
+        "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+
+    #[test]
+    fn test_valid_signature() {
+        assert!(is_valid_signature(SECRET, PAYLOAD, SIGNATURE));
+    }
+
+    #[test]
+    fn test_invalid_signature() {
+        assert!(!is_valid_signature(b"wrong secret", PAYLOAD, SIGNATURE));
+        assert!(!is_valid_signature(SECRET, b"Tampered payload", SIGNATURE));
+        assert!(!is_valid_signature(SECRET, PAYLOAD, ""));
+        assert!(!is_valid_signature(SECRET, PAYLOAD, "sha256=not-hex"));
+    }
+
+    #[test]
+    fn test_decode_hex() {
+        assert_eq!(decode_hex("00ff10"), Some(vec![0x00, 0xff, 0x10]));
+        assert_eq!(decode_hex("abc"), None);
+        assert_eq!(decode_hex("zz"), None);
+    }
+}
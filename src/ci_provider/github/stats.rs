@@ -0,0 +1,281 @@
+//! Failure analytics over the recent runs of a workflow.
+use super::{export::print_csv, util::failed_jobs, GitHub};
+use crate::{
+    commands::{OutputFormat, StatsArgs, WorkflowKind},
+    err_parse::parse_error_message,
+    *,
+};
+use octocrab::models::{
+    workflows::{Conclusion, Run},
+    JobId,
+};
+use std::{cmp::Reverse, collections::BTreeMap, io::Write};
+
+/// Failure statistics of the recent runs of a workflow
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WorkflowStats {
+    pub workflow: String,
+    pub runs: usize,
+    pub failed_runs: usize,
+    pub failure_rate: f64,
+    /// Mean time between the start of consecutive failed runs, `None` if less than two runs failed
+    pub mean_hours_between_failures: Option<f64>,
+    /// Jobs sorted by failure rate, highest first
+    pub jobs: Vec<JobStats>,
+    /// Failure labels from the error parser sorted by count, highest first
+    pub failure_labels: Vec<LabelCount>,
+}
+
+/// Failure statistics of a single job across the runs of a workflow
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JobStats {
+    pub name: String,
+    pub runs: usize,
+    pub failures: usize,
+    pub failure_rate: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LabelCount {
+    pub label: String,
+    pub count: usize,
+}
+
+impl GitHub {
+    /// Print failure statistics over the last `--runs` completed runs of a workflow.
+    ///
+    /// If `--kind` is set, the logs of the failed runs are downloaded and parsed to count the
+    /// failure labels, otherwise the failure labels are omitted. With `--format csv`, the failed
+    /// jobs of the failed runs are printed instead of the statistics.
+    pub async fn workflow_stats(&self, args: &StatsArgs) -> Result<()> {
+        let &StatsArgs {
+            ref repo,
+            ref workflow,
+            ref branch,
+            runs: num_runs,
+            kind,
+            format,
+        } = args;
+        let (owner, repo) = repo_to_owner_repo_fragments(repo)?;
+        let runs = self
+            .completed_workflow_runs(&owner, &repo, workflow, branch.as_deref(), num_runs)
+            .await?;
+        if runs.is_empty() {
+            bail!("No completed runs found for workflow {workflow}");
+        }
+        log::info!("Analyzing {} run(s) of workflow {workflow}", runs.len());
+
+        match Config::global().list_format(format) {
+            OutputFormat::Table => {
+                let stats = self
+                    .stats_of_runs(&owner, &repo, workflow, &runs, kind)
+                    .await?;
+                print_stats_table(&stats)?
+            }
+            OutputFormat::Json => {
+                let stats = self
+                    .stats_of_runs(&owner, &repo, workflow, &runs, kind)
+                    .await?;
+                pipe_println!("{}", serde_json::to_string_pretty(&stats)?)?
+            }
+            OutputFormat::Csv => {
+                let rows = self
+                    .failed_job_rows(
+                        &owner,
+                        &repo,
+                        &failed_runs_of(&runs),
+                        kind.unwrap_or(WorkflowKind::Auto),
+                    )
+                    .await?;
+                print_csv(&rows)?
+            }
+        }
+        Ok(())
+    }
+
+    /// Count the failures of `runs` per job and, if `kind` is set, per failure label
+    async fn stats_of_runs(
+        &self,
+        owner: &str,
+        repo: &str,
+        workflow: &str,
+        runs: &[Run],
+        kind: Option<WorkflowKind>,
+    ) -> Result<WorkflowStats> {
+        let failed_runs = failed_runs_of(runs);
+        // (runs, failures) per job name
+        let mut job_counts: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+        for run in runs {
+            let jobs = self.latest_attempt_jobs(owner, repo, run.id).await?;
+            let failed: Vec<JobId> = failed_jobs(&jobs).iter().map(|job| job.id).collect();
+            for job in &jobs {
+                let counts = job_counts.entry(job.name.clone()).or_default();
+                counts.0 += 1;
+                if failed.contains(&job.id) {
+                    counts.1 += 1;
+                }
+            }
+        }
+
+        let mut label_counts: BTreeMap<String, usize> = BTreeMap::new();
+        if let Some(kind) = kind {
+            for run in &failed_runs {
+                for job in self.failed_job_error_logs(owner, repo, run.id).await? {
+                    let error_message =
+                        parse_error_message(&job.logs_as_str(), kind.for_job(&job.job_name))?;
+                    if let Some(label) = error_message.failure_label() {
+                        *label_counts.entry(label).or_default() += 1;
+                    }
+                }
+            }
+        }
+
+        let mut jobs: Vec<JobStats> = job_counts
+            .into_iter()
+            .map(|(name, (runs, failures))| JobStats {
+                name,
+                runs,
+                failures,
+                failure_rate: ratio(failures, runs),
+            })
+            .collect();
+        jobs.sort_by(|a, b| b.failure_rate.total_cmp(&a.failure_rate));
+        let mut failure_labels: Vec<LabelCount> = label_counts
+            .into_iter()
+            .map(|(label, count)| LabelCount { label, count })
+            .collect();
+        failure_labels.sort_by_key(|label| Reverse(label.count));
+
+        let failure_timestamps: Vec<i64> = failed_runs
+            .iter()
+            .map(|run| run.created_at.timestamp())
+            .collect();
+        Ok(WorkflowStats {
+            workflow: workflow.to_owned(),
+            runs: runs.len(),
+            failed_runs: failed_runs.len(),
+            failure_rate: ratio(failed_runs.len(), runs.len()),
+            mean_hours_between_failures: mean_seconds_between(&failure_timestamps)
+                .map(|secs| secs / 3600.0),
+            jobs,
+            failure_labels,
+        })
+    }
+
+    /// Get up to `limit` completed runs of a workflow, most recent first
+    async fn completed_workflow_runs(
+        &self,
+        owner: &str,
+        repo: &str,
+        workflow: &str,
+        branch: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<Run>> {
+        const PER_PAGE: u8 = 100;
+        let workflows = self.client.workflows(owner, repo);
+        let mut runs = Vec::new();
+        for page in 1u32.. {
+            let mut request = workflows
+                .list_runs(workflow)
+                .status("completed")
+                .per_page(PER_PAGE)
+                .page(page);
+            if let Some(branch) = branch {
+                request = request.branch(branch);
+            }
+            let page_runs = request.send().await?.items;
+            let num_runs = page_runs.len();
+            runs.extend(page_runs);
+            if runs.len() >= limit || num_runs < usize::from(PER_PAGE) {
+                break;
+            }
+        }
+        runs.truncate(limit);
+        Ok(runs)
+    }
+}
+
+fn print_stats_table(stats: &WorkflowStats) -> Result<()> {
+    pipe_println!("Workflow:        {}", stats.workflow)?;
+    pipe_println!(
+        "Failed runs:     {}/{} ({:.1}%)",
+        stats.failed_runs,
+        stats.runs,
+        stats.failure_rate * 100.0
+    )?;
+    match stats.mean_hours_between_failures {
+        Some(hours) => pipe_println!("MTBF:            {hours:.1} hours")?,
+        None => pipe_println!("MTBF:            n/a")?,
+    }
+    pipe_println!("")?;
+    pipe_println!(
+        "{:<40} {:>6} {:>9} {:>8}",
+        "JOB",
+        "RUNS",
+        "FAILURES",
+        "RATE"
+    )?;
+    for job in &stats.jobs {
+        pipe_println!(
+            "{:<40} {:>6} {:>9} {:>7.1}%",
+            job.name,
+            job.runs,
+            job.failures,
+            job.failure_rate * 100.0
+        )?;
+    }
+    if !stats.failure_labels.is_empty() {
+        pipe_println!("")?;
+        pipe_println!("{:<40} {:>6}", "FAILURE LABEL", "COUNT")?;
+        for label in &stats.failure_labels {
+            pipe_println!("{:<40} {:>6}", label.label, label.count)?;
+        }
+    }
+    Ok(())
+}
+
+/// The failed runs among `runs`
+fn failed_runs_of(runs: &[Run]) -> Vec<&Run> {
+    runs.iter()
+        .filter(|run| run.conclusion.as_deref() == Some("failure"))
+        .collect()
+}
+
+fn ratio(part: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64
+    }
+}
+
+/// Mean number of seconds between consecutive unix timestamps, in any order.
+///
+/// Returns `None` if there are less than two timestamps.
+fn mean_seconds_between(timestamps: &[i64]) -> Option<f64> {
+    if timestamps.len() < 2 {
+        return None;
+    }
+    let first = timestamps.iter().min()?;
+    let last = timestamps.iter().max()?;
+    Some((last - first) as f64 / (timestamps.len() - 1) as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_mean_seconds_between() {
+        assert_eq!(mean_seconds_between(&[]), None);
+        assert_eq!(mean_seconds_between(&[100]), None);
+        assert_eq!(mean_seconds_between(&[400, 100, 200]), Some(150.0));
+    }
+
+    #[test]
+    fn test_ratio() {
+        assert_eq!(ratio(0, 0), 0.0);
+        assert_eq!(ratio(1, 4), 0.25);
+    }
+}
@@ -0,0 +1,93 @@
+//! Output variables of the GitHub Actions step running ci-manager, written to `$GITHUB_OUTPUT` so
+//! later steps of the job can use them, e.g. `steps.<id>.outputs.issue-url`
+use crate::{
+    notify::{IssueOutcome, RunReport},
+    *,
+};
+use std::io::Write;
+
+/// The output variables of a run report:
+/// - `issue-url`: the issue that tracks the failure, empty if none does
+/// - `issue-number`: the number of that issue
+/// - `deduplicated`: `true` if the failure was already tracked by an open issue
+/// - `failure-labels`: the failure labels of the failed jobs, separated by commas
+/// - `decision`: what was done about the failure, see [IssueOutcome::decision]
+pub fn step_outputs(report: &RunReport) -> Vec<(&'static str, String)> {
+    let issue_url = report.outcome.issue_url().unwrap_or_default();
+    let issue_number = issue_url
+        .rsplit_once("/issues/")
+        .map(|(_, number)| number)
+        .filter(|number| number.chars().all(|c| c.is_ascii_digit()))
+        .unwrap_or_default();
+    vec![
+        ("issue-url", issue_url.to_owned()),
+        ("issue-number", issue_number.to_owned()),
+        (
+            "deduplicated",
+            matches!(report.outcome, IssueOutcome::Duplicate { .. }).to_string(),
+        ),
+        ("failure-labels", report.failure_labels().join(",")),
+        ("decision", report.outcome.decision().to_owned()),
+    ]
+}
+
+/// Append the output variables of `report` to the file in `$GITHUB_OUTPUT`, if it is set, i.e. when
+/// running in GitHub Actions
+pub fn write_step_outputs(report: &RunReport) -> Result<()> {
+    let Ok(path) = env::var("GITHUB_OUTPUT") else {
+        return Ok(());
+    };
+    log::debug!("Writing the step outputs to {path}");
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open step output file: {path}"))?;
+    for (name, value) in step_outputs(report) {
+        writeln!(file, "{name}={value}")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notify::JobReport;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_step_outputs() {
+        let job = |failure_label: Option<&str>| JobReport {
+            name: "Build".to_owned(),
+            url: "https://github.com/luftkode/ci-manager/actions/runs/1/job/2".to_owned(),
+            failed_step: "Build".to_owned(),
+            failure_label: failure_label.map(str::to_owned),
+            fingerprint: "d0c5e0ac7e8c2a1b".to_owned(),
+            summary: "error".to_owned(),
+        };
+        let report = RunReport {
+            run_id: "1".to_owned(),
+            run_url: "https://github.com/luftkode/ci-manager/actions/runs/1".to_owned(),
+            title: "Build failed".to_owned(),
+            fingerprint: "4f1c7a2b9e3d5c60".to_owned(),
+            labels: vec!["CI".to_owned()],
+            failed_jobs: vec![job(Some("compiler-error")), job(None)],
+            outcome: IssueOutcome::Duplicate {
+                urls: vec!["https://github.com/luftkode/ci-manager/issues/42".to_owned()],
+            },
+        };
+        assert_eq!(
+            step_outputs(&report),
+            [
+                (
+                    "issue-url",
+                    "https://github.com/luftkode/ci-manager/issues/42".to_owned()
+                ),
+                ("issue-number", "42".to_owned()),
+                ("deduplicated", "true".to_owned()),
+                ("failure-labels", "compiler-error,unclassified".to_owned()),
+                ("decision", "duplicate".to_owned()),
+            ]
+        );
+    }
+}
@@ -1,8 +1,23 @@
 //! Contains the ErrorLog struct describing a failed job log from GitHub Actions.
+use crate::{
+    config::commands::WorkflowKind,
+    err_parse::{annotations::error_annotations, parse_error_message_at_step},
+    issue::{
+        excerpt::first_error_line,
+        similarity::{Similarity, SimilarityAlgo},
+        FailedJob, FirstFailedStep,
+    },
+    pipe_println, Config,
+};
+use anyhow::Result;
 use octocrab::models::{
-    workflows::{Job, Step},
+    workflows::{Conclusion, Job, Step},
     JobId,
 };
+use std::{
+    fmt,
+    io::{self, Write},
+};
 
 use super::JobLog;
 
@@ -11,6 +26,10 @@ pub struct JobErrorLog {
     pub job_id: JobId,
     pub job_name: String,
     pub failed_step_logs: Vec<StepErrorLog>,
+    /// Name of the runner that ran the job, if known
+    pub runner_name: Option<String>,
+    /// Labels of the runner that ran the job, i.e. the `runs-on` labels
+    pub runner_labels: Vec<String>,
 }
 
 impl JobErrorLog {
@@ -19,9 +38,21 @@ impl JobErrorLog {
             job_id,
             job_name,
             failed_step_logs: logs,
+            runner_name: None,
+            runner_labels: Vec::new(),
         }
     }
 
+    pub fn with_runner_name(mut self, runner_name: Option<String>) -> Self {
+        self.runner_name = runner_name;
+        self
+    }
+
+    pub fn with_runner_labels(mut self, runner_labels: Vec<String>) -> Self {
+        self.runner_labels = runner_labels;
+        self
+    }
+
     /// Returns the logs as a string
     pub fn logs_as_str(&self) -> String {
         let mut logs = String::new();
@@ -36,21 +67,77 @@ impl JobErrorLog {
 pub struct StepErrorLog {
     pub step_name: String,
     pub contents: String,
+    /// Number of the step in the job, which the log of the job on GitHub links to
+    pub step_number: Option<i64>,
+    /// Line number of the first error line in the log of the step, counted from 1, before any
+    /// lines are removed from `contents`
+    pub first_error_line: Option<usize>,
 }
 
 impl StepErrorLog {
     pub fn new(step_name: String, error_log: String) -> Self {
+        let lines: Vec<&str> = error_log.lines().collect();
+        let first_error_line = first_error_line(&lines).map(|idx| idx + 1);
         StepErrorLog {
             step_name,
             contents: error_log,
+            step_number: None,
+            first_error_line,
         }
     }
 
+    pub fn with_step_number(mut self, step_number: Option<i64>) -> Self {
+        self.step_number = step_number;
+        self
+    }
+
+    /// Anchor of the first error line of the step in the log of the job on GitHub, e.g.
+    /// `#step:4:120`, or of the start of the step if there is no error line
+    ///
+    /// # Example
+    /// ```
+    /// # use ci_manager::ci_provider::github::util::StepErrorLog;
+    /// let step = StepErrorLog::new("Build".to_owned(), "cargo build\nerror: linking failed\n".to_owned())
+    ///     .with_step_number(Some(4));
+    /// assert_eq!(step.log_anchor().unwrap(), "#step:4:2");
+    /// ```
+    pub fn log_anchor(&self) -> Option<String> {
+        let step_number = self.step_number?;
+        let line = self.first_error_line.unwrap_or(1);
+        Some(format!("#step:{step_number}:{line}"))
+    }
+
     pub fn contents(&self) -> &str {
         self.contents.as_str()
     }
 }
 
+/// The failed jobs of a run: the jobs that failed or timed out, or the cancelled jobs if there are
+/// none, e.g. because the run was cancelled. Jobs cancelled because another job failed are left out
+/// otherwise, as they are noise next to the job that failed.
+pub fn failed_jobs(jobs: &[Job]) -> Vec<&Job> {
+    select_failed(jobs, |job| job.conclusion.as_ref())
+}
+
+fn select_failed<T>(items: &[T], conclusion: impl Fn(&T) -> Option<&Conclusion>) -> Vec<&T> {
+    let failed: Vec<&T> = items
+        .iter()
+        .filter(|item| {
+            matches!(
+                conclusion(item),
+                Some(Conclusion::Failure | Conclusion::TimedOut)
+            )
+        })
+        .collect();
+    if !failed.is_empty() {
+        return failed;
+    }
+    items
+        .iter()
+        .filter(|item| conclusion(item) == Some(&Conclusion::Cancelled))
+        .collect()
+}
+
 pub fn repo_url_to_job_url(repo_url: &str, run_id: &str, job_id: &str) -> String {
     let run_url = repo_url_to_run_url(repo_url, run_id);
     run_url_to_job_url(&run_url, job_id)
@@ -76,6 +163,261 @@ pub fn distance_to_other_issues(
     crate::issue::similarity::issue_text_similarity(issue_body, &other_issue_bodies)
 }
 
+/// Find the issue with the body that is most similar to `issue_body` by `algo` and return it along
+/// with the distance.
+///
+/// Returns `None` if there are no other issues.
+pub fn closest_issue<'i>(
+    issue_body: &str,
+    other_issues: &'i [octocrab::models::issues::Issue],
+    algo: SimilarityAlgo,
+) -> Option<(&'i octocrab::models::issues::Issue, f64)> {
+    other_issues
+        .iter()
+        .map(|issue| {
+            let other_body = issue.body.as_deref().unwrap_or_default();
+            (issue, algo.distance(issue_body, other_body))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+}
+
+/// Find an issue created for a failure with the same fingerprint, see
+/// [Issue::fingerprint](crate::issue::Issue::fingerprint)
+pub fn issue_with_fingerprint<'i>(
+    fingerprint: &str,
+    other_issues: &'i [octocrab::models::issues::Issue],
+) -> Option<&'i octocrab::models::issues::Issue> {
+    let same_failure = other_issues.iter().find(|issue| {
+        let body = issue.body.as_deref().unwrap_or_default();
+        crate::issue::fingerprint_from_issue_body(body).is_some_and(|f| f == fingerprint)
+    })?;
+    log::warn!(
+        "Issue #{number} has the same failure fingerprint {fingerprint}",
+        number = same_failure.number
+    );
+    Some(same_failure)
+}
+
+/// The issue that tracks each of the failed jobs with `job_fingerprints`, if any, see
+/// [Issue::job_fingerprints](crate::issue::Issue::job_fingerprints)
+pub fn issues_tracking_jobs<'i>(
+    job_fingerprints: &[String],
+    other_issues: &'i [octocrab::models::issues::Issue],
+) -> Vec<Option<&'i octocrab::models::issues::Issue>> {
+    let tracked: Vec<(Vec<String>, &octocrab::models::issues::Issue)> = other_issues
+        .iter()
+        .filter_map(|issue| {
+            let body = issue.body.as_deref().unwrap_or_default();
+            let metadata = crate::issue::footer::IssueMetadata::from_issue_body(body)?;
+            Some((metadata.job_fingerprints, issue))
+        })
+        .collect();
+    job_fingerprints
+        .iter()
+        .map(|fingerprint| {
+            tracked
+                .iter()
+                .find(|(job_fingerprints, _)| job_fingerprints.contains(fingerprint))
+                .map(|(_, issue)| *issue)
+        })
+        .collect()
+}
+
+/// Why an issue is considered a duplicate of another, see [duplicate_issue]
+#[derive(Debug, Clone, PartialEq)]
+pub enum DuplicateReason {
+    /// The failures have the same fingerprint
+    Fingerprint(String),
+    /// The bodies are within the threshold of `similarity`
+    Similarity {
+        similarity: Similarity,
+        distance: f64,
+    },
+    /// Both are about network errors, see [INFRA_FLAKE_LABEL](crate::err_parse::network::INFRA_FLAKE_LABEL)
+    FlakeLabel(String),
+}
+
+impl fmt::Display for DuplicateReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DuplicateReason::Fingerprint(fingerprint) => {
+                write!(f, "same fingerprint {fingerprint}")
+            }
+            DuplicateReason::Similarity {
+                similarity,
+                distance,
+            } => write!(
+                f,
+                "{algo} distance {distance} is below the threshold {threshold}",
+                algo = similarity.algo,
+                threshold = similarity.threshold
+            ),
+            DuplicateReason::FlakeLabel(label) => write!(f, "both have the label {label}"),
+        }
+    }
+}
+
+/// Find an issue that tracks the same failure as an issue with `fingerprint` and `issue_body`.
+/// Issues with the same fingerprint take precedence over issues with a similar body, which only
+/// older issues without a fingerprint and failures that the normalization misses rely on.
+pub fn duplicate_issue<'i>(
+    fingerprint: &str,
+    issue_body: &str,
+    other_issues: &'i [octocrab::models::issues::Issue],
+    similarity: Similarity,
+) -> Option<(&'i octocrab::models::issues::Issue, DuplicateReason)> {
+    if let Some(issue) = issue_with_fingerprint(fingerprint, other_issues) {
+        return Some((issue, DuplicateReason::Fingerprint(fingerprint.to_owned())));
+    }
+    let (issue, distance) = similar_issue(issue_body, other_issues, similarity)?;
+    Some((
+        issue,
+        DuplicateReason::Similarity {
+            similarity,
+            distance,
+        },
+    ))
+}
+
+/// The steps of a decision about duplicates, printed with `--explain-dedup` and in dry runs so
+/// false positives can be debugged
+#[derive(Debug, Default)]
+pub struct DedupExplanation {
+    enabled: bool,
+    lines: Vec<String>,
+}
+
+impl DedupExplanation {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            lines: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, line: impl Into<String>) {
+        if self.enabled {
+            self.lines.push(line.into());
+        }
+    }
+
+    /// Print the explanation, ending with the decision
+    pub fn print(&self, decision: &str) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        pipe_println!("==== DUPLICATE DETECTION ====")?;
+        for line in &self.lines {
+            pipe_println!("- {line}")?;
+        }
+        pipe_println!("Decision: {decision}")?;
+        Ok(())
+    }
+}
+
+/// Find an issue with a body similar enough to `issue_body` to be considered a duplicate, see
+/// [Similarity], and return it along with the distance.
+pub fn similar_issue<'i>(
+    issue_body: &str,
+    other_issues: &'i [octocrab::models::issues::Issue],
+    similarity: Similarity,
+) -> Option<(&'i octocrab::models::issues::Issue, f64)> {
+    // Only the distances to similar issues are computed in full, see [Similarity::similar_distance]
+    let mut closest: Option<(&octocrab::models::issues::Issue, f64)> = None;
+    for issue in other_issues {
+        let other_body = issue.body.as_deref().unwrap_or_default();
+        let Some(distance) = similarity.similar_distance(issue_body, other_body) else {
+            continue;
+        };
+        if closest.is_none_or(|(_, min_distance)| distance < min_distance) {
+            closest = Some((issue, distance));
+        }
+        if distance == 0.0 {
+            break;
+        }
+    }
+    match closest {
+        Some((_, distance)) => log::info!(
+            "Minimum {algo} distance to similar issue: {distance}",
+            algo = similarity.algo
+        ),
+        None => log::info!(
+            "No issue within {algo} distance {threshold}",
+            algo = similarity.algo,
+            threshold = similarity.threshold
+        ),
+    }
+    let (similar_issue, distance) = closest?;
+    if distance == 0.0 {
+        log::warn!(
+            "Issue #{number} has the exact same body",
+            number = similar_issue.number
+        );
+    } else {
+        log::warn!(
+            "Issue #{number} has a similar body",
+            number = similar_issue.number
+        );
+    }
+    Some((similar_issue, distance))
+}
+
+/// Parse the job error logs with the parser for `kind` (see [WorkflowKind::for_job]) and map them
+/// to [FailedJob]s
+pub fn failed_jobs_from_job_error_logs(
+    job_error_logs: &[JobErrorLog],
+    run_url: &str,
+    kind: WorkflowKind,
+) -> Vec<FailedJob> {
+    job_error_logs
+        .iter()
+        .map(|job| {
+            let job_id_str = job.job_id.to_string();
+            let mut job_url = run_url_to_job_url(run_url, &job_id_str);
+            // Link to the first error of the first failed step instead of the top of the log
+            if let Some(anchor) = job
+                .failed_step_logs
+                .first()
+                .and_then(StepErrorLog::log_anchor)
+            {
+                job_url.push_str(&anchor);
+            }
+            let continuous_errorlog_msgs = job.logs_as_str();
+            let continuous_errorlog_msgs = Config::global()
+                .config_file()
+                .redact
+                .apply(&continuous_errorlog_msgs)
+                .into_owned();
+            let first_failed_step_name = job
+                .failed_step_logs
+                .first()
+                .map(|step| step.step_name.as_str());
+            let first_failed_step: FirstFailedStep = match first_failed_step_name {
+                Some(step_name) => FirstFailedStep::StepName(step_name.to_owned()),
+                // This can happen if the job times out while waiting for a runner to pick it up
+                // Relevant issue: https://github.com/luftkode/ci-manager/issues/4
+                None => FirstFailedStep::NoStepsExecuted,
+            };
+            let parsed_msg = parse_error_message_at_step(
+                &continuous_errorlog_msgs,
+                kind.for_job(&job.job_name),
+                first_failed_step_name,
+            )
+            .unwrap();
+            FailedJob::new(
+                job.job_name.to_owned(),
+                job_id_str,
+                job_url,
+                first_failed_step,
+                parsed_msg,
+            )
+            .with_runner_name(job.runner_name.clone())
+            .with_runner_labels(job.runner_labels.clone())
+            .with_error_annotations(error_annotations(&continuous_errorlog_msgs))
+        })
+        .collect()
+}
+
 /// Logs the job error logs to the info log in a readable summary
 pub fn log_info_downloaded_job_error_logs(job_error_logs: &[JobErrorLog]) {
     log::info!("Got {} job error log(s)", job_error_logs.len());
@@ -102,6 +444,47 @@ pub fn log_info_downloaded_job_error_logs(job_error_logs: &[JobErrorLog]) {
     }
 }
 
+/// Remove the lines from the failed step logs that also appear in the log of the same job and step
+/// in `baseline_logs`, e.g. the logs of the last successful run, so only the new lines remain.
+///
+/// Step logs without a matching baseline log, or where every line also appears in the baseline, are
+/// left untouched.
+pub fn remove_lines_present_in_baseline(
+    job_error_logs: &mut [JobErrorLog],
+    baseline_logs: &[JobLog],
+) {
+    for job in job_error_logs {
+        for step in &mut job.failed_step_logs {
+            let Some(baseline) = find_error_log(baseline_logs, &job.job_name, &step.step_name)
+            else {
+                log::info!(
+                    "No baseline log for step: {step_name} in job: {job_name}, keeping the full log",
+                    step_name = step.step_name,
+                    job_name = job.job_name
+                );
+                continue;
+            };
+            let new_lines = crate::util::lines_not_in(&step.contents, &baseline.content);
+            if new_lines.trim().is_empty() {
+                log::warn!(
+                    "Log of step: {step_name} in job: {job_name} has no lines that are not in the baseline, keeping the full log",
+                    step_name = step.step_name,
+                    job_name = job.job_name
+                );
+                continue;
+            }
+            log::info!(
+                "Reduced log of step: {step_name} in job: {job_name} from {old_len} to {new_len} characters",
+                step_name = step.step_name,
+                job_name = job.job_name,
+                old_len = step.contents.len(),
+                new_len = new_lines.len()
+            );
+            step.contents = new_lines;
+        }
+    }
+}
+
 /// Extracts the error logs from the logs, failed jobs and failed steps
 /// and returns a vector of [JobErrorLog].
 ///
@@ -121,8 +504,22 @@ pub fn job_error_logs_from_log_and_failed_jobs_and_steps(
         log::info!("Extracting error logs for job: {}", job.name);
         let name = job.name.clone();
         let step_error_logs: Vec<StepErrorLog> =
-            find_error_logs_for_job_steps(logs, &name, failed_steps);
-        job_error_logs.push(JobErrorLog::new(job.id, name, step_error_logs));
+            find_error_logs_for_job_steps(logs, &name, failed_steps)
+                .into_iter()
+                .map(|step_log| {
+                    let step_number = job
+                        .steps
+                        .iter()
+                        .find(|step| step.name == step_log.step_name)
+                        .map(|step| step.number);
+                    step_log.with_step_number(step_number)
+                })
+                .collect();
+        job_error_logs.push(
+            JobErrorLog::new(job.id, name, step_error_logs)
+                .with_runner_name(job.runner_name.clone())
+                .with_runner_labels(job.labels.clone()),
+        );
     }
     job_error_logs
 }
@@ -155,3 +552,33 @@ fn find_error_log<'j>(logs: &'j [JobLog], job_name: &str, step_name: &str) -> Op
     logs.iter()
         .find(|log| log.name.contains(step_name) && log.name.contains(job_name))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_select_failed() {
+        let names = |jobs: &[(&'static str, Option<Conclusion>)]| -> Vec<&'static str> {
+            select_failed(jobs, |job| job.1.as_ref())
+                .into_iter()
+                .map(|job| job.0)
+                .collect()
+        };
+        let jobs = [
+            ("Build", Some(Conclusion::Failure)),
+            ("Test", Some(Conclusion::TimedOut)),
+            ("Lint", Some(Conclusion::Cancelled)),
+            ("Docs", Some(Conclusion::Success)),
+        ];
+        assert_eq!(names(&jobs), ["Build", "Test"]);
+
+        let cancelled_run = [
+            ("Build", Some(Conclusion::Cancelled)),
+            ("Docs", Some(Conclusion::Success)),
+            ("Deploy", None),
+        ];
+        assert_eq!(names(&cancelled_run), ["Build"]);
+    }
+}
@@ -1,5 +1,7 @@
 #![allow(dead_code, unused_variables)]
-use crate::*;
+use crate::{err_parse::FailureClass, *};
+
+pub mod mr_note;
 
 pub struct GitLab {
     client: gitlab::Gitlab,
@@ -14,6 +16,80 @@ impl GitLab {
         Self { client }
     }
 
+    /// Retry the failed jobs of a pipeline.
+    ///
+    /// If `if_classified_as` is set, the jobs are only retried if the failure of the pipeline is
+    /// classified as that [FailureClass].
+    pub fn retry_pipeline(
+        &self,
+        repo: &str,
+        pipeline_id: &str,
+        if_classified_as: Option<FailureClass>,
+    ) -> Result<()> {
+        let (owner, repo) = repo_to_owner_repo_fragments(repo)?;
+        let project = format!("{owner}/{repo}");
+        let pipeline_id: u64 = pipeline_id.parse()?;
+
+        if let Some(expected_class) = if_classified_as {
+            let failed_job_logs = self.failed_job_traces(&project, pipeline_id)?;
+            let class = FailureClass::classify_all(failed_job_logs.iter().map(|(job, trace)| {
+                (
+                    trace.as_str(),
+                    commands::WorkflowKind::Auto.for_job(&job.name),
+                )
+            }));
+            log::info!("Failure of pipeline {pipeline_id} is classified as: {class}");
+            if class != expected_class {
+                log::warn!(
+                    "Failure is not classified as {expected_class}, not retrying failed jobs"
+                );
+                return Ok(());
+            }
+        }
+
+        if Config::global().dry_run() {
+            println!("DRY RUN MODE! The failed jobs of pipeline {pipeline_id} would be retried");
+            return Ok(());
+        }
+        log::info!("Retrying failed jobs of pipeline {pipeline_id}");
+        let endpoint = projects::pipelines::RetryPipeline::builder()
+            .project(project.as_str())
+            .pipeline(pipeline_id)
+            .build()?;
+        api::ignore(endpoint).query(&self.client)?;
+        Ok(())
+    }
+
+    fn pipeline(&self, project: &str, pipeline_id: u64) -> Result<Pipeline> {
+        let endpoint = projects::pipelines::Pipeline::builder()
+            .project(project)
+            .pipeline(pipeline_id)
+            .build()?;
+        Ok(endpoint.query(&self.client)?)
+    }
+
+    /// The failed jobs of a pipeline with their logs
+    fn failed_job_traces(&self, project: &str, pipeline_id: u64) -> Result<Vec<(Job, String)>> {
+        let endpoint = projects::pipelines::PipelineJobs::builder()
+            .project(project)
+            .pipeline(pipeline_id)
+            .build()?;
+        let pipeline_jobs: Vec<Job> = endpoint.query(&self.client)?;
+        let mut failed_jobs = Vec::new();
+        for job in pipeline_jobs
+            .into_iter()
+            .filter(|job| job.status == "failed")
+        {
+            let endpoint = projects::jobs::JobTrace::builder()
+                .project(project)
+                .job(job.id)
+                .build()?;
+            let trace = api::raw(endpoint).query(&self.client)?;
+            failed_jobs.push((job, String::from_utf8_lossy(&trace).to_string()));
+        }
+        Ok(failed_jobs)
+    }
+
     pub fn handle(&self, command: &commands::Command) -> Result<()> {
         let endpoint = projects::Project::builder()
             .project("CramBL/github-workflow-parser")
@@ -111,11 +187,22 @@ struct Issue {
     labels: Vec<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct Pipeline {
+    id: u64,
+    status: String,
+    #[serde(rename = "ref")]
+    ref_: String,
+    web_url: String,
+}
+
 #[derive(Debug, Deserialize)]
 struct Job {
     id: u64,
     name: String,
+    stage: String,
     status: String,
     #[serde(rename = "ref")]
     ref_: String,
+    web_url: String,
 }
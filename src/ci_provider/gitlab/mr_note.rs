@@ -0,0 +1,313 @@
+//! Notes with the failure summary on the merge request of a failed pipeline, the GitLab counterpart
+//! of `--pr-comment`
+//!
+//! The note starts a discussion on the merge request. The note is updated when the same pipeline
+//! fails again, e.g. after retrying a job, and the discussion is resolved when a later pipeline of
+//! the merge request fails (starting a new discussion) or succeeds.
+use super::{GitLab, Pipeline};
+use crate::{
+    commands::CreateIssueFromRunArgs,
+    err_parse::parse_error_message,
+    issue::{BodyLimits, FailedJob, FirstFailedStep, Issue},
+    *,
+};
+
+const GITLAB_API_URL: &str = "https://gitlab.com/api/v4";
+/// Number of discussions per page of the list of discussions
+const PER_PAGE: usize = 100;
+
+/// Hidden marker in a merge request note naming the pipeline it is about
+///
+/// # Example
+/// ```
+/// # use ci_manager::ci_provider::gitlab::mr_note::NoteMarker;
+/// # use pretty_assertions::assert_eq;
+/// let marker = NoteMarker { pipeline_id: 1180296622 };
+/// assert_eq!(marker.to_string(), "<!-- ci-manager mr-note pipeline=1180296622 -->");
+/// let body = format!("### Pipeline failed\n\nerror\n\n{marker}");
+/// assert_eq!(NoteMarker::from_note_body(&body), Some(marker));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoteMarker {
+    pub pipeline_id: u64,
+}
+
+impl NoteMarker {
+    /// Parse the marker from a note, returns `None` if it is not a note of ci-manager
+    pub fn from_note_body(body: &str) -> Option<Self> {
+        static MARKER_RE: Lazy<Regex> = Lazy::new(|| {
+            Regex::new(r"<!-- ci-manager mr-note pipeline=(?<PIPELINE>[0-9]+) -->").unwrap()
+        });
+        let captures = MARKER_RE.captures(body)?;
+        Some(Self {
+            pipeline_id: captures.name("PIPELINE")?.as_str().parse().ok()?,
+        })
+    }
+}
+
+impl fmt::Display for NoteMarker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "<!-- ci-manager mr-note pipeline={} -->",
+            self.pipeline_id
+        )
+    }
+}
+
+/// The IID of the merge request a pipeline ran for, from the ref of the pipeline
+///
+/// # Example
+/// ```
+/// # use ci_manager::ci_provider::gitlab::mr_note::merge_request_iid;
+/// assert_eq!(merge_request_iid("refs/merge-requests/42/head"), Some(42));
+/// assert_eq!(merge_request_iid("refs/merge-requests/42/merge"), Some(42));
+/// assert_eq!(merge_request_iid("main"), None);
+/// ```
+pub fn merge_request_iid(pipeline_ref: &str) -> Option<u64> {
+    static REF_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"^refs/merge-requests/(?<IID>[0-9]+)/(?:head|merge|train)$").unwrap()
+    });
+    REF_RE.captures(pipeline_ref)?["IID"].parse().ok()
+}
+
+/// A discussion of a merge request, see <https://docs.gitlab.com/ee/api/discussions.html>
+#[derive(Debug, Deserialize)]
+struct Discussion {
+    id: String,
+    notes: Vec<Note>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Note {
+    id: u64,
+    body: String,
+    #[serde(default)]
+    resolved: bool,
+}
+
+/// The discussions of a merge request, through the REST API authenticated with `CI_PAT`
+struct Discussions {
+    client: reqwest::Client,
+    url: String,
+    token: String,
+}
+
+impl Discussions {
+    fn new(project: &str, iid: u64) -> Result<Self> {
+        let token =
+            env::var("CI_PAT").context("CI_PAT must be set to comment on GitLab merge requests")?;
+        Ok(Self {
+            client: reqwest::Client::new(),
+            url: format!(
+                "{GITLAB_API_URL}/projects/{project}/merge_requests/{iid}/discussions",
+                project = project.replace('/', "%2F")
+            ),
+            token,
+        })
+    }
+
+    async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+        action: &str,
+    ) -> Result<reqwest::Response> {
+        let response = request.header("PRIVATE-TOKEN", &self.token).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let error = response.text().await.unwrap_or_default();
+            bail!("Failed to {action}: {status}: {error}");
+        }
+        Ok(response)
+    }
+
+    /// The unresolved discussions started by ci-manager, with the marker of their first note
+    async fn unresolved(&self) -> Result<Vec<(NoteMarker, Discussion)>> {
+        let mut unresolved = Vec::new();
+        for page in 1.. {
+            let url = format!("{}?per_page={PER_PAGE}&page={page}", self.url);
+            let discussions: Vec<Discussion> = self
+                .send(
+                    self.client.get(url),
+                    "list the discussions of the merge request",
+                )
+                .await?
+                .json()
+                .await?;
+            let last_page = discussions.len() < PER_PAGE;
+            for discussion in discussions {
+                let Some(first_note) = discussion.notes.first() else {
+                    continue;
+                };
+                if first_note.resolved {
+                    continue;
+                }
+                if let Some(marker) = NoteMarker::from_note_body(&first_note.body) {
+                    unresolved.push((marker, discussion));
+                }
+            }
+            if last_page {
+                break;
+            }
+        }
+        Ok(unresolved)
+    }
+
+    async fn start(&self, body: &str) -> Result<()> {
+        let request = self
+            .client
+            .post(&self.url)
+            .json(&serde_json::json!({ "body": body }));
+        self.send(request, "start a discussion on the merge request")
+            .await?;
+        Ok(())
+    }
+
+    async fn update(&self, discussion: &Discussion, body: &str) -> Result<()> {
+        let Some(note) = discussion.notes.first() else {
+            bail!("Discussion {} has no notes", discussion.id);
+        };
+        let url = format!("{}/{}/notes/{}", self.url, discussion.id, note.id);
+        let request = self
+            .client
+            .put(url)
+            .json(&serde_json::json!({ "body": body }));
+        self.send(request, "update the note on the merge request")
+            .await?;
+        Ok(())
+    }
+
+    async fn resolve(&self, discussion: &Discussion) -> Result<()> {
+        let url = format!("{}/{}?resolved=true", self.url, discussion.id);
+        self.send(
+            self.client.put(url),
+            "resolve the discussion on the merge request",
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+impl GitLab {
+    /// Comment the failure of a merge request pipeline on the merge request, or resolve the
+    /// comments about earlier pipelines once a pipeline of the merge request succeeds. In a dry run
+    /// the note is printed instead.
+    pub async fn comment_merge_request(&self, args: &CreateIssueFromRunArgs) -> Result<()> {
+        let (owner, repo) = repo_to_owner_repo_fragments(&args.repo)?;
+        let project = format!("{owner}/{repo}");
+        let pipeline_id: u64 = args.run_id.parse()?;
+        let pipeline = self.pipeline(&project, pipeline_id)?;
+        let Some(iid) = merge_request_iid(&pipeline.ref_) else {
+            bail!(
+                "Pipeline {pipeline_id} didn't run for a merge request ({ref_}), GitLab only supports commenting on merge requests yet",
+                ref_ = pipeline.ref_
+            );
+        };
+        let discussions = Discussions::new(&project, iid)?;
+        let unresolved = discussions.unresolved().await?;
+
+        if pipeline.status == "success" {
+            for (marker, discussion) in &unresolved {
+                if Config::global().dry_run() {
+                    println!(
+                        "DRY RUN MODE! The discussion about pipeline {pipeline} on merge request !{iid} would be resolved",
+                        pipeline = marker.pipeline_id
+                    );
+                    continue;
+                }
+                log::info!(
+                    "Pipeline {pipeline_id} succeeded, resolving the discussion about pipeline {pipeline} on merge request !{iid}",
+                    pipeline = marker.pipeline_id
+                );
+                discussions.resolve(discussion).await?;
+            }
+            return Ok(());
+        }
+
+        let body = self.failure_note(args, &project, &pipeline)?;
+        let (same_pipeline, earlier_pipelines): (Vec<_>, Vec<_>) = unresolved
+            .into_iter()
+            .partition(|(marker, _)| marker.pipeline_id == pipeline_id);
+        if Config::global().dry_run() {
+            match same_pipeline.first() {
+                Some(_) => println!(
+                    "DRY RUN MODE! The note about pipeline {pipeline_id} on merge request !{iid} would be updated with the body:"
+                ),
+                None => println!(
+                    "DRY RUN MODE! The following note would be posted on merge request !{iid}:"
+                ),
+            }
+            println!("{body}");
+            if !earlier_pipelines.is_empty() {
+                println!(
+                    "DRY RUN MODE! {num} discussion(s) about earlier pipelines would be resolved",
+                    num = earlier_pipelines.len()
+                );
+            }
+            return Ok(());
+        }
+
+        match same_pipeline.first() {
+            Some((_, discussion)) => {
+                log::info!(
+                    "Updating the note about pipeline {pipeline_id} on merge request !{iid}"
+                );
+                discussions.update(discussion, &body).await?;
+            }
+            None => {
+                log::info!("Commenting on merge request !{iid}");
+                discussions.start(&body).await?;
+            }
+        }
+        for (marker, discussion) in &earlier_pipelines {
+            if let Err(e) = discussions.resolve(discussion).await {
+                log::warn!(
+                    "Failed to resolve the discussion about pipeline {pipeline}: {e:#}",
+                    pipeline = marker.pipeline_id
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// The note with the summaries of the failed jobs of a pipeline, formatted like an issue body
+    fn failure_note(
+        &self,
+        args: &CreateIssueFromRunArgs,
+        project: &str,
+        pipeline: &Pipeline,
+    ) -> Result<String> {
+        let mut failed_jobs = Vec::new();
+        for (job, trace) in self.failed_job_traces(project, pipeline.id)? {
+            let trace = Config::global()
+                .config_file()
+                .redact
+                .apply(&trace)
+                .into_owned();
+            let error_message = parse_error_message(&trace, args.issue.kind.for_job(&job.name))?;
+            failed_jobs.push(FailedJob::new(
+                job.name,
+                job.id.to_string(),
+                job.web_url,
+                FirstFailedStep::StepName(job.stage),
+                error_message,
+            ));
+        }
+        let mut issue = Issue::new(
+            args.issue.title.clone(),
+            pipeline.id.to_string(),
+            pipeline.web_url.clone(),
+            failed_jobs,
+            args.issue.label[0].clone(),
+        );
+        issue.set_body_limits(BodyLimits::GITLAB);
+        let marker = NoteMarker {
+            pipeline_id: pipeline.id,
+        };
+        let body = issue.body();
+        Ok(format!(
+            "### {title}\n\n{body}\n\n{marker}",
+            title = issue.title()
+        ))
+    }
+}
@@ -1,15 +1,61 @@
-use time::{format_description::well_known, OffsetDateTime};
+use std::str::FromStr;
+use time::{format_description::well_known, macros::format_description, OffsetDateTime};
 
 use crate::*;
 
 /// Type representing a date in the format `YYYY-MM-DD`.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Date {
     pub year: u16,
     pub month: u8,
     pub day: u8,
 }
 
+impl Date {
+    /// The date `days` days before today (UTC)
+    pub fn days_ago(days: u32) -> Self {
+        (OffsetDateTime::now_utc() - time::Duration::days(i64::from(days)))
+            .date()
+            .into()
+    }
+
+    /// The date (UTC) of a unix timestamp
+    pub fn from_timestamp(unix_timestamp: i64) -> Result<Self> {
+        Ok(OffsetDateTime::from_unix_timestamp(unix_timestamp)?
+            .date()
+            .into())
+    }
+}
+
+impl From<time::Date> for Date {
+    fn from(date: time::Date) -> Self {
+        Self {
+            year: date.year() as u16,
+            month: u8::from(date.month()),
+            day: date.day(),
+        }
+    }
+}
+
+/// Parse a date in the format `YYYY-MM-DD`
+///
+/// # Example
+/// ```
+/// # use ci_manager::ci_provider::util::Date;
+/// let date: Date = "2024-02-18".parse().unwrap();
+/// assert_eq!(date, Date { year: 2024, month: 2, day: 18 });
+/// assert!("18-02-2024".parse::<Date>().is_err());
+/// ```
+impl FromStr for Date {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let date = time::Date::parse(s, format_description!("[year]-[month]-[day]"))
+            .with_context(|| format!("Invalid date: {s}, expected the format YYYY-MM-DD"))?;
+        Ok(date.into())
+    }
+}
+
 impl fmt::Display for Date {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let Date { year, month, day } = self;
@@ -22,6 +68,12 @@ impl fmt::Display for Date {
 pub enum DateFilter {
     Created(Date),
     Updated(Date),
+    /// Closed on or after the date
+    ClosedSince(Date),
+    /// Last updated before the date
+    UpdatedBefore(Date),
+    /// Last updated on or after the date
+    UpdatedSince(Date),
     None,
 }
 
@@ -30,6 +82,9 @@ impl fmt::Display for DateFilter {
         match self {
             DateFilter::Created(date) => write!(f, "created:{date}"),
             DateFilter::Updated(date) => write!(f, "updated:{date}"),
+            DateFilter::ClosedSince(date) => write!(f, "closed:>={date}"),
+            DateFilter::UpdatedBefore(date) => write!(f, "updated:<{date}"),
+            DateFilter::UpdatedSince(date) => write!(f, "updated:>={date}"),
             DateFilter::None => f.write_str(""), // No date filter
         }
     }
@@ -173,6 +228,26 @@ mod tests {
         assert_eq!(date.to_string(), "2021-06-02");
     }
 
+    #[test]
+    fn test_date_from_timestamp() {
+        let date = Date::from_timestamp(1_709_208_000).unwrap();
+        assert_eq!(
+            date,
+            Date {
+                year: 2024,
+                month: 2,
+                day: 29,
+            }
+        );
+    }
+
+    #[test]
+    fn test_date_ordering() {
+        let date: Date = "2024-02-29".parse().unwrap();
+        assert!(date < "2024-03-01".parse().unwrap());
+        assert!(date > "2023-12-31".parse().unwrap());
+    }
+
     #[test]
     fn test_date_filter_display() {
         let date = Date {
@@ -184,6 +259,39 @@ mod tests {
         assert_eq!(date_filter.to_string(), "created:2021-06-02");
     }
 
+    #[test]
+    fn test_date_filter_closed_since_display() {
+        let date = Date {
+            year: 2024,
+            month: 2,
+            day: 18,
+        };
+        let date_filter = DateFilter::ClosedSince(date);
+        assert_eq!(date_filter.to_string(), "closed:>=2024-02-18");
+    }
+
+    #[test]
+    fn test_date_filter_updated_before_display() {
+        let date = Date {
+            year: 2024,
+            month: 11,
+            day: 3,
+        };
+        let date_filter = DateFilter::UpdatedBefore(date);
+        assert_eq!(date_filter.to_string(), "updated:<2024-11-03");
+    }
+
+    #[test]
+    fn test_date_filter_updated_since_display() {
+        let date = Date {
+            year: 2024,
+            month: 11,
+            day: 3,
+        };
+        let date_filter = DateFilter::UpdatedSince(date);
+        assert_eq!(date_filter.to_string(), "updated:>=2024-11-03");
+    }
+
     #[test]
     fn test_label_filter_any_display() {
         let label_filter = LabelFilter::Any(["kind/bug", "area/bake"]);
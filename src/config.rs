@@ -1,14 +1,20 @@
 use super::*;
-use commands::Command;
+use commands::{Command, FailureLogFormat, LogFormat, OutputFormat, ResultFormat};
+use file::ConfigFile;
+use tracing_subscriber::layer::SubscriberExt;
 
 pub mod commands;
+pub mod file;
 
 pub static CONFIG: OnceLock<Config> = OnceLock::new();
 
+/// Prefix of the environment variables every option can be set with, e.g. `CI_MANAGER_DRY_RUN`
+pub const ENV_PREFIX: &str = "CI_MANAGER_";
+
 #[derive(Parser, Debug)]
 #[command(name = "CI manager")]
 #[command(bin_name = "ci-manager", version, propagate_version = true, author, styles = config_styles())]
-#[command(about = "Manage CI")]
+#[command(about = "Manage CI", after_long_help = crate::exit_code::EXIT_CODES_HELP)]
 pub struct Config {
     #[command(subcommand)]
     command: Option<Command>,
@@ -18,6 +24,19 @@ pub struct Config {
     /// Verbosity level (0-4)
     #[arg(short, long, global = true, default_value_t = 2)]
     verbosity: u8,
+    /// Don't log anything, only print the result, e.g. the URL of the issue tracking the failure of
+    /// `create-issue-from-run` or the path found by `locate-failure-log`
+    #[arg(
+        short,
+        long,
+        global = true,
+        default_value_t = false,
+        conflicts_with = "verbosity"
+    )]
+    quiet: bool,
+    /// Format of the log messages on stderr
+    #[arg(long, value_enum, global = true, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
     /// Debug flag to run through a scenario without making changes
     #[arg(long, default_value_t = false, global = true)]
     dry_run: bool,
@@ -30,9 +49,41 @@ pub struct Config {
     /// Trim the ansi codes from from the log output
     #[arg(long, global = true, default_value_t = false)]
     trim_ansi_codes: bool,
+    /// TOML configuration file, e.g. with custom parsers in a `[[parsers]]` section
+    #[arg(long, global = true, value_hint = ValueHint::FilePath)]
+    config_file: Option<PathBuf>,
+    /// External parser: a program that gets the failed step log on stdin and writes the parsed
+    /// error as JSON (`summary`, `label` and optional `logfile` with `name` and `contents`) to
+    /// stdout, or nothing (or `null`) to fall back to the built-in parsers
+    #[arg(long, global = true, value_hint = ValueHint::ExecutablePath)]
+    parser_cmd: Option<PathBuf>,
+    /// Export traces and metrics of the invocation to this OpenTelemetry collector (OTLP over
+    /// gRPC), e.g. `http://localhost:4317`
+    #[arg(long, global = true, value_name = "URL")]
+    otel_endpoint: Option<String>,
+    /// SQLite database that every run analyzed by `create-issue-from-run` is recorded in, with its
+    /// failed jobs, fingerprints and issue. Created if it doesn't exist, see the `history` command
+    #[arg(long, global = true, value_hint = ValueHint::FilePath)]
+    history_db: Option<PathBuf>,
+    /// Print the result of the subcommand in a machine-readable format on stdout, while the logs
+    /// stay on stderr: the report of `create-issue-from-run` with the issue URL, the decision and
+    /// the failure labels, the results of `create-issues-from-runs`, the located paths of
+    /// `locate-failure-log`, and the `--format json` output of the subcommands that list
+    /// information. The previews of dry runs are printed to stdout as well.
+    #[arg(long, value_enum, global = true)]
+    output_format: Option<ResultFormat>,
+    /// The contents of `config_file`, read by [init]
+    #[arg(skip)]
+    config_file_contents: ConfigFile,
 }
 
 impl Config {
+    /// The command line interface, with every option also read from its environment variable, see
+    /// [env_var_name]. Options given on the command line take precedence.
+    pub fn command_with_env() -> clap::Command {
+        with_env_vars(<Self as CommandFactory>::command())
+    }
+
     /// Get a reference to the global config
     pub fn global() -> &'static Config {
         CONFIG.get().expect("Config is not initialized")
@@ -65,6 +116,16 @@ impl Config {
         self.verbosity
     }
 
+    /// Get the quiet flag
+    pub fn quiet(&self) -> bool {
+        self.quiet
+    }
+
+    /// Get the log format
+    pub fn log_format(&self) -> LogFormat {
+        self.log_format
+    }
+
     /// Get the dry run flag
     pub fn dry_run(&self) -> bool {
         self.dry_run
@@ -84,35 +145,135 @@ impl Config {
     pub fn trim_ansi_codes(&self) -> bool {
         self.trim_ansi_codes
     }
+
+    /// Get the external parser command
+    pub fn parser_cmd(&self) -> Option<&Path> {
+        self.parser_cmd.as_deref()
+    }
+
+    /// Get the OpenTelemetry collector endpoint
+    pub fn otel_endpoint(&self) -> Option<&str> {
+        self.otel_endpoint.as_deref()
+    }
+
+    /// Get the history database
+    pub fn history_db(&self) -> Option<&Path> {
+        self.history_db.as_deref()
+    }
+
+    /// Get the machine-readable output format
+    pub fn output_format(&self) -> Option<ResultFormat> {
+        self.output_format
+    }
+
+    /// The output format of a subcommand that lists information, JSON with `--output-format json`
+    pub fn list_format(&self, format: OutputFormat) -> OutputFormat {
+        match self.output_format {
+            Some(ResultFormat::Json) => OutputFormat::Json,
+            None => format,
+        }
+    }
+
+    /// The output format of `locate-failure-log`, JSON with `--output-format json`
+    pub fn failure_log_format(&self, format: FailureLogFormat) -> FailureLogFormat {
+        match self.output_format {
+            Some(ResultFormat::Json) => FailureLogFormat::Json,
+            None => format,
+        }
+    }
+
+    /// Get the contents of the configuration file, empty if no configuration file was given
+    pub fn config_file(&self) -> &ConfigFile {
+        &self.config_file_contents
+    }
 }
 
 /// Initialize the CLI configuration
 pub fn init() -> Result<()> {
-    let config = Config::parse();
+    let matches = Config::command_with_env().get_matches();
+    let mut config = Config::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if let Some(path) = &config.config_file {
+        config.config_file_contents = ConfigFile::from_file(path)?;
+    }
     CONFIG.set(config).expect("Config is already initialized");
 
     use stderrlog::LogLevelNum;
-    let log_level = match Config::global().verbosity() {
+    let log_level = log_level(Config::global().verbosity(), Config::global().quiet());
+    match Config::global().log_format() {
+        LogFormat::Text => stderrlog::new()
+            .verbosity(log_level)
+            .quiet(Config::global().quiet())
+            .init()?,
+        LogFormat::Json => {
+            let max_level = match log_level {
+                LogLevelNum::Off => log::LevelFilter::Off,
+                LogLevelNum::Error => log::LevelFilter::Error,
+                LogLevelNum::Warn => log::LevelFilter::Warn,
+                LogLevelNum::Info => log::LevelFilter::Info,
+                LogLevelNum::Debug => log::LevelFilter::Debug,
+                LogLevelNum::Trace => log::LevelFilter::Trace,
+            };
+            // The log messages are forwarded to the JSON layer of the tracing subscriber, which is
+            // installed by the telemetry instead if it is exported
+            tracing_log::LogTracer::builder()
+                .with_max_level(max_level)
+                .init()?;
+            if Config::global().otel_endpoint().is_none() {
+                tracing::subscriber::set_global_default(
+                    tracing_subscriber::registry().with(json_log_layer()),
+                )?;
+            }
+        }
+    }
+
+    log::debug!("Config: {:#?}", Config::global());
+
+    if Config::global().dry_run() {
+        log::warn!("Running in dry-run mode. No writes/changes will be made");
+    }
+
+    Ok(())
+}
+
+/// The log level of `--verbosity`, nothing is logged with `--quiet`
+fn log_level(verbosity: u8, quiet: bool) -> stderrlog::LogLevelNum {
+    use stderrlog::LogLevelNum;
+    match verbosity {
+        _ if quiet => LogLevelNum::Off,
         0 => LogLevelNum::Error,
         1 => LogLevelNum::Warn,
         2 => LogLevelNum::Info,
         3 => LogLevelNum::Debug,
         4 => LogLevelNum::Trace,
         _ => {
-            eprintln!("Invalid verbosity level: {}", Config::global().verbosity());
+            eprintln!("Invalid verbosity level: {verbosity}");
             eprintln!("Using highest verbosity level: Trace");
             LogLevelNum::Trace
         }
-    };
-    stderrlog::new().verbosity(log_level).quiet(false).init()?;
-
-    log::debug!("Config: {:#?}", Config::global());
-
-    if Config::global().dry_run() {
-        log::warn!("Running in dry-run mode. No writes/changes will be made");
     }
+}
 
-    Ok(())
+/// The layer of the tracing subscriber that writes the log messages to stderr as JSON with
+/// `--log-format json`, `None` otherwise
+pub(crate) fn json_log_layer<S>() -> Option<impl tracing_subscriber::Layer<S>>
+where
+    S: tracing::Subscriber + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+{
+    (Config::global().log_format() == LogFormat::Json).then(|| json_layer(io::stderr))
+}
+
+/// The layer that writes every log message to `make_writer` as a JSON object on its own line
+fn json_layer<S, W>(make_writer: W) -> impl tracing_subscriber::Layer<S>
+where
+    S: tracing::Subscriber + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+    W: for<'w> tracing_subscriber::fmt::MakeWriter<'w> + 'static,
+{
+    tracing_subscriber::fmt::layer()
+        .json()
+        .flatten_event(true)
+        .with_current_span(true)
+        .with_span_list(true)
+        .with_writer(make_writer)
 }
 
 // Styles for the help messages in the CLI
@@ -129,8 +290,133 @@ fn generate_completion_script(shell: clap_complete::Shell) {
     log::info!("Generating completion script for {shell:?}");
     clap_complete::generate(
         shell,
-        &mut <Config as clap::CommandFactory>::command(),
+        &mut Config::command_with_env(),
         "ci-manager",
         &mut std::io::stdout(),
     );
 }
+
+/// The environment variable an option can be set with, from its long flag
+///
+/// # Example
+/// ```
+/// # use ci_manager::config::env_var_name;
+/// assert_eq!(env_var_name("dry-run"), "CI_MANAGER_DRY_RUN");
+/// assert_eq!(env_var_name("kind"), "CI_MANAGER_KIND");
+/// ```
+pub fn env_var_name(long: &str) -> String {
+    format!("{ENV_PREFIX}{}", long.replace('-', "_").to_uppercase())
+}
+
+/// Let every option of `command` and its subcommands be set with its environment variable, unless
+/// it already has one. Positional arguments are left out.
+fn with_env_vars(command: clap::Command) -> clap::Command {
+    let mut command = command.mut_args(|arg| {
+        if arg.get_env().is_some() {
+            return arg;
+        }
+        match arg.get_long().map(env_var_name) {
+            Some(name) => arg.env(name),
+            None => arg,
+        }
+    });
+    let subcommands: Vec<String> = command
+        .get_subcommands()
+        .map(|subcmd| subcmd.get_name().to_owned())
+        .collect();
+    for name in subcommands {
+        command = command.mut_subcommand(name, with_env_vars);
+    }
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_every_option_has_an_env_var() {
+        let command = Config::command_with_env();
+        let dry_run = command
+            .get_arguments()
+            .find(|arg| arg.get_id() == "dry_run")
+            .unwrap();
+        assert_eq!(
+            dry_run.get_env(),
+            Some(std::ffi::OsStr::new("CI_MANAGER_DRY_RUN"))
+        );
+        let create_issue = command.find_subcommand("create-issue-from-run").unwrap();
+        for arg in create_issue.get_arguments() {
+            if let Some(long) = arg.get_long() {
+                assert_eq!(
+                    arg.get_env(),
+                    Some(std::ffi::OsStr::new(&env_var_name(long))),
+                    "--{long}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_quiet_turns_off_logging() {
+        for verbosity in 0..=4 {
+            assert!(matches!(
+                log_level(verbosity, true),
+                stderrlog::LogLevelNum::Off
+            ));
+        }
+        assert!(matches!(log_level(2, false), stderrlog::LogLevelNum::Info));
+    }
+
+    #[test]
+    fn test_quiet_conflicts_with_verbosity() {
+        assert!(Config::try_parse_from(["ci-manager", "--quiet", "-v", "3"]).is_err());
+        let config = Config::try_parse_from(["ci-manager", "-q"]).unwrap();
+        assert!(config.quiet());
+    }
+
+    /// Collects what the JSON log layer writes
+    #[derive(Clone, Default)]
+    struct LogBuffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_json_log_records_are_json_objects() {
+        let buffer = LogBuffer::default();
+        let writer = buffer.clone();
+        let subscriber = tracing_subscriber::registry().with(json_layer(move || writer.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("create_issue_from_run", run_id = 42, repo = "a/b");
+            let _entered = span.enter();
+            tracing::info!("Creating issue with \"quotes\"\nand a newline");
+            tracing::warn!(attempt = 2, "Retrying");
+        });
+
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let records: Vec<serde_json::Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["level"], "INFO");
+        assert_eq!(
+            records[0]["message"],
+            "Creating issue with \"quotes\"\nand a newline"
+        );
+        assert_eq!(records[0]["span"]["run_id"], 42);
+        assert_eq!(records[0]["span"]["repo"], "a/b");
+        assert_eq!(records[1]["level"], "WARN");
+        assert_eq!(records[1]["attempt"], 2);
+        assert!(records.iter().all(|record| record["target"].is_string()));
+    }
+}
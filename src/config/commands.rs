@@ -1,31 +1,393 @@
 //! The `commands` module contains the subcommands for the `gh-workflow-parser` CLI.
 
-use crate::*;
+use crate::{
+    ci_provider::{
+        github::{
+            projects::ProjectRef,
+            runs::{DEFAULT_POLL_INTERVAL, DEFAULT_WAIT_TIMEOUT},
+        },
+        util::Date,
+    },
+    err_parse::FailureClass,
+    issue::{excerpt::ContextExcerpt, similarity::SimilarityAlgo, BodyLimits},
+    notify::NotifyTarget,
+    tracker::redmine::CustomFieldValue,
+    *,
+};
+use locate_failure_log::PathMap;
+use std::time::Duration;
 
+pub mod analyze_buildstats;
+pub mod cluster_failures;
+pub mod history;
 pub mod locate_failure_log;
+pub mod replay;
 
-#[derive(Debug, Subcommand)]
+#[derive(Debug, Subcommand, IntoStaticStr)]
+#[strum(serialize_all = "kebab-case")]
 pub enum Command {
     /// Create an issue from a failed CI run
-    CreateIssueFromRun {
-        /// The repository to parse
+    CreateIssueFromRun(CreateIssueFromRunArgs),
+
+    /// Create issues from several failed CI runs, of one or more repositories
+    ///
+    /// The runs are processed concurrently and a line with the result of every run is printed. Exits
+    /// with an error if the issue couldn't be created from any of the runs.
+    CreateIssuesFromRuns(CreateIssuesFromRunsArgs),
+
+    /// Close open issues created by ci-manager whose failure no longer reproduces in a successful run
+    CloseResolvedIssues {
+        /// The repository
         #[arg(long, value_hint = ValueHint::Url)]
         repo: String,
-        /// The workflow run ID
+        /// ID of a successful workflow run. If not provided, the most recent completed run of the
+        /// workflow each issue was created from is used
         #[arg(short = 'r', long)]
-        run_id: String,
-        /// The issue label
+        run_id: Option<String>,
+        /// Only consider issues with this label
         #[arg(short, long)]
         label: String,
+        /// The kind of workflow the issues were created from (e.g. Yocto), to compare the failures
+        /// of a failed run with the failures of the issues
+        #[arg(short, long)]
+        kind: WorkflowKind,
+    },
+
+    /// Close issues created by ci-manager that have had no activity for a number of days
+    IssueGc {
+        /// The repository
+        #[arg(long, value_hint = ValueHint::Url)]
+        repo: String,
+        /// Number of days without activity before an issue is considered stale
+        #[arg(long, value_name = "DAYS", default_value_t = 30)]
+        stale_after: u32,
+        /// Only consider issues with this label (can be repeated)
+        #[arg(short, long)]
+        label: Vec<String>,
+    },
+
+    /// List failed workflow runs of a repository
+    ListFailedRuns {
+        /// The repository
+        #[arg(long, value_hint = ValueHint::Url)]
+        repo: String,
+        #[command(flatten)]
+        filter: RunFilterArgs,
+        /// Maximum number of runs to list
+        #[arg(long, default_value_t = 30)]
+        limit: usize,
+        /// Output format. `csv` prints one row per failed job instead, with the run ID, date,
+        /// workflow, job, failure label and the issue created from the run
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+
+    /// Report failure statistics over the most recent runs of a workflow
+    Stats(StatsArgs),
+
+    /// Wait for a CI run to complete and print its conclusion
+    WatchRun {
+        /// The repository
+        #[arg(long, value_hint = ValueHint::Url)]
+        repo: String,
+        /// The workflow run ID
+        #[arg(short = 'r', long)]
+        run_id: String,
+        #[command(flatten)]
+        wait: WaitArgs,
+        #[command(subcommand)]
+        then: Option<WatchRunThen>,
+    },
+
+    /// Find the commit that broke a workflow by bisecting the commits since the last successful run
+    ///
+    /// The workflow is dispatched on temporary branches, so it must have a `workflow_dispatch` trigger
+    BisectRun {
+        /// The repository
+        #[arg(long, value_hint = ValueHint::Url)]
+        repo: String,
+        /// ID of the failed workflow run
+        #[arg(short = 'r', long)]
+        run_id: String,
+        #[command(flatten)]
+        wait: WaitArgs,
+        #[command(subcommand)]
+        then: Option<WatchRunThen>,
+    },
+
+    /// Print a Markdown summary of the failures in a CI run, without creating an issue
+    SummarizeRun {
+        /// The repository
+        #[arg(long, value_hint = ValueHint::Url)]
+        repo: String,
+        /// The workflow run ID
+        #[arg(short = 'r', long)]
+        run_id: String,
         /// The kind of workflow (e.g. Yocto)
+        #[arg(short, long, value_enum, default_value_t = WorkflowKind::Auto)]
+        kind: WorkflowKind,
+        /// Append the summary to the file in `$GITHUB_STEP_SUMMARY` instead of printing it to stdout
+        #[arg(long, default_value_t = false)]
+        step_summary: bool,
+        /// Also write the summary to FILE in the `--report-format`, e.g. `--report failures.xml`
+        #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+        report: Option<PathBuf>,
+        /// Format of the `--report` file
+        #[arg(long, value_enum, default_value_t = ReportFormat::Junit, requires = "report")]
+        report_format: ReportFormat,
+    },
+
+    /// Compare the failures of two CI runs
+    ///
+    /// Reports which jobs newly failed, which were fixed, and a diff of the parsed error summaries
+    /// of the jobs that failed in both runs
+    CompareRuns {
+        /// The repository
+        #[arg(long, value_hint = ValueHint::Url)]
+        repo: String,
+        /// ID of the run to compare against (e.g. the run before the fix)
+        old_run_id: String,
+        /// ID of the run to compare (e.g. the run after the fix)
+        new_run_id: String,
+        /// The kind of workflow (e.g. Yocto)
+        #[arg(short, long, value_enum, default_value_t = WorkflowKind::Auto)]
+        kind: WorkflowKind,
+    },
+
+    /// Re-run the failed jobs of a CI run
+    RerunFailedJobs {
+        /// The repository
+        #[arg(long, value_hint = ValueHint::Url)]
+        repo: String,
+        /// The workflow run ID (pipeline ID on GitLab)
+        #[arg(short = 'r', long)]
+        run_id: String,
+        /// Only re-run the failed jobs if the failure is classified as this class
+        #[arg(long, value_enum)]
+        if_classified_as: Option<FailureClass>,
+    },
+
+    /// Download the logs of a CI run (or a single job) into a local directory
+    ///
+    /// Use `--trim-timestamp` and `--trim-ansi-codes` to clean up the logs before they are written
+    DownloadLogs {
+        /// The repository
+        #[arg(long, value_hint = ValueHint::Url)]
+        repo: String,
+        /// The workflow run ID
+        #[arg(short = 'r', long)]
+        run_id: String,
+        /// Only download the log of the job with this ID
         #[arg(short, long)]
+        job_id: Option<u64>,
+        /// Directory to write the logs to [default: logs-<RUN_ID>]
+        #[arg(short, long, value_hint = ValueHint::DirPath)]
+        output_dir: Option<PathBuf>,
+    },
+
+    /// Export the metadata, failed jobs, raw logs and parsed error summaries of a CI run as JSON
+    ExportRun {
+        /// The repository
+        #[arg(long, value_hint = ValueHint::Url)]
+        repo: String,
+        /// The workflow run ID
+        #[arg(short = 'r', long)]
+        run_id: String,
+        /// The kind of workflow (e.g. Yocto), determines how the error summaries are parsed
+        #[arg(short, long, value_enum, default_value_t = WorkflowKind::Auto)]
+        kind: WorkflowKind,
+        /// File to write the JSON archive to (if not provided, writes to stdout)
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        output: Option<PathBuf>,
+    },
+
+    /// Build an issue from a run archive created with `export-run` (or a directory of log files)
+    /// and print it, without contacting the CI provider
+    Replay {
+        /// The run archive or directory of log files
+        #[arg(value_hint = ValueHint::AnyPath)]
+        input: PathBuf,
+        /// The kind of workflow (e.g. Yocto)
+        #[arg(short, long, value_enum, default_value_t = WorkflowKind::Auto)]
         kind: WorkflowKind,
         /// Title of the issue
-        #[arg(short, long)]
+        #[arg(short, long, default_value = "Replayed CI failure")]
         title: String,
-        /// Don't create the issue if a similar issue already exists
-        #[arg(short, long, default_value_t = true)]
-        no_duplicate: bool,
+        /// The issue label
+        #[arg(short, long, default_value = "CI")]
+        label: String,
+    },
+
+    /// Group similar failures of the issues created by ci-manager, or of run archives created with
+    /// `export-run`, and print the largest groups to find the most common CI problems
+    ///
+    /// Failures are grouped by their fingerprint, or else by the similarity of their issue bodies
+    ClusterFailures {
+        /// The repository whose issues are clustered
+        #[arg(long, value_hint = ValueHint::Url, required_unless_present = "archives", conflicts_with = "archives")]
+        repo: Option<String>,
+        /// Run archive, or directory of run archives, to cluster instead of issues. Can be repeated
+        #[arg(long = "archive", value_name = "PATH", value_hint = ValueHint::AnyPath)]
+        archives: Vec<PathBuf>,
+        /// Only consider issues with this label (can be repeated)
+        #[arg(short, long)]
+        label: Vec<String>,
+        /// The kind of workflow (e.g. Yocto), used to parse the logs of run archives
+        #[arg(short, long, value_enum, default_value_t = WorkflowKind::Auto)]
+        kind: WorkflowKind,
+        /// How the bodies of failures without the same fingerprint are compared
+        #[arg(long, value_enum, default_value_t = SimilarityAlgo::Levenshtein)]
+        similarity_algo: SimilarityAlgo,
+        /// Maximum distance of similar failures, see `create-issue-from-run --help`
+        #[arg(long, value_name = "DISTANCE")]
+        similarity_threshold: Option<f64>,
+        /// Number of the largest groups to print
+        #[arg(long, value_name = "N", default_value_t = 10)]
+        top: usize,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+
+    /// Report the runs recorded in the `--history-db` database: what was done about them, the
+    /// failure labels of their failed jobs, and the job failures that recur across runs
+    ///
+    /// Works offline and across repositories and CI providers. Query the database with SQL for
+    /// anything else, it has a `runs` and a `failed_jobs` table.
+    History {
+        /// Only report the runs recorded within the last `DAYS` days
+        #[arg(long, value_name = "DAYS")]
+        days: Option<u64>,
+        /// Number of recurring job failures to report
+        #[arg(long, value_name = "N", default_value_t = 10)]
+        top: usize,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+
+    /// Report the slowest tasks of a Yocto build and the build time compared to the previous
+    /// build, from the buildstats BitBake writes with the `buildstats` class
+    AnalyzeBuildstats {
+        /// The buildstats directory of a build, or `tmp/buildstats` to analyze the most recent
+        /// build in it
+        #[arg(value_hint = ValueHint::DirPath)]
+        buildstats: PathBuf,
+        /// The buildstats of the previous build to compare with, e.g. from an artifact of the
+        /// previous run
+        #[arg(short, long, value_hint = ValueHint::DirPath)]
+        previous: Option<PathBuf>,
+        /// Number of the slowest tasks to report
+        #[arg(long, value_name = "N", default_value_t = 10)]
+        top: usize,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+    },
+
+    /// Cancel queued and in-progress runs that are too old or superseded by newer runs
+    CancelStaleRuns {
+        /// The repository
+        #[arg(long, value_hint = ValueHint::Url)]
+        repo: String,
+        /// Only cancel runs of this workflow (file name e.g. build.yml, or ID)
+        #[arg(short, long)]
+        workflow: Option<String>,
+        /// Cancel runs that started more than this many minutes ago
+        #[arg(long, value_name = "MINUTES")]
+        older_than: Option<u64>,
+        /// Cancel runs superseded by a newer queued or in-progress run of the same workflow on the same branch
+        #[arg(long, default_value_t = false)]
+        superseded: bool,
+    },
+
+    /// Delete workflow artifacts that are old or large, in a repository or across an organization
+    CleanupArtifacts {
+        /// The repository
+        #[arg(long, value_hint = ValueHint::Url, required_unless_present = "org", conflicts_with = "org")]
+        repo: Option<String>,
+        /// Clean up the artifacts of every repository in this organization
+        #[arg(long)]
+        org: Option<String>,
+        /// Delete artifacts created more than this many days ago
+        #[arg(long, value_name = "DAYS")]
+        older_than: Option<u32>,
+        /// Delete artifacts larger than this many megabytes
+        #[arg(long, value_name = "MB")]
+        larger_than: Option<u64>,
+    },
+
+    /// Check that the configured token can do what ci-manager needs in a repository
+    ///
+    /// Reports the token scopes, the rate limit budget, whether issues can be created and whether
+    /// workflow logs can be downloaded
+    Doctor {
+        /// The repository
+        #[arg(long, value_hint = ValueHint::Url)]
+        repo: String,
+    },
+
+    /// Run a webhook server that creates an issue for every failed run reported by a `workflow_run` webhook
+    ///
+    /// The webhook secret is read from the `GITHUB_WEBHOOK_SECRET` environment variable, which must not be empty
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "0.0.0.0:8080")]
+        listen: std::net::SocketAddr,
+        #[command(flatten)]
+        issue: CreateIssueArgs,
+    },
+
+    /// Create an issue for every failed run of a scheduled workflow in the repositories of an
+    /// organization
+    ScanOrg {
+        /// The organization
+        #[arg(long)]
+        org: String,
+        /// Only scan the repositories with this topic
+        #[arg(long)]
+        topic: Option<String>,
+        /// Only include runs created on or after this date (YYYY-MM-DD)
+        #[arg(long)]
+        since: Date,
+        /// Only include runs triggered by this event
+        #[arg(long, default_value = "schedule")]
+        event: String,
+        #[command(flatten)]
+        issue: CreateIssueArgs,
+    },
+
+    /// Collect the runs that failed in the last day or week into a single digest issue, instead of
+    /// an issue per failed run
+    ///
+    /// An open digest issue for the same period is updated instead of creating a new one
+    Digest {
+        /// The repository
+        #[arg(long, value_hint = ValueHint::Url)]
+        repo: String,
+        /// The time window of the digest
+        #[arg(long, value_enum, default_value_t = DigestPeriod::Daily)]
+        period: DigestPeriod,
+        /// Only include runs of the workflow with this name
+        #[arg(short, long)]
+        workflow: Option<String>,
+        /// Only include runs on this branch
+        #[arg(short, long)]
+        branch: Option<String>,
+        /// The kind of workflow (e.g. Yocto), used to determine the failure kinds
+        #[arg(short, long, value_enum, default_value_t = WorkflowKind::Auto)]
+        kind: WorkflowKind,
+        /// The label of the digest issue
+        #[arg(short, long, default_value = "CI digest")]
+        label: String,
+    },
+
+    /// Periodically scan the repositories in a configuration file on a cron schedule and create
+    /// an issue for every new failed run
+    Monitor {
+        /// Path to the TOML configuration file with the schedule and the repositories to scan
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        config: PathBuf,
     },
 
     /// Locate the specific failure log in a failed build/test/other
@@ -37,16 +399,644 @@ pub enum Command {
         /// File to operate on (if not provided, reads from stdin)
         #[arg(short = 'f', long, value_hint = ValueHint::FilePath)]
         input_file: Option<PathBuf>,
+        /// Output format
+        #[arg(long, value_enum, default_value_t = FailureLogFormat::Path)]
+        format: FailureLogFormat,
+        /// Print every located failure log, one per line (a JSON array with `--format json`),
+        /// instead of only the first
+        #[arg(long, default_value_t = false)]
+        all: bool,
+        /// Terminate the paths printed with `--all` with a NUL character instead of a newline,
+        /// e.g. for `xargs -0`
+        #[arg(short = '0', long = "null", requires = "all")]
+        null: bool,
+        /// Directory to look for the failure logs in, if they are not at the path in the log, e.g.
+        /// the build directory of a build that ran in a container. Can be repeated
+        #[arg(long, value_name = "DIR", value_hint = ValueHint::DirPath)]
+        search_root: Vec<PathBuf>,
+        /// Replace a prefix of the paths in the log, e.g. `/app=/home/runner/work/repo` if the
+        /// repository was mounted at `/app` in a container. Can be repeated
+        #[arg(long, value_name = "FROM=TO")]
+        path_map: Vec<PathMap>,
     },
 }
 
+/// Arguments for the `create-issue-from-run` subcommand
+#[derive(Debug, Clone, Args)]
+pub struct CreateIssueFromRunArgs {
+    /// The repository to parse
+    #[arg(long, value_hint = ValueHint::Url)]
+    pub repo: String,
+    /// The workflow run ID
+    #[arg(short = 'r', long)]
+    pub run_id: String,
+    /// Wait for the run to complete before looking for failed jobs
+    #[arg(long, default_value_t = false)]
+    pub wait_for_completion: bool,
+    #[command(flatten)]
+    pub wait: WaitArgs,
+    #[command(flatten)]
+    pub issue: CreateIssueArgs,
+}
+
+/// Arguments for the `create-issues-from-runs` subcommand
+#[derive(Debug, Clone, Args)]
+pub struct CreateIssuesFromRunsArgs {
+    /// The repository of the runs. Give it once for runs of the same repository, or once per
+    /// `--run-id` in the same order
+    #[arg(long, value_hint = ValueHint::Url)]
+    pub repo: Vec<String>,
+    /// A workflow run ID (can be repeated)
+    #[arg(short = 'r', long)]
+    pub run_id: Vec<String>,
+    /// File with more runs, a `REPO RUN_ID` pair per line. Empty lines and lines starting with `#`
+    /// are skipped
+    #[arg(long, value_hint = ValueHint::FilePath)]
+    pub manifest: Option<PathBuf>,
+    /// Maximum number of runs processed at the same time
+    #[arg(long, value_name = "N", default_value_t = 4)]
+    pub max_concurrent: usize,
+    /// Wait for the runs to complete before looking for failed jobs
+    #[arg(long, default_value_t = false)]
+    pub wait_for_completion: bool,
+    #[command(flatten)]
+    pub wait: WaitArgs,
+    #[command(flatten)]
+    pub issue: CreateIssueArgs,
+}
+
+/// Options for creating an issue from a failed CI run
+#[derive(Debug, Clone, Args)]
+pub struct CreateIssueArgs {
+    /// The issue label (can be repeated)
+    #[arg(short, long, required = true)]
+    pub label: Vec<String>,
+    /// The kind of workflow (e.g. Yocto)
+    #[arg(short, long, value_enum, default_value_t = WorkflowKind::Auto)]
+    pub kind: WorkflowKind,
+    /// Title of the issue
+    #[arg(short, long)]
+    pub title: String,
+    /// Don't create the issue if an issue already exists for a failure with the same fingerprint,
+    /// or else with a similar body
+    #[arg(short, long, default_value_t = true)]
+    pub no_duplicate: bool,
+    /// How the bodies of issues are compared to find a similar issue
+    #[arg(long, value_enum, default_value_t = SimilarityAlgo::Levenshtein)]
+    pub similarity_algo: SimilarityAlgo,
+    /// Maximum distance of similar issue bodies, in characters for `levenshtein` (default 100),
+    /// and between 0 and 1 for `jaccard` (default 0.1) and `cosine` (default 0.05)
+    #[arg(long, value_name = "DISTANCE")]
+    pub similarity_threshold: Option<f64>,
+    /// Also look for an open issue of the same failure in a central repository, e.g.
+    /// `luftkode/ci-failures`, and comment on it instead of creating another issue
+    #[arg(long, value_name = "OWNER/REPO", conflicts_with = "org_wide_dedup")]
+    pub central_repo: Option<String>,
+    /// Also look for an open issue of the same failure in every repository of the organization, and
+    /// comment on it instead of creating another issue
+    #[arg(long)]
+    pub org_wide_dedup: bool,
+    /// Number of occurrences of the same failure before the `recurring` label is added to the issue
+    #[arg(long, default_value_t = 3)]
+    pub recurring_threshold: u32,
+    /// Also search issues closed within the last `DAYS` days for the same failure, and reopen a
+    /// matching issue instead of creating a new one
+    #[arg(long, value_name = "DAYS")]
+    pub reopen_closed_within: Option<u32>,
+    /// Only search issues updated within the last `DAYS` days for the same failure
+    #[arg(long, value_name = "DAYS")]
+    pub dedup_window: Option<u32>,
+    /// Which issues are searched for the same failure. Closed issues found with `all` are
+    /// duplicates too, e.g. of failures that won't be fixed, use `--reopen-closed-within` to reopen
+    /// them instead.
+    #[arg(long, value_enum, default_value_t = DedupState::Open)]
+    pub dedup_state: DedupState,
+    /// Only search issues with all these labels for the same failure, instead of issues with all
+    /// the labels of the issue, e.g. `ci,nightly`
+    #[arg(long, value_name = "LABELS", value_delimiter = ',')]
+    pub dedup_labels: Vec<String>,
+    /// Print why an existing issue is or isn't considered a duplicate: the matched issue, the
+    /// fingerprint or distance compared, and the normalization rules applied to the errors. Always
+    /// printed in dry runs.
+    #[arg(long, default_value_t = false)]
+    pub explain_dedup: bool,
+    /// Re-run the failed jobs up to `N` times if the failure is classified as a flake, and only
+    /// create the issue if the failure persists
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    pub retry_flakes: u32,
+    /// Only include the log lines that are not in the log of the same step in the last successful
+    /// run of the workflow on the same branch
+    #[arg(long, default_value_t = false)]
+    pub diff_against_last_success: bool,
+    /// Add a section to the issue with the commits since the last successful run of the workflow
+    /// on the same branch
+    #[arg(long, default_value_t = false)]
+    pub commits_since_last_success: bool,
+    /// Add a `recipe:<name>` label for the recipe of every failed Yocto task, e.g. `recipe:sqlite3`
+    #[arg(long, default_value_t = false)]
+    pub recipe_labels: bool,
+    /// Name of an artifact of the run containing the logs of the failed Yocto tasks, `*` matches
+    /// anything, e.g. `yocto-logs-*`. The last lines of the log of every failed task are attached
+    /// to the issue.
+    #[arg(long, value_name = "PATTERN")]
+    pub task_log_artifact: Option<String>,
+    #[command(flatten)]
+    pub excerpt: ExcerptArgs,
+    /// What happens to the error summaries and logs that are shortened to fit in the issue body
+    #[arg(long, value_enum, default_value_t = LongLogs::Comments)]
+    pub long_logs: LongLogs,
+    /// Tera template of the issue body replacing the default. It can refer to the `run_id`,
+    /// `run_url`, `failed_jobs` and `sections` of the run, and render a failed job `job` like the
+    /// default template with `{% include "job" %}`
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath)]
+    pub template: Option<PathBuf>,
+    /// Assign the issue to a user, by their login. Can be repeated.
+    #[arg(long = "assignee", value_name = "LOGIN")]
+    pub assignees: Vec<String>,
+    /// Title or number of the milestone of the issue
+    #[arg(long)]
+    pub milestone: Option<String>,
+    /// Add the issue to a GitHub project, e.g. `luftkode/3`
+    #[arg(long, value_name = "OWNER/NUMBER")]
+    pub project: Option<ProjectRef>,
+    /// Assign the likely owners of the failure to the issue
+    #[arg(long, value_enum)]
+    pub auto_assign: Option<AutoAssign>,
+    /// Maximum length of the issue body in bytes, defaults to the maximum of the issue tracker
+    #[arg(long, value_name = "BYTES")]
+    pub max_body_len: Option<usize>,
+    /// Maximum length of the error summary and log of each failed job in the issue body in bytes,
+    /// by default the issue body is shared fairly between the jobs
+    #[arg(long, value_name = "BYTES")]
+    pub max_job_len: Option<usize>,
+    /// Format of the issue body, plain text is for trackers and notification channels that don't
+    /// render Markdown
+    #[arg(long, value_enum, default_value_t = BodyFormat::Markdown)]
+    pub body_format: BodyFormat,
+    /// Where the issue is created. Jira issues are always in Jira wiki markup, and Jira and Redmine
+    /// issues aren't checked for duplicates.
+    #[arg(long, value_enum, default_value_t = Tracker::GitHub)]
+    pub tracker: Tracker,
+    /// Publish the analysis as a failed check run on the head commit of the run instead of creating
+    /// an issue, with annotations at the files and lines of the compiler errors, linter findings
+    /// and failed pytest tests. The token needs the `checks: write` permission, and shortened logs
+    /// are only kept with `--long-logs gist` or `storage`.
+    #[arg(long, conflicts_with = "tracker")]
+    pub check_run: bool,
+    /// If the run was triggered by a pull request, comment the failure on the pull request instead
+    /// of creating an issue. Another failed attempt of the run updates the comment, and a failed
+    /// later run of the workflow collapses it as outdated. Shortened logs are only kept with
+    /// `--long-logs gist` or `storage`. On GitLab, the failure of a merge request pipeline is
+    /// posted as a discussion on the merge request, that is resolved once a later pipeline
+    /// succeeds.
+    #[arg(long, conflicts_with = "check_run")]
+    pub pr_comment: bool,
+    /// Set the `ci-manager/analysis` commit status on the head commit of the run, describing what
+    /// was done about the failure and the failure labels, and linking to the issue. The token needs
+    /// the `statuses: write` permission.
+    #[arg(long)]
+    pub commit_status: bool,
+    #[command(flatten)]
+    pub jira: JiraArgs,
+    #[command(flatten)]
+    pub linear: LinearArgs,
+    #[command(flatten)]
+    pub redmine: RedmineArgs,
+    /// Send a notification about the failure and its issue once the issue is created or found to be
+    /// a duplicate, e.g. `slack:#ci-failures` or `slack:https://hooks.slack.com/services/...` for a
+    /// Slack channel or incoming webhook, or `webhook:<URL>` to POST the run, failed jobs, error
+    /// summaries and issue decision as JSON (can be repeated). Slack channels are posted to with
+    /// the bot token in `SLACK_BOT_TOKEN`.
+    #[arg(long, value_name = "KIND:TARGET")]
+    pub notify: Vec<NotifyTarget>,
+    #[command(flatten)]
+    pub alert: AlertArgs,
+}
+
+/// Options of `--tracker jira`. The Jira Cloud site and the credentials are read from the
+/// `JIRA_URL`, `JIRA_USER` (email) and `JIRA_API_TOKEN` environment variables.
+#[derive(Debug, Clone, Args)]
+pub struct JiraArgs {
+    /// Key of the Jira project the issue is created in, e.g. `CI`
+    #[arg(long, value_name = "KEY", required_if_eq("tracker", "jira"))]
+    pub jira_project: Option<String>,
+    /// Name of the type of the Jira issue
+    #[arg(long, value_name = "TYPE", default_value = "Bug")]
+    pub jira_issue_type: String,
+    /// Component of the Jira issue (can be repeated)
+    #[arg(long = "jira-component", value_name = "NAME")]
+    pub jira_components: Vec<String>,
+}
+
+/// Options of `--tracker linear`. The API key is read from the `LINEAR_API_KEY` environment
+/// variable.
+#[derive(Debug, Clone, Args)]
+pub struct LinearArgs {
+    /// Key of the Linear team the issue is created in, e.g. `ENG`
+    #[arg(long, value_name = "KEY", required_if_eq("tracker", "linear"))]
+    pub linear_team: Option<String>,
+    /// Priority of the Linear issue
+    #[arg(long, value_enum, default_value_t = LinearPriority::None)]
+    pub linear_priority: LinearPriority,
+}
+
+/// Options of `--tracker redmine`. The Redmine server and the API key are read from the
+/// `REDMINE_URL` and `REDMINE_API_KEY` environment variables. Redmine issues have no labels, and
+/// their description is only rendered as Markdown if that is the text formatting of the server, use
+/// `--body-format plain` otherwise.
+#[derive(Debug, Clone, Args)]
+pub struct RedmineArgs {
+    /// Identifier or ID of the Redmine project the issue is created in, e.g. `hardware`
+    #[arg(long, value_name = "PROJECT", required_if_eq("tracker", "redmine"))]
+    pub redmine_project: Option<String>,
+    /// Name or ID of the Redmine tracker of the issue, e.g. `Bug`. Defaults to the default tracker
+    /// of the project.
+    #[arg(long, value_name = "TRACKER")]
+    pub redmine_tracker: Option<String>,
+    /// Name or ID of the category of the Redmine issue
+    #[arg(long, value_name = "CATEGORY")]
+    pub redmine_category: Option<String>,
+    /// Value of a custom field of the Redmine issue by the ID of the field, e.g. `4=Rev B` (can be
+    /// repeated)
+    #[arg(long = "redmine-custom-field", value_name = "ID=VALUE")]
+    pub redmine_custom_fields: Vec<CustomFieldValue>,
+}
+
+/// Options for alerting about failed jobs classified as infrastructure failures: a lost runner, a
+/// full disk or the network. The PagerDuty routing key is read from the `PAGERDUTY_ROUTING_KEY`
+/// environment variable, and the Opsgenie API key from `OPSGENIE_API_KEY`.
+#[derive(Debug, Clone, Args)]
+pub struct AlertArgs {
+    /// Trigger an alert for every failed job that is an infrastructure failure. Alerts of the same
+    /// failure are deduplicated by its fingerprint.
+    #[arg(long = "alert", value_enum)]
+    pub service: Option<AlertService>,
+    /// Don't put the failed jobs that an alert was triggered for in the issue, and don't create an
+    /// issue if every failed job was alerted about
+    #[arg(long, requires = "service")]
+    pub alert_instead_of_issue: bool,
+}
+
+/// Options for shortening error summaries that are too long for the issue
+#[derive(Debug, Clone, Args)]
+pub struct ExcerptArgs {
+    /// How error summaries that are too long for the issue are shortened
+    #[arg(long = "excerpt", value_enum, default_value_t = ExcerptStrategy::Tail)]
+    pub strategy: ExcerptStrategy,
+    /// Number of lines before the first error line kept with `--excerpt context`
+    #[arg(long = "excerpt-before", value_name = "N", default_value_t = 10)]
+    pub before: usize,
+    /// Number of lines after the first error line kept with `--excerpt context`
+    #[arg(long = "excerpt-after", value_name = "M", default_value_t = 20)]
+    pub after: usize,
+    /// Number of the last lines of the log kept with `--excerpt context`
+    #[arg(long = "excerpt-tail", value_name = "N", default_value_t = 20)]
+    pub tail: usize,
+}
+
+impl ExcerptArgs {
+    /// The excerpt around the first error line, `None` if the end of the summary is kept
+    pub fn context_excerpt(&self) -> Option<ContextExcerpt> {
+        match self.strategy {
+            ExcerptStrategy::Tail => None,
+            ExcerptStrategy::Context => Some(ContextExcerpt {
+                before: self.before,
+                after: self.after,
+                tail: self.tail,
+            }),
+        }
+    }
+}
+
+/// Options for waiting on a CI run to complete
+#[derive(Debug, Clone, Args)]
+pub struct WaitArgs {
+    /// Seconds between polls of the run status
+    #[arg(long, value_name = "SECONDS", default_value_t = DEFAULT_POLL_INTERVAL.as_secs())]
+    pub poll_interval: u64,
+    /// Maximum number of seconds to wait for the run to complete
+    #[arg(long, value_name = "SECONDS", default_value_t = DEFAULT_WAIT_TIMEOUT.as_secs())]
+    pub wait_timeout: u64,
+}
+
+impl WaitArgs {
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_secs(self.poll_interval)
+    }
+
+    pub fn wait_timeout(&self) -> Duration {
+        Duration::from_secs(self.wait_timeout)
+    }
+}
+
+/// What to do after `watch-run` or `bisect-run` is done
+#[derive(Debug, Subcommand)]
+pub enum WatchRunThen {
+    /// Create an issue if the run failed
+    CreateIssue(CreateIssueArgs),
+}
+
+/// Arguments for the `stats` subcommand
+#[derive(Debug, Clone, Args)]
+pub struct StatsArgs {
+    /// The repository
+    #[arg(long, value_hint = ValueHint::Url)]
+    pub repo: String,
+    /// The workflow file name (e.g. build.yml) or ID
+    #[arg(short, long)]
+    pub workflow: String,
+    /// Only include runs on this branch
+    #[arg(short, long)]
+    pub branch: Option<String>,
+    /// Number of completed runs to analyze
+    #[arg(long, value_name = "N", default_value_t = 50)]
+    pub runs: usize,
+    /// The kind of workflow (e.g. Yocto). If provided, the logs of the failed runs are parsed
+    /// to report the most common failure labels
+    #[arg(short, long)]
+    pub kind: Option<WorkflowKind>,
+    /// Output format. `csv` prints one row per failed job of the failed runs instead, with the
+    /// run ID, date, workflow, job, failure label (parsed as `--kind`, `auto` by default) and the
+    /// issue created from the run
+    #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+    pub format: OutputFormat,
+}
+
+/// Filters for selecting workflow runs
+#[derive(Debug, Clone, Args)]
+pub struct RunFilterArgs {
+    /// Only include runs of the workflow with this name
+    #[arg(short, long)]
+    pub workflow: Option<String>,
+    /// Only include runs on this branch
+    #[arg(short, long)]
+    pub branch: Option<String>,
+    /// Only include runs triggered by this event (e.g. schedule or push)
+    #[arg(short, long)]
+    pub event: Option<String>,
+    /// Only include runs created on or after this date (YYYY-MM-DD)
+    #[arg(long)]
+    pub since: Option<Date>,
+    /// Only include runs created on or before this date (YYYY-MM-DD)
+    #[arg(long)]
+    pub until: Option<Date>,
+}
+
+/// Output format of subcommands that list information
+#[derive(ValueEnum, Display, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human readable table
+    #[value(name = "table")]
+    Table,
+    /// JSON array
+    #[value(name = "json")]
+    Json,
+    /// Comma-separated values with a header row, e.g. for spreadsheets. Only supported by
+    /// `list-failed-runs` and `stats`
+    #[value(name = "csv")]
+    Csv,
+}
+
+/// Machine-readable format of the result of a subcommand, see `--output-format`
+#[derive(ValueEnum, Display, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ResultFormat {
+    /// JSON on stdout
+    #[value(name = "json")]
+    Json,
+}
+
+/// Format of the log messages on stderr
+#[derive(ValueEnum, Display, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human readable lines
+    #[value(name = "text")]
+    Text,
+    /// A JSON object per line with the timestamp, level, target, message and the fields of the
+    /// spans the message was logged in, e.g. `run_id` and `repo`
+    #[value(name = "json")]
+    Json,
+}
+
+/// Format of the report file written by `summarize-run --report`
+#[derive(ValueEnum, Display, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// JUnit XML with a failed test case for every failed job, e.g. for CI dashboards
+    #[value(name = "junit")]
+    Junit,
+}
+
+/// How error summaries that are too long for the issue are shortened
+#[derive(ValueEnum, Display, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExcerptStrategy {
+    /// Keep the end of the summary
+    #[value(name = "tail")]
+    Tail,
+    /// Keep the lines around the first error line and the last lines of the log
+    #[value(name = "context")]
+    Context,
+}
+
+/// What happens to the error summaries and logs that are shortened to fit in the issue body
+#[derive(ValueEnum, Display, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LongLogs {
+    /// Only the shortened summary is kept
+    #[value(name = "truncate")]
+    Truncate,
+    /// The full summary and log of every shortened job is posted as a comment on the issue
+    #[value(name = "comments")]
+    Comments,
+    /// The full summary and log of every shortened job is uploaded to a secret gist, which is
+    /// linked in the issue body instead
+    #[value(name = "gist")]
+    Gist,
+    /// The full summary and log of every shortened job is uploaded to the object storage (S3, GCS
+    /// or Azure) of the `[storage]` section of the configuration file, and linked in the issue
+    /// body instead
+    #[value(name = "storage")]
+    Storage,
+}
+
+/// How the likely owners of a failure are determined
+#[derive(ValueEnum, Display, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AutoAssign {
+    /// The code owners of the paths in the error summaries, from the CODEOWNERS file. Teams are
+    /// mentioned in the issue instead of assigned.
+    #[value(name = "codeowners")]
+    Codeowners,
+    /// The authors of the commits since the last successful run of the workflow on the same branch
+    #[value(name = "last-committers")]
+    LastCommitters,
+}
+
+/// Which issues are searched for an issue of the same failure
+#[derive(ValueEnum, Display, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DedupState {
+    #[value(name = "open")]
+    Open,
+    /// Open and closed issues
+    #[value(name = "all")]
+    All,
+}
+
+/// Issue tracker the issue is created in
+#[derive(ValueEnum, Display, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Tracker {
+    /// The GitHub repository of the run
+    #[value(name = "github")]
+    GitHub,
+    /// A Jira Cloud project, see [JiraArgs]
+    #[value(name = "jira")]
+    Jira,
+    /// A Linear team, see [LinearArgs]
+    #[value(name = "linear")]
+    Linear,
+    /// A Redmine project, see [RedmineArgs]
+    #[value(name = "redmine")]
+    Redmine,
+}
+
+impl Tracker {
+    /// The limits of an issue body in the tracker
+    pub fn body_limits(self) -> BodyLimits {
+        match self {
+            // Linear documents no maximum length of a description, and Redmine stores it in a text
+            // column as long as the body of a GitHub issue
+            Self::GitHub | Self::Linear | Self::Redmine => BodyLimits::GITHUB,
+            Self::Jira => BodyLimits::JIRA,
+        }
+    }
+
+    /// The format of the issue body in the tracker, `requested` if the tracker renders it
+    pub fn body_format(self, requested: BodyFormat) -> BodyFormat {
+        match self {
+            Self::GitHub | Self::Linear | Self::Redmine => requested,
+            Self::Jira => BodyFormat::Jira,
+        }
+    }
+}
+
+/// Incident management service that `--alert` triggers alerts in
+#[derive(ValueEnum, Display, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AlertService {
+    /// A PagerDuty service, through the Events API v2
+    #[value(name = "pagerduty")]
+    PagerDuty,
+    #[value(name = "opsgenie")]
+    Opsgenie,
+}
+
+/// Priority of a Linear issue
+#[derive(ValueEnum, Display, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LinearPriority {
+    #[value(name = "none")]
+    None,
+    #[value(name = "urgent")]
+    Urgent,
+    #[value(name = "high")]
+    High,
+    #[value(name = "medium")]
+    Medium,
+    #[value(name = "low")]
+    Low,
+}
+
+impl LinearPriority {
+    /// The priority as a number in the Linear API
+    pub fn value(self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Urgent => 1,
+            Self::High => 2,
+            Self::Medium => 3,
+            Self::Low => 4,
+        }
+    }
+}
+
+/// Format of the issue body
+#[derive(ValueEnum, Display, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BodyFormat {
+    #[value(name = "markdown")]
+    Markdown,
+    /// The same sections as the Markdown body as plain text, without formatting
+    #[value(name = "plain")]
+    Plain,
+    /// Jira wiki markup, the format of the descriptions of Jira issues
+    #[value(name = "jira")]
+    Jira,
+}
+
+/// Output format of `locate-failure-log`
+#[derive(ValueEnum, Display, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FailureLogFormat {
+    /// The absolute path of the failure log
+    #[value(name = "path")]
+    Path,
+    /// JSON object with the absolute path and, for Yocto, the recipe, version, task and failure kind
+    #[value(name = "json")]
+    Json,
+}
+
+/// Time window of a digest
+#[derive(ValueEnum, Display, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DigestPeriod {
+    /// The last 24 hours
+    #[value(name = "daily")]
+    Daily,
+    /// The last 7 days
+    #[value(name = "weekly")]
+    Weekly,
+}
+
+impl DigestPeriod {
+    /// Length of the period in days
+    pub fn days(self) -> u32 {
+        match self {
+            Self::Daily => 1,
+            Self::Weekly => 7,
+        }
+    }
+}
+
 /// The kind of workflow (e.g. Yocto)
 #[derive(ValueEnum, Display, Copy, Clone, Debug, PartialEq, Eq)]
 pub enum WorkflowKind {
     #[value(name = "yocto", aliases = ["Yocto", "YOCTO"])]
     Yocto,
+    /// C or C++ built with GCC or Clang, and its tests
+    #[value(name = "cpp", aliases = ["c", "c++", "C", "C++", "Cpp", "CPP"])]
+    Cpp,
+    /// Java, Kotlin or other JVM languages built with Gradle or Maven
+    #[value(name = "jvm", aliases = ["gradle", "maven", "Jvm", "JVM"])]
+    Jvm,
+    /// `pre-commit run`
+    #[value(name = "pre-commit", aliases = ["precommit", "PreCommit"])]
+    PreCommit,
+    /// ShellCheck or another linter
+    #[value(name = "lint", aliases = ["shellcheck", "Lint"])]
+    Lint,
     #[value(name = "other", aliases = ["Other", "OTHER"])]
     Other,
+    /// Use the kind mapped to the name of each failed job in the `[job_kinds]` table of the
+    /// configuration file, or detect it from the log of the job
+    #[value(name = "auto", aliases = ["Auto", "AUTO"])]
+    Auto,
+}
+
+impl WorkflowKind {
+    /// The kind to parse the logs of the job named `job_name` with. [WorkflowKind::Auto] is
+    /// resolved with the `[job_kinds]` table of the configuration file if it maps the job, any
+    /// other kind is used as is.
+    pub fn for_job(self, job_name: &str) -> Self {
+        if self != Self::Auto {
+            return self;
+        }
+        Config::global()
+            .config_file()
+            .job_kinds
+            .kind_of(job_name)
+            .unwrap_or(self)
+    }
 }
 
 /// The kind of step in CI, e.g. Yocto, Pytest, Pre-commit, Docker build, etc.
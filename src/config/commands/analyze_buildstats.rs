@@ -0,0 +1,369 @@
+//! Analysis of the buildstats BitBake writes to `tmp/buildstats/<build name>/` with the
+//! `buildstats` class, to spot the slowest tasks of a Yocto build and how the build time changed
+//! since the previous build
+use super::OutputFormat;
+use crate::*;
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Name of the file with the statistics of the whole build, in the directory of a build
+const BUILD_STATS_FILE: &str = "build_stats";
+
+/// Duration of a task of a recipe
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TaskStats {
+    /// The recipe, without the version, e.g. `sqlite3`
+    pub recipe: String,
+    /// The version and revision of the recipe, e.g. `3.45.1-r0`
+    pub version: Option<String>,
+    /// The task, e.g. `do_compile`
+    pub task: String,
+    pub elapsed_seconds: f64,
+}
+
+impl TaskStats {
+    /// Parse the file BitBake writes for a task, e.g. `sqlite3-3.45.1-r0/do_compile`, `None` if it
+    /// has no elapsed time (e.g. the task is still running)
+    fn from_file(recipe_dir_name: &str, task: &str, contents: &str) -> Option<Self> {
+        // e.g. `sqlite3-3.45.1-r0`, the version cannot contain dashes
+        static RECIPE_DIR_RE: Lazy<Regex> =
+            Lazy::new(|| Regex::new(r"^(?<recipe>.+)-(?<version>[^-]+-r\d+(?:\.\d+)*)$").unwrap());
+
+        let elapsed_seconds = elapsed_seconds(contents)?;
+        let (recipe, version) = match RECIPE_DIR_RE.captures(recipe_dir_name) {
+            Some(caps) => (caps["recipe"].to_owned(), Some(caps["version"].to_owned())),
+            None => (recipe_dir_name.to_owned(), None),
+        };
+        Some(Self {
+            recipe,
+            version,
+            task: task.to_owned(),
+            elapsed_seconds,
+        })
+    }
+
+    /// Identifies the task across builds, also if the version of the recipe changed
+    fn key(&self) -> (&str, &str) {
+        (&self.recipe, &self.task)
+    }
+}
+
+/// The buildstats of a build
+#[derive(Debug, Clone, PartialEq)]
+pub struct Buildstats {
+    /// The directory of the build
+    pub dir: PathBuf,
+    /// Elapsed time of the whole build, `None` if the build did not finish
+    pub elapsed_seconds: Option<f64>,
+    pub tasks: Vec<TaskStats>,
+}
+
+impl Buildstats {
+    /// Read the buildstats of a build, from the directory of the build or from the `buildstats`
+    /// directory, in which case the most recent build is read
+    pub fn from_dir(dir: &Path) -> Result<Self> {
+        let dir = build_dir(dir)?;
+        log::info!("Reading buildstats from {dir:?}");
+        let elapsed_seconds = match fs::read_to_string(dir.join(BUILD_STATS_FILE)) {
+            Ok(contents) => elapsed_seconds(&contents),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {dir:?}")),
+        };
+        let mut tasks = Vec::new();
+        for recipe_dir in sorted_subdirs(&dir)? {
+            let recipe_dir_name = file_name(&recipe_dir);
+            for task_file in fs::read_dir(&recipe_dir)? {
+                let task_file = task_file?.path();
+                let task = file_name(&task_file);
+                if !task_file.is_file() || !task.starts_with("do_") {
+                    continue;
+                }
+                let contents = fs::read_to_string(&task_file)
+                    .with_context(|| format!("Failed to read {task_file:?}"))?;
+                if let Some(stats) = TaskStats::from_file(&recipe_dir_name, &task, &contents) {
+                    tasks.push(stats);
+                }
+            }
+        }
+        if tasks.is_empty() && elapsed_seconds.is_none() {
+            bail!("No buildstats found in {dir:?}");
+        }
+        tasks.sort_by(|a, b| b.elapsed_seconds.total_cmp(&a.elapsed_seconds));
+        Ok(Self {
+            dir,
+            elapsed_seconds,
+            tasks,
+        })
+    }
+}
+
+/// A task among the slowest of a build, and its duration in the previous build
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SlowTask {
+    #[serde(flatten)]
+    pub stats: TaskStats,
+    /// `None` if there is no previous build or the task did not run in it
+    pub previous_elapsed_seconds: Option<f64>,
+}
+
+/// The slowest tasks of a build compared to the previous build
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BuildstatsReport {
+    pub elapsed_seconds: Option<f64>,
+    pub previous_elapsed_seconds: Option<f64>,
+    /// Number of tasks that ran
+    pub tasks: usize,
+    /// Sum of the durations of all tasks, more than the build time as tasks run in parallel
+    pub task_seconds: f64,
+    pub previous_task_seconds: Option<f64>,
+    pub slowest_tasks: Vec<SlowTask>,
+}
+
+impl BuildstatsReport {
+    pub fn new(current: &Buildstats, previous: Option<&Buildstats>, top: usize) -> Self {
+        let previous_tasks: HashMap<(&str, &str), f64> = previous
+            .map(|previous| {
+                previous
+                    .tasks
+                    .iter()
+                    .map(|task| (task.key(), task.elapsed_seconds))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let slowest_tasks = current
+            .tasks
+            .iter()
+            .take(top)
+            .map(|task| SlowTask {
+                stats: task.clone(),
+                previous_elapsed_seconds: previous_tasks.get(&task.key()).copied(),
+            })
+            .collect();
+        Self {
+            elapsed_seconds: current.elapsed_seconds,
+            previous_elapsed_seconds: previous.and_then(|previous| previous.elapsed_seconds),
+            tasks: current.tasks.len(),
+            task_seconds: total_seconds(&current.tasks),
+            previous_task_seconds: previous.map(|previous| total_seconds(&previous.tasks)),
+            slowest_tasks,
+        }
+    }
+
+    fn print_table(&self) -> Result<()> {
+        pipe_println!(
+            "Build time:      {}",
+            format_change(self.elapsed_seconds, self.previous_elapsed_seconds)
+        )?;
+        pipe_println!(
+            "Task time:       {}",
+            format_change(Some(self.task_seconds), self.previous_task_seconds)
+        )?;
+        pipe_println!("Tasks:           {}", self.tasks)?;
+        pipe_println!("")?;
+        pipe_println!(
+            "{:<50} {:>10} {:>10} {:>8}",
+            "TASK",
+            "DURATION",
+            "PREVIOUS",
+            "CHANGE"
+        )?;
+        for task in &self.slowest_tasks {
+            pipe_println!(
+                "{:<50} {:>10} {:>10} {:>8}",
+                format!("{} {}", task.stats.recipe, task.stats.task),
+                format_duration(task.stats.elapsed_seconds),
+                task.previous_elapsed_seconds
+                    .map_or("n/a".to_owned(), format_duration),
+                percent_change(task.stats.elapsed_seconds, task.previous_elapsed_seconds)
+                    .unwrap_or_else(|| "n/a".to_owned())
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Report the slowest tasks of a build and the build time compared to the previous build
+///
+/// # Arguments
+///
+/// * `buildstats` - The buildstats directory of a build, or the `buildstats` directory with the
+///   builds in it, in which case the most recent build is analyzed
+/// * `previous` - The buildstats of the previous build to compare with
+/// * `top` - Number of the slowest tasks to report
+/// * `format` - Output format
+pub fn analyze_buildstats(
+    buildstats: &Path,
+    previous: Option<&Path>,
+    top: usize,
+    format: OutputFormat,
+) -> Result<()> {
+    let current = Buildstats::from_dir(buildstats)?;
+    let previous = previous.map(Buildstats::from_dir).transpose()?;
+    let report = BuildstatsReport::new(&current, previous.as_ref(), top);
+    match format {
+        OutputFormat::Table => report.print_table()?,
+        OutputFormat::Json => pipe_println!("{}", serde_json::to_string_pretty(&report)?)?,
+        OutputFormat::Csv => {
+            bail!("`--format csv` is only supported by `list-failed-runs` and `stats`")
+        }
+    }
+    Ok(())
+}
+
+/// The directory of a build, `dir` itself if it has a `build_stats` file or task directories,
+/// otherwise the most recent build in it. Builds are named after their start time, so the most
+/// recent build sorts last.
+fn build_dir(dir: &Path) -> Result<PathBuf> {
+    if !dir.is_dir() {
+        bail!("Not a buildstats directory: {dir:?}");
+    }
+    if dir.join(BUILD_STATS_FILE).is_file() {
+        return Ok(dir.to_owned());
+    }
+    let subdirs = sorted_subdirs(dir)?;
+    let is_build_dir = |dir: &PathBuf| {
+        dir.join(BUILD_STATS_FILE).is_file()
+            || sorted_subdirs(dir).is_ok_and(|subdirs| {
+                subdirs
+                    .iter()
+                    .any(|subdir| subdir.join("do_fetch").is_file())
+            })
+    };
+    match subdirs.into_iter().rev().find(is_build_dir) {
+        Some(build_dir) => Ok(build_dir),
+        None => Ok(dir.to_owned()),
+    }
+}
+
+/// The `Elapsed time: 12.34 seconds` of a task or build
+fn elapsed_seconds(contents: &str) -> Option<f64> {
+    static ELAPSED_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"(?m)^Elapsed time: (?<seconds>[\d.]+) seconds").unwrap());
+    ELAPSED_RE
+        .captures(contents)
+        .and_then(|caps| caps["seconds"].parse().ok())
+}
+
+fn total_seconds(tasks: &[TaskStats]) -> f64 {
+    tasks.iter().map(|task| task.elapsed_seconds).sum()
+}
+
+/// Change of a duration relative to the previous one in percent, e.g. `+12.5%`
+fn percent_change(seconds: f64, previous: Option<f64>) -> Option<String> {
+    let previous = previous.filter(|previous| *previous > 0.0)?;
+    Some(format!("{:+.1}%", (seconds - previous) / previous * 100.0))
+}
+
+/// Format a duration and its change since the previous build, e.g. `1h 02m 03s (previous 58m 10s,
+/// +6.7%)`
+fn format_change(seconds: Option<f64>, previous: Option<f64>) -> String {
+    let Some(seconds) = seconds else {
+        return "n/a".to_owned();
+    };
+    match (previous, percent_change(seconds, previous)) {
+        (Some(previous), Some(change)) => format!(
+            "{} (previous {}, {change})",
+            format_duration(seconds),
+            format_duration(previous)
+        ),
+        _ => format_duration(seconds),
+    }
+}
+
+fn sorted_subdirs(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut subdirs = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read {dir:?}"))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            subdirs.push(path);
+        }
+    }
+    subdirs.sort();
+    Ok(subdirs)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use temp_dir::TempDir;
+
+    fn write_task(build_dir: &Path, recipe_dir: &str, task: &str, seconds: f64) {
+        let recipe_dir = build_dir.join(recipe_dir);
+        fs::create_dir_all(&recipe_dir).unwrap();
+        fs::write(
+            recipe_dir.join(task),
+            format!(
+                "Event: TaskStarted\n\
+                Started: 1700000000.00\n\
+                Elapsed time: {seconds:.2} seconds\n\
+                Status: PASSED\n"
+            ),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_buildstats_report_compared_to_previous_build() {
+        let dir = TempDir::new().unwrap();
+        let previous = dir.path().join("previous/20240301120000");
+        let current = dir.path().join("current/20240302120000");
+        for (build_dir, build_seconds) in [(&previous, 3000.0), (&current, 3723.0)] {
+            fs::create_dir_all(build_dir).unwrap();
+            fs::write(
+                build_dir.join(BUILD_STATS_FILE),
+                format!("Build Started: 1700000000.00\nElapsed time: {build_seconds:.2} seconds\n"),
+            )
+            .unwrap();
+        }
+        write_task(&previous, "sqlite3-3.44.0-r0", "do_compile", 100.0);
+        write_task(&previous, "busybox-1.36.1-r0", "do_compile", 40.0);
+        write_task(&current, "sqlite3-3.45.1-r0", "do_compile", 125.0);
+        write_task(&current, "sqlite3-3.45.1-r0", "do_fetch", 2.5);
+        write_task(&current, "linux-yocto-6.6.21+git-r0", "do_compile", 600.0);
+
+        // The most recent build in the buildstats directory is read
+        let current = Buildstats::from_dir(&dir.path().join("current")).unwrap();
+        let previous = Buildstats::from_dir(&previous).unwrap();
+        let report = BuildstatsReport::new(&current, Some(&previous), 2);
+
+        assert_eq!(report.elapsed_seconds, Some(3723.0));
+        assert_eq!(report.previous_elapsed_seconds, Some(3000.0));
+        assert_eq!(report.tasks, 3);
+        assert_eq!(report.task_seconds, 727.5);
+        assert_eq!(
+            report.slowest_tasks,
+            [
+                SlowTask {
+                    stats: TaskStats {
+                        recipe: "linux-yocto".to_owned(),
+                        version: Some("6.6.21+git-r0".to_owned()),
+                        task: "do_compile".to_owned(),
+                        elapsed_seconds: 600.0,
+                    },
+                    previous_elapsed_seconds: None,
+                },
+                SlowTask {
+                    stats: TaskStats {
+                        recipe: "sqlite3".to_owned(),
+                        version: Some("3.45.1-r0".to_owned()),
+                        task: "do_compile".to_owned(),
+                        elapsed_seconds: 125.0,
+                    },
+                    previous_elapsed_seconds: Some(100.0),
+                },
+            ]
+        );
+        assert_eq!(
+            format_change(report.elapsed_seconds, report.previous_elapsed_seconds),
+            "1h 02m 03s (previous 50m 00s, +24.1%)"
+        );
+    }
+}
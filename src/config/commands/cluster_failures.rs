@@ -0,0 +1,252 @@
+//! Group similar failures of existing issues or exported runs, to find the most common CI problems
+use super::{OutputFormat, WorkflowKind};
+use crate::ci_provider::github::{archive::RunArchive, util::failed_jobs_from_job_error_logs};
+use crate::issue::{fingerprint_from_issue_body, similarity::Similarity, Issue};
+use crate::*;
+use std::{cmp::Reverse, io::Write};
+
+/// Longest summary shown in the table output
+const MAX_SUMMARY_LEN: usize = 100;
+
+/// A failure to cluster, from an issue or a run archive
+#[derive(Debug, Clone, PartialEq)]
+pub struct FailureSample {
+    /// Where the failure comes from, e.g. `#42` or the path of a run archive
+    pub source: String,
+    /// Fingerprint of the failure, if it is known, see [Issue::fingerprint]
+    pub fingerprint: Option<String>,
+    /// The text compared to the other failures if the fingerprints don't tell, the issue body
+    pub text: String,
+    /// The first line of the error summary
+    pub summary: String,
+}
+
+impl FailureSample {
+    /// A failure from the body of an issue created by ci-manager, summarized by the first line of
+    /// its first error summary, or else by the title of the issue
+    pub fn from_issue(source: String, title: &str, body: &str) -> Self {
+        Self {
+            source,
+            fingerprint: fingerprint_from_issue_body(body),
+            text: body.to_owned(),
+            summary: first_code_line(body).unwrap_or(title).to_owned(),
+        }
+    }
+
+    /// A failure from a run archive created with `export-run`, parsed as `kind`
+    pub fn from_archive(path: &Path, kind: WorkflowKind) -> Result<Self> {
+        let archive = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read run archive: {path:?}"))?;
+        let archive: RunArchive = serde_json::from_str(&archive)
+            .with_context(|| format!("Invalid run archive: {path:?}"))?;
+        let failed_jobs =
+            failed_jobs_from_job_error_logs(&archive.job_error_logs(), &archive.run_url, kind);
+        if failed_jobs.is_empty() {
+            bail!("No failed jobs in run archive: {path:?}");
+        }
+        let summary = failed_jobs[0]
+            .full_log()
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .unwrap_or_default()
+            .to_owned();
+        let mut issue = Issue::new(
+            String::new(),
+            archive.run_id.to_string(),
+            archive.run_url,
+            failed_jobs,
+            String::new(),
+        );
+        Ok(Self {
+            source: format!("{} run {}", archive.repo, archive.run_id),
+            fingerprint: Some(issue.fingerprint()),
+            text: issue.body(),
+            summary,
+        })
+    }
+}
+
+/// A group of failures with the same fingerprint or similar issue bodies
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FailureCluster {
+    pub count: usize,
+    /// Fingerprint of the first failure in the cluster that has one
+    pub fingerprint: Option<String>,
+    /// Summary of the first failure in the cluster
+    pub summary: String,
+    pub sources: Vec<String>,
+    /// Text of the first failure, that the other failures are compared to
+    #[serde(skip)]
+    text: String,
+}
+
+/// Group the failures with the same fingerprint, or else with a body within the threshold of
+/// `similarity` of the first failure of a group, largest group first
+pub fn cluster(samples: Vec<FailureSample>, similarity: Similarity) -> Vec<FailureCluster> {
+    let mut clusters: Vec<FailureCluster> = Vec::new();
+    for sample in samples {
+        let same_cluster =
+            clusters.iter_mut().find(
+                |cluster| match (&cluster.fingerprint, &sample.fingerprint) {
+                    (Some(fingerprint), Some(other)) if fingerprint == other => true,
+                    _ => similarity
+                        .similar_distance(&cluster.text, &sample.text)
+                        .is_some(),
+                },
+            );
+        match same_cluster {
+            Some(cluster) => {
+                cluster.count += 1;
+                cluster.sources.push(sample.source);
+                if cluster.fingerprint.is_none() {
+                    cluster.fingerprint = sample.fingerprint;
+                }
+            }
+            None => clusters.push(FailureCluster {
+                count: 1,
+                fingerprint: sample.fingerprint,
+                summary: sample.summary,
+                sources: vec![sample.source],
+                text: sample.text,
+            }),
+        }
+    }
+    // Stable, so clusters of the same size keep the order they were first seen in
+    clusters.sort_by_key(|cluster| Reverse(cluster.count));
+    clusters
+}
+
+/// Print the `top` largest clusters
+pub fn print_clusters(clusters: &[FailureCluster], top: usize, format: OutputFormat) -> Result<()> {
+    let clusters = &clusters[..clusters.len().min(top)];
+    match format {
+        OutputFormat::Json => pipe_println!("{}", serde_json::to_string_pretty(clusters)?)?,
+        OutputFormat::Table => {
+            pipe_println!("{:>5}  {:<16}  SUMMARY", "COUNT", "FINGERPRINT")?;
+            for cluster in clusters {
+                let summary = match cluster.summary.char_indices().nth(MAX_SUMMARY_LEN) {
+                    Some((end, _)) => format!("{}...", &cluster.summary[..end]),
+                    None => cluster.summary.clone(),
+                };
+                pipe_println!(
+                    "{:>5}  {:<16}  {summary}",
+                    cluster.count,
+                    cluster.fingerprint.as_deref().unwrap_or("-"),
+                )?;
+                pipe_println!("{:>5}  {:<16}  {}", "", "", cluster.sources.join(", "))?;
+            }
+        }
+        OutputFormat::Csv => {
+            bail!("`--format csv` is only supported by `list-failed-runs` and `stats`")
+        }
+    }
+    Ok(())
+}
+
+/// Cluster the failures of run archives created with `export-run`
+///
+/// # Arguments
+///
+/// * `archives` - Run archives, or directories with run archives (`*.json`) in them
+/// * `kind` - The [WorkflowKind] used to parse the logs (e.g. Yocto)
+/// * `similarity` - How the failures are compared if their fingerprints differ
+/// * `top` - Number of the largest clusters to print
+/// * `format` - Output format
+pub fn cluster_archives(
+    archives: &[PathBuf],
+    kind: WorkflowKind,
+    similarity: Similarity,
+    top: usize,
+    format: OutputFormat,
+) -> Result<()> {
+    let mut samples = Vec::new();
+    for path in archives {
+        if path.is_dir() {
+            let mut entries: Vec<PathBuf> = fs::read_dir(path)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<io::Result<_>>()?;
+            entries.retain(|entry| entry.extension().is_some_and(|ext| ext == "json"));
+            entries.sort();
+            for entry in entries {
+                samples.push(FailureSample::from_archive(&entry, kind)?);
+            }
+        } else {
+            samples.push(FailureSample::from_archive(path, kind)?);
+        }
+    }
+    log::info!(
+        "Clustering the failures of {} run archive(s)",
+        samples.len()
+    );
+    print_clusters(&cluster(samples, similarity), top, format)
+}
+
+/// The first non-empty line of the first code block of a Markdown text
+fn first_code_line(markdown: &str) -> Option<&str> {
+    let (_, code) = markdown.split_once("```")?;
+    // Skip the info string of the code block, e.g. `rust`
+    let (_, code) = code.split_once('\n')?;
+    code.lines()
+        .take_while(|line| !line.starts_with("```"))
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::issue::similarity::SimilarityAlgo;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_cluster_by_fingerprint_then_similarity() {
+        let body = |error: &str| {
+            format!("**Run ID**: 1\n\n*Best effort error summary*:\n```\n{error}\n```")
+        };
+        let samples = vec![
+            FailureSample {
+                fingerprint: Some("d0c5e0ac7e8c2a1b".to_owned()),
+                ..FailureSample::from_issue("#1".to_owned(), "Run failed", &body("disk full"))
+            },
+            FailureSample::from_issue(
+                "#2".to_owned(),
+                "Run failed",
+                &body("ERROR: Task do_compile failed"),
+            ),
+            FailureSample {
+                fingerprint: Some("d0c5e0ac7e8c2a1b".to_owned()),
+                ..FailureSample::from_issue(
+                    "#3".to_owned(),
+                    "Run failed",
+                    &body("No space left on device"),
+                )
+            },
+            FailureSample::from_issue(
+                "#4".to_owned(),
+                "Run failed",
+                &body("ERROR: Task do_install failed"),
+            ),
+            FailureSample::from_issue("#5".to_owned(), "Run failed", "Cancelled"),
+        ];
+        let similarity = Similarity::new(SimilarityAlgo::Levenshtein, Some(10.0)).unwrap();
+
+        let clusters = cluster(samples, similarity);
+        let summary: Vec<(usize, &str, Vec<String>)> = clusters
+            .iter()
+            .map(|c| (c.count, c.summary.as_str(), c.sources.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (2, "disk full", vec!["#1".to_owned(), "#3".to_owned()]),
+                (
+                    2,
+                    "ERROR: Task do_compile failed",
+                    vec!["#2".to_owned(), "#4".to_owned()]
+                ),
+                (1, "Run failed", vec!["#5".to_owned()]),
+            ]
+        );
+    }
+}
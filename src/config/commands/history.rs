@@ -0,0 +1,85 @@
+//! Report of the runs recorded in the `--history-db` database, across repositories and CI
+//! providers and without contacting the CI provider
+use super::OutputFormat;
+use crate::history::{DecisionCount, History, LabelCount, RecurringFailure};
+use crate::*;
+use std::io::Write;
+
+/// What the history database tells about the recorded runs
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HistoryReport {
+    /// Runs within the last `days` days, or all runs if `None`
+    pub days: Option<u64>,
+    pub decisions: Vec<DecisionCount>,
+    pub failure_labels: Vec<LabelCount>,
+    pub recurring_failures: Vec<RecurringFailure>,
+}
+
+/// Print the decisions about the recorded runs, the failure labels of their failed jobs, and the
+/// `top` job failures recorded in the most runs
+///
+/// # Arguments
+///
+/// * `path` - The history database
+/// * `days` - Only report the runs recorded within the last `days` days
+/// * `top` - Number of recurring failures to print
+/// * `format` - Output format
+pub fn history(path: &Path, days: Option<u64>, top: usize, format: OutputFormat) -> Result<()> {
+    if !path.exists() {
+        bail!("No history database at {path:?}, it is created by `create-issue-from-run --history-db`");
+    }
+    let history = History::open(path)?;
+    let report = HistoryReport {
+        days,
+        decisions: history.decisions(days)?,
+        failure_labels: history.failure_labels(days)?,
+        recurring_failures: history.recurring_failures(days, top)?,
+    };
+    match format {
+        OutputFormat::Json => pipe_println!("{}", serde_json::to_string_pretty(&report)?)?,
+        OutputFormat::Table => print_table(&report)?,
+        OutputFormat::Csv => {
+            bail!("`--format csv` is only supported by `list-failed-runs` and `stats`")
+        }
+    }
+    Ok(())
+}
+
+fn print_table(report: &HistoryReport) -> Result<()> {
+    let runs: usize = report.decisions.iter().map(|d| d.count).sum();
+    match report.days {
+        Some(days) => pipe_println!("Runs in the last {days} day(s): {runs}")?,
+        None => pipe_println!("Runs: {runs}")?,
+    }
+    for decision in &report.decisions {
+        pipe_println!("  {:<14} {:>6}", decision.decision, decision.count)?;
+    }
+    if !report.failure_labels.is_empty() {
+        pipe_println!("")?;
+        pipe_println!("{:<40} {:>6}", "FAILURE LABEL", "COUNT")?;
+        for label in &report.failure_labels {
+            pipe_println!("{:<40} {:>6}", label.label, label.count)?;
+        }
+    }
+    if !report.recurring_failures.is_empty() {
+        pipe_println!("")?;
+        pipe_println!(
+            "{:<16}  {:<40} {:>5} {:>6}  LAST SEEN",
+            "FINGERPRINT",
+            "JOB",
+            "RUNS",
+            "REPOS"
+        )?;
+        for failure in &report.recurring_failures {
+            pipe_println!(
+                "{:<16}  {:<40} {:>5} {:>6}  {}",
+                failure.fingerprint,
+                failure.job,
+                failure.runs,
+                failure.repos,
+                failure.last_seen
+            )?;
+        }
+    }
+    Ok(())
+}
@@ -1,19 +1,88 @@
-use super::StepKind;
+use super::{FailureLogFormat, StepKind};
 use crate::err_parse::yocto::util;
 use crate::*;
 use std::io::Write;
 
+/// A located failure log, with the metadata of the failed task for Yocto builds
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct FailureLog {
+    /// Absolute path of the failure log
+    pub path: PathBuf,
+    /// The recipe of the failed task, e.g. `sqlite3-native`
+    pub recipe: Option<String>,
+    /// The version of the recipe, e.g. `3.43.2`
+    pub version: Option<String>,
+    /// The failed task, e.g. `do_fetch`
+    pub task: Option<String>,
+    /// The [YoctoFailureKind](util::YoctoFailureKind) of the failed task
+    pub kind: Option<String>,
+}
+
+impl FailureLog {
+    fn from_path(path: PathBuf) -> Self {
+        Self {
+            path,
+            recipe: None,
+            version: None,
+            task: None,
+            kind: None,
+        }
+    }
+
+    fn set_task(&mut self, task: util::FailedTask) {
+        self.kind = Some(task.kind.to_string());
+        self.recipe = task.recipe;
+        self.version = task.version;
+        self.task = Some(task.task);
+    }
+
+    /// Write the failure log to stdout in the given format
+    pub fn print(&self, format: FailureLogFormat) -> Result<()> {
+        match format {
+            FailureLogFormat::Path => pipe_print!("{}", self.path.to_string_lossy())?,
+            FailureLogFormat::Json => pipe_println!("{}", serde_json::to_string_pretty(self)?)?,
+        }
+        Ok(())
+    }
+
+    /// Write all the failure logs to stdout in the given format, the paths are each terminated by
+    /// a NUL character if `null_separated` is set, otherwise by a newline
+    pub fn print_all(logs: &[Self], format: FailureLogFormat, null_separated: bool) -> Result<()> {
+        match format {
+            FailureLogFormat::Path => {
+                let terminator = if null_separated { '\0' } else { '\n' };
+                for log in logs {
+                    pipe_print!("{}{terminator}", log.path.to_string_lossy())?;
+                }
+            }
+            FailureLogFormat::Json => pipe_println!("{}", serde_json::to_string_pretty(logs)?)?,
+        }
+        Ok(())
+    }
+}
+
 /// Locate the specific failure log in a failed build/test/other from a log file
 ///
 /// # Arguments
 ///
 /// * `kind` - The [StepKind] (e.g. Yocto)
 /// * `log_file` - Log file to search for the failure log (e.g. log.txt or read from stdin)
+/// * `format` - Print the path of the failure log, or a JSON object with its metadata
+/// * `all` - Print every failure log found instead of only the first
+/// * `null_separated` - Terminate the paths printed with `all` with NUL instead of a newline
+/// * `resolver` - How to find the failure logs if they were written in e.g. a container
 ///
 /// e.g. if you have the log of a failed Yocto build (stdout & stderr) stored in log.txt, you can run use
 /// `gh-workflow-parser locate-failure-log --kind Yocto log.txt` to get an absolute path to the failure log
 /// e.g. a log.do_fetch.1234 file
-pub fn locate_failure_log(kind: StepKind, log_file: Option<&PathBuf>) -> Result<()> {
+pub fn locate_failure_log(
+    kind: StepKind,
+    log_file: Option<&PathBuf>,
+    format: FailureLogFormat,
+    all: bool,
+    null_separated: bool,
+    resolver: &PathResolver,
+) -> Result<()> {
     let logfile_content: String = match log_file {
         Some(file) => {
             log::info!("Reading log file: {file:?}");
@@ -32,9 +101,23 @@ pub fn locate_failure_log(kind: StepKind, log_file: Option<&PathBuf>) -> Result<
         }
     };
 
+    if all {
+        let failure_logs = match kind {
+            StepKind::Yocto => yocto_failure_logs(&logfile_content, resolver)?,
+            StepKind::Other => generic_failure_log_paths(&logfile_content, resolver)
+                .into_iter()
+                .map(FailureLog::from_path)
+                .collect(),
+        };
+        if failure_logs.is_empty() {
+            bail!("No failure logs found");
+        }
+        return FailureLog::print_all(&failure_logs, format, null_separated);
+    }
+
     match kind {
-        StepKind::Yocto => locate_yocto_failure_log(&logfile_content)?,
-        StepKind::Other => todo!("This feature is not implemented yet!"),
+        StepKind::Yocto => locate_yocto_failure_log(&logfile_content, format, resolver)?,
+        StepKind::Other => locate_generic_failure_log(&logfile_content, format, resolver)?,
     }
 
     Ok(())
@@ -44,6 +127,8 @@ pub fn locate_failure_log(kind: StepKind, log_file: Option<&PathBuf>) -> Result<
 ///
 /// # Arguments
 /// * `logfile_content` - The contents of the log file
+/// * `format` - The output format
+/// * `resolver` - How to find the failure log if it was written in e.g. a container
 ///
 /// # Returns
 /// The absolute path to the failure log
@@ -53,25 +138,249 @@ pub fn locate_failure_log(kind: StepKind, log_file: Option<&PathBuf>) -> Result<
 ///
 /// # Example
 /// ```no_run
-/// # use ci_manager::config::commands::locate_failure_log::locate_yocto_failure_log;
+/// # use ci_manager::config::commands::{
+/// #     locate_failure_log::{locate_yocto_failure_log, PathResolver},
+/// #     FailureLogFormat,
+/// # };
 /// let logfile_content = r#"multi line
 /// test string foo/bar/baz.txt and other
 /// contents"#;
-/// locate_yocto_failure_log(logfile_content).unwrap();
+/// locate_yocto_failure_log(logfile_content, FailureLogFormat::Path, &PathResolver::default()).unwrap();
 /// // Prints the absolute path to "foo/bar/baz.txt" to stdout
 /// ```
 ///
-pub fn locate_yocto_failure_log(logfile_content: &str) -> Result<()> {
+pub fn locate_yocto_failure_log(
+    logfile_content: &str,
+    format: FailureLogFormat,
+    resolver: &PathResolver,
+) -> Result<()> {
+    yocto_failure_log(logfile_content, resolver)?.print(format)
+}
+
+/// Find the failure log of a failed Yocto build, with the recipe, version and task it belongs to
+pub fn yocto_failure_log(logfile_content: &str, resolver: &PathResolver) -> Result<FailureLog> {
     log::trace!("Finding failure log in log file contents: {logfile_content}");
     let error_summary = util::yocto_error_summary(logfile_content)?;
     let error_summary = util::trim_trailing_just_recipes(&error_summary)?;
     log::trace!("Trimmed error summary: {error_summary}");
     let log_file_line = util::find_yocto_failure_log_str(&error_summary)?;
-    let path = logfile_path_from_str(log_file_line)?;
-    // write to stdout
-    pipe_print!("{}", path.to_string_lossy())?;
+    let path = resolver.logfile_path_from_str(log_file_line)?;
+    let mut failure_log = FailureLog::from_path(path);
+    if let Some(task) = util::failed_tasks(log_file_line).into_iter().next() {
+        failure_log.set_task(task);
+    }
+    Ok(failure_log)
+}
 
-    Ok(())
+/// Find the failure logs of all the failed tasks of a Yocto build, in the order BitBake reported
+/// them. Failure logs that do not exist are skipped.
+pub fn yocto_failure_logs(
+    logfile_content: &str,
+    resolver: &PathResolver,
+) -> Result<Vec<FailureLog>> {
+    let error_summary = util::yocto_error_summary(logfile_content)?;
+    let error_summary = util::trim_trailing_just_recipes(&error_summary)?;
+    let mut failure_logs = Vec::new();
+    for task in util::failed_tasks(&error_summary) {
+        match resolver.logfile_path_from_str(&task.logfile.to_string_lossy()) {
+            Ok(path) => {
+                let mut failure_log = FailureLog::from_path(path);
+                failure_log.set_task(task);
+                failure_logs.push(failure_log);
+            }
+            Err(e) => log::warn!("Skipping the failure log of {task}: {e}"),
+        }
+    }
+    Ok(failure_logs)
+}
+
+/// Locate the failure log of any kind of failed step from the contents of its log file
+///
+/// Looks for lines pointing at a log file, like `Logfile of failure stored in: <path>` or
+/// `see <path> for details`, starting from the end of the log. If none of them point at an
+/// existing file, the paths mentioned in the last block of lines with an error are tried.
+///
+/// # Errors
+/// Returns an error if the log does not mention an existing log file
+pub fn locate_generic_failure_log(
+    logfile_content: &str,
+    format: FailureLogFormat,
+    resolver: &PathResolver,
+) -> Result<()> {
+    let path = generic_failure_log_path(logfile_content, resolver)?;
+    FailureLog::from_path(path).print(format)
+}
+
+/// The existing log files that lines of the log point at, in the order they are mentioned, e.g.
+/// `Logfile of failure stored in: <path>` or `see <path> for details`
+fn pointed_log_paths(logfile_content: &str, resolver: &PathResolver) -> Vec<PathBuf> {
+    static LOG_POINTER_RES: Lazy<[Regex; 3]> = Lazy::new(|| {
+        [
+            // e.g. "Logfile of failure stored in: <path>" or "log written to <path>"
+            Regex::new(r"(?i)\blog(?:file)?\b.*\b(?:stored|written|saved|available)\s+(?:in|at|to):?\s+(?<path>\S+)").unwrap(),
+            // e.g. "see <path> for details" or "See the log file at <path> for more information"
+            Regex::new(r#"(?i)\bsee\s+(?:the\s+)?(?:log\s+(?:file\s+)?)?(?:at\s+|in\s+)?['"`]?(?<path>[^\s'"`]+)['"`]?\s+for\s+(?:more\s+)?(?:details|information)"#).unwrap(),
+            // e.g. "The full log is at <path>" or "Complete log: <path>"
+            Regex::new(r"(?i)\b(?:full|complete)\s+log(?:\s+is)?(?:\s+(?:at|in))?:?\s+(?<path>\S+)").unwrap(),
+        ]
+    });
+
+    let mut paths = Vec::new();
+    for line in logfile_content.lines() {
+        let found = LOG_POINTER_RES.iter().find_map(|re| {
+            let caps = re.captures(line)?;
+            let path = caps["path"].trim_end_matches(['.', ',', ':', ';', ')']);
+            resolver
+                .logfile_path_from_str(path)
+                .map_err(|e| log::debug!("Log file mentioned in {line:?} not found: {e}"))
+                .ok()
+        });
+        paths.extend(found);
+    }
+    paths
+}
+
+/// All the failure logs of any kind of failed step, the log files the log points at or else the
+/// failure log found by [generic_failure_log_path]
+fn generic_failure_log_paths(logfile_content: &str, resolver: &PathResolver) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for path in pointed_log_paths(logfile_content, resolver) {
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    if paths.is_empty() {
+        paths.extend(generic_failure_log_path(logfile_content, resolver).ok());
+    }
+    paths
+}
+
+fn generic_failure_log_path(logfile_content: &str, resolver: &PathResolver) -> Result<PathBuf> {
+    static ERROR_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)\berror\b").unwrap());
+
+    // The last pointer is the closest to where the step failed
+    if let Some(path) = pointed_log_paths(logfile_content, resolver).pop() {
+        return Ok(path);
+    }
+
+    let lines: Vec<&str> = logfile_content.lines().collect();
+    log::info!("No line points at an existing log file, looking for paths in the last error block");
+    if let Some(last_error) = lines.iter().rposition(|line| ERROR_RE.is_match(line)) {
+        // The block is the surrounding lines up to the nearest blank lines
+        let start = lines[..last_error]
+            .iter()
+            .rposition(|line| line.trim().is_empty())
+            .map_or(0, |i| i + 1);
+        let end = lines[last_error..]
+            .iter()
+            .position(|line| line.trim().is_empty())
+            .map_or(lines.len(), |i| last_error + i);
+        for line in &lines[start..end] {
+            if let Ok(path) = resolver.logfile_path_from_str(line) {
+                return Ok(path);
+            }
+        }
+    }
+
+    bail!(
+        "No failure log found. Looked for lines like `log stored in <path>` or `see <path> for details`, \
+        and for existing files mentioned in the last error block"
+    )
+}
+
+/// A prefix of the paths in a log replaced by another prefix, to find logs written in another
+/// environment, e.g. `/app=/home/runner/work/repo` for a build that ran in a container with the
+/// repository mounted at `/app`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathMap {
+    pub from: PathBuf,
+    pub to: PathBuf,
+}
+
+impl std::str::FromStr for PathMap {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let Some((from, to)) = s.split_once('=') else {
+            bail!("Expected a path map like FROM=TO, got: {s}");
+        };
+        if from.is_empty() || to.is_empty() {
+            bail!("Expected a path map like FROM=TO, got: {s}");
+        }
+        Ok(Self {
+            from: PathBuf::from(from),
+            to: PathBuf::from(to),
+        })
+    }
+}
+
+/// Finds the log files that paths in a log point at, which can be written in another environment
+/// than the one ci-manager runs in, e.g. a container
+///
+/// A path is tried as is, then with the [PathMap]s applied, then below each search root, and
+/// finally with the heuristic of [logfile_path_from_str].
+#[derive(Debug, Default, Clone)]
+pub struct PathResolver {
+    pub search_roots: Vec<PathBuf>,
+    pub path_maps: Vec<PathMap>,
+}
+
+impl PathResolver {
+    pub fn new(search_roots: Vec<PathBuf>, path_maps: Vec<PathMap>) -> Self {
+        Self {
+            search_roots,
+            path_maps,
+        }
+    }
+
+    /// Find the absolute path of the first path found in a string
+    ///
+    /// # Example
+    /// ```
+    /// # use ci_manager::config::commands::locate_failure_log::PathResolver;
+    /// let dir = temp_dir::TempDir::new().unwrap();
+    /// let log_path = dir.path().join("build/tmp/work/foo/1.0/temp/log.do_compile.42");
+    /// std::fs::create_dir_all(log_path.parent().unwrap()).unwrap();
+    /// std::fs::write(&log_path, "the details").unwrap();
+    ///
+    /// let resolver = PathResolver::new(
+    ///     Vec::new(),
+    ///     vec![format!("/app={}", dir.path().display()).parse().unwrap()],
+    /// );
+    /// let line = "ERROR: Logfile of failure stored in: /app/build/tmp/work/foo/1.0/temp/log.do_compile.42";
+    /// assert_eq!(resolver.logfile_path_from_str(line).unwrap(), log_path.canonicalize().unwrap());
+    /// ```
+    pub fn logfile_path_from_str(&self, s: &str) -> Result<PathBuf> {
+        let path = first_path_from_str(s)?;
+        if path.is_file() {
+            return canonicalize_if_file(path);
+        }
+        for map in &self.path_maps {
+            if let Ok(rest) = path.strip_prefix(&map.from) {
+                let mapped = map.to.join(rest);
+                log::debug!("Looking for file at mapped path: {mapped:?}");
+                if mapped.is_file() {
+                    return canonicalize_if_file(mapped);
+                }
+            }
+        }
+        // The longest part of the path that exists below a search root
+        let parts = path.components().collect::<Vec<_>>();
+        for i in 0..parts.len() {
+            let tail = parts[i..].iter().collect::<PathBuf>();
+            if tail.is_absolute() {
+                continue;
+            }
+            for root in &self.search_roots {
+                let candidate = root.join(&tail);
+                log::debug!("Looking for file at path: {candidate:?}");
+                if candidate.is_file() {
+                    return canonicalize_if_file(candidate);
+                }
+            }
+        }
+        logfile_path_from_str(s)
+    }
 }
 
 /// Find the absolute path of the first path found in a string.
@@ -173,4 +482,96 @@ other contents",
         // Check that the path is the same as the temporary file
         assert_eq!(path, path_to_log);
     }
+
+    #[test]
+    fn test_yocto_failure_log_metadata() {
+        let dir = TempDir::new().unwrap();
+        let path_to_log = dir.path().join(
+            "yocto/build/tmp/work/x86_64-linux/sqlite3-native/3.43.2/temp/log.do_fetch.21616",
+        );
+        std::fs::create_dir_all(path_to_log.parent().unwrap()).unwrap();
+        std::fs::write(&path_to_log, "the details").unwrap();
+        let log = format!(
+            "ERROR: Logfile of failure stored in: {}",
+            path_to_log.to_string_lossy()
+        );
+
+        let failure_log = yocto_failure_log(&log, &PathResolver::default()).unwrap();
+        assert_eq!(
+            failure_log,
+            FailureLog {
+                path: path_to_log,
+                recipe: Some("sqlite3-native".to_owned()),
+                version: Some("3.43.2".to_owned()),
+                task: Some("do_fetch".to_owned()),
+                kind: Some("do_fetch".to_owned()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_yocto_failure_logs_of_all_failed_tasks() {
+        let dir = TempDir::new().unwrap();
+        let work_dir = dir.path().join("yocto/build/tmp/work/cortexa72-poky-linux");
+        let fetch_log = work_dir.join("zlib/1.3.1/temp/log.do_fetch.1001");
+        let compile_log = work_dir.join("foo/1.0/temp/log.do_compile.1002");
+        for log in [&fetch_log, &compile_log] {
+            std::fs::create_dir_all(log.parent().unwrap()).unwrap();
+            std::fs::write(log, "the details").unwrap();
+        }
+        let log = format!(
+            "ERROR: Logfile of failure stored in: {fetch}\n\
+            ERROR: Logfile of failure stored in: /does/not/exist/temp/log.do_install.1003\n\
+            ERROR: Logfile of failure stored in: {compile}",
+            fetch = fetch_log.to_string_lossy(),
+            compile = compile_log.to_string_lossy(),
+        );
+
+        let failure_logs = yocto_failure_logs(&log, &PathResolver::default()).unwrap();
+        let paths: Vec<&Path> = failure_logs.iter().map(|log| log.path.as_path()).collect();
+        assert_eq!(paths, [fetch_log.as_path(), compile_log.as_path()]);
+        assert_eq!(failure_logs[1].task.as_deref(), Some("do_compile"));
+    }
+
+    #[test]
+    fn test_generic_failure_log_path_from_pointer() {
+        let dir = TempDir::new().unwrap();
+        let log_file = dir.child("build-output.txt");
+        std::fs::write(&log_file, "the details").unwrap();
+        let log = format!(
+            "Building...\n\
+            error: build failed\n\
+            See {path} for details.\n\
+            Done",
+            path = log_file.to_string_lossy()
+        );
+
+        let path = generic_failure_log_path(&log, &PathResolver::default()).unwrap();
+        assert_eq!(path, log_file);
+    }
+
+    #[test]
+    fn test_generic_failure_log_path_from_last_error_block() {
+        let dir = TempDir::new().unwrap();
+        let log_file = dir.child("test-results.log");
+        std::fs::write(&log_file, "the details").unwrap();
+        let log = format!(
+            "Running tests\n\
+            \n\
+            ERROR: 2 tests failed\n\
+            results in {path}\n\
+            \n\
+            Cleaning up",
+            path = log_file.to_string_lossy()
+        );
+
+        let path = generic_failure_log_path(&log, &PathResolver::default()).unwrap();
+        assert_eq!(path, log_file);
+    }
+
+    #[test]
+    fn test_generic_failure_log_path_not_found() {
+        let log = "ERROR: something went wrong\nsee /does/not/exist.log for details";
+        assert!(generic_failure_log_path(log, &PathResolver::default()).is_err());
+    }
 }
@@ -0,0 +1,137 @@
+use super::WorkflowKind;
+use crate::ci_provider::github::{
+    archive::RunArchive,
+    util::{failed_jobs_from_job_error_logs, repo_url_to_run_url, JobErrorLog, StepErrorLog},
+};
+use crate::issue::Issue;
+use crate::*;
+use octocrab::models::JobId;
+use std::io::Write;
+
+/// Build an issue from a previously exported run and print it, without contacting the CI provider
+///
+/// # Arguments
+///
+/// * `input` - A JSON archive created with `export-run`, or a directory of log files
+/// * `kind` - The [WorkflowKind] used to parse the logs (e.g. Yocto)
+/// * `title` - Title of the issue
+/// * `label` - The issue label
+///
+/// A directory is read the way `download-logs` writes it: every subdirectory is a failed job and
+/// every file in it is the log of a failed step. Files directly in the directory are treated as
+/// jobs with a single step.
+pub fn replay(input: &Path, kind: WorkflowKind, title: &str, label: &str) -> Result<()> {
+    let (run_id, run_url, job_error_logs) = if input.is_dir() {
+        log::info!("Reading logs from directory: {input:?}");
+        let run_url = repo_url_to_run_url("github.com/local/replay", "0");
+        ("0".to_owned(), run_url, job_error_logs_from_dir(input)?)
+    } else {
+        log::info!("Reading run archive: {input:?}");
+        let archive = fs::read_to_string(input)
+            .with_context(|| format!("Failed to read run archive: {input:?}"))?;
+        let archive: RunArchive = serde_json::from_str(&archive)
+            .with_context(|| format!("Invalid run archive: {input:?}"))?;
+        (
+            archive.run_id.to_string(),
+            archive.run_url.clone(),
+            archive.job_error_logs(),
+        )
+    };
+    log::info!("Replaying {} failed job(s)", job_error_logs.len());
+
+    let failed_jobs = failed_jobs_from_job_error_logs(&job_error_logs, &run_url, kind);
+    let mut issue = Issue::new(
+        title.to_owned(),
+        run_id,
+        run_url,
+        failed_jobs,
+        label.to_owned(),
+    );
+    issue.rename_labels(&Config::global().config_file().labels);
+    log::info!("Fingerprint of the failure: {}", issue.fingerprint());
+
+    pipe_println!("==== ISSUE TITLE ==== \n{}", issue.title())?;
+    pipe_println!("==== ISSUE LABEL(S) ==== \n{}", issue.labels().join(","))?;
+    pipe_println!("==== START OF ISSUE BODY ==== \n{}", issue.body())?;
+    pipe_println!("==== END OF ISSUE BODY ====")?;
+    Ok(())
+}
+
+/// Read the failed jobs from a directory of log files, sorted by name
+fn job_error_logs_from_dir(dir: &Path) -> Result<Vec<JobErrorLog>> {
+    let mut job_error_logs = Vec::new();
+    for (idx, entry) in sorted_dir_entries(dir)?.into_iter().enumerate() {
+        let job_id = JobId(idx as u64 + 1);
+        let step_logs = if entry.is_dir() {
+            sorted_dir_entries(&entry)?
+                .iter()
+                .filter(|path| path.is_file())
+                .map(|path| step_error_log_from_file(path))
+                .collect::<Result<Vec<_>>>()?
+        } else {
+            vec![step_error_log_from_file(&entry)?]
+        };
+        job_error_logs.push(JobErrorLog::new(job_id, file_stem(&entry), step_logs));
+    }
+    if job_error_logs.is_empty() {
+        bail!("No log files found in directory: {dir:?}");
+    }
+    Ok(job_error_logs)
+}
+
+fn step_error_log_from_file(path: &Path) -> Result<StepErrorLog> {
+    let contents =
+        fs::read_to_string(path).with_context(|| format!("Failed to read log file: {path:?}"))?;
+    Ok(StepErrorLog::new(file_stem(path), contents))
+}
+
+fn sorted_dir_entries(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort();
+    Ok(entries)
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn test_job_error_logs_from_dir() {
+        let dir = TempDir::new().unwrap();
+        let job_dir = dir.path().join("build");
+        fs::create_dir(&job_dir).unwrap();
+        fs::write(job_dir.join("3_Build image.txt"), "ERROR: build failed").unwrap();
+        fs::write(job_dir.join("2_Checkout.txt"), "checked out").unwrap();
+        fs::write(dir.path().join("test.txt"), "FAILED test_foo").unwrap();
+
+        let job_error_logs = job_error_logs_from_dir(dir.path()).unwrap();
+
+        assert_eq!(job_error_logs.len(), 2);
+        assert_eq!(job_error_logs[0].job_name, "build");
+        let step_names: Vec<&str> = job_error_logs[0]
+            .failed_step_logs
+            .iter()
+            .map(|step| step.step_name.as_str())
+            .collect();
+        assert_eq!(step_names, ["2_Checkout", "3_Build image"]);
+        assert_eq!(job_error_logs[1].job_name, "test");
+        assert_eq!(job_error_logs[1].logs_as_str(), "FAILED test_foo");
+    }
+
+    #[test]
+    fn test_job_error_logs_from_empty_dir_is_error() {
+        let dir = TempDir::new().unwrap();
+        assert!(job_error_logs_from_dir(dir.path()).is_err());
+    }
+}
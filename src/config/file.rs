@@ -0,0 +1,281 @@
+//! The optional configuration file given with `--config-file`
+use crate::{commands::WorkflowKind, err_parse::custom::CustomParser, storage::StorageConfig, *};
+use std::collections::BTreeMap;
+
+/// Contents of the configuration file
+///
+/// # Example
+/// ```toml
+/// [[parsers]]
+/// name = "acme-compiler"
+/// extract = '(?m)^acmec: error: .+$'
+/// label = "acme-compiler-error"
+///
+/// [job_kinds]
+/// "Test template *" = "yocto"
+/// "lint" = "pre-commit"
+///
+/// [[redact]]
+/// pattern = '[\w-]+\.corp\.example\.com'
+/// replacement = "<internal-host>"
+///
+/// [labels.do_compile]
+/// name = "yocto: compile"
+/// color = "d73a4a"
+/// description = "A Yocto recipe failed to compile"
+///
+/// [storage]
+/// provider = "s3"
+/// bucket = "ci-logs"
+/// region = "eu-north-1"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    /// Custom parsers, see [CustomParser]
+    pub parsers: Vec<CustomParser>,
+    /// Workflow kinds of jobs by their name, used when `--kind` is omitted, see [JobKinds]
+    pub job_kinds: JobKinds,
+    /// Rules to mask parts of the logs, see [Redactions]
+    pub redact: Redactions,
+    /// Names and styles of the labels of created issues, see [Labels]
+    pub labels: Labels,
+    /// Object storage that `--long-logs storage` uploads logs to, see [StorageConfig]
+    pub storage: Option<StorageConfig>,
+}
+
+impl ConfigFile {
+    /// Read and validate a configuration file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("Invalid configuration file {}", path.display()))
+    }
+}
+
+/// Workflow kinds of jobs by patterns of their name, where `*` matches any characters
+///
+/// If several patterns match a job name, the longest pattern wins, so the mapping does not depend
+/// on the order of the table.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(try_from = "BTreeMap<String, String>")]
+pub struct JobKinds(Vec<JobKindPattern>);
+
+#[derive(Debug, Clone)]
+struct JobKindPattern {
+    pattern: String,
+    regex: Regex,
+    kind: WorkflowKind,
+}
+
+impl TryFrom<BTreeMap<String, String>> for JobKinds {
+    type Error = anyhow::Error;
+
+    fn try_from(table: BTreeMap<String, String>) -> Result<Self> {
+        let mut patterns = Vec::with_capacity(table.len());
+        for (pattern, kind) in table {
+            let Ok(kind) = WorkflowKind::from_str(&kind, true) else {
+                bail!("Invalid workflow kind {kind:?} of jobs matching {pattern:?}");
+            };
+            patterns.push(JobKindPattern {
+                regex: wildcard_regex(&pattern),
+                pattern,
+                kind,
+            });
+        }
+        patterns.sort_by_key(|p| std::cmp::Reverse(p.pattern.len()));
+        Ok(Self(patterns))
+    }
+}
+
+impl JobKinds {
+    /// The workflow kind of the job named `job_name`, `None` if no pattern matches it
+    ///
+    /// # Example
+    /// ```
+    /// # use ci_manager::config::{commands::WorkflowKind, file::ConfigFile};
+    /// let config: ConfigFile = toml::from_str(r#"
+    /// [job_kinds]
+    /// "Test template *" = "yocto"
+    /// "Test template * (lint)" = "lint"
+    /// "#).unwrap();
+    ///
+    /// let kinds = &config.job_kinds;
+    /// assert_eq!(kinds.kind_of("Test template x86"), Some(WorkflowKind::Yocto));
+    /// assert_eq!(kinds.kind_of("Test template x86 (lint)"), Some(WorkflowKind::Lint));
+    /// assert_eq!(kinds.kind_of("Build docs"), None);
+    /// ```
+    pub fn kind_of(&self, job_name: &str) -> Option<WorkflowKind> {
+        self.0.iter().find(|p| p.regex.is_match(job_name)).map(|p| {
+            log::debug!(
+                "Job {job_name} matches {:?}, parsing it as {}",
+                p.pattern,
+                p.kind
+            );
+            p.kind
+        })
+    }
+}
+
+/// Rules replacing the matches of a regex in the logs, e.g. to mask internal hostnames or artifact
+/// URLs, applied before the logs are parsed, included in issues and compared to existing issues
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(try_from = "Vec<RedactionConfig>")]
+pub struct Redactions(Vec<Redaction>);
+
+#[derive(Debug, Clone)]
+struct Redaction {
+    regex: Regex,
+    replacement: String,
+}
+
+/// A rule as written in the configuration file, before the regex is compiled
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RedactionConfig {
+    pattern: String,
+    /// Can refer to capture groups, e.g. `${1}`
+    #[serde(default = "default_replacement")]
+    replacement: String,
+}
+
+fn default_replacement() -> String {
+    "<redacted>".to_owned()
+}
+
+impl TryFrom<Vec<RedactionConfig>> for Redactions {
+    type Error = anyhow::Error;
+
+    fn try_from(rules: Vec<RedactionConfig>) -> Result<Self> {
+        rules
+            .into_iter()
+            .map(|rule| {
+                let regex = Regex::new(&rule.pattern)
+                    .with_context(|| format!("Invalid redaction regex: {}", rule.pattern))?;
+                Ok(Redaction {
+                    regex,
+                    replacement: rule.replacement,
+                })
+            })
+            .collect::<Result<_>>()
+            .map(Self)
+    }
+}
+
+impl Redactions {
+    /// Apply the rules to `text` in the order of the configuration file
+    ///
+    /// # Example
+    /// ```
+    /// # use ci_manager::config::file::ConfigFile;
+    /// let config: ConfigFile = toml::from_str(r#"
+    /// [[redact]]
+    /// pattern = '[\w-]+\.corp\.example\.com'
+    /// replacement = "<internal-host>"
+    ///
+    /// [[redact]]
+    /// pattern = 'https://artifacts\.example\.com/\S+/(?<file>[^/\s]+)'
+    /// replacement = "<artifact ${file}>"
+    /// "#).unwrap();
+    ///
+    /// assert_eq!(
+    ///     config.redact.apply("Failed to fetch https://artifacts.example.com/builds/42/sdk.tar.gz from build-7.corp.example.com"),
+    ///     "Failed to fetch <artifact sdk.tar.gz> from <internal-host>"
+    /// );
+    /// ```
+    pub fn apply<'t>(&self, text: &'t str) -> borrow::Cow<'t, str> {
+        let mut text = borrow::Cow::Borrowed(text);
+        for rule in &self.0 {
+            if let borrow::Cow::Owned(redacted) =
+                rule.regex.replace_all(&text, rule.replacement.as_str())
+            {
+                text = borrow::Cow::Owned(redacted);
+            }
+        }
+        text
+    }
+}
+
+/// Color of labels that are not styled in the configuration file
+pub const DEFAULT_LABEL_COLOR: &str = "FF0000";
+
+/// Name, color and description of labels, by the label ci-manager would otherwise apply, e.g. the
+/// failure kind `do_compile`, a label of a custom parser or the `--label` of the issue
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(try_from = "BTreeMap<String, LabelStyle>")]
+pub struct Labels(BTreeMap<String, LabelStyle>);
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LabelStyle {
+    /// Name of the label instead of the label it is configured for
+    pub name: Option<String>,
+    /// Hex color of the label, e.g. `d73a4a`
+    #[serde(default = "default_label_color")]
+    pub color: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+fn default_label_color() -> String {
+    DEFAULT_LABEL_COLOR.to_owned()
+}
+
+impl TryFrom<BTreeMap<String, LabelStyle>> for Labels {
+    type Error = anyhow::Error;
+
+    fn try_from(mut styles: BTreeMap<String, LabelStyle>) -> Result<Self> {
+        for (label, style) in &mut styles {
+            let color = style.color.trim_start_matches('#');
+            if color.len() != 6 || !color.chars().all(|c| c.is_ascii_hexdigit()) {
+                bail!(
+                    "Invalid color of label {label}: {}, expected a hex color like d73a4a",
+                    style.color
+                );
+            }
+            style.color = color.to_owned();
+        }
+        Ok(Self(styles))
+    }
+}
+
+impl Labels {
+    /// The name of `label` on the issue, `label` itself unless it is renamed
+    ///
+    /// # Example
+    /// ```
+    /// # use ci_manager::config::file::ConfigFile;
+    /// let config: ConfigFile = toml::from_str(r##"
+    /// [labels.do_compile]
+    /// name = "yocto: compile"
+    /// color = "#d73a4a"
+    ///
+    /// [labels.CI]
+    /// description = "Failure of a scheduled build"
+    /// "##).unwrap();
+    ///
+    /// let labels = &config.labels;
+    /// assert_eq!(labels.name_of("do_compile"), "yocto: compile");
+    /// assert_eq!(labels.name_of("do_fetch"), "do_fetch");
+    /// assert_eq!(labels.style_of("yocto: compile"), ("d73a4a", ""));
+    /// assert_eq!(labels.style_of("CI"), ("FF0000", "Failure of a scheduled build"));
+    /// assert_eq!(labels.style_of("do_fetch"), ("FF0000", ""));
+    /// ```
+    pub fn name_of<'a>(&'a self, label: &'a str) -> &'a str {
+        self.0
+            .get(label)
+            .and_then(|style| style.name.as_deref())
+            .unwrap_or(label)
+    }
+
+    /// The color and description of a label by its name on the issue
+    pub fn style_of(&self, name: &str) -> (&str, &str) {
+        self.0
+            .iter()
+            .find(|(label, _)| self.name_of(label) == name)
+            .map_or((DEFAULT_LABEL_COLOR, ""), |(_, style)| {
+                (style.color.as_str(), style.description.as_str())
+            })
+    }
+}
@@ -9,45 +9,218 @@ use self::yocto::YoctoError;
 /// The maximum size of a GitHub issue body is 65536
 pub const LOGFILE_MAX_LEN: usize = 5000;
 
+pub mod annotations;
+pub mod compiler;
+pub mod crash;
+pub mod custom;
+pub mod external;
+pub mod jvm;
+pub mod linker;
+pub mod lint;
+pub mod network;
+pub mod pre_commit;
+pub mod pytest;
+pub mod resource;
+pub mod runner;
+pub mod timeout;
 pub mod yocto;
 
+/// Labels of failures caused by the runner rather than the code under test. The issue names the
+/// runner of jobs that failed with one of these labels.
+pub const RUNNER_FAILURE_LABELS: &[&str] = &[
+    runner::RUNNER_INFRA_LABEL,
+    resource::RUNNER_DISK_FULL_LABEL,
+    resource::OUT_OF_MEMORY_LABEL,
+];
+
+/// Labels of failures of the CI infrastructure rather than the code under test, that `--alert`
+/// triggers alerts for. Running out of memory is left out, as the code under test is as likely to
+/// be the cause as the runner.
+pub const INFRA_FAILURE_LABELS: &[&str] = &[
+    runner::RUNNER_INFRA_LABEL,
+    resource::RUNNER_DISK_FULL_LABEL,
+    network::INFRA_FLAKE_LABEL,
+];
+
 #[derive(Debug)]
 pub enum ErrorMessageSummary {
     Yocto(YoctoError),
+    /// Error parsed by one of the parsers for the other kinds of workflows, e.g. C/C++
+    Parsed(ParsedError),
     Other(String),
 }
 
+/// Summary of an error in a failed step found by a parser, and the label of the kind of failure
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedError {
+    summary: String,
+    label: Option<String>,
+    logfile: Option<Logfile>,
+}
+
+/// A log file attached to the issue, e.g. the full log of the failed tool
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Logfile {
+    pub name: String,
+    pub contents: String,
+}
+
+impl ParsedError {
+    pub fn new(summary: String, label: Option<String>) -> Self {
+        Self {
+            summary,
+            label,
+            logfile: None,
+        }
+    }
+
+    pub fn with_logfile(mut self, logfile: Logfile) -> Self {
+        self.logfile = Some(logfile);
+        self
+    }
+
+    pub fn summary(&self) -> &str {
+        &self.summary
+    }
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+    pub fn logfile(&self) -> Option<&Logfile> {
+        self.logfile.as_ref()
+    }
+}
+
 impl ErrorMessageSummary {
     pub fn summary(&self) -> &str {
         match self {
             ErrorMessageSummary::Yocto(err) => err.summary(),
+            ErrorMessageSummary::Parsed(err) => err.summary(),
             ErrorMessageSummary::Other(o) => o.as_str(),
         }
     }
     pub fn log(&self) -> Option<&str> {
         match self {
             ErrorMessageSummary::Yocto(err) => err.logfile().map(|log| log.contents.as_str()),
-            ErrorMessageSummary::Other(_) => None, // Does not come with a log file
+            ErrorMessageSummary::Parsed(err) => err.logfile().map(|log| log.contents.as_str()),
+            // Does not come with a log file
+            ErrorMessageSummary::Other(_) => None,
         }
     }
     pub fn logfile_name(&self) -> Option<&str> {
         match self {
             ErrorMessageSummary::Yocto(err) => err.logfile().map(|log| log.name.as_str()),
-            ErrorMessageSummary::Other(_) => None, // Does not come with a log file
+            ErrorMessageSummary::Parsed(err) => err.logfile().map(|log| log.name.as_str()),
+            // Does not come with a log file
+            ErrorMessageSummary::Other(_) => None,
         }
     }
 
     pub fn failure_label(&self) -> Option<String> {
         match self {
             ErrorMessageSummary::Yocto(err) => Some(err.kind().to_string()),
+            ErrorMessageSummary::Parsed(err) => err.label().map(str::to_owned),
             ErrorMessageSummary::Other(_) => None,
         }
     }
+
+    /// Whether the failure was caused by the runner, see [RUNNER_FAILURE_LABELS]
+    pub fn is_runner_failure(&self) -> bool {
+        self.failure_label()
+            .is_some_and(|label| RUNNER_FAILURE_LABELS.contains(&label.as_str()))
+    }
+
+    /// Whether the failure was caused by the CI infrastructure, see [INFRA_FAILURE_LABELS]
+    pub fn is_infra_failure(&self) -> bool {
+        self.failure_label()
+            .is_some_and(|label| INFRA_FAILURE_LABELS.contains(&label.as_str()))
+    }
+}
+
+/// Broad classification of a failure, used to decide how to react to it, e.g. whether to retry it
+#[derive(ValueEnum, Display, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FailureClass {
+    /// Transient infrastructure or network failure that is likely to pass when retried
+    #[value(name = "flake")]
+    Flake,
+    /// Any other failure
+    #[value(name = "other")]
+    Other,
+}
+
+impl FailureClass {
+    /// Classify a parsed failure. It is only a [FailureClass::Flake] if the parsers found a broken
+    /// runner, or network errors and nothing more specific, as a real failure often logs a
+    /// transient network error along the way.
+    ///
+    /// # Example
+    /// ```
+    /// # use ci_manager::err_parse::{ErrorMessageSummary, FailureClass, ParsedError};
+    /// let network_error = ParsedError::new(
+    ///     "Could not resolve host: github.com".to_owned(),
+    ///     Some("infra-flake".to_owned()),
+    /// );
+    /// let summary = ErrorMessageSummary::Parsed(network_error);
+    /// assert_eq!(FailureClass::of(&summary), FailureClass::Flake);
+    ///
+    /// let summary = ErrorMessageSummary::Other("error[E0308]: mismatched types".to_owned());
+    /// assert_eq!(FailureClass::of(&summary), FailureClass::Other);
+    /// ```
+    pub fn of(summary: &ErrorMessageSummary) -> Self {
+        let is_flake = match summary {
+            ErrorMessageSummary::Yocto(err) => err.kind() == YoctoFailureKind::DoFetchNetwork,
+            ErrorMessageSummary::Parsed(err) => matches!(
+                err.label(),
+                Some(network::INFRA_FLAKE_LABEL | runner::RUNNER_INFRA_LABEL)
+            ),
+            ErrorMessageSummary::Other(_) => false,
+        };
+        if is_flake {
+            Self::Flake
+        } else {
+            Self::Other
+        }
+    }
+
+    /// Classify the log of a failed job of a `kind` of workflow, see [FailureClass::of]
+    pub fn classify(log: &str, kind: WorkflowKind) -> Self {
+        match parse_error_message(log, kind) {
+            Ok(summary) => Self::of(&summary),
+            Err(e) => {
+                log::warn!("Failed to parse the log, classifying the failure as other: {e:#}");
+                Self::Other
+            }
+        }
+    }
+
+    /// Classify the logs of all failed jobs in a run, with the kind of workflow of each job. The
+    /// run is only classified as a [FailureClass::Flake] if every failed job is.
+    pub fn classify_all<'l>(logs: impl IntoIterator<Item = (&'l str, WorkflowKind)>) -> Self {
+        let mut logs = logs.into_iter().peekable();
+        if logs.peek().is_none() {
+            return Self::Other;
+        }
+        if logs.all(|(log, kind)| Self::classify(log, kind) == Self::Flake) {
+            Self::Flake
+        } else {
+            Self::Other
+        }
+    }
 }
 
 pub fn parse_error_message(
     err_msg: &str,
     workflow: WorkflowKind,
+) -> anyhow::Result<ErrorMessageSummary> {
+    parse_error_message_at_step(err_msg, workflow, None)
+}
+
+/// Same as [parse_error_message], with the name of the step that failed (or was running when the
+/// job was cancelled) to include in the summary where relevant
+#[tracing::instrument(skip(err_msg), fields(len = err_msg.len()))]
+pub fn parse_error_message_at_step(
+    err_msg: &str,
+    workflow: WorkflowKind,
+    step: Option<&str>,
 ) -> anyhow::Result<ErrorMessageSummary> {
     let err_msg = if Config::global().trim_timestamp() {
         log::info!("Trimming timestamps from the log error message");
@@ -63,6 +236,36 @@ pub fn parse_error_message(
     };
     let err_msg = err_msg.to_string();
 
+    let workflow = match workflow {
+        WorkflowKind::Auto => {
+            let detected = detect_workflow_kind(&err_msg);
+            log::info!("Detected workflow kind: {detected}");
+            detected
+        }
+        workflow => workflow,
+    };
+
+    let custom_parsers = &Config::global().config_file().parsers;
+    if let Some(parsed) =
+        custom::parse_custom_error(custom_parsers, custom::ParserOrder::Before, &err_msg)
+    {
+        return Ok(ErrorMessageSummary::Parsed(parsed));
+    }
+    if let Some(cmd) = Config::global().parser_cmd() {
+        match external::parse_with_command(cmd, &err_msg, workflow, step) {
+            Ok(Some(parsed)) => return Ok(ErrorMessageSummary::Parsed(parsed)),
+            Ok(None) => {
+                log::info!("The parser command found nothing, continuing with the built-in parsers")
+            }
+            Err(e) => log::warn!("{e:?}\nContinuing with the built-in parsers"),
+        }
+    }
+
+    if let Some(parsed) = parse_overriding_error(&err_msg, step) {
+        log::warn!("Found a failure that is not specific to the workflow kind, skipping the {workflow} parser");
+        return Ok(ErrorMessageSummary::Parsed(parsed));
+    }
+
     let err_msg = match workflow {
         WorkflowKind::Yocto => {
             ErrorMessageSummary::Yocto(yocto::parse_yocto_error(&err_msg).unwrap_or_else(|e| {
@@ -70,7 +273,224 @@ pub fn parse_error_message(
                 YoctoError::new(err_msg, YoctoFailureKind::default(), None)
             }))
         }
-        WorkflowKind::Other => ErrorMessageSummary::Other(err_msg.to_string()),
+        WorkflowKind::Cpp => match parse_cpp_error(&err_msg) {
+            Some(parsed) => ErrorMessageSummary::Parsed(parsed),
+            None => {
+                log::warn!("No compiler errors, linker errors or crashes found, returning error message as is");
+                ErrorMessageSummary::Other(err_msg)
+            }
+        },
+        WorkflowKind::Jvm => match jvm::parse_jvm_error(&err_msg) {
+            Some(parsed) => ErrorMessageSummary::Parsed(parsed),
+            None => {
+                log::warn!("No Gradle or Maven build failure found, returning error message as is");
+                ErrorMessageSummary::Other(err_msg)
+            }
+        },
+        WorkflowKind::PreCommit => match pre_commit::parse_pre_commit_error(&err_msg) {
+            Some(parsed) => ErrorMessageSummary::Parsed(parsed),
+            None => {
+                log::warn!("No failed pre-commit hooks found, returning error message as is");
+                ErrorMessageSummary::Other(err_msg)
+            }
+        },
+        WorkflowKind::Lint => match lint::parse_lint_error(&err_msg) {
+            Some(parsed) => ErrorMessageSummary::Parsed(parsed),
+            None => {
+                log::warn!("No linter findings found, returning error message as is");
+                ErrorMessageSummary::Other(err_msg)
+            }
+        },
+        WorkflowKind::Other | WorkflowKind::Auto => ErrorMessageSummary::Other(err_msg.to_string()),
+    };
+    // Network errors are only the cause of the failure if the parsers found nothing more specific
+    let err_msg = match err_msg {
+        ErrorMessageSummary::Other(err_msg) => {
+            if let Some(parsed) =
+                custom::parse_custom_error(custom_parsers, custom::ParserOrder::After, &err_msg)
+            {
+                return Ok(ErrorMessageSummary::Parsed(parsed));
+            }
+            match network::parse_network_error(&err_msg) {
+                Some(parsed) => {
+                    log::warn!(
+                        "Found network errors, classifying the failure as {}",
+                        network::INFRA_FLAKE_LABEL
+                    );
+                    ErrorMessageSummary::Parsed(parsed)
+                }
+                None => ErrorMessageSummary::Other(err_msg),
+            }
+        }
+        err_msg => err_msg,
     };
     Ok(err_msg)
 }
+
+/// Detect the kind of workflow a log comes from, by how confidently the parser of each kind
+/// recognizes it. Ties go to the kind listed first in [WorkflowKind], and
+/// [WorkflowKind::Other] is returned if no parser recognizes the log.
+///
+/// # Example
+/// ```
+/// # use ci_manager::{config::commands::WorkflowKind, err_parse::detect_workflow_kind};
+/// let log = "src/main.c:5:3: error: use of undeclared identifier 'y'";
+/// assert_eq!(detect_workflow_kind(log), WorkflowKind::Cpp);
+///
+/// let log = "FAILURE: Build failed with an exception.\n\n* What went wrong:\nTask failed";
+/// assert_eq!(detect_workflow_kind(log), WorkflowKind::Jvm);
+///
+/// assert_eq!(detect_workflow_kind("Error: Process completed with exit code 1."), WorkflowKind::Other);
+/// ```
+pub fn detect_workflow_kind(log: &str) -> WorkflowKind {
+    let confidence = |kind: WorkflowKind| -> u8 {
+        match kind {
+            WorkflowKind::Yocto if log.contains(yocto::util::YOCTO_ERROR_SUMMARY_SIGNATURE) => 3,
+            WorkflowKind::Yocto
+                if log.contains("Logfile of failure stored in")
+                    || yocto::util::kas_config_error_summary(log).is_some() =>
+            {
+                2
+            }
+            WorkflowKind::Jvm if jvm::parse_jvm_error(log).is_some() => 3,
+            WorkflowKind::PreCommit if !pre_commit::failed_hooks(log).is_empty() => 3,
+            WorkflowKind::Cpp
+                if !compiler::compiler_errors(log).is_empty()
+                    || linker::linker_errors(log).is_some() =>
+            {
+                2
+            }
+            WorkflowKind::Cpp if !crash::crash_reports(log).is_empty() => 1,
+            // Most linters use the same format as compilers, so it's a weak match
+            WorkflowKind::Lint if !lint::lint_findings(log).is_empty() => 1,
+            _ => 0,
+        }
+    };
+    let mut detected = (WorkflowKind::Other, 0);
+    for &kind in WorkflowKind::value_variants() {
+        let confidence = confidence(kind);
+        log::debug!("Confidence that the log is from a {kind} workflow: {confidence}");
+        if confidence > detected.1 {
+            detected = (kind, confidence);
+        }
+    }
+    detected.0
+}
+
+/// Parse the failures that make any kind of build fail in confusing ways, e.g. a full disk, a
+/// timeout or a broken runner. These override the parser of the workflow kind.
+fn parse_overriding_error(log: &str, step: Option<&str>) -> Option<ParsedError> {
+    // A runner that shuts down or loses connection also cancels the job
+    runner::parse_runner_error(log)
+        .or_else(|| timeout::parse_timeout_error(log, step))
+        .or_else(|| resource::parse_disk_full_error(log))
+        .or_else(|| resource::parse_out_of_memory_error(log))
+}
+
+/// Parse the output of a C/C++ build or test run. Compiler errors take precedence over linker
+/// errors, as a failed compilation often also makes the linker fail, and both take precedence over
+/// crashes (segmentation faults and sanitizer reports) of the tests.
+fn parse_cpp_error(log: &str) -> Option<ParsedError> {
+    if let Some(summary) = compiler::compiler_error_summary(log) {
+        return Some(ParsedError::new(
+            summary,
+            Some(compiler::COMPILER_ERROR_LABEL.to_owned()),
+        ));
+    }
+    if let Some(summary) = linker::linker_error_summary(log) {
+        return Some(ParsedError::new(
+            summary,
+            Some(linker::LINKER_ERROR_LABEL.to_owned()),
+        ));
+    }
+    crash::crash_summary(log)
+        .map(|summary| ParsedError::new(summary, Some(crash::CRASH_LABEL.to_owned())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CONFIG;
+    use pretty_assertions::assert_eq;
+
+    /// The parsers read their options from the global config
+    fn init_config() {
+        CONFIG.get_or_init(|| Config::parse_from(["ci-manager"]));
+    }
+
+    #[test]
+    fn test_classify_flakes() {
+        init_config();
+        for log in [
+            "curl: (6) Could not resolve host: downloads.yoctoproject.org",
+            "Temporary failure in name resolution",
+            "dial tcp 140.82.121.4:443: connect: connection timed out",
+            "read: Connection reset by peer",
+            "net/http: TLS handshake timeout",
+            "error pulling image: 429 Too Many Requests",
+            "The runner has received a shutdown signal.",
+            "The hosted runner: GitHub Actions 2 lost communication with the server.",
+        ] {
+            assert_eq!(
+                FailureClass::classify(log, WorkflowKind::Auto),
+                FailureClass::Flake,
+                "{log}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_classify_other() {
+        init_config();
+        let log = "ERROR: Task (/app/yocto/poky/meta/recipes-core/glibc/glibc_2.39.bb:do_compile) failed with exit code '1'";
+        assert_eq!(
+            FailureClass::classify(log, WorkflowKind::Yocto),
+            FailureClass::Other
+        );
+    }
+
+    #[test]
+    fn test_classify_compiler_error_with_network_error_as_other() {
+        init_config();
+        let log = "\
+warning: spurious network error (2 tries remaining): [6] Could not resolve host: index.crates.io
+src/main.c:5:3: error: use of undeclared identifier 'y'
+    5 |   y = 1;
+      |   ^
+make: *** [Makefile:4: main.o] Error 1";
+        assert_eq!(
+            FailureClass::classify(log, WorkflowKind::Auto),
+            FailureClass::Other
+        );
+        assert_eq!(
+            FailureClass::classify(log, WorkflowKind::Cpp),
+            FailureClass::Other
+        );
+        // Without the compiler error, the network error is the cause
+        let log = log.lines().next().unwrap();
+        assert_eq!(
+            FailureClass::classify(log, WorkflowKind::Cpp),
+            FailureClass::Flake
+        );
+    }
+
+    #[test]
+    fn test_classify_all_needs_every_job_to_be_a_flake() {
+        init_config();
+        let flake = ("Could not resolve host: github.com", WorkflowKind::Auto);
+        let other = ("error[E0308]: mismatched types", WorkflowKind::Auto);
+        assert_eq!(
+            FailureClass::classify_all([flake, flake]),
+            FailureClass::Flake
+        );
+        assert_eq!(
+            FailureClass::classify_all([flake, other]),
+            FailureClass::Other
+        );
+    }
+
+    #[test]
+    fn test_classify_all_without_logs_is_other() {
+        assert_eq!(FailureClass::classify_all([]), FailureClass::Other);
+    }
+}
@@ -0,0 +1,108 @@
+//! Extraction of the error annotations actions create with the `::error::` workflow command
+use crate::*;
+
+/// An error annotation, e.g. from `::error file=src/main.rs,line=3,title=Build::expected ';'`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub title: Option<String>,
+    pub message: String,
+}
+
+impl fmt::Display for Annotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.file, self.line) {
+            (Some(file), Some(line)) => write!(f, "`{file}:{line}`: ")?,
+            (Some(file), None) => write!(f, "`{file}`: ")?,
+            _ => (),
+        }
+        if let Some(title) = &self.title {
+            write!(f, "**{title}**: ")?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Find the error annotations in a step log, both the `::error ...::message` commands and the
+/// `##[error]message` lines GitHub writes for them. The `Process completed with exit code` error
+/// of every failed step is left out.
+///
+/// # Example
+/// ```
+/// # use ci_manager::err_parse::annotations::error_annotations;
+/// let log = r#"2024-03-04T10:00:01.0000000Z ::error file=src/main.rs,line=3,title=Build failed::expected `;`%0Afound `}`
+/// 2024-03-04T10:00:01.0000000Z ::warning file=src/lib.rs,line=1::unused import
+/// 2024-03-04T10:00:02.0000000Z ##[error]Tests failed
+/// 2024-03-04T10:00:02.0000000Z ##[error]Process completed with exit code 1."#;
+///
+/// let annotations = error_annotations(log);
+/// assert_eq!(annotations.len(), 2);
+/// assert_eq!(
+///     annotations[0].to_string(),
+///     "`src/main.rs:3`: **Build failed**: expected `;` found `}`"
+/// );
+/// assert_eq!(annotations[1].to_string(), "Tests failed");
+/// ```
+pub fn error_annotations(log: &str) -> Vec<Annotation> {
+    static COMMAND_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"::error(?: (?<params>[^:]*))?::(?<message>.*)$").unwrap());
+    static ERROR_LINE_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"##\[error\](?<message>.*)$").unwrap());
+
+    let log = remove_timestamp_prefixes(log);
+    let mut annotations: Vec<Annotation> = Vec::new();
+    for line in log.lines() {
+        let annotation = if let Some(caps) = COMMAND_RE.captures(line) {
+            let mut annotation = Annotation {
+                file: None,
+                line: None,
+                title: None,
+                message: unescape_data(&caps["message"]),
+            };
+            let params = caps.name("params").map_or("", |p| p.as_str());
+            for (key, value) in params.split(',').filter_map(|p| p.split_once('=')) {
+                let value = unescape_property(value.trim());
+                match key.trim() {
+                    "file" => annotation.file = Some(value),
+                    "line" => annotation.line = value.parse().ok(),
+                    "title" => annotation.title = Some(value),
+                    _ => (),
+                }
+            }
+            annotation
+        } else if let Some(caps) = ERROR_LINE_RE.captures(line) {
+            let message = caps["message"].trim();
+            if message.starts_with("Process completed with exit code") {
+                continue;
+            }
+            Annotation {
+                file: None,
+                line: None,
+                title: None,
+                message: message.to_owned(),
+            }
+        } else {
+            continue;
+        };
+        if !annotation.message.is_empty() && !annotations.contains(&annotation) {
+            annotations.push(annotation);
+        }
+    }
+    annotations
+}
+
+/// Undo the escaping of the message of a workflow command, joining multiple lines with spaces so
+/// the message fits in a list item
+fn unescape_data(data: &str) -> String {
+    data.replace("%0D", "")
+        .replace("%0A", " ")
+        .replace("%25", "%")
+        .trim()
+        .to_owned()
+}
+
+/// Undo the escaping of a property of a workflow command
+fn unescape_property(value: &str) -> String {
+    unescape_data(&value.replace("%3A", ":").replace("%2C", ","))
+}
@@ -0,0 +1,175 @@
+//! Extraction of GCC and Clang error diagnostics from compiler output
+use crate::*;
+
+/// Label of failures with compiler errors
+pub const COMPILER_ERROR_LABEL: &str = "compiler-error";
+/// Number of lines after a diagnostic included as its context, i.e. the source line and the caret
+const CONTEXT_LINES: usize = 2;
+/// Maximum number of diagnostics included in a summary
+const MAX_DIAGNOSTICS: usize = 20;
+
+/// A compiler error such as `src/main.c:12:5: error: 'x' undeclared`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub file: String,
+    pub line: u32,
+    pub column: Option<u32>,
+    pub message: String,
+    /// The lines following the diagnostic, usually the source line and a caret pointing at the error
+    pub context: Vec<String>,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)?;
+        if let Some(column) = self.column {
+            write!(f, ":{column}")?;
+        }
+        write!(f, ": error: {}", self.message)?;
+        for line in &self.context {
+            write!(f, "\n{line}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Extract the error diagnostics from the output of GCC or Clang, with a couple of lines of
+/// context each.
+///
+/// Diagnostics repeated for every template instantiation (or every time a header is included) are
+/// only included once, and the notes on where a template was instantiated from are left out.
+///
+/// # Example
+/// ```
+/// # use ci_manager::err_parse::compiler::compiler_errors;
+/// let log = r#"In file included from src/main.cpp:1:
+/// src/util.hpp: In instantiation of 'T twice(T) [with T = Foo]':
+/// src/main.cpp:8:10:   required from here
+/// src/util.hpp:3:14: error: no match for 'operator*' [with T = Foo]
+///     3 |     return x * 2;
+///       |            ~~^~~
+/// src/util.hpp: In instantiation of 'T twice(T) [with T = Bar]':
+/// src/main.cpp:9:10:   required from here
+/// src/util.hpp:3:14: error: no match for 'operator*' [with T = Bar]
+///     3 |     return x * 2;
+///       |            ~~^~~
+/// src/main.c:12: error: expected ';' before '}' token"#;
+///
+/// let errors = compiler_errors(log);
+/// assert_eq!(errors.len(), 2);
+/// assert_eq!(
+///     errors[0].to_string(),
+///     "src/util.hpp:3:14: error: no match for 'operator*'\n    3 |     return x * 2;\n      |            ~~^~~"
+/// );
+/// assert_eq!(errors[1].file, "src/main.c");
+/// assert_eq!(errors[1].column, None);
+/// ```
+pub fn compiler_errors(log: &str) -> Vec<Diagnostic> {
+    static ERROR_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"(?:^|\s)(?<file>[^\s:]+):(?<line>\d+):(?:(?<column>\d+):)?\s+(?:fatal )?error:\s+(?<message>.+)$").unwrap()
+    });
+    // Any line the compiler prints that is not the context of the previous diagnostic
+    static COMPILER_LINE_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"(?:[^\s:]+:\d+(?::\d+)?:\s+(?:fatal error|error|warning|note|required from)|In file included from|\bfrom [^\s:]+:\d+|: In (?:function|member function|instantiation of|constructor|destructor))").unwrap()
+    });
+    // The template arguments of an instantiation, e.g. ` [with T = Foo]`
+    static TEMPLATE_ARGS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\s*\[with [^\]]*\]").unwrap());
+
+    let lines: Vec<&str> = log.lines().collect();
+    let mut errors: Vec<Diagnostic> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let Some(caps) = ERROR_RE.captures(line) else {
+            continue;
+        };
+        let context = lines[i + 1..]
+            .iter()
+            .take(CONTEXT_LINES)
+            .take_while(|line| !line.trim().is_empty() && !COMPILER_LINE_RE.is_match(line))
+            .map(|line| line.to_string())
+            .collect();
+        let error = Diagnostic {
+            file: caps["file"].to_owned(),
+            line: caps["line"].parse().unwrap_or_default(),
+            column: caps.name("column").and_then(|c| c.as_str().parse().ok()),
+            message: TEMPLATE_ARGS_RE
+                .replace_all(caps["message"].trim_end(), "")
+                .into_owned(),
+            context,
+        };
+        let is_duplicate = errors.iter().any(|e| {
+            e.file == error.file
+                && e.line == error.line
+                && e.column == error.column
+                && e.message == error.message
+        });
+        if !is_duplicate {
+            errors.push(error);
+        }
+    }
+    errors
+}
+
+/// Summary of the compiler errors in a log, `None` if there are no compiler errors
+pub fn compiler_error_summary(log: &str) -> Option<String> {
+    let errors = compiler_errors(log);
+    if errors.is_empty() {
+        return None;
+    }
+    let mut summary = errors
+        .iter()
+        .take(MAX_DIAGNOSTICS)
+        .map(Diagnostic::to_string)
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    if errors.len() > MAX_DIAGNOSTICS {
+        summary.push_str(&format!(
+            "\n\n... and {} more error(s)",
+            errors.len() - MAX_DIAGNOSTICS
+        ));
+    }
+    summary.push('\n');
+    Some(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_compiler_error_summary_clang() {
+        let log = r#"[ 50%] Building C object CMakeFiles/app.dir/main.c.o
+/src/app/main.c:5:3: error: use of undeclared identifier 'y'
+  y = 2;
+  ^
+/src/app/main.c:4:7: warning: unused variable 'x' [-Wunused-variable]
+  int x;
+      ^
+1 warning and 1 error generated.
+make[2]: *** [CMakeFiles/app.dir/build.make:76: CMakeFiles/app.dir/main.c.o] Error 1"#;
+
+        assert_eq!(
+            compiler_error_summary(log).unwrap(),
+            "/src/app/main.c:5:3: error: use of undeclared identifier 'y'\n  y = 2;\n  ^\n"
+        );
+    }
+
+    #[test]
+    fn test_compiler_error_summary_fatal_error() {
+        let log = "main.c:1:10: fatal error: foo.h: No such file or directory\n\
+            \x20   1 | #include \"foo.h\"\n\
+            \x20     |          ^~~~~~~\n\
+            compilation terminated.";
+
+        assert_eq!(
+            compiler_error_summary(log).unwrap(),
+            "main.c:1:10: error: foo.h: No such file or directory\n    1 | #include \"foo.h\"\n      |          ^~~~~~~\n"
+        );
+    }
+
+    #[test]
+    fn test_compiler_error_summary_no_errors() {
+        let log = "main.c:4:7: warning: unused variable 'x'\nBuild succeeded";
+        assert_eq!(compiler_error_summary(log), None);
+    }
+}
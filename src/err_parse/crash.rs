@@ -0,0 +1,179 @@
+//! Extraction of crash reports, i.e. segmentation faults and reports of the Address, Leak, Thread,
+//! Memory and UndefinedBehavior sanitizers
+use crate::*;
+
+/// Label of failures caused by a crashing program
+pub const CRASH_LABEL: &str = "crash";
+/// Maximum number of stack frames included in a report
+const MAX_FRAMES: usize = 5;
+/// Maximum number of lines between the header of a report and its stack trace, e.g. `READ of size 4`
+const MAX_DETAIL_LINES: usize = 3;
+/// Maximum number of reports included in a summary
+const MAX_REPORTS: usize = 5;
+
+/// A crash, with the top frames of the stack if the log contains a stack trace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrashReport {
+    /// The line reporting the crash, e.g. `ERROR: AddressSanitizer: heap-use-after-free ...`
+    pub header: String,
+    /// The lines between the header and the stack trace
+    pub details: Vec<String>,
+    pub frames: Vec<String>,
+}
+
+impl fmt::Display for CrashReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.header)?;
+        for line in self.details.iter().chain(&self.frames) {
+            write!(f, "\n{line}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Find the segmentation faults and sanitizer reports in a log, with the top frames of the stack
+///
+/// # Example
+/// ```
+/// # use ci_manager::err_parse::crash::crash_reports;
+/// let log = r#"[ RUN      ] Buffer.Append
+/// =================================================================
+/// ==4242==ERROR: AddressSanitizer: heap-use-after-free on address 0x602000000010 at pc 0x4c3b2a
+/// READ of size 4 at 0x602000000010 thread T0
+///     #0 0x4c3b2a in Buffer::append(int) /src/buffer.cpp:12:5
+///     #1 0x4c1f0e in Buffer_Append_Test::TestBody() /src/buffer_test.cpp:8:3
+///
+/// 0x602000000010 is located 0 bytes inside of 4-byte region
+/// SUMMARY: AddressSanitizer: heap-use-after-free /src/buffer.cpp:12:5 in Buffer::append(int)
+/// /src/math.c:3:12: runtime error: signed integer overflow: 2147483647 + 1 cannot be represented in type 'int'"#;
+///
+/// let reports = crash_reports(log);
+/// assert_eq!(reports.len(), 2);
+/// assert_eq!(
+///     reports[0].to_string(),
+///     "ERROR: AddressSanitizer: heap-use-after-free on address 0x602000000010 at pc 0x4c3b2a\n\
+///     READ of size 4 at 0x602000000010 thread T0\n\
+///     \x20   #0 0x4c3b2a in Buffer::append(int) /src/buffer.cpp:12:5\n\
+///     \x20   #1 0x4c1f0e in Buffer_Append_Test::TestBody() /src/buffer_test.cpp:8:3"
+/// );
+/// assert!(reports[1].frames.is_empty());
+/// ```
+pub fn crash_reports(log: &str) -> Vec<CrashReport> {
+    static HEADER_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"(?:(?:ERROR|WARNING): (?:Address|Leak|Thread|Memory|UndefinedBehavior)Sanitizer: .+|\S+:\d+(?::\d+)?: runtime error: .+|Segmentation fault(?: \(core dumped\))?|Bus error(?: \(core dumped\))?|Program received signal SIG[A-Z]+.*|Caught signal \d+.*)$").unwrap()
+    });
+    // e.g. `    #0 0x4c3b2a in main /src/main.c:6:10` or the frames of a gdb backtrace
+    static FRAME_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\s*#\d+\s+\S").unwrap());
+    // The process ID sanitizers prefix their reports with, e.g. `==4242==`
+    static PID_PREFIX_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^==\d+==").unwrap());
+
+    let lines: Vec<&str> = log.lines().collect();
+    let mut reports: Vec<CrashReport> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let Some(header) = HEADER_RE.find(line) else {
+            continue;
+        };
+        let header = PID_PREFIX_RE.replace(header.as_str(), "").trim().to_owned();
+        if reports.iter().any(|report| report.header == header) {
+            continue;
+        }
+        let following = &lines[i + 1..];
+        let first_frame = following
+            .iter()
+            .take(MAX_DETAIL_LINES + 1)
+            .position(|line| FRAME_RE.is_match(line));
+        let (details, frames) = match first_frame {
+            Some(first_frame) => (
+                following[..first_frame]
+                    .iter()
+                    .filter(|line| !line.trim().is_empty())
+                    .map(|line| line.trim_end().to_owned())
+                    .collect(),
+                following[first_frame..]
+                    .iter()
+                    .take_while(|line| FRAME_RE.is_match(line))
+                    .take(MAX_FRAMES)
+                    .map(|line| line.trim_end().to_owned())
+                    .collect(),
+            ),
+            None => (Vec::new(), Vec::new()),
+        };
+        reports.push(CrashReport {
+            header,
+            details,
+            frames,
+        });
+    }
+    reports
+}
+
+/// Summary of the crashes in a log, `None` if nothing crashed
+pub fn crash_summary(log: &str) -> Option<String> {
+    let reports = crash_reports(log);
+    if reports.is_empty() {
+        return None;
+    }
+    let mut summary = format!(
+        "{}\n",
+        reports
+            .iter()
+            .take(MAX_REPORTS)
+            .map(CrashReport::to_string)
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    );
+    if reports.len() > MAX_REPORTS {
+        summary.push_str(&format!(
+            "\n... and {} more crash report(s)\n",
+            reports.len() - MAX_REPORTS
+        ));
+    }
+    Some(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_crash_summary_segfault_with_gdb_backtrace() {
+        let log = r#"Running tests...
+Program received signal SIGSEGV, Segmentation fault.
+0x0000555555555131 in parse (input=0x0) at parser.c:4
+#0  0x0000555555555131 in parse (input=0x0) at parser.c:4
+#1  0x000055555555515e in main () at main.c:9
+Segmentation fault (core dumped)"#;
+
+        assert_eq!(
+            crash_summary(log).unwrap(),
+            "Program received signal SIGSEGV, Segmentation fault.\n\
+            0x0000555555555131 in parse (input=0x0) at parser.c:4\n\
+            #0  0x0000555555555131 in parse (input=0x0) at parser.c:4\n\
+            #1  0x000055555555515e in main () at main.c:9\n\
+            \n\
+            Segmentation fault (core dumped)\n"
+        );
+    }
+
+    #[test]
+    fn test_crash_summary_thread_sanitizer() {
+        let log = r#"==================
+WARNING: ThreadSanitizer: data race (pid=7)
+  Write of size 4 at 0x7b0400000000 by thread T1:
+    #0 worker /src/counter.c:5 (counter+0x1234)
+    #1 <null> <null> (libtsan.so.0+0x2d1af)
+
+  Previous write of size 4 at 0x7b0400000000 by main thread:
+    #0 main /src/counter.c:12 (counter+0x1250)
+"#;
+
+        assert_eq!(
+            crash_summary(log).unwrap(),
+            "WARNING: ThreadSanitizer: data race (pid=7)\n\
+            \x20 Write of size 4 at 0x7b0400000000 by thread T1:\n\
+            \x20   #0 worker /src/counter.c:5 (counter+0x1234)\n\
+            \x20   #1 <null> <null> (libtsan.so.0+0x2d1af)\n"
+        );
+    }
+}
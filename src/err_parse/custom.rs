@@ -0,0 +1,220 @@
+//! Parsers defined in the `[[parsers]]` section of the configuration file, so in-house tools can be
+//! supported without changes to ci-manager
+use super::ParsedError;
+use crate::*;
+
+/// Maximum number of extracted lines included in a summary
+const MAX_LINES: usize = 20;
+
+/// When a custom parser is applied, relative to the built-in parser of the workflow kind
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParserOrder {
+    /// Before the built-in parser, the custom parser wins if it matches
+    #[default]
+    Before,
+    /// Only if the built-in parser found nothing
+    After,
+}
+
+/// A parser defined in the configuration file
+///
+/// # Example
+/// ```toml
+/// [[parsers]]
+/// name = "acme-compiler"
+/// # The parser is only applied to logs matching any of these, or to all logs if empty
+/// match = ["acmec version \\d+"]
+/// # Every match of `extract` is a line in the summary
+/// extract = '(?m)^acmec: error: (?<file>\S+):(?<line>\d+): (?<message>.+)$'
+/// # Optional, how a match is written to the summary, the whole match by default
+/// summary = "${file}:${line}: ${message}"
+/// label = "acme-compiler-error"
+/// order = "before"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "CustomParserConfig")]
+pub struct CustomParser {
+    name: String,
+    match_patterns: Vec<Regex>,
+    extract: Regex,
+    summary_template: Option<String>,
+    label: Option<String>,
+    order: ParserOrder,
+}
+
+/// A parser as written in the configuration file, before the regexes are compiled
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CustomParserConfig {
+    name: String,
+    #[serde(default, rename = "match")]
+    match_patterns: Vec<String>,
+    extract: String,
+    summary: Option<String>,
+    label: Option<String>,
+    #[serde(default)]
+    order: ParserOrder,
+}
+
+impl TryFrom<CustomParserConfig> for CustomParser {
+    type Error = anyhow::Error;
+
+    fn try_from(config: CustomParserConfig) -> Result<Self> {
+        let name = config.name;
+        let match_patterns = config
+            .match_patterns
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern)
+                    .with_context(|| format!("Invalid match regex of parser {name}: {pattern}"))
+            })
+            .collect::<Result<_>>()?;
+        let extract = Regex::new(&config.extract).with_context(|| {
+            format!(
+                "Invalid extract regex of parser {name}: {extract}",
+                extract = config.extract
+            )
+        })?;
+        Ok(Self {
+            name,
+            match_patterns,
+            extract,
+            summary_template: config.summary,
+            label: config.label,
+            order: config.order,
+        })
+    }
+}
+
+impl CustomParser {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn order(&self) -> ParserOrder {
+        self.order
+    }
+
+    /// Parse a log, `None` if the log does not match or nothing was extracted
+    pub fn parse(&self, log: &str) -> Option<ParsedError> {
+        if !self.match_patterns.is_empty() && !self.match_patterns.iter().any(|re| re.is_match(log))
+        {
+            return None;
+        }
+        let mut lines: Vec<String> = Vec::new();
+        for caps in self.extract.captures_iter(log) {
+            let line = match &self.summary_template {
+                Some(template) => {
+                    let mut line = String::new();
+                    caps.expand(template, &mut line);
+                    line
+                }
+                None => caps[0].to_owned(),
+            };
+            let line = line.trim_end().to_owned();
+            if !lines.contains(&line) {
+                lines.push(line);
+            }
+        }
+        if lines.is_empty() {
+            return None;
+        }
+        let mut summary = String::new();
+        for line in lines.iter().take(MAX_LINES) {
+            summary.push_str(line);
+            summary.push('\n');
+        }
+        if lines.len() > MAX_LINES {
+            summary.push_str(&format!(
+                "... and {} more line(s)\n",
+                lines.len() - MAX_LINES
+            ));
+        }
+        Some(ParsedError::new(summary, self.label.clone()))
+    }
+}
+
+/// Parse a log with the first of the custom parsers applied in `order` that matches
+pub fn parse_custom_error(
+    parsers: &[CustomParser],
+    order: ParserOrder,
+    log: &str,
+) -> Option<ParsedError> {
+    parsers
+        .iter()
+        .filter(|parser| parser.order() == order)
+        .find_map(|parser| {
+            let parsed = parser.parse(log)?;
+            log::info!(
+                "Parsed the error message with custom parser {}",
+                parser.name()
+            );
+            Some(parsed)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[derive(Debug, Deserialize)]
+    struct Parsers {
+        parsers: Vec<CustomParser>,
+    }
+
+    const PARSERS: &str = r#"
+[[parsers]]
+name = "acme-compiler"
+match = ["acmec version \\d+"]
+extract = '(?m)^acmec: error: (?<file>\S+):(?<line>\d+): (?<message>.+)$'
+summary = "${file}:${line}: ${message}"
+label = "acme-compiler-error"
+
+[[parsers]]
+name = "acme-tests"
+extract = '(?m)^TEST FAILED: .+$'
+order = "after"
+"#;
+
+    #[test]
+    fn test_custom_parsers() {
+        let Parsers { parsers } = toml::from_str(PARSERS).unwrap();
+        let log = "acmec version 3\n\
+            acmec: error: src/motor.acme:12: unknown register r9\n\
+            acmec: error: src/motor.acme:12: unknown register r9\n\
+            TEST FAILED: motor_test";
+
+        assert_eq!(
+            parse_custom_error(&parsers, ParserOrder::Before, log).unwrap(),
+            ParsedError::new(
+                "src/motor.acme:12: unknown register r9\n".to_owned(),
+                Some("acme-compiler-error".to_owned())
+            )
+        );
+        assert_eq!(
+            parse_custom_error(&parsers, ParserOrder::After, log).unwrap(),
+            ParsedError::new("TEST FAILED: motor_test\n".to_owned(), None)
+        );
+        assert_eq!(
+            parse_custom_error(&parsers, ParserOrder::Before, "TEST FAILED: motor_test"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_custom_parser_invalid_regex() {
+        let err = toml::from_str::<Parsers>(
+            r#"
+[[parsers]]
+name = "broken"
+extract = "(unclosed"
+"#,
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Invalid extract regex of parser broken"));
+    }
+}
@@ -0,0 +1,134 @@
+//! Parsing with an external program given with `--parser-cmd`, for tools whose parsers can't be
+//! part of ci-manager
+use super::{Logfile, ParsedError};
+use crate::{config::commands::WorkflowKind, *};
+use std::process::{Command, Stdio};
+
+/// What the external parser writes to stdout, or `null` if it found nothing
+///
+/// # Example
+/// ```json
+/// {
+///   "summary": "acmec: error: src/motor.acme:12: unknown register r9\n",
+///   "label": "acme-compiler-error",
+///   "logfile": { "name": "acmec.log", "contents": "..." }
+/// }
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExternalParserOutput {
+    summary: String,
+    label: Option<String>,
+    logfile: Option<Logfile>,
+}
+
+/// Pipe a log to an external parser and read the parsed error from its stdout
+///
+/// The workflow kind and the failed step are passed to the parser in the `CI_MANAGER_KIND` and
+/// `CI_MANAGER_STEP` environment variables. Returns `None` if the parser wrote nothing or `null`.
+pub fn parse_with_command(
+    cmd: &Path,
+    log: &str,
+    kind: WorkflowKind,
+    step: Option<&str>,
+) -> Result<Option<ParsedError>> {
+    log::info!("Parsing the error message with {}", cmd.display());
+    let mut child = Command::new(cmd)
+        .env(
+            "CI_MANAGER_KIND",
+            kind.to_possible_value()
+                .map(|value| value.get_name().to_owned())
+                .unwrap_or_default(),
+        )
+        .env("CI_MANAGER_STEP", step.unwrap_or_default())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .with_context(|| format!("Failed to run parser command {}", cmd.display()))?;
+
+    // Write from another thread, the parser might not read all of stdin before writing to stdout
+    let mut stdin = child
+        .stdin
+        .take()
+        .context("Failed to open stdin of parser")?;
+    let log = log.to_owned();
+    let writer = std::thread::spawn(move || {
+        use std::io::Write;
+        stdin.write_all(log.as_bytes())
+    });
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("Failed to wait for parser command {}", cmd.display()))?;
+    if let Ok(Err(e)) = writer.join() {
+        // The parser may exit before reading everything, e.g. if it only needs the first lines
+        log::debug!("Failed to write the whole log to the parser: {e}");
+    }
+    if !output.status.success() {
+        bail!("Parser command {} failed: {}", cmd.display(), output.status);
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if stdout.trim().is_empty() {
+        return Ok(None);
+    }
+    let parsed: Option<ExternalParserOutput> = serde_json::from_str(&stdout)
+        .with_context(|| format!("Invalid output of parser command {}", cmd.display()))?;
+    Ok(parsed.map(|output| {
+        let parsed = ParsedError::new(output.summary, output.label);
+        match output.logfile {
+            Some(logfile) => parsed.with_logfile(logfile),
+            None => parsed,
+        }
+    }))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::os::unix::fs::PermissionsExt;
+    use temp_dir::TempDir;
+
+    fn parser_script(dir: &TempDir, script: &str) -> PathBuf {
+        let path = dir.child("parser.sh");
+        fs::write(&path, format!("#!/bin/sh\n{script}")).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_parse_with_command() {
+        let dir = TempDir::new().unwrap();
+        let cmd = parser_script(
+            &dir,
+            r#"printf '{"summary": "%s\\n", "label": "acme", "logfile": {"name": "kind.txt", "contents": "%s"}}' "$(grep error)" "$CI_MANAGER_KIND""#,
+        );
+
+        let parsed = parse_with_command(&cmd, "ok\nerror: boom\n", WorkflowKind::Other, None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(parsed.summary(), "error: boom\n");
+        assert_eq!(parsed.label(), Some("acme"));
+        assert_eq!(
+            parsed.logfile(),
+            Some(&Logfile {
+                name: "kind.txt".to_owned(),
+                contents: "other".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_with_command_no_match_and_failure() {
+        let dir = TempDir::new().unwrap();
+        let cmd = parser_script(&dir, "cat > /dev/null\necho null");
+        assert_eq!(
+            parse_with_command(&cmd, "log", WorkflowKind::Other, None).unwrap(),
+            None
+        );
+
+        let cmd = parser_script(&dir, "exit 3");
+        assert!(parse_with_command(&cmd, "log", WorkflowKind::Other, None).is_err());
+    }
+}
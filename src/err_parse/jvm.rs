@@ -0,0 +1,125 @@
+//! Parsing of failed Gradle and Maven builds
+use super::ParsedError;
+use crate::*;
+
+/// Label of failed Gradle builds
+pub const GRADLE_LABEL: &str = "gradle";
+/// Label of failed Maven builds
+pub const MAVEN_LABEL: &str = "maven";
+
+/// Parse the log of a failed Gradle or Maven build, `None` if it is neither
+pub fn parse_jvm_error(log: &str) -> Option<ParsedError> {
+    if let Some(summary) = gradle_error_summary(log) {
+        return Some(ParsedError::new(summary, Some(GRADLE_LABEL.to_owned())));
+    }
+    maven_error_summary(log).map(|summary| ParsedError::new(summary, Some(MAVEN_LABEL.to_owned())))
+}
+
+/// Summary of a failed Gradle build, from the `FAILURE: Build failed with an exception` block,
+/// headed by the failed task(s)
+///
+/// # Example
+/// ```
+/// # use ci_manager::err_parse::jvm::gradle_error_summary;
+/// let log = r#"> Task :app:compileJava FAILED
+///
+/// FAILURE: Build failed with an exception.
+///
+/// * What went wrong:
+/// Execution failed for task ':app:compileJava'.
+/// > Compilation failed; see the compiler error output for details.
+///
+/// * Try:
+/// > Run with --stacktrace option to get the stack trace.
+///
+/// BUILD FAILED in 2s"#;
+///
+/// assert_eq!(
+///     gradle_error_summary(log).unwrap(),
+///     "Failed Gradle task(s): :app:compileJava\n\
+///     \n\
+///     FAILURE: Build failed with an exception.\n\
+///     \n\
+///     * What went wrong:\n\
+///     Execution failed for task ':app:compileJava'.\n\
+///     > Compilation failed; see the compiler error output for details.\n"
+/// );
+/// ```
+pub fn gradle_error_summary(log: &str) -> Option<String> {
+    static FAILED_TASK_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"Execution failed for task '(?<task>[^']+)'").unwrap());
+
+    let start = log.find("FAILURE: Build ")?;
+    let block = &log[start..];
+    // The hints on how to get more output and the build scan link are noise in the issue
+    let end = ["\n* Try:", "\nBUILD FAILED"]
+        .iter()
+        .filter_map(|marker| block.find(marker))
+        .min()
+        .unwrap_or(block.len());
+    let block = block[..end].trim_end();
+
+    let mut tasks: Vec<&str> = Vec::new();
+    for caps in FAILED_TASK_RE.captures_iter(block) {
+        let task = caps.name("task").map_or("", |t| t.as_str());
+        if !tasks.contains(&task) {
+            tasks.push(task);
+        }
+    }
+    let mut summary = String::new();
+    if !tasks.is_empty() {
+        summary.push_str(&format!("Failed Gradle task(s): {}\n\n", tasks.join(", ")));
+    }
+    summary.push_str(block);
+    summary.push('\n');
+    Some(summary)
+}
+
+/// Summary of a failed Maven build with the failed goal and module, and the `[ERROR]` lines
+/// before Maven's help text
+///
+/// # Example
+/// ```
+/// # use ci_manager::err_parse::jvm::maven_error_summary;
+/// let log = r#"[INFO] BUILD FAILURE
+/// [ERROR] Failed to execute goal org.apache.maven.plugins:maven-compiler-plugin:3.11.0:compile (default-compile) on project my-app: Compilation failure
+/// [ERROR] /src/main/java/App.java:[10,5] cannot find symbol
+/// [ERROR] -> [Help 1]
+/// [ERROR]
+/// [ERROR] To see the full stack trace of the errors, re-run Maven with the -e switch."#;
+///
+/// assert_eq!(
+///     maven_error_summary(log).unwrap(),
+///     "Failed Maven goal org.apache.maven.plugins:maven-compiler-plugin:3.11.0:compile (default-compile) in module my-app\n\
+///     \n\
+///     Failed to execute goal org.apache.maven.plugins:maven-compiler-plugin:3.11.0:compile (default-compile) on project my-app: Compilation failure\n\
+///     /src/main/java/App.java:[10,5] cannot find symbol\n"
+/// );
+/// ```
+pub fn maven_error_summary(log: &str) -> Option<String> {
+    static FAILED_GOAL_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(
+            r"Failed to execute goal (?<goal>\S+(?: \([^)]+\))?) on project (?<project>[^:\s]+)",
+        )
+        .unwrap()
+    });
+    static ERROR_PREFIX_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^.*?\[ERROR\] ?").unwrap());
+
+    let caps = FAILED_GOAL_RE.captures(log)?;
+    let mut summary = format!(
+        "Failed Maven goal {goal} in module {project}\n\n",
+        goal = &caps["goal"],
+        project = &caps["project"]
+    );
+    let error_lines = log
+        .lines()
+        .filter(|line| line.contains("[ERROR]"))
+        .map(|line| ERROR_PREFIX_RE.replace(line, ""))
+        .take_while(|line| !line.starts_with("-> [Help"))
+        .filter(|line| !line.trim().is_empty());
+    for line in error_lines {
+        summary.push_str(&line);
+        summary.push('\n');
+    }
+    Some(summary)
+}
@@ -0,0 +1,141 @@
+//! Detection of linker errors in the output of GNU ld, gold, lld and the macOS linker
+use crate::*;
+
+/// Label of failures caused by the linker
+pub const LINKER_ERROR_LABEL: &str = "linker-error";
+
+/// The symbols and libraries the linker complained about
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LinkerErrors {
+    pub undefined_symbols: Vec<String>,
+    pub duplicate_symbols: Vec<String>,
+    pub missing_libraries: Vec<String>,
+}
+
+/// Find the linker errors in a log, `None` if the linker did not fail
+///
+/// # Example
+/// ```
+/// # use ci_manager::err_parse::linker::linker_errors;
+/// let log = r#"/usr/bin/ld: main.o: in function `main':
+/// main.c:(.text+0x1a): undefined reference to `foo'
+/// /usr/bin/ld: util.o:(.bss+0x0): multiple definition of `counter'; main.o:(.bss+0x0): first defined here
+/// /usr/bin/ld: cannot find -lssl: No such file or directory
+/// collect2: error: ld returned 1 exit status"#;
+///
+/// let errors = linker_errors(log).unwrap();
+/// assert_eq!(errors.undefined_symbols, ["foo"]);
+/// assert_eq!(errors.duplicate_symbols, ["counter"]);
+/// assert_eq!(errors.missing_libraries, ["ssl"]);
+///
+/// assert_eq!(linker_errors("error: expected ';'"), None);
+/// ```
+pub fn linker_errors(log: &str) -> Option<LinkerErrors> {
+    static LINKER_FAILED_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"ld returned \d+ exit status|linker command failed|undefined reference to|multiple definition of|duplicate symbol|undefined symbol|Undefined symbols for architecture").unwrap()
+    });
+    static UNDEFINED_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r#"undefined reference to [`'"](?<symbol>[^'`"]+)['`"]|undefined symbol: (?<lld_symbol>.+)$|^\s+"(?<macos_symbol>[^"]+)", referenced from:"#).unwrap()
+    });
+    static DUPLICATE_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r#"multiple definition of [`'"](?<symbol>[^'`"]+)['`"]|duplicate symbol:? '?(?<other_symbol>[^'\s]+)'?"#).unwrap()
+    });
+    static MISSING_LIB_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(
+            r"cannot find -l(?<library>[^\s:]+)|library not found for -l(?<macos_library>[^\s:]+)",
+        )
+        .unwrap()
+    });
+
+    if !LINKER_FAILED_RE.is_match(log) {
+        return None;
+    }
+    let mut errors = LinkerErrors::default();
+    for line in log.lines() {
+        if let Some(caps) = UNDEFINED_RE.captures(line) {
+            let symbol = caps
+                .name("symbol")
+                .or_else(|| caps.name("lld_symbol"))
+                .or_else(|| caps.name("macos_symbol"));
+            push_unique(&mut errors.undefined_symbols, symbol.map(|s| s.as_str()));
+        }
+        if let Some(caps) = DUPLICATE_RE.captures(line) {
+            let symbol = caps.name("symbol").or_else(|| caps.name("other_symbol"));
+            push_unique(&mut errors.duplicate_symbols, symbol.map(|s| s.as_str()));
+        }
+        if let Some(caps) = MISSING_LIB_RE.captures(line) {
+            let library = caps.name("library").or_else(|| caps.name("macos_library"));
+            push_unique(&mut errors.missing_libraries, library.map(|l| l.as_str()));
+        }
+    }
+    Some(errors)
+}
+
+fn push_unique(items: &mut Vec<String>, item: Option<&str>) {
+    if let Some(item) = item.map(str::trim) {
+        if !items.iter().any(|i| i == item) {
+            items.push(item.to_owned());
+        }
+    }
+}
+
+/// Summary of the linker errors in a log with the symbols and libraries as bullet lists, `None`
+/// if the linker did not fail
+pub fn linker_error_summary(log: &str) -> Option<String> {
+    let errors = linker_errors(log)?;
+    let mut summary = String::from("Linking failed\n");
+    for (title, items) in [
+        ("Undefined symbols", &errors.undefined_symbols),
+        ("Duplicate symbols", &errors.duplicate_symbols),
+        ("Missing libraries", &errors.missing_libraries),
+    ] {
+        if items.is_empty() {
+            continue;
+        }
+        summary.push_str(&format!("\n{title}:\n"));
+        for item in items {
+            summary.push_str(&format!("- {item}\n"));
+        }
+    }
+    Some(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_linker_error_summary_lld() {
+        let log = r#"ld.lld: error: undefined symbol: foo::bar(int)
+>>> referenced by main.cpp:12
+>>>               main.o:(main)
+ld.lld: error: duplicate symbol: counter
+clang: error: linker command failed with exit code 1 (use -v to see invocation)"#;
+
+        assert_eq!(
+            linker_error_summary(log).unwrap(),
+            "Linking failed\n\
+            \n\
+            Undefined symbols:\n\
+            - foo::bar(int)\n\
+            \n\
+            Duplicate symbols:\n\
+            - counter\n"
+        );
+    }
+
+    #[test]
+    fn test_linker_error_summary_macos() {
+        let log = r#"Undefined symbols for architecture arm64:
+  "_foo", referenced from:
+      _main in main.o
+ld: symbol(s) not found for architecture arm64
+clang: error: linker command failed with exit code 1 (use -v to see invocation)"#;
+
+        assert_eq!(
+            linker_error_summary(log).unwrap(),
+            "Linking failed\n\nUndefined symbols:\n- _foo\n"
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//! Parsing of the findings of ShellCheck and other linters
+use super::ParsedError;
+use crate::*;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Label of failures caused by linter findings
+pub const LINT_LABEL: &str = "lint";
+
+/// A single finding of a linter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintFinding {
+    pub file: String,
+    pub line: u32,
+    /// The rule code, e.g. `SC2086` or `F401`
+    pub code: String,
+    pub message: String,
+}
+
+/// Parse the output of a failed lint step, `None` if there are no findings
+pub fn parse_lint_error(log: &str) -> Option<ParsedError> {
+    lint_error_summary(log).map(|summary| ParsedError::new(summary, Some(LINT_LABEL.to_owned())))
+}
+
+/// Find the linter findings in a log
+///
+/// Supports the default (`tty`) and `gcc` output of ShellCheck, and the
+/// `file:line:col: CODE message` and `file:line:col: message [code]` formats used by most other
+/// linters, e.g. flake8, ruff, hadolint and ESLint's `unix` formatter.
+///
+/// # Example
+/// ```
+/// # use ci_manager::err_parse::lint::lint_findings;
+/// let log = r#"In scripts/build.sh line 3:
+/// echo $TARGET
+///      ^-----^ SC2086 (info): Double quote to prevent globbing and word splitting.
+///
+/// app/main.py:1:1: F401 'os' imported but unused
+/// Dockerfile:5 DL3008 warning: Pin versions in apt get install
+/// src/index.js:3:7: 'x' is assigned a value but never used. [Error/no-unused-vars]"#;
+///
+/// let findings = lint_findings(log);
+/// assert_eq!(findings.len(), 4);
+/// assert_eq!(findings[0].file, "scripts/build.sh");
+/// assert_eq!(findings[0].code, "SC2086");
+/// assert_eq!(findings[1].code, "F401");
+/// assert_eq!(findings[2].code, "DL3008");
+/// assert_eq!(findings[3].code, "no-unused-vars");
+/// ```
+pub fn lint_findings(log: &str) -> Vec<LintFinding> {
+    // ShellCheck's default output names the file and line, and the finding follows the source line
+    static SHELLCHECK_LOCATION_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"In (?<file>\S+) line (?<line>\d+):").unwrap());
+    static SHELLCHECK_FINDING_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"(?<code>SC\d{4}) \(\w+\): (?<message>.+)$").unwrap());
+    // e.g. `app.py:1:1: F401 'os' imported but unused` or `Dockerfile:5 DL3008 warning: Pin versions`
+    static CODE_FIRST_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"(?<file>[^\s:]+):(?<line>\d+)(?::\d+)?:?\s+(?<code>[A-Z]+\d+)\s+(?:\[\*\]\s+)?(?<message>.+)$").unwrap()
+    });
+    // e.g. `script.sh:3:6: note: Double quote to prevent globbing [SC2086]`
+    static CODE_LAST_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"(?<file>[^\s:]+):(?<line>\d+)(?::\d+)?:\s+(?:(?:error|warning|note|info|style):\s+)?(?<message>.+?)\s+\[(?:\w+/)?(?<code>[\w-]+)\]$").unwrap()
+    });
+
+    let log = remove_timestamp_prefixes(log);
+    let log = remove_ansi_codes(&log);
+    let mut findings = Vec::new();
+    let mut shellcheck_location: Option<(String, u32)> = None;
+    for line in log.lines() {
+        if let Some(caps) = SHELLCHECK_LOCATION_RE.captures(line) {
+            shellcheck_location =
+                Some((caps["file"].to_owned(), caps["line"].parse().unwrap_or(0)));
+            continue;
+        }
+        if let (Some((file, line_number)), Some(caps)) = (
+            shellcheck_location.as_ref(),
+            SHELLCHECK_FINDING_RE.captures(line),
+        ) {
+            findings.push(LintFinding {
+                file: file.clone(),
+                line: *line_number,
+                code: caps["code"].to_owned(),
+                message: caps["message"].trim().to_owned(),
+            });
+            continue;
+        }
+        if let Some(caps) = CODE_LAST_RE
+            .captures(line)
+            .or_else(|| CODE_FIRST_RE.captures(line))
+        {
+            findings.push(LintFinding {
+                file: caps["file"].to_owned(),
+                line: caps["line"].parse().unwrap_or(0),
+                code: caps["code"].to_owned(),
+                message: caps["message"].trim().to_owned(),
+            });
+        }
+    }
+    findings
+}
+
+/// Summary of the linter findings in a log as a table with a row per file and rule code, `None` if
+/// there are no findings
+pub fn lint_error_summary(log: &str) -> Option<String> {
+    let findings = lint_findings(log);
+    if findings.is_empty() {
+        return None;
+    }
+    // (file, code) -> (line numbers, first message)
+    let mut groups: BTreeMap<(&str, &str), (Vec<u32>, &str)> = BTreeMap::new();
+    for finding in &findings {
+        let group = groups
+            .entry((finding.file.as_str(), finding.code.as_str()))
+            .or_insert_with(|| (Vec::new(), finding.message.as_str()));
+        group.0.push(finding.line);
+    }
+    let num_files = groups
+        .keys()
+        .map(|(file, _)| file)
+        .collect::<BTreeSet<_>>()
+        .len();
+
+    let rows: Vec<[String; 5]> = groups
+        .into_iter()
+        .map(|((file, code), (lines, message))| {
+            [
+                file.to_owned(),
+                code.to_owned(),
+                lines.len().to_string(),
+                lines
+                    .iter()
+                    .map(u32::to_string)
+                    .collect::<Vec<_>>()
+                    .join(","),
+                message.to_owned(),
+            ]
+        })
+        .collect();
+    let header = ["FILE", "RULE", "COUNT", "LINES", "MESSAGE"].map(str::to_owned);
+    let mut widths = header.clone().map(|h| h.len());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+
+    let mut summary = format!(
+        "{num_findings} lint finding(s) in {num_files} file(s)\n\n",
+        num_findings = findings.len()
+    );
+    for row in std::iter::once(&header).chain(&rows) {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        summary.push_str(line.trim_end());
+        summary.push('\n');
+    }
+    Some(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_lint_error_summary_groups_by_file_and_rule() {
+        let log = r#"scripts/build.sh:3:6: note: Double quote to prevent globbing and word splitting. [SC2086]
+scripts/build.sh:9:6: note: Double quote to prevent globbing and word splitting. [SC2086]
+scripts/build.sh:12:1: warning: foo appears unused. Verify use (or export if used externally). [SC2034]
+app/main.py:1:1: F401 'os' imported but unused"#;
+
+        assert_eq!(
+            lint_error_summary(log).unwrap(),
+            "4 lint finding(s) in 2 file(s)
+
+FILE              RULE    COUNT  LINES  MESSAGE
+app/main.py       F401    1      1      'os' imported but unused
+scripts/build.sh  SC2034  1      12     foo appears unused. Verify use (or export if used externally).
+scripts/build.sh  SC2086  2      3,9    Double quote to prevent globbing and word splitting.
+"
+        );
+    }
+
+    #[test]
+    fn test_lint_error_summary_no_findings() {
+        assert_eq!(lint_error_summary("All checks passed!"), None);
+    }
+}
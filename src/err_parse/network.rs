@@ -0,0 +1,110 @@
+//! Detection of transient network failures, e.g. DNS failures or a registry responding with 503
+use super::ParsedError;
+use crate::*;
+
+/// Label of failures caused by transient network or infrastructure problems
+pub const INFRA_FLAKE_LABEL: &str = "infra-flake";
+/// Maximum number of log lines included in a summary
+const MAX_LINES: usize = 10;
+
+/// Find the lines of a log reporting DNS failures, TLS handshake errors, connection resets and
+/// timeouts, and HTTP 5xx or 429 responses from registries and other servers
+///
+/// # Example
+/// ```
+/// # use ci_manager::err_parse::network::network_errors;
+/// let log = r#"Step 1/8 : FROM rust:1.77
+/// error pulling image configuration: Get "https://registry-1.docker.io/v2/": net/http: TLS handshake timeout
+/// npm ERR! request to https://registry.npmjs.org/foo failed, reason: getaddrinfo EAI_AGAIN registry.npmjs.org
+/// toomanyrequests: You have reached your pull rate limit.
+/// error: could not find `foo` in the registry index"#;
+///
+/// assert_eq!(network_errors(log).len(), 3);
+/// assert!(network_errors("error[E0308]: mismatched types").is_empty());
+/// ```
+pub fn network_errors(log: &str) -> Vec<&str> {
+    static NETWORK_ERROR_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(
+            r"(?xi)
+            could\ not\ resolve\ host
+            | temporary\ failure\ in\ name\ resolution
+            | name\ or\ service\ not\ known
+            | getaddrinfo\ (?:ENOTFOUND|EAI_AGAIN)
+            | dial\ tcp:\ lookup\ \S+.*no\ such\ host
+            | tls\ handshake\ (?:timeout|failure)
+            | tls:\ handshake\ failure
+            | ssl_error_syscall
+            | ssl\ handshake
+            | connection\ reset\ by\ peer
+            | \bECONNRESET\b
+            | connection\ timed\ out
+            | \bETIMEDOUT\b
+            | 429\ too\ many\ requests
+            | \btoomanyrequests\b
+            | \b50[0234]\ (?:internal\ server\ error|bad\ gateway|service\ unavailable|gateway\ time-?out)
+            | (?:status|status\ code|response|returned\ error):?\ (?:5\d\d|429)\b
+        ",
+        )
+        .unwrap()
+    });
+
+    let mut lines: Vec<&str> = Vec::new();
+    for line in log.lines().filter(|line| NETWORK_ERROR_RE.is_match(line)) {
+        let line = line.trim();
+        if !lines.contains(&line) {
+            lines.push(line);
+        }
+    }
+    lines
+}
+
+/// Parse the log of a job that failed because of network errors, `None` if there are none
+pub fn parse_network_error(log: &str) -> Option<ParsedError> {
+    let lines = network_errors(log);
+    if lines.is_empty() {
+        return None;
+    }
+    let mut summary = String::from(
+        "The job failed because of network errors, most likely a transient infrastructure problem\n\n",
+    );
+    for line in lines.iter().take(MAX_LINES) {
+        summary.push_str(line);
+        summary.push('\n');
+    }
+    if lines.len() > MAX_LINES {
+        summary.push_str(&format!(
+            "... and {} more line(s)\n",
+            lines.len() - MAX_LINES
+        ));
+    }
+    Some(ParsedError::new(
+        summary,
+        Some(INFRA_FLAKE_LABEL.to_owned()),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse_network_error_registry() {
+        let log = r#"Downloading crates ...
+error: failed to download from `https://index.crates.io/config.json`
+Caused by:
+  failed to get successful HTTP response from `https://index.crates.io/config.json`, got 503
+  body: 503 Service Unavailable"#;
+
+        assert_eq!(
+            parse_network_error(log).unwrap(),
+            ParsedError::new(
+                "The job failed because of network errors, most likely a transient infrastructure problem\n\
+                \n\
+                body: 503 Service Unavailable\n"
+                    .to_owned(),
+                Some(INFRA_FLAKE_LABEL.to_owned())
+            )
+        );
+    }
+}
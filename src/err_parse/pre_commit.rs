@@ -0,0 +1,167 @@
+//! Parsing of failed `pre-commit run` output
+use super::ParsedError;
+use crate::*;
+
+/// Label of failures in pre-commit hooks
+pub const PRE_COMMIT_LABEL: &str = "pre-commit";
+
+/// A hook that failed in a `pre-commit run`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailedHook {
+    pub name: String,
+    /// The `- hook id: ...` lines and the output of the hook
+    pub output: String,
+}
+
+/// Parse the output of a failed `pre-commit run`, `None` if no hook failed
+pub fn parse_pre_commit_error(log: &str) -> Option<ParsedError> {
+    pre_commit_error_summary(log)
+        .map(|summary| ParsedError::new(summary, Some(PRE_COMMIT_LABEL.to_owned())))
+}
+
+/// Find the hooks that failed in the output of `pre-commit run`, with the output of each hook
+///
+/// # Example
+/// ```
+/// # use ci_manager::err_parse::pre_commit::failed_hooks;
+/// let log = r#"trim trailing whitespace.................................................Passed
+/// fix end of files.........................................................Failed
+/// - hook id: end-of-file-fixer
+/// - exit code: 1
+/// - files were modified by this hook
+///
+/// Fixing README.md
+///
+/// check yaml...........................................(no files to check)Skipped"#;
+///
+/// let hooks = failed_hooks(log);
+/// assert_eq!(hooks.len(), 1);
+/// assert_eq!(hooks[0].name, "fix end of files");
+/// assert_eq!(
+///     hooks[0].output,
+///     "- hook id: end-of-file-fixer\n- exit code: 1\n- files were modified by this hook\n\nFixing README.md"
+/// );
+/// ```
+pub fn failed_hooks(log: &str) -> Vec<FailedHook> {
+    static HOOK_STATUS_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"^(?<name>\S.*?)\.{3,}(?:\([^)]*\))?(?<status>Passed|Failed|Skipped)\s*$")
+            .unwrap()
+    });
+    // Lines printed by pre-commit or GitHub Actions after the hooks have run
+    static END_OF_HOOKS_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"^(?:pre-commit hook\(s\) made changes|All changes made by hooks:|##\[error\])")
+            .unwrap()
+    });
+
+    let log = remove_timestamp_prefixes(log);
+    let log = remove_ansi_codes(&log);
+    let mut hooks: Vec<FailedHook> = Vec::new();
+    let mut current: Option<(String, Vec<&str>)> = None;
+    for line in log.lines() {
+        let status = HOOK_STATUS_RE.captures(line);
+        if status.is_some() || END_OF_HOOKS_RE.is_match(line) {
+            if let Some((name, output)) = current.take() {
+                hooks.push(FailedHook {
+                    name,
+                    output: output.join("\n").trim().to_owned(),
+                });
+            }
+        }
+        match status {
+            Some(caps) if &caps["status"] == "Failed" => {
+                current = Some((caps["name"].trim().to_owned(), Vec::new()));
+            }
+            Some(_) => (),
+            None => {
+                if let Some((_, output)) = current.as_mut() {
+                    output.push(line);
+                }
+            }
+        }
+    }
+    if let Some((name, output)) = current {
+        hooks.push(FailedHook {
+            name,
+            output: output.join("\n").trim().to_owned(),
+        });
+    }
+    hooks
+}
+
+/// Summary of a failed `pre-commit run` listing the failed hooks followed by the output of each,
+/// and the diff of the changes made by the hooks if pre-commit ran with `--show-diff-on-failure`
+pub fn pre_commit_error_summary(log: &str) -> Option<String> {
+    let hooks = failed_hooks(log);
+    if hooks.is_empty() {
+        return None;
+    }
+    let mut summary = format!(
+        "Failed pre-commit hooks: {}\n",
+        hooks
+            .iter()
+            .map(|hook| hook.name.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    for hook in &hooks {
+        summary.push_str(&format!("\n{}:\n{}\n", hook.name, hook.output));
+    }
+    let log = remove_timestamp_prefixes(log);
+    if let Some(start) = log.find("All changes made by hooks:") {
+        let diff = &log[start..];
+        let end = diff.find("\n##[error]").unwrap_or(diff.len());
+        summary.push_str(&format!("\n{}\n", diff[..end].trim_end()));
+    }
+    Some(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const PRE_COMMIT_LOG: &str = r#"2024-03-04T10:00:01.0000000Z check yaml...............................................................Passed
+2024-03-04T10:00:01.0000000Z rustfmt..................................................................Failed
+2024-03-04T10:00:01.0000000Z - hook id: fmt
+2024-03-04T10:00:01.0000000Z - exit code: 1
+
+2024-03-04T10:00:01.0000000Z Diff in /src/main.rs at line 1:
+2024-03-04T10:00:01.0000000Z -fn main(){}
+2024-03-04T10:00:01.0000000Z +fn main() {}
+
+2024-03-04T10:00:02.0000000Z pre-commit hook(s) made changes.
+2024-03-04T10:00:02.0000000Z If you are seeing this message in CI, reproduce locally with: `pre-commit run --all-files`.
+2024-03-04T10:00:02.0000000Z To run `pre-commit` as part of git workflow, use `pre-commit install`.
+2024-03-04T10:00:02.0000000Z All changes made by hooks:
+2024-03-04T10:00:02.0000000Z diff --git a/src/main.rs b/src/main.rs
+2024-03-04T10:00:02.0000000Z -fn main(){}
+2024-03-04T10:00:02.0000000Z +fn main() {}
+2024-03-04T10:00:02.0000000Z ##[error]Process completed with exit code 1."#;
+
+    #[test]
+    fn test_pre_commit_error_summary() {
+        assert_eq!(
+            pre_commit_error_summary(PRE_COMMIT_LOG).unwrap(),
+            "Failed pre-commit hooks: rustfmt\n\
+            \n\
+            rustfmt:\n\
+            - hook id: fmt\n\
+            - exit code: 1\n\
+            \n\
+            Diff in /src/main.rs at line 1:\n\
+            -fn main(){}\n\
+            +fn main() {}\n\
+            \n\
+            All changes made by hooks:\n\
+            diff --git a/src/main.rs b/src/main.rs\n\
+            -fn main(){}\n\
+            +fn main() {}\n"
+        );
+    }
+
+    #[test]
+    fn test_pre_commit_error_summary_all_passed() {
+        let log = "check yaml.............................................Passed";
+        assert_eq!(pre_commit_error_summary(log), None);
+    }
+}
@@ -0,0 +1,70 @@
+//! Extraction of the locations of the failed tests in the tracebacks of pytest
+use crate::*;
+
+/// A failed test at the line of a test file where it failed, e.g. `tests/test_math.py:12: AssertionError`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PytestFailure {
+    pub file: String,
+    pub line: u32,
+    /// The `E` lines of the traceback, or the exception if there are none
+    pub message: String,
+}
+
+/// Find the locations of the failures in the tracebacks of pytest (`--tb=long`, the default, or
+/// `--tb=short`), with the `E` lines explaining the failure
+///
+/// # Example
+/// ```
+/// # use ci_manager::err_parse::pytest::pytest_failures;
+/// let log = r#"_________________________________ test_add _________________________________
+///
+///     def test_add():
+/// >       assert add(1, 1) == 3
+/// E       assert 2 == 3
+/// E        +  where 2 = add(1, 1)
+///
+/// tests/test_math.py:12: AssertionError
+/// =========================== short test summary info ============================
+/// FAILED tests/test_math.py::test_add - assert 2 == 3"#;
+///
+/// let failures = pytest_failures(log);
+/// assert_eq!(failures.len(), 1);
+/// assert_eq!(failures[0].file, "tests/test_math.py");
+/// assert_eq!(failures[0].line, 12);
+/// assert_eq!(failures[0].message, "assert 2 == 3\n +  where 2 = add(1, 1)");
+/// ```
+pub fn pytest_failures(log: &str) -> Vec<PytestFailure> {
+    static LOCATION_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"^(?<file>[^\s:]+\.py):(?<line>\d+): (?<exception>[\w.]*(?:Error|Exception|Failed)\w*)\s*$")
+            .unwrap()
+    });
+    static EXPLANATION_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^E {1,7}(?<line>.*)$").unwrap());
+
+    let mut failures: Vec<PytestFailure> = Vec::new();
+    let mut explanation: Vec<&str> = Vec::new();
+    for line in log.lines() {
+        let line = line.trim_end();
+        if let Some(caps) = EXPLANATION_RE.captures(line) {
+            explanation.push(caps.name("line").map_or("", |l| l.as_str()));
+        } else if let Some(caps) = LOCATION_RE.captures(line) {
+            let message = if explanation.is_empty() {
+                caps["exception"].to_owned()
+            } else {
+                explanation.join("\n")
+            };
+            let failure = PytestFailure {
+                file: caps["file"].to_owned(),
+                line: caps["line"].parse().unwrap_or_default(),
+                message,
+            };
+            if !failures.contains(&failure) {
+                failures.push(failure);
+            }
+            explanation.clear();
+        } else if line.starts_with('_') || line.starts_with('=') {
+            // The header of the next test or section
+            explanation.clear();
+        }
+    }
+    failures
+}
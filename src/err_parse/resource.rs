@@ -0,0 +1,215 @@
+//! Detection of failures caused by the runner running out of resources, e.g. disk space or memory
+use super::ParsedError;
+use crate::*;
+
+/// Label of failures caused by the runner running out of disk space
+pub const RUNNER_DISK_FULL_LABEL: &str = "runner-disk-full";
+/// Label of failures caused by the job running out of memory
+pub const OUT_OF_MEMORY_LABEL: &str = "out-of-memory";
+/// Maximum number of log lines included in a summary
+const MAX_LINES: usize = 10;
+
+/// Parse the log of a job that failed because the runner ran out of disk space, `None` if the disk
+/// did not run full
+pub fn parse_disk_full_error(log: &str) -> Option<ParsedError> {
+    disk_full_summary(log)
+        .map(|summary| ParsedError::new(summary, Some(RUNNER_DISK_FULL_LABEL.to_owned())))
+}
+
+/// Summary of a job that failed because the runner ran out of disk space, with the lines of the
+/// log that reported it, `None` if the disk did not run full
+///
+/// # Example
+/// ```
+/// # use ci_manager::err_parse::resource::disk_full_summary;
+/// let log = r#"Step 4/9 : RUN apt-get install -y build-essential
+/// E: Write error - write (28: No space left on device)
+/// npm ERR! code ENOSPC"#;
+///
+/// assert_eq!(
+///     disk_full_summary(log).unwrap(),
+///     "The runner ran out of disk space\n\
+///     \n\
+///     E: Write error - write (28: No space left on device)\n\
+///     npm ERR! code ENOSPC\n"
+/// );
+/// assert_eq!(disk_full_summary("error: expected ';'"), None);
+/// ```
+pub fn disk_full_summary(log: &str) -> Option<String> {
+    static DISK_FULL_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"(?i)no space left on device|\bENOSPC\b|not enough (?:free )?disk space|disk quota exceeded").unwrap()
+    });
+
+    let mut lines: Vec<&str> = Vec::new();
+    for line in log.lines().filter(|line| DISK_FULL_RE.is_match(line)) {
+        let line = line.trim();
+        if !lines.contains(&line) {
+            lines.push(line);
+        }
+    }
+    if lines.is_empty() {
+        return None;
+    }
+    let mut summary = String::from("The runner ran out of disk space\n\n");
+    for line in lines.iter().take(MAX_LINES) {
+        summary.push_str(line);
+        summary.push('\n');
+    }
+    if lines.len() > MAX_LINES {
+        summary.push_str(&format!(
+            "... and {} more line(s)\n",
+            lines.len() - MAX_LINES
+        ));
+    }
+    Some(summary)
+}
+
+/// A process killed by the out-of-memory killer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KilledProcess {
+    pub name: String,
+    pub pid: Option<u32>,
+    /// Resident memory of the process when it was killed
+    pub rss_bytes: Option<u64>,
+}
+
+impl fmt::Display for KilledProcess {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        match (self.pid, self.rss_bytes) {
+            (Some(pid), Some(rss)) => write!(f, " (PID {pid}, RSS {})", format_bytes(rss)),
+            (Some(pid), None) => write!(f, " (PID {pid})"),
+            (None, Some(rss)) => write!(f, " (RSS {})", format_bytes(rss)),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// Parse the log of a job that ran out of memory, `None` if nothing was killed
+pub fn parse_out_of_memory_error(log: &str) -> Option<ParsedError> {
+    out_of_memory_summary(log)
+        .map(|summary| ParsedError::new(summary, Some(OUT_OF_MEMORY_LABEL.to_owned())))
+}
+
+/// Find the processes killed by the out-of-memory killer, from the kernel messages and the
+/// messages of GCC when one of its programs is killed
+///
+/// # Example
+/// ```
+/// # use ci_manager::err_parse::resource::killed_processes;
+/// let log = r#"[ 4182.123456] Out of memory: Killed process 31337 (cc1plus) total-vm:3021364kB, anon-rss:2621440kB, file-rss:0kB, shmem-rss:0kB
+/// c++: fatal error: Killed signal terminated program cc1plus"#;
+///
+/// let killed = killed_processes(log);
+/// assert_eq!(killed.len(), 1);
+/// assert_eq!(killed[0].to_string(), "cc1plus (PID 31337, RSS 2.5 GiB)");
+/// ```
+pub fn killed_processes(log: &str) -> Vec<KilledProcess> {
+    static KERNEL_OOM_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"(?i)out of memory: kill(?:ed)? process (?<pid>\d+) \((?<name>[^)]+)\)(?:.*?anon-rss:(?<rss>\d+)kB)?").unwrap()
+    });
+    static GCC_KILLED_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"Killed signal terminated program (?<name>\S+)").unwrap());
+
+    let mut killed: Vec<KilledProcess> = Vec::new();
+    for line in log.lines() {
+        if let Some(caps) = KERNEL_OOM_RE.captures(line) {
+            killed.push(KilledProcess {
+                name: caps["name"].to_owned(),
+                pid: caps["pid"].parse().ok(),
+                rss_bytes: caps
+                    .name("rss")
+                    .and_then(|rss| rss.as_str().parse::<u64>().ok())
+                    .map(|kib| kib * 1024),
+            });
+        } else if let Some(caps) = GCC_KILLED_RE.captures(line) {
+            // The kernel message is more detailed, if it is in the log
+            if !killed.iter().any(|process| process.name == caps["name"]) {
+                killed.push(KilledProcess {
+                    name: caps["name"].to_owned(),
+                    pid: None,
+                    rss_bytes: None,
+                });
+            }
+        }
+    }
+    killed
+}
+
+/// Summary of a job that ran out of memory, naming the killed processes, or the lines reporting
+/// that a process got a SIGKILL (exit code 137). `None` if nothing was killed.
+pub fn out_of_memory_summary(log: &str) -> Option<String> {
+    static SIGKILL_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"(?i)exit (?:code|status) 137\b|exited with (?:code )?137\b|\bError 137\b|killed by signal 9\b|signal: killed|\bSIGKILL\b").unwrap()
+    });
+
+    let killed = killed_processes(log);
+    if !killed.is_empty() {
+        let mut summary =
+            String::from("The job ran out of memory, the out-of-memory killer killed:\n");
+        for process in &killed {
+            summary.push_str(&format!("- {process}\n"));
+        }
+        return Some(summary);
+    }
+
+    let mut lines: Vec<&str> = Vec::new();
+    for line in log.lines().filter(|line| SIGKILL_RE.is_match(line)) {
+        let line = line.trim();
+        if !lines.contains(&line) {
+            lines.push(line);
+        }
+    }
+    if lines.is_empty() {
+        return None;
+    }
+    let mut summary = String::from(
+        "A process was killed with SIGKILL (exit code 137), most likely because the job ran out of memory\n\n",
+    );
+    for line in lines.iter().take(MAX_LINES) {
+        summary.push_str(line);
+        summary.push('\n');
+    }
+    Some(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_disk_full_summary_bitbake() {
+        let log = r#"ERROR: No new tasks can be executed since the disk space monitor action is "STOPTASKS"!
+ERROR: core-image-minimal-1.0-r0 do_rootfs: Error: [Errno 28] No space left on device: '/build/tmp/work/rootfs'
+ERROR: core-image-minimal-1.0-r0 do_rootfs: Error: [Errno 28] No space left on device: '/build/tmp/work/rootfs'"#;
+
+        assert_eq!(
+            parse_disk_full_error(log).unwrap(),
+            ParsedError::new(
+                "The runner ran out of disk space\n\
+                \n\
+                ERROR: core-image-minimal-1.0-r0 do_rootfs: Error: [Errno 28] No space left on device: '/build/tmp/work/rootfs'\n"
+                    .to_owned(),
+                Some(RUNNER_DISK_FULL_LABEL.to_owned())
+            )
+        );
+    }
+
+    #[test]
+    fn test_out_of_memory_summary_exit_code_137() {
+        let log = r#"[ 80%] Building CXX object src/CMakeFiles/app.dir/huge.cpp.o
+##[error]Process completed with exit code 137."#;
+
+        assert_eq!(
+            parse_out_of_memory_error(log).unwrap(),
+            ParsedError::new(
+                "A process was killed with SIGKILL (exit code 137), most likely because the job ran out of memory\n\
+                \n\
+                ##[error]Process completed with exit code 137.\n"
+                    .to_owned(),
+                Some(OUT_OF_MEMORY_LABEL.to_owned())
+            )
+        );
+    }
+}
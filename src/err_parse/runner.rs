@@ -0,0 +1,94 @@
+//! Detection of failures of the runner itself, e.g. a runner that shut down or a broken Docker
+//! daemon on a self-hosted runner
+use super::ParsedError;
+use crate::*;
+
+/// Label of failures caused by the runner infrastructure rather than the code under test
+pub const RUNNER_INFRA_LABEL: &str = "runner-infra";
+/// Maximum number of log lines included in a summary
+const MAX_LINES: usize = 10;
+
+/// Find the lines of a log reporting that the runner shut down or lost connection, or that the
+/// Docker daemon of the runner failed
+///
+/// # Example
+/// ```
+/// # use ci_manager::err_parse::runner::runner_errors;
+/// let log = r#"##[error]The runner has received a shutdown signal. This can happen when the runner service is stopped, or a manually started runner is canceled.
+/// docker: Cannot connect to the Docker daemon at unix:///var/run/docker.sock. Is the docker daemon running?
+/// error: expected ';'"#;
+///
+/// assert_eq!(runner_errors(log).len(), 2);
+/// ```
+pub fn runner_errors(log: &str) -> Vec<&str> {
+    static RUNNER_ERROR_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(
+            r"(?xi)
+            the\ runner\ has\ received\ a\ shutdown\ signal
+            | lost\ communication\ with\ the\ server
+            | the\ job\ was\ not\ acquired\ by\ runner
+            | cannot\ connect\ to\ the\ docker\ daemon
+            | error\ response\ from\ daemon:\ (?:failed\ to\ create\ (?:shim\ )?task|driver\ failed|.*read-only\ file\ system|.*layer\ does\ not\ exist)
+        ",
+        )
+        .unwrap()
+    });
+
+    let mut lines: Vec<&str> = Vec::new();
+    for line in log.lines().filter(|line| RUNNER_ERROR_RE.is_match(line)) {
+        let line = line.trim();
+        if !lines.contains(&line) {
+            lines.push(line);
+        }
+    }
+    lines
+}
+
+/// Parse the log of a job that failed because of the runner, `None` if the runner did not fail
+pub fn parse_runner_error(log: &str) -> Option<ParsedError> {
+    let lines = runner_errors(log);
+    if lines.is_empty() {
+        return None;
+    }
+    let mut summary = String::from(
+        "The job failed because of a problem with the runner, not the code under test\n\n",
+    );
+    for line in lines.iter().take(MAX_LINES) {
+        summary.push_str(line);
+        summary.push('\n');
+    }
+    if lines.len() > MAX_LINES {
+        summary.push_str(&format!(
+            "... and {} more line(s)\n",
+            lines.len() - MAX_LINES
+        ));
+    }
+    Some(ParsedError::new(
+        summary,
+        Some(RUNNER_INFRA_LABEL.to_owned()),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse_runner_error_lost_communication() {
+        let log = r#"Building image...
+##[error]The self-hosted runner: yocto-runner-3 lost communication with the server. Verify the machine is running and has a healthy network connection. Anything in your workflow that terminates the runner process, starves it for CPU/Memory, or blocks its network access can cause this error.
+##[error]The operation was canceled."#;
+
+        assert_eq!(
+            parse_runner_error(log).unwrap(),
+            ParsedError::new(
+                "The job failed because of a problem with the runner, not the code under test\n\
+                \n\
+                ##[error]The self-hosted runner: yocto-runner-3 lost communication with the server. Verify the machine is running and has a healthy network connection. Anything in your workflow that terminates the runner process, starves it for CPU/Memory, or blocks its network access can cause this error.\n"
+                    .to_owned(),
+                Some(RUNNER_INFRA_LABEL.to_owned())
+            )
+        );
+    }
+}
@@ -0,0 +1,124 @@
+//! Detection of jobs that timed out or were cancelled
+use super::ParsedError;
+use crate::*;
+
+/// Label of jobs that exceeded their `timeout-minutes`
+pub const TIMEOUT_LABEL: &str = "timeout";
+/// Label of jobs that were cancelled
+pub const CANCELLED_LABEL: &str = "cancelled";
+/// Number of log lines before the cancellation included in the summary, to show where it hung
+const LAST_LINES: usize = 10;
+
+/// Parse the log of a job that timed out or was cancelled, `None` if it was neither
+///
+/// `step` is the step that was running when the job was stopped, if known.
+///
+/// # Example
+/// ```
+/// # use ci_manager::err_parse::timeout::parse_timeout_error;
+/// // rustdoc turns a line starting with `##` into `#`, so the `##[error]` lines are kept inline
+/// let log = "Waiting for the device to boot...\n##[error]The job running on runner yocto-runner-3 \
+///     has exceeded the maximum execution time of 360 minutes.\n##[error]The operation was canceled.";
+///
+/// let parsed = parse_timeout_error(log, Some("Run image tests")).unwrap();
+/// assert_eq!(parsed.label(), Some("timeout"));
+/// assert_eq!(
+///     parsed.summary(),
+///     "The job timed out after 360 min at step `Run image tests`\n\
+///     \n\
+///     Last lines of the log:\n\
+///     Waiting for the device to boot...\n"
+/// );
+/// ```
+pub fn parse_timeout_error(log: &str, step: Option<&str>) -> Option<ParsedError> {
+    static TIMED_OUT_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"has exceeded the maximum execution time of (?<minutes>\d+) minutes").unwrap()
+    });
+    static FAIL_FAST_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r#"The job was canceled because "(?<job>[^"]+)" failed"#).unwrap());
+    static CANCELLED_BY_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"The run was canceled by @?(?<user>[\w-]+)").unwrap());
+    static CANCELLED_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"The operation was canceled\.").unwrap());
+
+    let (mut summary, label) = if let Some(caps) = TIMED_OUT_RE.captures(log) {
+        (
+            format!("The job timed out after {} min", &caps["minutes"]),
+            TIMEOUT_LABEL,
+        )
+    } else if let Some(caps) = FAIL_FAST_RE.captures(log) {
+        (
+            format!("The job was cancelled because `{}` failed", &caps["job"]),
+            CANCELLED_LABEL,
+        )
+    } else if let Some(caps) = CANCELLED_BY_RE.captures(log) {
+        (
+            format!("The run was cancelled by @{}", &caps["user"]),
+            CANCELLED_LABEL,
+        )
+    } else if CANCELLED_RE.is_match(log) {
+        ("The job was cancelled".to_owned(), CANCELLED_LABEL)
+    } else {
+        return None;
+    };
+    if let Some(step) = step {
+        summary.push_str(&format!(" at step `{step}`"));
+    }
+    summary.push('\n');
+
+    let last_lines: Vec<&str> = log
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.contains("##[error]"))
+        .collect();
+    if !last_lines.is_empty() {
+        summary.push_str("\nLast lines of the log:\n");
+        for line in &last_lines[last_lines.len().saturating_sub(LAST_LINES)..] {
+            summary.push_str(line);
+            summary.push('\n');
+        }
+    }
+    Some(ParsedError::new(summary, Some(label.to_owned())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_parse_timeout_error_timed_out_at_step() {
+        let log = "\
+bitbake core-image-minimal
+Loading cache...
+##[error]The job running on runner yocto-runner-1 has exceeded the maximum execution time of 120 minutes.
+##[error]The operation was canceled.";
+
+        assert_eq!(
+            parse_timeout_error(log, Some("Build image")).unwrap(),
+            ParsedError::new(
+                "The job timed out after 120 min at step `Build image`\n\
+                \n\
+                Last lines of the log:\n\
+                bitbake core-image-minimal\n\
+                Loading cache...\n"
+                    .to_owned(),
+                Some(TIMEOUT_LABEL.to_owned())
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_timeout_error_cancelled_run() {
+        let log =
+            "##[error]The run was canceled by @octocat.\n##[error]The operation was canceled.";
+
+        assert_eq!(
+            parse_timeout_error(log, None).unwrap(),
+            ParsedError::new(
+                "The run was cancelled by @octocat\n".to_owned(),
+                Some(CANCELLED_LABEL.to_owned())
+            )
+        );
+        assert_eq!(parse_timeout_error("error: expected ';'", None), None);
+    }
+}
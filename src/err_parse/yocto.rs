@@ -1,6 +1,7 @@
 use crate::*;
 use crate::{
-    config::commands::locate_failure_log::logfile_path_from_str, err_parse::LOGFILE_MAX_LEN,
+    config::commands::locate_failure_log::logfile_path_from_str,
+    err_parse::{compiler::compiler_error_summary, linker::linker_error_summary, LOGFILE_MAX_LEN},
 };
 
 use self::util::YoctoFailureKind;
@@ -12,6 +13,15 @@ pub struct YoctoError {
     summary: String,
     kind: YoctoFailureKind,
     logfile: Option<YoctoFailureLog>,
+    /// The recipe of the failed task, e.g. `sqlite3-native`
+    recipe: Option<String>,
+    /// The version of the recipe, e.g. `3.43.2`
+    version: Option<String>,
+    /// The failed task, e.g. `do_fetch`
+    task: Option<String>,
+    /// Path of the failure log of the task in the build environment, which is not necessarily
+    /// where ci-manager runs
+    logfile_path: Option<PathBuf>,
 }
 
 impl YoctoError {
@@ -20,9 +30,27 @@ impl YoctoError {
             summary,
             kind,
             logfile,
+            recipe: None,
+            version: None,
+            task: None,
+            logfile_path: None,
         }
     }
 
+    /// Set the recipe, version and task from the failed task
+    pub fn with_failed_task(mut self, task: &util::FailedTask) -> Self {
+        self.recipe.clone_from(&task.recipe);
+        self.version.clone_from(&task.version);
+        self.task = Some(task.task.clone());
+        self.logfile_path = Some(task.logfile.clone());
+        self
+    }
+
+    /// Attach the failure log, e.g. if it was not found locally but in an artifact of the run
+    pub fn attach_logfile(&mut self, logfile: YoctoFailureLog) {
+        self.logfile = Some(logfile);
+    }
+
     pub fn summary(&self) -> &str {
         &self.summary
     }
@@ -32,6 +60,64 @@ impl YoctoError {
     pub fn logfile(&self) -> Option<&YoctoFailureLog> {
         self.logfile.as_ref()
     }
+    pub fn recipe(&self) -> Option<&str> {
+        self.recipe.as_deref()
+    }
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+    pub fn task(&self) -> Option<&str> {
+        self.task.as_deref()
+    }
+    pub fn logfile_path(&self) -> Option<&Path> {
+        self.logfile_path.as_deref()
+    }
+
+    /// The recipe without the `-native`, `-cross` or `nativesdk-` variant, e.g. `sqlite3` for
+    /// `sqlite3-native`
+    pub fn base_recipe(&self) -> Option<&str> {
+        let recipe = self.recipe.as_deref()?;
+        let recipe = recipe.strip_prefix("nativesdk-").unwrap_or(recipe);
+        let recipe = ["-native", "-crosssdk", "-cross-canadian", "-cross"]
+            .iter()
+            .find_map(|suffix| recipe.split_once(suffix).map(|(base, _)| base))
+            .unwrap_or(recipe);
+        Some(recipe)
+    }
+
+    /// Label of the recipe of the failed task, e.g. `recipe:sqlite3`
+    pub fn recipe_label(&self) -> Option<String> {
+        self.base_recipe().map(|recipe| format!("recipe:{recipe}"))
+    }
+
+    /// A Markdown table with the recipe, version and task, `None` if the recipe is not known
+    ///
+    /// # Example
+    /// ```
+    /// # use ci_manager::err_parse::yocto::{util::{failed_tasks, YoctoFailureKind}, YoctoError};
+    /// let log = "ERROR: Logfile of failure stored in: /build/tmp/work/x86_64-linux/sqlite3-native/3.43.2/temp/log.do_fetch.21616";
+    /// let error = YoctoError::new(String::new(), YoctoFailureKind::DoFetch, None)
+    ///     .with_failed_task(&failed_tasks(log)[0]);
+    ///
+    /// assert_eq!(error.recipe_label().unwrap(), "recipe:sqlite3");
+    /// assert_eq!(
+    ///     error.metadata_table().unwrap(),
+    ///     "| Recipe | Version | Task |\n\
+    ///     |--------|---------|------|\n\
+    ///     | `sqlite3-native` | `3.43.2` | `do_fetch` |\n"
+    /// );
+    /// ```
+    pub fn metadata_table(&self) -> Option<String> {
+        let recipe = self.recipe.as_deref()?;
+        let cell = |value: Option<&str>| value.map_or("-".to_owned(), |v| format!("`{v}`"));
+        Some(format!(
+            "| Recipe | Version | Task |\n\
+            |--------|---------|------|\n\
+            | `{recipe}` | {version} | {task} |\n",
+            version = cell(self.version.as_deref()),
+            task = cell(self.task.as_deref()),
+        ))
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -42,22 +128,48 @@ pub struct YoctoFailureLog {
 
 /// Parse a log from a Yocto build and return a [YoctoError] containing error
 /// summary, error kind, and logfile contents if it exists and is not too large.
+///
+/// Builds run with kas are supported, if kas failed to set up the build (e.g. a layer is missing)
+/// the error is a [YoctoFailureKind::KasConfig]. Sstate signature and hash equivalence errors are
+/// a [YoctoFailureKind::SstateIssue], whichever task they failed.
 pub fn parse_yocto_error(log: &str) -> anyhow::Result<YoctoError> {
+    let log = util::remove_kas_prefixes(log);
+    let log = log.as_ref();
+    if !log.contains(util::YOCTO_ERROR_SUMMARY_SIGNATURE) {
+        if let Some(summary) = util::kas_config_error_summary(log) {
+            return Ok(YoctoError::new(summary, YoctoFailureKind::KasConfig, None));
+        }
+    }
+
+    // A task can fail because of the sstate cache or hash equivalence server, so these are
+    // reported instead of the task
+    if let Some(mut summary) = util::sstate_error_summary(log) {
+        let kind = YoctoFailureKind::SstateIssue;
+        if let Some(hint) = kind.hint() {
+            summary.push_str(&format!("\n{hint}\n"));
+        }
+        return Ok(YoctoError::new(summary, kind, None));
+    }
+
     let error_summary = util::yocto_error_summary(log)?;
     log::debug!(
         "Yocto error before trimming just recipe failures: \n{}",
         error_summary
     );
 
-    let error_summary = util::trim_trailing_just_recipes(&error_summary)?;
+    let mut error_summary = util::trim_trailing_just_recipes(&error_summary)?;
     log::debug!("Yocto error: \n{}", error_summary);
 
-    // Find the kind of yocto failure in the string e.g. this would be `do_fetch`
+    // Find the failed tasks from the lines like
     // ERROR: Logfile of failure stored in: /app/yocto/build/tmp/work/x86_64-linux/sqlite3-native/3.43.2/temp/log.do_fetch.21616
-
-    // Find the line with the `Logfile of failure stored in` and get the path
-    let log_file_line = util::find_yocto_failure_log_str(&error_summary)?;
-    let path = first_path_from_str(log_file_line)?;
+    // and take the most severe one as the failure kind, e.g. `do_compile` over `do_fetch`
+    let failed_tasks = util::failed_tasks(&error_summary);
+    let primary_task = failed_tasks
+        .iter()
+        .rev()
+        .max_by_key(|task| task.kind.severity())
+        .context("No log file line found")?;
+    let path = primary_task.logfile.clone();
     let fname = path
         .file_stem()
         .with_context(|| format!("No file stem in {path:?}"))?
@@ -72,19 +184,8 @@ pub fn parse_yocto_error(log: &str) -> anyhow::Result<YoctoError> {
         }
     };
 
-    let failure_log: Option<YoctoFailureLog> = match logfile_path_from_str(path.to_str().unwrap()) {
-        Ok(p) => {
-            let contents = fs::read_to_string(p)?;
-            if contents.len() > LOGFILE_MAX_LEN {
-                log::warn!("Logfile of yocto failure exceeds maximum length of {LOGFILE_MAX_LEN}. It will not be added to the issue body.");
-                None
-            } else {
-                Some(YoctoFailureLog {
-                    name: fname.to_owned(),
-                    contents,
-                })
-            }
-        }
+    let failure_log_contents = match logfile_path_from_str(path.to_str().unwrap()) {
+        Ok(p) => Some(fs::read_to_string(p)?),
         Err(e) => {
             log::trace!("{e}");
             log::error!("Logfile from error summary does not exist at: {path:?}");
@@ -93,11 +194,179 @@ pub fn parse_yocto_error(log: &str) -> anyhow::Result<YoctoError> {
         }
     };
 
-    let yocto_error = YoctoError {
-        summary: error_summary,
-        kind: yocto_failure_kind,
-        logfile: failure_log,
+    let yocto_failure_kind = match yocto_failure_kind {
+        YoctoFailureKind::DoFetch => util::classify_fetch_failure(
+            [
+                Some(error_summary.as_str()),
+                failure_log_contents.as_deref(),
+            ]
+            .into_iter()
+            .flatten(),
+        ),
+        kind => kind,
+    };
+    // QA issues can fail other tasks than `do_package_qa`, e.g. `installed-vs-shipped` fails `do_package`
+    if let Some(qa_summary) = util::qa_issue_summary(&error_summary).or_else(|| {
+        failure_log_contents
+            .as_deref()
+            .and_then(util::qa_issue_summary)
+    }) {
+        // The grouped QA issues replace the raw messages, which repeat the same text for every
+        // package. A message ends with the name of the check in brackets, or at the next message.
+        let mut in_qa_issue = false;
+        error_summary = error_summary
+            .lines()
+            .filter(|line| {
+                if line.contains("QA Issue:") {
+                    in_qa_issue = true;
+                } else if ["ERROR: ", "WARNING: ", "NOTE: "]
+                    .iter()
+                    .any(|prefix| line.starts_with(prefix))
+                {
+                    in_qa_issue = false;
+                }
+                let keep = !in_qa_issue;
+                if line.trim_end().ends_with(']') {
+                    in_qa_issue = false;
+                }
+                keep
+            })
+            .fold(format!("{qa_summary}\n"), |acc, line| acc + line + "\n");
+    }
+    if failed_tasks.len() > 1 {
+        error_summary.push_str(&format!("\nFailed tasks ({}):\n", failed_tasks.len()));
+        for task in &failed_tasks {
+            error_summary.push_str(&format!(
+                "- {task}: `{logfile}`{primary}\n",
+                logfile = task.logfile.display(),
+                primary = if task == primary_task {
+                    " (labelled)"
+                } else {
+                    ""
+                }
+            ));
+        }
+    }
+    if let Some(hint) = yocto_failure_kind.hint() {
+        error_summary.push_str(&format!("\n{hint}\n"));
+    }
+
+    if yocto_failure_kind == YoctoFailureKind::DoCompile {
+        // BitBake also prints the log of the failed task (prefixed with `| `) in its own output
+        let compile_log = failure_log_contents.as_deref().unwrap_or(log);
+        if let Some(compiler_errors) = compiler_error_summary(compile_log) {
+            error_summary.push_str("\nCompiler errors:\n");
+            error_summary.push_str(&compiler_errors);
+        } else if let Some(linker_errors) = linker_error_summary(compile_log) {
+            error_summary.push('\n');
+            error_summary.push_str(&linker_errors);
+        }
+    }
+
+    let failure_log_contents = if yocto_failure_kind == YoctoFailureKind::DoTestImage {
+        let testimage_log = failure_log_contents.as_deref().unwrap_or(log);
+        let failed_tests = util::testimage_failed_tests(testimage_log);
+        if !failed_tests.is_empty() {
+            error_summary.push_str("\nFailed tests:\n");
+            for test in &failed_tests {
+                error_summary.push_str(&format!("- {test}\n"));
+            }
+        }
+        let qemu_errors = util::qemu_errors(testimage_log);
+        if !qemu_errors.is_empty() {
+            error_summary.push_str("\nQEMU errors:\n");
+            for line in &qemu_errors {
+                error_summary.push_str(&format!("{line}\n"));
+            }
+        }
+        // Attach the reports of the failed tests rather than the whole log
+        util::testimage_failure_section(testimage_log).or(failure_log_contents)
+    } else {
+        failure_log_contents
     };
 
+    let failure_log: Option<YoctoFailureLog> = failure_log_contents.and_then(|contents| {
+        if contents.len() > LOGFILE_MAX_LEN {
+            log::warn!("Logfile of yocto failure exceeds maximum length of {LOGFILE_MAX_LEN}. It will not be added to the issue body.");
+            None
+        } else {
+            Some(YoctoFailureLog {
+                name: fname.to_owned(),
+                contents: Config::global()
+                    .config_file()
+                    .redact
+                    .apply(&contents)
+                    .into_owned(),
+            })
+        }
+    });
+
+    let yocto_error = YoctoError::new(error_summary, yocto_failure_kind, failure_log)
+        .with_failed_task(primary_task);
+
     Ok(yocto_error)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const MULTIPLE_FAILED_TASKS: &str = r#"--- Error summary ---
+ERROR: zlib-1.3.1-r0 do_fetch: Bitbake Fetcher Error: MalformedUrl('${SOURCE_MIRROR_URL}')
+ERROR: Logfile of failure stored in: /app/yocto/build/tmp/work/cortexa72-poky-linux/zlib/1.3.1/temp/log.do_fetch.1001
+ERROR: Task (/app/yocto/poky/meta/recipes-core/zlib/zlib_1.3.1.bb:do_fetch) failed with exit code '1'
+ERROR: foo-1.0-r0 do_compile: oe_runmake failed
+ERROR: Logfile of failure stored in: /app/yocto/build/tmp/work/cortexa72-poky-linux/foo/1.0/temp/log.do_compile.1002
+ERROR: Task (/app/yocto/meta-foo/recipes-foo/foo/foo_1.0.bb:do_compile) failed with exit code '1'
+ERROR: bar-2.1-r0 do_compile: oe_runmake failed
+ERROR: Logfile of failure stored in: /app/yocto/build/tmp/work/cortexa72-poky-linux/bar/2.1/temp/log.do_compile.1003
+ERROR: Task (/app/yocto/meta-foo/recipes-bar/bar/bar_2.1.bb:do_compile) failed with exit code '1'
+Summary: 3 tasks failed:
+  /app/yocto/poky/meta/recipes-core/zlib/zlib_1.3.1.bb:do_fetch
+  /app/yocto/meta-foo/recipes-foo/foo/foo_1.0.bb:do_compile
+  /app/yocto/meta-foo/recipes-bar/bar/bar_2.1.bb:do_compile"#;
+
+    #[test]
+    fn test_parse_yocto_error_qa_issues() {
+        let log = r#"--- Error summary ---
+ERROR: foo-1.0-r0 do_package_qa: QA Issue: /usr/bin/foo-helper contained in package foo requires /bin/bash, but no providers found in RDEPENDS:foo? [file-rdeps]
+ERROR: foo-1.0-r0 do_package_qa: QA Issue: foo: Files/directories were installed but not shipped in any package:
+  /usr/lib/libfoo.so.1
+Please set FILES such that these items are packaged. Alternatively if they are unneeded, avoid installing them or delete them within do_install.
+foo: 1 installed and not shipped files. [installed-vs-shipped]
+ERROR: foo-1.0-r0 do_package_qa: Fatal QA errors were found, failing task.
+ERROR: Logfile of failure stored in: /app/yocto/build/tmp/work/cortexa72-poky-linux/foo/1.0/temp/log.do_package_qa.1001
+ERROR: Task (/app/yocto/meta-foo/recipes-foo/foo/foo_1.0.bb:do_package_qa) failed with exit code '1'"#;
+
+        let yocto_error = parse_yocto_error(log).unwrap();
+
+        assert_eq!(yocto_error.kind(), YoctoFailureKind::DoPackageQa);
+        assert_eq!(
+            yocto_error.summary(),
+            "QA issues:\n\
+            file-rdeps:\n\
+            - foo-1.0-r0: /usr/bin/foo-helper contained in package foo requires /bin/bash, but no providers found in RDEPENDS:foo?\n\
+            installed-vs-shipped:\n\
+            - foo-1.0-r0: foo: Files/directories were installed but not shipped in any package\n\
+            \x20 - `/usr/lib/libfoo.so.1`\n\
+            \n\
+            ERROR: foo-1.0-r0 do_package_qa: Fatal QA errors were found, failing task.\n\
+            ERROR: Logfile of failure stored in: /app/yocto/build/tmp/work/cortexa72-poky-linux/foo/1.0/temp/log.do_package_qa.1001\n\
+            ERROR: Task (/app/yocto/meta-foo/recipes-foo/foo/foo_1.0.bb:do_package_qa) failed with exit code '1'\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_yocto_error_multiple_failed_tasks() {
+        let yocto_error = parse_yocto_error(MULTIPLE_FAILED_TASKS).unwrap();
+
+        assert_eq!(yocto_error.kind(), YoctoFailureKind::DoCompile);
+        assert!(yocto_error.summary().contains(
+            "Failed tasks (3):\n\
+            - zlib 1.3.1 do_fetch: `/app/yocto/build/tmp/work/cortexa72-poky-linux/zlib/1.3.1/temp/log.do_fetch.1001`\n\
+            - foo 1.0 do_compile: `/app/yocto/build/tmp/work/cortexa72-poky-linux/foo/1.0/temp/log.do_compile.1002` (labelled)\n\
+            - bar 2.1 do_compile: `/app/yocto/build/tmp/work/cortexa72-poky-linux/bar/2.1/temp/log.do_compile.1003`\n"
+        ));
+    }
+}
@@ -20,10 +20,29 @@ pub enum YoctoFailureKind {
     /// Other tasks
     #[strum(serialize = "do_fetch")]
     DoFetch,
+    /// `do_fetch` failed because the checksum of a downloaded file does not match the recipe
+    #[strum(serialize = "fetch-checksum")]
+    DoFetchChecksum,
+    /// `do_fetch` failed because a source or mirror could not be reached
+    #[strum(serialize = "fetch-network")]
+    DoFetchNetwork,
     #[strum(serialize = "do_rootfs")]
     DoRootFs,
     #[strum(serialize = "do_image")]
     DoImage,
+    /// Quality assurance checks of the packages of a recipe, e.g. a missing runtime dependency
+    #[strum(serialize = "do_package_qa")]
+    DoPackageQa,
+    /// Runtime tests of an image, usually booted in QEMU
+    #[strum(serialize = "do_testimage")]
+    DoTestImage,
+    /// Sstate signature or hash equivalence error, fixed by maintaining the sstate cache or the
+    /// hash equivalence server rather than the code
+    #[strum(serialize = "sstate-issue")]
+    SstateIssue,
+    /// Error in the kas configuration before BitBake ran, e.g. a missing layer or a bad refspec
+    #[strum(serialize = "kas-config")]
+    KasConfig,
     /// If it's a type of failure we're not familiar with or parsing fails, default to this
     #[default]
     #[strum(serialize = "misc")]
@@ -53,11 +72,411 @@ impl YoctoFailureKind {
         }
         bail!("Could not determine task from input: {fname}")
     }
+
+    /// How severe the failure is compared to other failed tasks of the same build, the label of a
+    /// build with several failed tasks is the kind of the most severe one. Broken code that fails
+    /// to configure or compile ranks above failures that are more likely caused by the environment.
+    pub fn severity(&self) -> u8 {
+        match self {
+            Self::KasConfig | Self::SstateIssue => 6,
+            Self::DoConfigure
+            | Self::DoConfigurePtestBase
+            | Self::DoCompile
+            | Self::DoCompilePtestBase
+            | Self::DoPackageQa => 5,
+            Self::DoTestImage => 4,
+            Self::DoRootFs | Self::DoImage | Self::DoDeploy | Self::DoBuild => 3,
+            Self::DoFetch | Self::DoFetchChecksum => 2,
+            Self::DoFetchNetwork => 1,
+            Self::Misc => 0,
+        }
+    }
+
+    /// A hint on what to check for the kind of failure, included in the issue
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            Self::DoFetchChecksum => Some(
+                "Hint: A downloaded file does not match the checksum in the recipe. Check whether the file \
+                changed on the premirror (PREMIRRORS/SOURCE_MIRROR_URL) or upstream, and update the \
+                SRC_URI checksum or the mirrored file.",
+            ),
+            Self::DoFetchNetwork => Some(
+                "Hint: A source could not be downloaded. Check that the premirror \
+                (PREMIRRORS/SOURCE_MIRROR_URL), the sstate mirror (SSTATE_MIRRORS) and the upstream \
+                server are reachable from the runner.",
+            ),
+            Self::SstateIssue => Some(
+                "Hint: The sstate cache or the hash equivalence server is inconsistent with the \
+                metadata. Check that the hash equivalence server (BB_HASHSERVE) is reachable, and \
+                clean the affected tasks (`bitbake -c cleansstate <recipe>`) or the sstate mirror \
+                (SSTATE_MIRRORS).",
+            ),
+            _ => None,
+        }
+    }
+}
+
+/// Classify a failed `do_fetch` task from its error messages as a checksum mismatch, a network
+/// problem, or neither (plain [YoctoFailureKind::DoFetch])
+///
+/// # Example
+/// ```
+/// # use ci_manager::err_parse::yocto::util::{classify_fetch_failure, YoctoFailureKind};
+/// let log = "ERROR: Fetcher failure for URL: 'https://example.com/foo.tar.gz'. Checksum mismatch!";
+/// assert_eq!(classify_fetch_failure([log]), YoctoFailureKind::DoFetchChecksum);
+///
+/// let log = "ERROR: Fetcher failure: Fetch command failed: Could not resolve host: example.com";
+/// assert_eq!(classify_fetch_failure([log]), YoctoFailureKind::DoFetchNetwork);
+///
+/// let log = "ERROR: Bitbake Fetcher Error: MalformedUrl('${SOURCE_MIRROR_URL}')";
+/// assert_eq!(classify_fetch_failure([log]), YoctoFailureKind::DoFetch);
+/// ```
+pub fn classify_fetch_failure<'l>(logs: impl IntoIterator<Item = &'l str>) -> YoctoFailureKind {
+    static CHECKSUM_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"(?i)checksum mismatch|(?:md5|sha1|sha256|sha384|sha512)sum mismatch|checksums? (?:did|does) not match").unwrap()
+    });
+    static NETWORK_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(
+            r"(?xi)
+            timed\ out
+            | could\ not\ resolve\ host
+            | temporary\ failure\ in\ name\ resolution
+            | name\ or\ service\ not\ known
+            | network\ is\ unreachable
+            | connection\ refused
+            | connection\ reset\ by\ peer
+            | failed\ to\ connect
+            | unable\ to\ access
+            | tls\ handshake
+            | ssl_error
+            | HTTP\ request\ sent,\ awaiting\ response\.\.\.\ (?:5\d\d|429)
+            | unable\ to\ fetch\ url\ from\ any\ source
+        ",
+        )
+        .unwrap()
+    });
+    let logs: Vec<&str> = logs.into_iter().collect();
+    if logs.iter().any(|log| CHECKSUM_RE.is_match(log)) {
+        YoctoFailureKind::DoFetchChecksum
+    } else if logs.iter().any(|log| NETWORK_RE.is_match(log)) {
+        YoctoFailureKind::DoFetchNetwork
+    } else {
+        YoctoFailureKind::DoFetch
+    }
+}
+
+/// Line separating the reports of failed tests in the output of Python's unittest
+const UNITTEST_SEPARATOR: &str =
+    "======================================================================";
+
+/// Find the failed test cases in the log of `do_testimage` (or `oe-selftest`)
+///
+/// # Example
+/// ```
+/// # use ci_manager::err_parse::yocto::util::testimage_failed_tests;
+/// let log = r#"NOTE: ======================================================================
+/// NOTE: FAIL: test_ssh (ssh.SSHTest.test_ssh)
+/// NOTE: ----------------------------------------------------------------------
+/// NOTE: AssertionError: 255 != 0 : SSH command failed
+/// RESULTS:
+/// RESULTS - ping.PingTest.test_ping: PASSED (0.05s)
+/// RESULTS - ssh.SSHTest.test_ssh: FAILED (0.01s)
+/// RESULTS - systemd.SystemdBasicTests.test_systemd_basic: ERROR (2.12s)"#;
+///
+/// assert_eq!(
+///     testimage_failed_tests(log),
+///     ["ssh.SSHTest.test_ssh", "systemd.SystemdBasicTests.test_systemd_basic"]
+/// );
+/// ```
+pub fn testimage_failed_tests(log: &str) -> Vec<String> {
+    static RESULT_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"RESULTS - (?<test>\S+): (?:FAILED|ERROR)\b").unwrap());
+    static UNITTEST_FAILURE_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"(?:FAIL|ERROR): (?<method>\w+) \((?<test>[^)]+)\)").unwrap());
+
+    let mut tests: Vec<String> = Vec::new();
+    for line in log.lines() {
+        let test = if let Some(caps) = RESULT_RE.captures(line) {
+            caps["test"].to_owned()
+        } else if let Some(caps) = UNITTEST_FAILURE_RE.captures(line) {
+            // Python 3.11 includes the method in the test ID, older versions do not
+            let (method, test) = (&caps["method"], &caps["test"]);
+            if test.ends_with(&format!(".{method}")) {
+                test.to_owned()
+            } else {
+                format!("{test}.{method}")
+            }
+        } else {
+            continue;
+        };
+        if !tests.contains(&test) {
+            tests.push(test);
+        }
+    }
+    tests
+}
+
+/// The reports of the failed tests in the log of `do_testimage` (or `oe-selftest`), with the test
+/// summary, `None` if there are no reports. Most of the log is the output of the tests that passed.
+pub fn testimage_failure_section(log: &str) -> Option<String> {
+    static FAILURE_START_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"(?:FAIL|ERROR): \w+ \(").unwrap());
+    static FAILURE_END_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"Ran \d+ tests? in|RESULTS:|SUMMARY:").unwrap());
+    static SUMMARY_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"Ran \d+ tests? in| - FAIL - |RESULTS - \S+: (?:FAILED|ERROR)\b").unwrap()
+    });
+
+    let mut section: Vec<&str> = Vec::new();
+    let mut in_failure = false;
+    for line in log.lines() {
+        if FAILURE_START_RE.is_match(line) {
+            in_failure = true;
+        } else if in_failure && (line.contains(UNITTEST_SEPARATOR) || FAILURE_END_RE.is_match(line))
+        {
+            in_failure = false;
+        }
+        if in_failure || SUMMARY_RE.is_match(line) {
+            section.push(line);
+        }
+    }
+    if section.is_empty() {
+        None
+    } else {
+        Some(section.join("\n"))
+    }
 }
 
+/// Find the lines reporting that QEMU failed to start or the image failed to boot
+///
+/// # Example
+/// ```
+/// # use ci_manager::err_parse::yocto::util::qemu_errors;
+/// let log = "runqemu - ERROR - Failed to run qemu: qemu-system-aarch64: -m 4096: cannot set up guest memory\n\
+///     ERROR: core-image-minimal-1.0-r0 do_testimage: Couldn't start qemu";
+/// assert_eq!(qemu_errors(log).len(), 2);
+/// ```
+pub fn qemu_errors(log: &str) -> Vec<&str> {
+    static QEMU_ERROR_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"(?i)runqemu - ERROR|failed to start qemu|couldn't start qemu|qemu.*(?:didn't|did not) (?:start|boot)|target did not reach a login prompt").unwrap()
+    });
+    let mut lines: Vec<&str> = Vec::new();
+    for line in log.lines().filter(|line| QEMU_ERROR_RE.is_match(line)) {
+        let line = line.trim();
+        if !lines.contains(&line) {
+            lines.push(line);
+        }
+    }
+    lines
+}
+
+/// A QA issue that failed a task, e.g. `do_package_qa`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QaIssue {
+    /// Name of the QA check, e.g. `file-rdeps` or `installed-vs-shipped`
+    pub check: String,
+    /// The recipe that failed the check, e.g. `foo-1.0-r0`
+    pub recipe: Option<String>,
+    /// The first line of the message, without the name of the check
+    pub message: String,
+    /// The files listed in the message, e.g. the files that were installed but not shipped
+    pub files: Vec<String>,
+}
+
+/// Find the QA issues that failed a task, i.e. the `ERROR: <recipe> <task>: QA Issue:` messages,
+/// which can span several lines up to the name of the check in brackets
+///
+/// # Example
+/// ```
+/// # use ci_manager::err_parse::yocto::util::qa_issues;
+/// let log = r#"ERROR: foo-1.0-r0 do_package: QA Issue: foo: Files/directories were installed but not shipped in any package:
+///   /usr/lib/libfoo.so.1
+///   /usr/share/foo/foo.conf
+/// Please set FILES such that these items are packaged. Alternatively if they are unneeded, avoid installing them or delete them within do_install.
+/// foo: 2 installed and not shipped files. [installed-vs-shipped]
+/// WARNING: bar-2.0-r0 do_package_qa: QA Issue: bar: ELF binary /usr/bin/bar has relocations in .text [textrel]"#;
+///
+/// let issues = qa_issues(log);
+/// assert_eq!(issues.len(), 1);
+/// assert_eq!(issues[0].check, "installed-vs-shipped");
+/// assert_eq!(issues[0].recipe.as_deref(), Some("foo-1.0-r0"));
+/// assert_eq!(issues[0].files, ["/usr/lib/libfoo.so.1", "/usr/share/foo/foo.conf"]);
+/// ```
+pub fn qa_issues(log: &str) -> Vec<QaIssue> {
+    static QA_ISSUE_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"ERROR: (?:(?<recipe>\S+) do_\w+: )?QA Issue: (?<message>.*)$").unwrap()
+    });
+    static CHECK_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"\s*\[(?<check>[a-z0-9-]+)\]\s*$").unwrap());
+    /// Maximum number of lines of a single QA issue message
+    const MAX_MESSAGE_LINES: usize = 100;
+
+    let lines: Vec<&str> = log.lines().collect();
+    let mut issues: Vec<QaIssue> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let Some(caps) = QA_ISSUE_RE.captures(line) else {
+            continue;
+        };
+        let message = &caps["message"];
+        let mut files: Vec<String> = Vec::new();
+        let check = lines[i..]
+            .iter()
+            .take(MAX_MESSAGE_LINES)
+            .enumerate()
+            .find_map(|(j, line)| {
+                if j > 0 && QA_ISSUE_RE.is_match(line) {
+                    return Some(None);
+                }
+                let line = if j == 0 { message } else { line.trim() };
+                if j > 0 && line.starts_with('/') {
+                    files.push(line.to_owned());
+                }
+                CHECK_RE
+                    .captures(line)
+                    .map(|caps| Some(caps["check"].to_owned()))
+            })
+            .flatten();
+        let Some(check) = check else {
+            continue;
+        };
+        let issue = QaIssue {
+            message: CHECK_RE
+                .replace(message, "")
+                .trim_end_matches(':')
+                .to_owned(),
+            check,
+            recipe: caps.name("recipe").map(|recipe| recipe.as_str().to_owned()),
+            files,
+        };
+        if !issues.contains(&issue) {
+            issues.push(issue);
+        }
+    }
+    issues
+}
+
+/// Summary of the QA issues in a log, grouped by the name of the check, `None` if there are none
+///
+/// # Example
+/// ```
+/// # use ci_manager::err_parse::yocto::util::qa_issue_summary;
+/// let log = r#"ERROR: foo-1.0-r0 do_package_qa: QA Issue: /usr/bin/foo-helper contained in package foo requires /bin/bash, but no providers found in RDEPENDS:foo? [file-rdeps]
+/// ERROR: bar-2.0-r0 do_package_qa: QA Issue: /usr/bin/bar.py contained in package bar requires /usr/bin/python3, but no providers found in RDEPENDS:bar? [file-rdeps]
+/// ERROR: foo-1.0-r0 do_package_qa: Fatal QA errors were found, failing task."#;
+///
+/// assert_eq!(
+///     qa_issue_summary(log).unwrap(),
+///     "QA issues:\n\
+///     file-rdeps:\n\
+///     - foo-1.0-r0: /usr/bin/foo-helper contained in package foo requires /bin/bash, but no providers found in RDEPENDS:foo?\n\
+///     - bar-2.0-r0: /usr/bin/bar.py contained in package bar requires /usr/bin/python3, but no providers found in RDEPENDS:bar?\n"
+/// );
+/// ```
+pub fn qa_issue_summary(log: &str) -> Option<String> {
+    let issues = qa_issues(log);
+    if issues.is_empty() {
+        return None;
+    }
+    let mut checks: Vec<&str> = Vec::new();
+    for issue in &issues {
+        if !checks.contains(&issue.check.as_str()) {
+            checks.push(&issue.check);
+        }
+    }
+    let mut summary = String::from("QA issues:\n");
+    for check in checks {
+        summary.push_str(&format!("{check}:\n"));
+        for issue in issues.iter().filter(|issue| issue.check == check) {
+            match &issue.recipe {
+                Some(recipe) => summary.push_str(&format!("- {recipe}: {}\n", issue.message)),
+                None => summary.push_str(&format!("- {}\n", issue.message)),
+            }
+            for file in &issue.files {
+                summary.push_str(&format!("  - `{file}`\n"));
+            }
+        }
+    }
+    Some(summary)
+}
+
+/// Summary of the sstate signature and hash equivalence errors in a log, with the signatures of
+/// the affected tasks, `None` if there are none
+///
+/// # Example
+/// ```
+/// # use ci_manager::err_parse::yocto::util::sstate_error_summary;
+/// let log = r#"NOTE: Started hash equivalence server on unix:///build/hashserve.sock
+/// ERROR: Taskhash mismatch 2c8f1e5a versus 9a1b7d3c for /build/meta-foo/recipes-foo/foo/foo_1.0.bb:do_compile
+/// ERROR: Taskhash mismatch 2c8f1e5a versus 9a1b7d3c for /build/meta-foo/recipes-foo/foo/foo_1.0.bb:do_compile"#;
+///
+/// assert_eq!(
+///     sstate_error_summary(log).unwrap(),
+///     "BitBake sstate or hash equivalence error\n\
+///     \n\
+///     Affected task signatures:\n\
+///     - `/build/meta-foo/recipes-foo/foo/foo_1.0.bb:do_compile`: `2c8f1e5a` -> `9a1b7d3c`\n\
+///     \n\
+///     ERROR: Taskhash mismatch 2c8f1e5a versus 9a1b7d3c for /build/meta-foo/recipes-foo/foo/foo_1.0.bb:do_compile\n"
+/// );
+/// assert_eq!(sstate_error_summary("NOTE: Started hash equivalence server"), None);
+/// ```
+pub fn sstate_error_summary(log: &str) -> Option<String> {
+    static SIGNATURE_RES: Lazy<[Regex; 2]> = Lazy::new(|| {
+        [
+            Regex::new(r"Taskhash mismatch (?<old>[0-9a-f]+) versus (?<new>[0-9a-f]+) for (?<task>\S+)").unwrap(),
+            Regex::new(r"When reparsing (?<task>\S+?),? the basehash value changed from (?<old>[0-9a-f]+) to (?<new>[0-9a-f]+)").unwrap(),
+        ]
+    });
+    static SSTATE_ERROR_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"(?i)hash ?equivalence|hashserv|unihash|taskhash mismatch|basehash value changed|sstate\S* .*(?:mismatch|corrupt|invalid|signature)").unwrap()
+    });
+    const MAX_LINES: usize = 10;
+
+    let mut signatures: Vec<String> = Vec::new();
+    let mut lines: Vec<&str> = Vec::new();
+    for line in log
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("ERROR:") && SSTATE_ERROR_RE.is_match(line))
+    {
+        if let Some(caps) = SIGNATURE_RES.iter().find_map(|re| re.captures(line)) {
+            let signature = format!(
+                "`{task}`: `{old}` -> `{new}`",
+                task = &caps["task"],
+                old = &caps["old"],
+                new = &caps["new"]
+            );
+            if !signatures.contains(&signature) {
+                signatures.push(signature);
+            }
+        }
+        if !lines.contains(&line) {
+            lines.push(line);
+        }
+    }
+    if lines.is_empty() {
+        return None;
+    }
+    let mut summary = String::from("BitBake sstate or hash equivalence error\n");
+    if !signatures.is_empty() {
+        summary.push_str("\nAffected task signatures:\n");
+        for signature in &signatures {
+            summary.push_str(&format!("- {signature}\n"));
+        }
+    }
+    summary.push('\n');
+    for line in lines.iter().take(MAX_LINES) {
+        summary.push_str(line);
+        summary.push('\n');
+    }
+    Some(summary)
+}
+
+/// Start of the summary BitBake prints when tasks failed
+pub const YOCTO_ERROR_SUMMARY_SIGNATURE: &str = "--- Error summary ---";
+
 /// Find the `--- Error summary ---` section in the log and return the rest of the log.
 pub fn yocto_error_summary(log: &str) -> Result<String> {
-    const YOCTO_ERROR_SUMMARY_SIGNATURE: &str = "--- Error summary ---";
     let error_summary = log
         .split(YOCTO_ERROR_SUMMARY_SIGNATURE)
         .collect::<Vec<&str>>()
@@ -66,6 +485,78 @@ pub fn yocto_error_summary(log: &str) -> Result<String> {
     Ok(error_summary.trim().to_string())
 }
 
+/// Remove the `2024-02-16 12:45:43 - ERROR    - ` prefixes kas adds to its log lines
+///
+/// # Example
+/// ```
+/// # use ci_manager::err_parse::yocto::util::remove_kas_prefixes;
+/// let log = "2024-02-16 12:45:43 - ERROR    - Command \"bitbake\" failed with error 1";
+/// assert_eq!(remove_kas_prefixes(log), "Command \"bitbake\" failed with error 1");
+/// ```
+pub fn remove_kas_prefixes(log: &str) -> borrow::Cow<'_, str> {
+    static RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(
+            r"\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2} - (?:DEBUG|INFO|WARNING|ERROR|CRITICAL)\s+- ",
+        )
+        .unwrap()
+    });
+    RE.replace_all(log, "")
+}
+
+/// Summary of an error in the kas configuration, such as a layer that does not exist or a refspec
+/// that cannot be checked out, `None` if there is no kas configuration error in the log
+///
+/// # Example
+/// ```
+/// # use ci_manager::err_parse::yocto::util::kas_config_error_summary;
+/// let log = r#"2024-02-16 12:45:43 - INFO     - Cloning repository meta-foo
+/// 2024-02-16 12:45:44 - ERROR    - fatal: couldn't find remote ref refs/heads/no-such-branch
+/// 2024-02-16 12:45:44 - ERROR    - Unable to fetch repository meta-foo"#;
+///
+/// assert_eq!(
+///     kas_config_error_summary(log).unwrap(),
+///     "kas configuration error: bad refspec\n\
+///     fatal: couldn't find remote ref refs/heads/no-such-branch\n"
+/// );
+/// ```
+pub fn kas_config_error_summary(log: &str) -> Option<String> {
+    static KAS_ERROR_RES: Lazy<[(&str, Regex); 3]> = Lazy::new(|| {
+        [
+            (
+                "missing layer",
+                Regex::new(r"(?i)\blayer\b.*\b(?:not found|does not exist|could not be found)").unwrap(),
+            ),
+            (
+                "bad refspec",
+                Regex::new(r"(?i)couldn't find remote ref|did not match any file\(s\) known to git|unknown revision|not a valid (?:refspec|object name)|\b(?:refspec|commit|branch|tag)\b.*\b(?:not found|does not exist)").unwrap(),
+            ),
+            (
+                "invalid configuration",
+                Regex::new(r"(?i)kas: error:|\b(?:config|configuration) file\b.*\b(?:not found|does not exist)|validation error|unknown (?:key|option)").unwrap(),
+            ),
+        ]
+    });
+
+    let log = remove_kas_prefixes(log);
+    let mut kind = None;
+    let mut lines: Vec<&str> = Vec::new();
+    for line in log.lines() {
+        let Some((line_kind, _)) = KAS_ERROR_RES.iter().find(|(_, re)| re.is_match(line)) else {
+            continue;
+        };
+        kind.get_or_insert(*line_kind);
+        let line = line.trim();
+        if !lines.contains(&line) {
+            lines.push(line);
+        }
+    }
+    let kind = kind?;
+    Some(format!(
+        "kas configuration error: {kind}\n{lines}\n",
+        lines = lines.join("\n")
+    ))
+}
+
 /// Trim the trailing `error: Recipe` lines from the error summary
 /// This is to remove the noise of just recipe failures
 pub fn trim_trailing_just_recipes(log: &str) -> Result<String> {
@@ -111,6 +602,98 @@ pub fn find_yocto_failure_log_str(log: &str) -> Result<&str> {
     Ok(log_file_line)
 }
 
+/// A task that BitBake reported as failed, from the `Logfile of failure stored in` line of the task
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailedTask {
+    /// Name of the task, e.g. `do_compile`, also if it is not a known [YoctoFailureKind]
+    pub task: String,
+    pub kind: YoctoFailureKind,
+    /// The recipe, from the work directory of the task, e.g. `sqlite3-native`
+    pub recipe: Option<String>,
+    /// The version of the recipe, from the work directory of the task, e.g. `3.43.2`
+    pub version: Option<String>,
+    pub logfile: PathBuf,
+}
+
+impl fmt::Display for FailedTask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(recipe) = &self.recipe {
+            write!(f, "{recipe} ")?;
+        }
+        if let Some(version) = &self.version {
+            write!(f, "{version} ")?;
+        }
+        write!(f, "{}", self.task)
+    }
+}
+
+/// Find the failed tasks in the error summary of a build, in the order BitBake reported them
+///
+/// The recipe and version are taken from the path of the logfile, which is in the work directory
+/// of the recipe, i.e. `tmp/work/<arch>/<recipe>/<version>/temp/log.<task>.<pid>`.
+///
+/// # Example
+/// ```
+/// # use ci_manager::err_parse::yocto::util::{failed_tasks, YoctoFailureKind};
+/// let log = r#"ERROR: sqlite3-native-3_3.43.2-r0 do_fetch: Bitbake Fetcher Error: MalformedUrl('${SOURCE_MIRROR_URL}')
+/// ERROR: Logfile of failure stored in: /app/yocto/build/tmp/work/x86_64-linux/sqlite3-native/3.43.2/temp/log.do_fetch.21616
+/// ERROR: Task (virtual:native:/app/yocto/poky/meta/recipes-support/sqlite/sqlite3_3.43.2.bb:do_fetch) failed with exit code '1'
+/// ERROR: foo-1.0-r0 do_compile: oe_runmake failed
+/// ERROR: Logfile of failure stored in: /app/yocto/build/tmp/work/cortexa72-poky-linux/foo/1.0/temp/log.do_compile.4242
+/// Summary: 2 tasks failed:"#;
+///
+/// let tasks = failed_tasks(log);
+/// assert_eq!(tasks.len(), 2);
+/// assert_eq!(tasks[0].to_string(), "sqlite3-native 3.43.2 do_fetch");
+/// assert_eq!(tasks[1].kind, YoctoFailureKind::DoCompile);
+/// assert_eq!(tasks[1].recipe.as_deref(), Some("foo"));
+/// ```
+pub fn failed_tasks(log: &str) -> Vec<FailedTask> {
+    static LOGFILE_NAME_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"^log\.(?<task>.+?)(?:\.\d+)?$").unwrap());
+
+    let mut tasks: Vec<FailedTask> = Vec::new();
+    for line in log
+        .lines()
+        .filter(|line| line.contains("Logfile of failure stored in"))
+    {
+        let Ok(logfile) = first_path_from_str(line) else {
+            continue;
+        };
+        if tasks.iter().any(|task| task.logfile == logfile) {
+            continue;
+        }
+        let Some(fname) = logfile.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let task = LOGFILE_NAME_RE
+            .captures(fname)
+            .map_or(fname, |caps| caps.name("task").unwrap().as_str())
+            .to_owned();
+        let kind = YoctoFailureKind::parse_from_logfilename(fname).unwrap_or_default();
+        // e.g. .../sqlite3-native/3.43.2/temp/log.do_fetch.21616
+        let work_dir = logfile
+            .parent()
+            .filter(|temp| temp.file_name().is_some_and(|name| name == "temp"))
+            .and_then(Path::parent);
+        let version = work_dir
+            .and_then(Path::file_name)
+            .map(|name| name.to_string_lossy().into_owned());
+        let recipe = work_dir
+            .and_then(Path::parent)
+            .and_then(Path::file_name)
+            .map(|name| name.to_string_lossy().into_owned());
+        tasks.push(FailedTask {
+            task,
+            kind,
+            recipe,
+            version,
+            logfile,
+        });
+    }
+    tasks
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -177,4 +760,52 @@ ERROR: Task (virtual:native:/app/yocto/build/../poky/meta/recipes-support/sqlite
         eprintln!("{trimmed}");
         assert_eq!(trimmed, TEST_EXPECT_TRIMMED_YOCTO_ERROR_SUMMARY);
     }
+
+    #[test]
+    fn test_classify_fetch_failure_wget_log() {
+        let fetch_log = r#"DEBUG: Executing python function base_do_fetch
+DEBUG: Trying PREMIRRORS
+--2024-05-02 08:13:11--  https://downloads.example.com/sources/zlib-1.3.1.tar.xz
+Connecting to downloads.example.com (downloads.example.com)|10.0.0.12|:443... failed: Connection timed out.
+WARNING: Failed to fetch URL https://zlib.net/zlib-1.3.1.tar.xz, attempting MIRRORS if available
+ERROR: Fetcher failure: Unable to fetch URL from any source."#;
+
+        let kind = classify_fetch_failure([
+            "ERROR: zlib-1.3.1-r0 do_fetch: Bitbake Fetcher Error",
+            fetch_log,
+        ]);
+        assert_eq!(kind, YoctoFailureKind::DoFetchNetwork);
+        assert_eq!(kind.to_string(), "fetch-network");
+    }
+
+    #[test]
+    fn test_testimage_failure_section() {
+        let log = r#"NOTE: test_ping (ping.PingTest.test_ping) ... ok
+NOTE: test_ssh (ssh.SSHTest.test_ssh) ... FAIL
+NOTE: ======================================================================
+NOTE: FAIL: test_ssh (ssh.SSHTest.test_ssh)
+NOTE: ----------------------------------------------------------------------
+NOTE: Traceback (most recent call last):
+NOTE: AssertionError: 255 != 0 : SSH command failed
+NOTE: ----------------------------------------------------------------------
+NOTE: Ran 2 tests in 3.456s
+NOTE: FAILED (failures=1)
+RESULTS:
+RESULTS - ping.PingTest.test_ping: PASSED (0.05s)
+RESULTS - ssh.SSHTest.test_ssh: FAILED (0.01s)
+SUMMARY:
+core-image-minimal - FAIL - Required tests failed (successes=1, skipped=0, failures=1, errors=0)"#;
+
+        assert_eq!(
+            testimage_failure_section(log).unwrap(),
+            "NOTE: FAIL: test_ssh (ssh.SSHTest.test_ssh)
+NOTE: ----------------------------------------------------------------------
+NOTE: Traceback (most recent call last):
+NOTE: AssertionError: 255 != 0 : SSH command failed
+NOTE: ----------------------------------------------------------------------
+NOTE: Ran 2 tests in 3.456s
+RESULTS - ssh.SSHTest.test_ssh: FAILED (0.01s)
+core-image-minimal - FAIL - Required tests failed (successes=1, skipped=0, failures=1, errors=0)"
+        );
+    }
 }
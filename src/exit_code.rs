@@ -0,0 +1,117 @@
+//! Exit codes telling the outcome of an invocation apart, so a calling workflow can distinguish
+//! "nothing to do" from an error instead of treating every non-zero exit as a failure
+use crate::{notify::IssueOutcome, *};
+
+/// The outcome of the issue created for a single run, set by [set_outcome]
+static OUTCOME: OnceLock<Exit> = OnceLock::new();
+
+/// The exit codes in `--help`
+pub const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0   Success, e.g. the issue was created
+  1   Any other error
+  10  The failure is already tracked by an issue, no issue was created
+  11  The run had no failed jobs, or succeeded when its flaky jobs were re-run
+  20  Authentication error: the token is invalid or lacks a permission
+  21  The API rate limit was exceeded
+
+10 and 11 only apply to the issue of a single run, created by `create-issue-from-run` or the
+`create-issue` of `watch-run` and `bisect-run`. Commands that handle many runs, i.e.
+`create-issues-from-runs`, `digest`, `scan-org`, `monitor` and `serve`, never exit with them.";
+
+/// Exit code of ci-manager, see [EXIT_CODES_HELP]
+///
+/// | Code | Meaning |
+/// |------|---------|
+/// | 0    | Success, e.g. the issue was created |
+/// | 1    | Any other error |
+/// | 10   | The failure is already tracked by an issue, no issue was created |
+/// | 11   | The run had no failed jobs, or succeeded when its flaky jobs were re-run |
+/// | 20   | Authentication error: the token is invalid or lacks a permission |
+/// | 21   | The API rate limit was exceeded |
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Exit {
+    Success = 0,
+    Error = 1,
+    Duplicate = 10,
+    NoFailures = 11,
+    AuthError = 20,
+    RateLimited = 21,
+}
+
+impl Exit {
+    /// The exit code of the run report of the issue of a single run, `None` if no issue was needed
+    pub fn of_report(report: Option<&notify::RunReport>) -> Self {
+        match report.map(|report| &report.outcome) {
+            None => Self::NoFailures,
+            Some(IssueOutcome::Duplicate { .. }) => Self::Duplicate,
+            Some(_) => Self::Success,
+        }
+    }
+
+    /// The exit code of an error, from the HTTP status of the failed API request if there was one
+    pub fn of_error(error: &anyhow::Error) -> Self {
+        for cause in error.chain() {
+            if let Some(octocrab::Error::GitHub { source, .. }) = cause.downcast_ref() {
+                let rate_limited = source.message.to_lowercase().contains("rate limit");
+                return Self::of_status(source.status_code.as_u16(), rate_limited);
+            }
+            if let Some(status) = cause
+                .downcast_ref::<reqwest::Error>()
+                .and_then(reqwest::Error::status)
+            {
+                return Self::of_status(status.as_u16(), false);
+            }
+        }
+        Self::Error
+    }
+
+    /// The exit code of an HTTP error status. GitHub answers `403 Forbidden` when the rate limit is
+    /// exceeded, so the message tells if it was
+    fn of_status(status: u16, rate_limited: bool) -> Self {
+        match status {
+            429 => Self::RateLimited,
+            403 if rate_limited => Self::RateLimited,
+            401 | 403 => Self::AuthError,
+            _ => Self::Error,
+        }
+    }
+
+    /// The exit code of a successful invocation, the outcome set by [set_outcome] if there was one
+    pub fn outcome() -> Self {
+        OUTCOME.get().copied().unwrap_or(Self::Success)
+    }
+}
+
+impl From<Exit> for ExitCode {
+    fn from(exit: Exit) -> Self {
+        ExitCode::from(exit as u8)
+    }
+}
+
+/// Set the exit code of the invocation if it succeeds, see [Exit::outcome]
+pub fn set_outcome(exit: Exit) {
+    if OUTCOME.set(exit).is_err() {
+        log::debug!("The outcome is already set, ignoring {exit:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_exit_of_status() {
+        assert_eq!(Exit::of_status(401, false), Exit::AuthError);
+        assert_eq!(Exit::of_status(403, false), Exit::AuthError);
+        assert_eq!(Exit::of_status(403, true), Exit::RateLimited);
+        assert_eq!(Exit::of_status(429, false), Exit::RateLimited);
+        assert_eq!(Exit::of_status(500, false), Exit::Error);
+        assert_eq!(
+            Exit::of_error(&anyhow::anyhow!("No jobs found")),
+            Exit::Error
+        );
+    }
+}
@@ -0,0 +1,287 @@
+//! The SQLite database of `--history-db`, that records every run analyzed by
+//! `create-issue-from-run`: its failed jobs, their fingerprints and what was done about the
+//! failure. It can be queried offline with the `history` command, or with plain SQL.
+use crate::{
+    notify::{RunReport, UNCLASSIFIED_LABEL},
+    *,
+};
+use rusqlite::{params, Connection};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    repo TEXT NOT NULL,
+    run_id TEXT NOT NULL,
+    run_url TEXT NOT NULL,
+    title TEXT NOT NULL,
+    fingerprint TEXT NOT NULL,
+    decision TEXT NOT NULL,
+    issue_url TEXT,
+    analyzed_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+CREATE TABLE IF NOT EXISTS failed_jobs (
+    run INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    url TEXT NOT NULL,
+    failed_step TEXT NOT NULL,
+    failure_label TEXT,
+    fingerprint TEXT NOT NULL,
+    summary TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS failed_jobs_fingerprint ON failed_jobs(fingerprint);
+";
+
+/// Number of recorded runs with a decision, see [IssueOutcome::decision](notify::IssueOutcome::decision)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DecisionCount {
+    pub decision: String,
+    pub count: usize,
+}
+
+/// Number of failed jobs with a failure label, [UNCLASSIFIED_LABEL] if the parser found none
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LabelCount {
+    pub label: String,
+    pub count: usize,
+}
+
+/// A job failure that was recorded in more than one run, e.g. a flaky test or a broken runner
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RecurringFailure {
+    pub fingerprint: String,
+    /// Name of the job that last failed this way
+    pub job: String,
+    pub failure_label: Option<String>,
+    pub runs: usize,
+    pub repos: usize,
+    pub last_seen: String,
+}
+
+/// A connection to the history database
+#[derive(Debug)]
+pub struct History {
+    conn: Connection,
+}
+
+impl History {
+    /// Open the history database at `path`, and create it if it doesn't exist
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open the history database: {path:?}"))?;
+        Self::init(conn)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)
+            .context("Failed to create the tables of the history database")?;
+        Ok(Self { conn })
+    }
+
+    /// Record the analyzed run of `repo` described by `report`
+    pub fn record(&mut self, repo: &str, report: &RunReport) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO runs (repo, run_id, run_url, title, fingerprint, decision, issue_url)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                repo,
+                report.run_id,
+                report.run_url,
+                report.title,
+                report.fingerprint,
+                report.outcome.decision(),
+                report.outcome.issue_url(),
+            ],
+        )?;
+        let run = tx.last_insert_rowid();
+        for job in &report.failed_jobs {
+            tx.execute(
+                "INSERT INTO failed_jobs (run, name, url, failed_step, failure_label, fingerprint, summary)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    run,
+                    job.name,
+                    job.url,
+                    job.failed_step,
+                    job.failure_label,
+                    job.fingerprint,
+                    job.summary,
+                ],
+            )?;
+        }
+        tx.commit()?;
+        log::info!(
+            "Recorded run {} with {} failed job(s) in the history database",
+            report.run_id,
+            report.failed_jobs.len()
+        );
+        Ok(())
+    }
+
+    /// Number of the runs analyzed within the last `days` days (all time if `None`) by decision,
+    /// most common first
+    pub fn decisions(&self, days: Option<u64>) -> Result<Vec<DecisionCount>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT decision, COUNT(*) FROM runs
+             WHERE ?1 IS NULL OR analyzed_at >= datetime('now', ?1)
+             GROUP BY decision ORDER BY COUNT(*) DESC, decision",
+        )?;
+        let rows = stmt.query_map([since(days)], |row| {
+            Ok(DecisionCount {
+                decision: row.get(0)?,
+                count: row.get(1)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Number of the jobs that failed within the last `days` days by failure label, most common
+    /// first
+    pub fn failure_labels(&self, days: Option<u64>) -> Result<Vec<LabelCount>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT COALESCE(failure_label, ?2) AS label, COUNT(*) FROM failed_jobs
+             JOIN runs ON runs.id = failed_jobs.run
+             WHERE ?1 IS NULL OR runs.analyzed_at >= datetime('now', ?1)
+             GROUP BY label ORDER BY COUNT(*) DESC, label",
+        )?;
+        let rows = stmt.query_map(params![since(days), UNCLASSIFIED_LABEL], |row| {
+            Ok(LabelCount {
+                label: row.get(0)?,
+                count: row.get(1)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// The `top` job failures recorded in the most runs within the last `days` days, that were
+    /// recorded in more than one run
+    pub fn recurring_failures(
+        &self,
+        days: Option<u64>,
+        top: usize,
+    ) -> Result<Vec<RecurringFailure>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT failed_jobs.fingerprint,
+                    (SELECT name FROM failed_jobs AS last WHERE last.fingerprint = failed_jobs.fingerprint ORDER BY last.run DESC LIMIT 1),
+                    MAX(failed_jobs.failure_label),
+                    COUNT(DISTINCT runs.id),
+                    COUNT(DISTINCT runs.repo),
+                    MAX(runs.analyzed_at)
+             FROM failed_jobs JOIN runs ON runs.id = failed_jobs.run
+             WHERE ?1 IS NULL OR runs.analyzed_at >= datetime('now', ?1)
+             GROUP BY failed_jobs.fingerprint
+             HAVING COUNT(DISTINCT runs.id) > 1
+             ORDER BY COUNT(DISTINCT runs.id) DESC, MAX(runs.analyzed_at) DESC
+             LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![since(days), top], |row| {
+            Ok(RecurringFailure {
+                fingerprint: row.get(0)?,
+                job: row.get(1)?,
+                failure_label: row.get(2)?,
+                runs: row.get(3)?,
+                repos: row.get(4)?,
+                last_seen: row.get(5)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
+
+/// The SQLite date modifier of the start of the last `days` days, `NULL` for all time
+fn since(days: Option<u64>) -> Option<String> {
+    days.map(|days| format!("-{days} days"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notify::{IssueOutcome, JobReport};
+    use pretty_assertions::assert_eq;
+
+    fn report(
+        run_id: &str,
+        jobs: &[(&str, Option<&str>, &str)],
+        outcome: IssueOutcome,
+    ) -> RunReport {
+        RunReport {
+            run_id: run_id.to_owned(),
+            run_url: format!("https://github.com/luftkode/ci-manager/actions/runs/{run_id}"),
+            title: "Nightly failed".to_owned(),
+            fingerprint: format!("run-{run_id}"),
+            labels: vec!["CI".to_owned()],
+            failed_jobs: jobs
+                .iter()
+                .map(|(name, label, fingerprint)| JobReport {
+                    name: name.to_string(),
+                    url: format!(
+                        "https://github.com/luftkode/ci-manager/actions/runs/{run_id}/job/1"
+                    ),
+                    failed_step: "Build".to_owned(),
+                    failure_label: label.map(str::to_owned),
+                    fingerprint: fingerprint.to_string(),
+                    summary: "error".to_owned(),
+                })
+                .collect(),
+            outcome,
+        }
+    }
+
+    #[test]
+    fn test_history_reports() {
+        let mut history = History::init(Connection::open_in_memory().unwrap()).unwrap();
+        let issue = "https://github.com/luftkode/ci-manager/issues/42".to_owned();
+        history
+            .record(
+                "luftkode/ci-manager",
+                &report(
+                    "1",
+                    &[
+                        ("Build", Some("runner-disk-full"), "aaaa"),
+                        ("Test", None, "bbbb"),
+                    ],
+                    IssueOutcome::Created { url: issue.clone() },
+                ),
+            )
+            .unwrap();
+        history
+            .record(
+                "luftkode/other",
+                &report(
+                    "2",
+                    &[("Build image", Some("runner-disk-full"), "aaaa")],
+                    IssueOutcome::Duplicate { urls: vec![issue] },
+                ),
+            )
+            .unwrap();
+
+        let decisions: Vec<(String, usize)> = history
+            .decisions(Some(7))
+            .unwrap()
+            .into_iter()
+            .map(|d| (d.decision, d.count))
+            .collect();
+        assert_eq!(
+            decisions,
+            [("created".to_owned(), 1), ("duplicate".to_owned(), 1)]
+        );
+        let labels: Vec<(String, usize)> = history
+            .failure_labels(None)
+            .unwrap()
+            .into_iter()
+            .map(|l| (l.label, l.count))
+            .collect();
+        assert_eq!(
+            labels,
+            [
+                ("runner-disk-full".to_owned(), 2),
+                (UNCLASSIFIED_LABEL.to_owned(), 1)
+            ]
+        );
+        let recurring = history.recurring_failures(None, 10).unwrap();
+        assert_eq!(recurring.len(), 1);
+        assert_eq!(recurring[0].fingerprint, "aaaa");
+        assert_eq!(recurring[0].job, "Build image");
+        assert_eq!((recurring[0].runs, recurring[0].repos), (2, 2));
+    }
+}
@@ -4,16 +4,106 @@
 //! in a repository. It contains a title, label, and body. The body is a
 //! collection of FailedJob structs, which contain information about the failed
 //! jobs in a GitHub Actions workflow run.
-use crate::{ensure_https_prefix, err_parse::ErrorMessageSummary};
+use crate::{
+    config::{commands::BodyFormat, file::Labels},
+    ensure_https_prefix,
+    err_parse::{annotations::Annotation, yocto::YoctoError, ErrorMessageSummary},
+    fingerprint, normalize_error, normalize_error_with_rules, Lazy, Regex,
+};
 use anyhow::Ok;
-use std::fmt::{self, Display, Formatter, Write};
+use excerpt::ContextExcerpt;
+use matrix::MatrixParam;
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter, Write},
+};
+use template::{IssueContext, IssueTemplate, JobContext, SectionContext};
 
+/// Maximum length of the body of a GitHub issue
+const ISSUE_BODY_MAX_LEN: usize = 65535;
+/// Maximum length of a GitHub issue comment
+const COMMENT_MAX_LEN: usize = 65535;
+/// Number of failed jobs from which every job but the first is collapsed in the issue body
+const COLLAPSE_JOBS_MIN: usize = 4;
+
+/// How long the issue body and the error sections of the jobs in it may be, in bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BodyLimits {
+    /// Maximum length of the issue body
+    pub max_len: usize,
+    /// Maximum length of the error summary and log of a job, `None` to share the issue body fairly
+    /// between the jobs, see [fair_shares]
+    pub max_job_len: Option<usize>,
+}
+
+impl BodyLimits {
+    pub const GITHUB: Self = Self::with_max_len(ISSUE_BODY_MAX_LEN);
+    /// GitLab allows descriptions of 1 000 000 characters, but pages that long are unusable
+    pub const GITLAB: Self = Self::with_max_len(1_000_000);
+    /// The maximum length of a Jira text field
+    pub const JIRA: Self = Self::with_max_len(32_767);
+
+    pub const fn with_max_len(max_len: usize) -> Self {
+        Self {
+            max_len,
+            max_job_len: None,
+        }
+    }
+}
+
+impl Default for BodyLimits {
+    fn default() -> Self {
+        Self::GITHUB
+    }
+}
+
+pub mod excerpt;
+pub mod footer;
+pub mod junit;
+pub mod matrix;
+pub mod occurrence;
 pub mod similarity;
+pub mod template;
+
+/// Extract the run ID from the body of an issue created by ci-manager, from the
+/// [IssueMetadata](footer::IssueMetadata) or else from the link to the run
+///
+/// # Example
+/// ```
+/// # use ci_manager::issue::run_id_from_issue_body;
+/// let body = "**Run ID**: 7945016152 [LINK TO RUN](https://github.com/luftkode/distro-template/actions/runs/7945016152)";
+/// assert_eq!(run_id_from_issue_body(body), Some(7945016152));
+/// let body = "Run: github.com/luftkode/distro-template/actions/runs/7945016152";
+/// assert_eq!(run_id_from_issue_body(body), Some(7945016152));
+/// assert_eq!(run_id_from_issue_body("Some other issue"), None);
+/// ```
+pub fn run_id_from_issue_body(body: &str) -> Option<u64> {
+    if let Some(metadata) = footer::IssueMetadata::from_issue_body(body) {
+        return Some(metadata.run_id);
+    }
+    static RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"/actions/runs/(?<RUN_ID>[0-9]+)").unwrap());
+    RE.captures(body)?.name("RUN_ID")?.as_str().parse().ok()
+}
+
+/// Extract the fingerprint of the failure from the body of an issue created by ci-manager, from
+/// the [IssueMetadata](footer::IssueMetadata) or else from the
+/// [OccurrenceMarker](occurrence::OccurrenceMarker)
+pub fn fingerprint_from_issue_body(body: &str) -> Option<String> {
+    if let Some(metadata) = footer::IssueMetadata::from_issue_body(body) {
+        return Some(metadata.fingerprint);
+    }
+    occurrence::OccurrenceMarker::from_issue_body(body).map(|m| m.fingerprint().to_owned())
+}
 
 #[derive(Debug)]
 pub struct Issue {
     title: String,
     labels: Vec<String>,
+    assignees: Vec<String>,
+    /// Title or number of the milestone
+    milestone: Option<String>,
+    /// The subcommand and the workflow kind the issue is created with, shown in the footer
+    created_by: Option<(String, String)>,
     body: IssueBody,
 }
 
@@ -38,6 +128,9 @@ impl Issue {
         Self {
             title,
             labels,
+            assignees: Vec::new(),
+            milestone: None,
+            created_by: None,
             body: IssueBody::new(run_id, run_link, failed_jobs),
         }
     }
@@ -50,8 +143,212 @@ impl Issue {
         self.labels.as_slice()
     }
 
+    pub fn run_id(&self) -> &str {
+        &self.body.run_id
+    }
+
+    pub fn run_url(&self) -> &str {
+        &self.body.run_link
+    }
+
+    /// Add labels after the label given to [Issue::new]
+    pub fn add_labels<I, S>(&mut self, labels: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        for label in labels {
+            let label = label.into();
+            if !self.labels.contains(&label) {
+                self.labels.push(label);
+            }
+        }
+    }
+
+    /// Rename the labels of the issue as configured, see [Labels::name_of]
+    pub fn rename_labels(&mut self, labels: &Labels) {
+        let mut renamed: Vec<String> = Vec::with_capacity(self.labels.len());
+        for label in &self.labels {
+            let name = labels.name_of(label);
+            if !renamed.iter().any(|r| r == name) {
+                renamed.push(name.to_owned());
+            }
+        }
+        self.labels = renamed;
+    }
+
+    pub fn failed_jobs(&self) -> &[FailedJob] {
+        self.body.failed_jobs.as_slice()
+    }
+
+    pub fn assignees(&self) -> &[String] {
+        self.assignees.as_slice()
+    }
+
+    /// Assign the issue to users, by their login
+    pub fn add_assignees<I, S>(&mut self, assignees: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        for assignee in assignees {
+            let assignee = assignee.into();
+            if !self.assignees.contains(&assignee) {
+                self.assignees.push(assignee);
+            }
+        }
+    }
+
+    /// Title or number of the milestone of the issue
+    pub fn milestone(&self) -> Option<&str> {
+        self.milestone.as_deref()
+    }
+
+    pub fn set_milestone(&mut self, milestone: impl Into<String>) {
+        self.milestone = Some(milestone.into());
+    }
+
+    /// The issue body including the footer, if [Issue::set_created_by] was called, and the
+    /// hidden [OccurrenceMarker](occurrence::OccurrenceMarker)
     pub fn body(&mut self) -> String {
-        self.body.to_markdown_string()
+        let body = self.body.to_markdown_string();
+        let marker = occurrence::OccurrenceMarker::new(self.fingerprint());
+        let marker = match self.body.template.format() {
+            BodyFormat::Jira => format!("{{noformat}}{marker}{{noformat}}"),
+            BodyFormat::Markdown | BodyFormat::Plain => marker.to_string(),
+        };
+        match self.created_by.clone() {
+            Some((command, kind)) => {
+                let metadata = footer::IssueMetadata {
+                    version: env!("CARGO_PKG_VERSION").to_owned(),
+                    command,
+                    kind,
+                    run_id: self.body.run_id.parse().unwrap_or_default(),
+                    fingerprint: self.fingerprint(),
+                    job_fingerprints: self.job_fingerprints(),
+                };
+                {
+                    let footer = metadata.footer(self.body.template.format());
+                    format!("{body}\n\n{footer}\n{marker}")
+                }
+            }
+            None => format!("{body}\n\n{marker}"),
+        }
+    }
+
+    /// Add a footer to the issue body saying which subcommand created the issue, with hidden
+    /// [IssueMetadata](footer::IssueMetadata) for later subcommands
+    pub fn set_created_by(&mut self, command: impl Into<String>, kind: impl Into<String>) {
+        self.created_by = Some((command.into(), kind.into()));
+    }
+
+    /// Fingerprint of the failure described by the issue, see [fingerprint]
+    ///
+    /// Only the structured summaries of the failed jobs are part of the fingerprint (see
+    /// [FailedJob::error_key]), so the same failure has the same fingerprint whatever the template,
+    /// the limits of the body or the sections added with [Issue::add_section].
+    pub fn fingerprint(&self) -> String {
+        let mut keys: Vec<String> = self
+            .body
+            .failed_jobs
+            .iter()
+            .map(FailedJob::error_key)
+            .collect();
+        // The order the jobs are listed in doesn't change the failure
+        keys.sort_unstable();
+        fingerprint(&keys.join("\n\n"))
+    }
+
+    /// The rules of [normalize_error] that changed the error summaries of the failed jobs, i.e.
+    /// what the fingerprint of the failure ignores
+    pub fn applied_normalizations(&self) -> Vec<&'static str> {
+        let mut applied = Vec::new();
+        for job in &self.body.failed_jobs {
+            let (_, rules) = normalize_error_with_rules(job.error_message.summary());
+            for rule in rules {
+                if !applied.contains(&rule) {
+                    applied.push(rule);
+                }
+            }
+        }
+        applied
+    }
+
+    /// Fingerprints of the failures of the failed jobs, in the order of the jobs, so a later run can
+    /// tell which of its failed jobs are already tracked
+    pub fn job_fingerprints(&self) -> Vec<String> {
+        self.body
+            .failed_jobs
+            .iter()
+            .map(|job| fingerprint(&job.error_key()))
+            .collect()
+    }
+
+    /// Remove the failed jobs whose fingerprint is in `fingerprints`, e.g. because other issues
+    /// already track them, and return them. The last job is never removed.
+    pub fn remove_failed_jobs(&mut self, fingerprints: &[String]) -> Vec<FailedJob> {
+        let mut removed = Vec::new();
+        let mut kept = Vec::new();
+        for job in self.body.failed_jobs.drain(..) {
+            if fingerprints.contains(&fingerprint(&job.error_key())) {
+                removed.push(job);
+            } else {
+                kept.push(job);
+            }
+        }
+        if kept.is_empty() {
+            kept.extend(removed.pop());
+        }
+        self.body.failed_jobs = kept;
+        removed
+    }
+
+    /// Add a `recipe:<name>` label for the recipe of every failed Yocto task, see
+    /// [YoctoError::recipe_label](crate::err_parse::yocto::YoctoError::recipe_label)
+    pub fn add_recipe_labels(&mut self) {
+        for job in &self.body.failed_jobs {
+            if let Some(recipe_label) = job.recipe_label() {
+                if !self.labels.contains(&recipe_label) {
+                    log::debug!("Adding recipe label {recipe_label} to issue");
+                    self.labels.push(recipe_label);
+                }
+            }
+        }
+    }
+
+    /// Add a section with a bold title to the issue body, after the list of failed jobs
+    pub fn add_section(&mut self, title: impl Into<String>, content: impl Into<String>) {
+        self.body.sections.push((title.into(), content.into()));
+    }
+
+    /// The jobs whose error summary or log is shortened to fit in the issue body
+    pub fn shortened_jobs_mut(&mut self) -> Vec<&mut FailedJob> {
+        self.body.shortened_jobs_mut()
+    }
+
+    /// A comment with the full error summary and log of every job that was shortened to fit in the
+    /// issue body, to post on the issue after creating it
+    pub fn overflow_comments(&mut self) -> Vec<String> {
+        self.body.overflow_comments()
+    }
+
+    /// Shorten error summaries that are too long to the lines around their first error and their
+    /// last lines, instead of keeping only the end of the summary
+    pub fn set_excerpt(&mut self, excerpt: ContextExcerpt) {
+        self.body.excerpt = Some(excerpt);
+    }
+
+    /// Render the issue body with another template than the default, e.g. a custom one or the
+    /// plain text one of [IssueTemplate::default_for]
+    pub fn set_template(&mut self, template: IssueTemplate) {
+        self.body.template = template;
+        self.body.reset_job_markdown();
+    }
+
+    /// Fit the issue body in other limits than the ones of GitHub
+    pub fn set_body_limits(&mut self, limits: BodyLimits) {
+        self.body.limits = limits;
+        self.body.reset_job_markdown();
     }
 }
 
@@ -60,6 +357,12 @@ pub struct IssueBody {
     run_id: String,
     run_link: String,
     failed_jobs: Vec<FailedJob>,
+    /// Additional sections as (title, content)
+    sections: Vec<(String, String)>,
+    /// How error summaries that are too long are shortened, `None` to keep the end of the summary
+    excerpt: Option<ContextExcerpt>,
+    template: IssueTemplate,
+    limits: BodyLimits,
 }
 
 impl IssueBody {
@@ -68,56 +371,158 @@ impl IssueBody {
             run_id,
             run_link,
             failed_jobs,
+            sections: Vec::new(),
+            excerpt: None,
+            template: IssueTemplate::default(),
+            limits: BodyLimits::default(),
         }
     }
 
     pub fn to_markdown_string(&mut self) -> String {
-        let mut output_str = format!(
-            "**Run ID**: {id} [LINK TO RUN]({run_url})
-
-**{failed_jobs_list_title}**
-{failed_jobs_name_list}",
-            id = self.run_id,
-            run_url = self.run_link,
-            failed_jobs_list_title = format_args!(
-                "{cnt} {job} failed:",
-                cnt = self.failed_jobs.len(),
-                job = if self.failed_jobs.len() == 1 {
-                    "job"
-                } else {
-                    "jobs"
-                }
-            ),
-            failed_jobs_name_list =
-                self.failed_jobs
-                    .iter()
-                    .fold(String::new(), |mut s_out, job| {
-                        let _ = writeln!(s_out, "- **`{}`**", job.name);
-                        s_out
-                    })
-        );
-        let output_len = output_str.len();
-        let output_left_before_max = 65535 - output_len;
-        assert_ne!(self.failed_jobs.len(), 0);
-        let available_len_per_job = output_left_before_max / self.failed_jobs.len();
+        self.render(true)
+    }
+
+    /// Forget how the jobs were formatted, so they are formatted again with other settings
+    fn reset_job_markdown(&mut self) {
+        for job in &mut self.failed_jobs {
+            job.markdown_formatted = None;
+        }
+    }
+
+    fn overflow_comments(&mut self) -> Vec<String> {
+        let excerpt = self.excerpt;
+        self.shortened_jobs_mut()
+            .into_iter()
+            .map(|job| job.overflow_comment(excerpt))
+            .collect()
+    }
+
+    fn shortened_jobs_mut(&mut self) -> Vec<&mut FailedJob> {
+        // Format the jobs within the limits of the body, to find the ones that were shortened
+        self.render(true);
+        self.failed_jobs
+            .iter_mut()
+            .filter(|job| job.is_shortened())
+            .collect()
+    }
 
-        let mut failed_jobs_str = String::new();
-        for job in self.failed_jobs.as_mut_slice() {
-            failed_jobs_str.push_str(job.to_markdown_formatted_limit(available_len_per_job));
+    fn render(&mut self, include_sections: bool) -> String {
+        assert_ne!(self.failed_jobs.len(), 0);
+        let sections: &[(String, String)] = if include_sections {
+            &self.sections
+        } else {
+            &[]
+        };
+        // The heading, failed step and link of every job are always kept, only the error
+        // summaries are shrunk to share what is left
+        let preface_len = self
+            .template
+            .render_issue(&self.context(sections, vec![String::new(); self.failed_jobs.len()]))
+            .len();
+        let header_lens: Vec<usize> = self
+            .failed_jobs
+            .iter()
+            .map(|job| job.markdown_header_len(&self.template))
+            .collect();
+        let error_section_lens: Vec<usize> = self
+            .failed_jobs
+            .iter()
+            .zip(&header_lens)
+            .map(|(job, header_len)| job.markdown_len(&self.template) - header_len)
+            .collect();
+        let available_len = self
+            .limits
+            .max_len
+            .saturating_sub(preface_len + header_lens.iter().sum::<usize>());
+        let mut error_section_budgets = fair_shares(available_len, &error_section_lens);
+        if let Some(max_job_len) = self.limits.max_job_len {
+            for budget in &mut error_section_budgets {
+                *budget = (*budget).min(max_job_len);
+            }
         }
 
-        output_str.push_str(&failed_jobs_str);
+        let job_markdowns: Vec<String> = self
+            .failed_jobs
+            .iter_mut()
+            .zip(header_lens)
+            .zip(error_section_budgets)
+            .map(|((job, header_len), budget)| {
+                job.to_markdown_formatted_limit(header_len + budget, self.excerpt, &self.template)
+                    .to_owned()
+            })
+            .collect();
+        let mut output_str = self
+            .template
+            .render_issue(&self.context(sections, job_markdowns));
 
-        // Final check if it is too long, if it is still too long, we failed to format it properly within the max length
-        // to still create an issue we do a dumb truncate as a last out
-        if output_str.len() > 65535 {
-            let remove_content_len = 65535 - output_str.len();
+        // Only the case if the headings of the jobs alone exceed the max length, to still create
+        // an issue we do a dumb truncate as a last out
+        let max_len = self.limits.max_len;
+        if output_str.len() > max_len {
+            let remove_content_len = output_str.len() - max_len;
             log::warn!("Failed to properly format issue body within content max length, truncating {remove_content_len} characters from the end of the issue body to fit within issue content limits");
-            output_str.truncate(remove_content_len);
+            output_str.truncate(floor_char_boundary(&output_str, max_len));
         }
 
         output_str
     }
+
+    /// The context of the issue template, with the jobs as they were last formatted
+    fn context<'a>(
+        &'a self,
+        sections: &'a [(String, String)],
+        job_markdowns: Vec<String>,
+    ) -> IssueContext<'a> {
+        IssueContext {
+            run_id: &self.run_id,
+            run_url: &self.run_link,
+            failed_jobs: self
+                .failed_jobs
+                .iter()
+                .zip(job_markdowns)
+                .map(|(job, markdown)| JobContext {
+                    markdown,
+                    ..job.template_context(&job.error_section)
+                })
+                .collect(),
+            sections: sections
+                .iter()
+                .map(|(title, content)| SectionContext { title, content })
+                .collect(),
+            collapse_jobs: self.failed_jobs.len() >= COLLAPSE_JOBS_MIN,
+        }
+    }
+}
+
+/// Split `available` bytes over the error sections of the jobs, so a section shorter than an equal
+/// share is kept in full and what it leaves is split over the longer sections. A single huge log
+/// then only shrinks its own section.
+///
+/// # Example
+/// ```
+/// # use ci_manager::issue::fair_shares;
+/// assert_eq!(fair_shares(100, &[10, 500, 60]), [10, 45, 45]);
+/// assert_eq!(fair_shares(100, &[10, 20]), [10, 20]);
+/// ```
+pub fn fair_shares(available: usize, lens: &[usize]) -> Vec<usize> {
+    let mut by_len: Vec<usize> = (0..lens.len()).collect();
+    by_len.sort_by_key(|&idx| lens[idx]);
+    let mut shares = vec![0; lens.len()];
+    let mut left = available;
+    for (done, idx) in by_len.into_iter().enumerate() {
+        let share = lens[idx].min(left / (lens.len() - done));
+        shares[idx] = share;
+        left -= share;
+    }
+    shares
+}
+
+/// The largest index at most `idx` that is on a char boundary of `s`
+fn floor_char_boundary(s: &str, idx: usize) -> usize {
+    (0..=idx.min(s.len()))
+        .rev()
+        .find(|&idx| s.is_char_boundary(idx))
+        .unwrap_or(0)
 }
 
 #[derive(Debug, PartialEq)]
@@ -142,7 +547,31 @@ pub struct FailedJob {
     url: String,
     failed_step: FirstFailedStep,
     error_message: ErrorMessageSummary,
+    /// Name and labels of the runner, only shown if the failure was caused by the runner
+    runner_name: Option<String>,
+    runner_labels: Vec<String>,
+    /// Parameters of a matrix job, from the name of the job
+    matrix: Vec<MatrixParam>,
+    /// Errors reported with the `::error::` workflow command
+    error_annotations: Vec<Annotation>,
+    /// Link to the full error summary and log, shown instead of them if they are shortened
+    full_log_url: Option<String>,
     markdown_formatted: Option<String>,
+    /// What the error section of `markdown_formatted` contains
+    error_section: ErrorSection,
+}
+
+/// What the error section of a job contains in the issue body
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ErrorSection {
+    /// The error summary and the attached log
+    Full,
+    /// A link to the full error summary and log, as they do not fit
+    Link,
+    /// A shortened error summary, and the attached log if it fits
+    Shortened { summary: String, with_log: bool },
+    /// Neither the summary nor the log fit
+    Omitted,
 }
 
 impl FailedJob {
@@ -154,13 +583,110 @@ impl FailedJob {
         error_message: ErrorMessageSummary,
     ) -> Self {
         ensure_https_prefix(&mut url);
+        let matrix = matrix::matrix_params(&name, &HashMap::new());
         Self {
             name,
             id,
             url,
             failed_step,
             error_message,
+            runner_name: None,
+            runner_labels: Vec::new(),
+            matrix,
+            error_annotations: Vec::new(),
+            full_log_url: None,
             markdown_formatted: None,
+            error_section: ErrorSection::Full,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn failed_step(&self) -> &FirstFailedStep {
+        &self.failed_step
+    }
+
+    /// The parsed error summary
+    pub fn summary(&self) -> &str {
+        self.error_message.summary()
+    }
+
+    pub fn matrix(&self) -> &[MatrixParam] {
+        &self.matrix
+    }
+
+    /// Name the matrix parameters with the keys of the matrix of the job, see
+    /// [matrix::matrix_keys_of_workflow]
+    pub fn set_matrix_keys(&mut self, keys: &HashMap<String, Vec<String>>) {
+        self.matrix = matrix::matrix_params(&self.name, keys);
+        self.markdown_formatted = None;
+    }
+
+    pub fn with_runner_name(mut self, runner_name: Option<String>) -> Self {
+        self.runner_name = runner_name;
+        self
+    }
+
+    pub fn with_runner_labels(mut self, runner_labels: Vec<String>) -> Self {
+        self.runner_labels = runner_labels;
+        self
+    }
+
+    pub fn with_error_annotations(mut self, error_annotations: Vec<Annotation>) -> Self {
+        self.error_annotations = error_annotations;
+        self
+    }
+
+    /// The list of error annotations, as they are often a better summary than the parser's,
+    /// otherwise an empty string
+    fn error_annotations_list(&self) -> String {
+        if self.error_annotations.is_empty() {
+            return String::new();
+        }
+        let mut list = String::from("\n\n**Error annotations:**");
+        for annotation in &self.error_annotations {
+            let _ = write!(list, "\n- {annotation}");
+        }
+        list.push('\n');
+        list
+    }
+
+    /// The line naming the runner and its labels if the failure was caused by it, so it can be
+    /// fixed or cleaned up, otherwise an empty string
+    fn runner_line(&self) -> String {
+        if !self.error_message.is_runner_failure() {
+            return String::new();
+        }
+        let mut line = match self.runner_name.as_deref() {
+            Some(runner) => format!("\n\\\n**Runner:** `{runner}`"),
+            None if !self.runner_labels.is_empty() => "\n\\\n**Runner:** unknown".to_owned(),
+            None => return String::new(),
+        };
+        if !self.runner_labels.is_empty() {
+            let labels: Vec<String> = self
+                .runner_labels
+                .iter()
+                .map(|label| format!("`{label}`"))
+                .collect();
+            let _ = write!(line, " (labels: {})", labels.join(", "));
+        }
+        line
+    }
+
+    /// The recipe, version and task of a failed Yocto task as a table, otherwise an empty string
+    fn yocto_metadata_table(&self) -> String {
+        match &self.error_message {
+            ErrorMessageSummary::Yocto(err) => err
+                .metadata_table()
+                .map(|table| format!("\n\n{table}"))
+                .unwrap_or_default(),
+            _ => String::new(),
         }
     }
 
@@ -168,6 +694,47 @@ impl FailedJob {
         self.error_message.failure_label()
     }
 
+    /// Whether the job failed because of the CI infrastructure, e.g. a lost runner or the network
+    pub fn is_infra_failure(&self) -> bool {
+        self.error_message.is_infra_failure()
+    }
+
+    /// What identifies the failure of the job: the job, the failed step, what the parser made of
+    /// the error and its [normalized](normalize_error) summary
+    pub fn error_key(&self) -> String {
+        let mut key = format!("{name}\n{step}", name = self.name, step = self.failed_step);
+        if let Some(label) = self.failure_label() {
+            let _ = write!(key, "\n{label}");
+        }
+        if let ErrorMessageSummary::Yocto(err) = &self.error_message {
+            let recipe = err.base_recipe().unwrap_or_default();
+            let task = err.task().unwrap_or_default();
+            let _ = write!(key, "\n{recipe}:{task}");
+        }
+        let _ = write!(key, "\n{}", normalize_error(self.error_message.summary()));
+        key
+    }
+
+    /// The failed Yocto task whose failure log was not found, so it can be looked for elsewhere
+    pub fn yocto_error_without_logfile(&mut self) -> Option<&mut YoctoError> {
+        match &mut self.error_message {
+            ErrorMessageSummary::Yocto(err) if err.logfile().is_none() => {
+                // The formatting is outdated once a log file is attached
+                self.markdown_formatted = None;
+                Some(err)
+            }
+            _ => None,
+        }
+    }
+
+    /// The `recipe:<name>` label of a failed Yocto task
+    pub fn recipe_label(&self) -> Option<String> {
+        match &self.error_message {
+            ErrorMessageSummary::Yocto(err) => err.recipe_label(),
+            _ => None,
+        }
+    }
+
     pub fn markdown_formatted_len(&mut self) -> usize {
         if let Some(markdown_formatted_str) = self.markdown_formatted.as_deref() {
             markdown_formatted_str.len()
@@ -180,124 +747,226 @@ impl FailedJob {
     pub fn to_markdown_formatted(&mut self) -> &str {
         if self.markdown_formatted.is_none() {
             self.markdown_formatted = Some(self.to_string());
+            self.error_section = ErrorSection::Full;
         }
         self.markdown_formatted.as_deref().unwrap()
     }
 
-    pub fn to_markdown_formatted_limit(&mut self, max_len: usize) -> &str {
+    /// The markdown of the job with the error summary shrunk to fit in `max_len`. The heading,
+    /// failed step and link are always kept, even if they alone exceed `max_len`.
+    pub fn to_markdown_formatted_limit(
+        &mut self,
+        max_len: usize,
+        excerpt: Option<ContextExcerpt>,
+        template: &IssueTemplate,
+    ) -> &str {
         // If the formatting hasn't been done yet or it has been formatted resulting in a larger length than `max_len`, format it again to meet the max_len criteria.
-        if self.markdown_formatted.is_none()
-            || self
-                .markdown_formatted
-                .as_deref()
-                .is_some_and(|md| md.len() > max_len)
+        if self
+            .markdown_formatted
+            .as_deref()
+            .is_none_or(|md| md.len() > max_len)
         {
-            let summary = self.error_message.summary();
-            let optional_log = match (self.error_message.logfile_name(), self.error_message.log()) {
-                (Some(name), Some(contents)) => format!(
-                    "
-<details>
-<summary>{name}</summary>
-<br>
+            let (error_section, markdown) = self.fit_markdown(max_len, excerpt, template);
+            self.error_section = error_section;
+            self.markdown_formatted = Some(markdown);
+        }
 
-```
-{contents}
-```
+        self.markdown_formatted.as_deref().unwrap()
+    }
 
-</details>"
-                ),
-                _ => String::from(""),
+    /// Render the job with the error summary and log if they fit in `max_len`, otherwise with a
+    /// link to them, a shortened summary, a shortened summary without the log, or neither
+    fn fit_markdown(
+        &self,
+        max_len: usize,
+        excerpt: Option<ContextExcerpt>,
+        template: &IssueTemplate,
+    ) -> (ErrorSection, String) {
+        let render = |section: ErrorSection| {
+            let markdown = template.render_job(&self.template_context(&section));
+            (section, markdown)
+        };
+        let full = render(ErrorSection::Full);
+        if full.1.len() <= max_len || self.failed_step == FirstFailedStep::NoStepsExecuted {
+            return full;
+        }
+        if self.full_log_url.is_some() {
+            let link = render(ErrorSection::Link);
+            if link.1.len() <= max_len {
+                return link;
+            }
+        }
+        let summary = self.error_message.summary();
+        let has_log = self.error_message.log().is_some();
+        for with_log in [true, false] {
+            if with_log && !has_log {
+                continue;
+            }
+            let empty_summary_len = render(ErrorSection::Shortened {
+                summary: String::new(),
+                with_log,
+            })
+            .1
+            .len();
+            let Some(summary_max_len) = max_len.checked_sub(empty_summary_len) else {
+                continue;
             };
-            let mut formatted_preface_str: String = format!(
-                "
-### `{name}` (ID {id})
-**Step failed:** `{failed_step}`
-\\
-**Log:** {url}",
-                name = self.name,
-                id = self.id,
-                failed_step = self.failed_step,
-                url = self.url,
-            );
-
-            let orig_formatted_err_str = if self.failed_step == FirstFailedStep::NoStepsExecuted {
-                "".to_string()
-            } else {
-                // Only add the `Best effort error summary` text if steps were actually executed
-                formatted_preface_str.push_str(
-                    "
-\\
-*Best effort error summary*:",
+            if has_log && !with_log {
+                log::warn!(
+                    "Leaving out the log file of job {} to fit the issue",
+                    self.name
                 );
-                format!(
-                    "\n```\n{error_message}```{optional_log}",
-                    error_message = summary,
-                )
-            };
-            let preface_len = formatted_preface_str.len();
-            let formatted_err_str_len = orig_formatted_err_str.len();
-            let mkdown_len = preface_len + formatted_err_str_len;
-            if mkdown_len > max_len {
-                let len_diff = mkdown_len - max_len;
-                let target_formatted_err_str_len = orig_formatted_err_str.len() - len_diff;
-                let error_message = summary.to_string();
-                debug_assert!(error_message.len() >= len_diff);
-                let formatted_err_str = if error_message.len() >= len_diff {
-                    let (_, error_message) = error_message.split_at(len_diff);
-                    let formatted_err_str = format!("\n```\n{error_message}```{optional_log}",);
-                    debug_assert_eq!(formatted_err_str.len(), target_formatted_err_str_len);
-                    formatted_err_str
-                } else {
-                    // Removing the error message is not enough to reach the target max_len so instead we remove the error summary completely
-                    "(content > max len)".to_string()
-                };
-                formatted_preface_str.push_str(&formatted_err_str);
-            } else {
-                formatted_preface_str.push_str(&orig_formatted_err_str);
             }
-            let final_mkdown = formatted_preface_str;
-            self.markdown_formatted = Some(final_mkdown);
+            return render(ErrorSection::Shortened {
+                summary: shrink_summary(summary, summary_max_len, excerpt),
+                with_log,
+            });
         }
+        render(ErrorSection::Omitted)
+    }
 
-        self.markdown_formatted.as_deref().unwrap()
+    /// The context of the job template, with the error summary and log of `error_section`
+    fn template_context(&self, error_section: &ErrorSection) -> JobContext<'_> {
+        let yocto_error = match &self.error_message {
+            ErrorMessageSummary::Yocto(err) => Some(err),
+            _ => None,
+        };
+        let log = self
+            .error_message
+            .logfile_name()
+            .zip(self.error_message.log());
+        let (summary, log, full_log_url) = match error_section {
+            ErrorSection::Full => (Some(self.error_message.summary().to_owned()), log, None),
+            ErrorSection::Link => (None, None, self.full_log_url.as_deref()),
+            ErrorSection::Shortened { summary, with_log } => {
+                (Some(summary.clone()), log.filter(|_| *with_log), None)
+            }
+            ErrorSection::Omitted => (None, None, None),
+        };
+        JobContext {
+            name: &self.name,
+            id: &self.id,
+            url: &self.url,
+            failed_step: self.failed_step.to_string(),
+            steps_executed: self.failed_step != FirstFailedStep::NoStepsExecuted,
+            runner_name: self.runner_name.as_deref(),
+            runner_labels: &self.runner_labels,
+            runner_line: self.runner_line(),
+            matrix: &self.matrix,
+            error_annotations: self
+                .error_annotations
+                .iter()
+                .map(Annotation::to_string)
+                .collect(),
+            error_annotations_list: self.error_annotations_list(),
+            failure_label: self.failure_label(),
+            recipe: yocto_error.and_then(YoctoError::recipe),
+            version: yocto_error.and_then(YoctoError::version),
+            task: yocto_error.and_then(YoctoError::task),
+            yocto_metadata_table: self.yocto_metadata_table(),
+            summary,
+            log_name: log.map(|(name, _)| name),
+            log: log.map(|(_, contents)| contents),
+            full_log_url,
+            markdown: String::new(),
+        }
     }
-}
 
-impl Display for FailedJob {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+    /// The error summary followed by the attached log, if any
+    pub fn full_log(&self) -> String {
         let summary = self.error_message.summary();
+        match (self.error_message.logfile_name(), self.error_message.log()) {
+            (Some(name), Some(contents)) => format!("{summary}\n==== {name} ====\n{contents}"),
+            _ => summary.to_owned(),
+        }
+    }
+
+    /// Link to the full error summary and log instead of shortening them in the issue body
+    pub fn set_full_log_url(&mut self, url: String) {
+        self.full_log_url = Some(url);
+        // The formatting is outdated once the link is set
+        self.markdown_formatted = None;
+    }
+
+    /// Whether the error summary or log was shortened when the job was last formatted with
+    /// [FailedJob::to_markdown_formatted_limit]
+    pub fn is_shortened(&self) -> bool {
+        self.markdown_formatted.is_some() && self.error_section != ErrorSection::Full
+    }
+
+    /// A comment with the error summary and log of the job, shortened only if they exceed the
+    /// length of a comment
+    fn overflow_comment(&self, excerpt: Option<ContextExcerpt>) -> String {
+        let mut comment = format!(
+            "**Full error summary of `{name}` (ID {id})**, shortened in the issue body:\n```\n",
+            name = self.name,
+            id = self.id,
+        );
         let optional_log = match (self.error_message.logfile_name(), self.error_message.log()) {
             (Some(name), Some(contents)) => format!(
-                "
-<details>
-<summary>{name}</summary>
-<br>
-
-```
-{contents}
-```
-</details>"
+                "\n<details>\n<summary>{name}</summary>\n<br>\n\n```\n{contents}\n```\n\n</details>"
             ),
-            _ => String::from(""),
+            _ => String::new(),
+        };
+        let summary = self.error_message.summary();
+        let fixed_len = comment.len() + "```".len();
+        let optional_log = if fixed_len + summary.len() + optional_log.len() <= COMMENT_MAX_LEN {
+            optional_log
+        } else {
+            String::new()
+        };
+        let summary_max_len = COMMENT_MAX_LEN.saturating_sub(fixed_len + optional_log.len());
+        comment.push_str(&shrink_summary(summary, summary_max_len, excerpt));
+        comment.push_str("```");
+        comment.push_str(&optional_log);
+        comment
+    }
+
+    /// Length of the markdown of the job without the error summary and log, which are the only
+    /// parts that are shrunk
+    pub fn markdown_header_len(&self, template: &IssueTemplate) -> usize {
+        let section = match self.failed_step {
+            FirstFailedStep::NoStepsExecuted => ErrorSection::Full,
+            FirstFailedStep::StepName(_) => ErrorSection::Shortened {
+                summary: String::new(),
+                with_log: false,
+            },
         };
+        template.render_job(&self.template_context(&section)).len()
+    }
+
+    /// Length of the markdown of the job before shrinking
+    pub fn markdown_len(&self, template: &IssueTemplate) -> usize {
+        template
+            .render_job(&self.template_context(&ErrorSection::Full))
+            .len()
+    }
+}
 
+/// Shorten an error summary to at most `max_len` bytes, to the excerpt around the first error if
+/// given, otherwise to the end of the summary
+fn shrink_summary(summary: &str, max_len: usize, excerpt: Option<ContextExcerpt>) -> String {
+    if summary.len() <= max_len {
+        return summary.to_owned();
+    }
+    excerpt
+        .and_then(|excerpt| excerpt.excerpt(summary, max_len))
+        .unwrap_or_else(|| {
+            // Keep the end of the summary
+            let start = (summary.len() - max_len..=summary.len())
+                .find(|&idx| summary.is_char_boundary(idx))
+                .unwrap_or(summary.len());
+            summary[start..].to_owned()
+        })
+}
+
+impl Display for FailedJob {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let template = IssueTemplate::default();
         write!(
             f,
-            "
-### `{name}` (ID {id})
-**Step failed:** `{failed_step}`
-\\
-**Log:** {url}
-\\
-*Best effort error summary*:
-```
-{error_message}```{optional_log}",
-            name = self.name,
-            id = self.id,
-            failed_step = self.failed_step,
-            url = self.url,
-            error_message = summary,
-            optional_log = optional_log
+            "{}",
+            template.render_job(&self.template_context(&ErrorSection::Full))
         )
     }
 }
@@ -397,4 +1066,343 @@ Yocto error: ERROR: No recipes available for: ...
         assert_eq!(issue_body.to_markdown_string(), EXAMPLE_ISSUE_BODY);
         //std::fs::write("test2.md", issue_body.to_markdown_string()).unwrap();
     }
+
+    #[test]
+    fn test_failed_job_names_runner_of_runner_failure() {
+        let new_job = |error_message| {
+            FailedJob::new(
+                "Build".to_string(),
+                "1".to_string(),
+                "https://github.com/luftkode/ci-manager/actions/runs/1/job/1".to_string(),
+                FirstFailedStep::StepName("Build".to_owned()),
+                error_message,
+            )
+            .with_runner_name(Some("yocto-runner-3".to_owned()))
+            .with_runner_labels(vec!["self-hosted".to_owned(), "yocto".to_owned()])
+        };
+        let disk_full = crate::err_parse::resource::parse_disk_full_error(
+            "OSError: [Errno 28] No space left on device",
+        )
+        .unwrap();
+
+        let job = new_job(ErrorMessageSummary::Parsed(disk_full));
+        assert!(job
+            .to_string()
+            .contains("**Step failed:** `Build`\n\\\n**Runner:** `yocto-runner-3` (labels: `self-hosted`, `yocto`)\n"));
+        let job = new_job(ErrorMessageSummary::Other("error: expected ';'".to_owned()));
+        assert!(!job.to_string().contains("**Runner:**"));
+    }
+
+    #[test]
+    fn test_failed_job_lists_error_annotations() {
+        let job = FailedJob::new(
+            "Build".to_string(),
+            "1".to_string(),
+            "https://github.com/luftkode/ci-manager/actions/runs/1/job/1".to_string(),
+            FirstFailedStep::StepName("Build".to_owned()),
+            ErrorMessageSummary::Other("error: expected ';'\n".to_owned()),
+        )
+        .with_error_annotations(crate::err_parse::annotations::error_annotations(
+            "::error file=src/main.c,line=3::expected ';'",
+        ));
+
+        assert!(job.to_string().contains(
+            "**Log:** https://github.com/luftkode/ci-manager/actions/runs/1/job/1\n\
+            \n\
+            **Error annotations:**\n\
+            - `src/main.c:3`: expected ';'\n\
+            \n\
+            \\\n\
+            *Best effort error summary*:"
+        ));
+    }
+
+    #[test]
+    fn test_failed_job_lists_matrix_params() {
+        let mut job = FailedJob::new(
+            "build (arm64, debug)".to_string(),
+            "1".to_string(),
+            "https://github.com/luftkode/ci-manager/actions/runs/1/job/1".to_string(),
+            FirstFailedStep::StepName("Build".to_owned()),
+            ErrorMessageSummary::Other("error: linking failed".to_owned()),
+        );
+        assert!(job.to_string().contains("**Matrix:** `arm64`, `debug`\n"));
+
+        let keys = HashMap::from([(
+            "build".to_owned(),
+            vec!["arch".to_owned(), "profile".to_owned()],
+        )]);
+        job.set_matrix_keys(&keys);
+        assert!(job
+            .to_string()
+            .contains("**Matrix:** `arch: arm64`, `profile: debug`\n"));
+    }
+
+    #[test]
+    fn test_issue_with_recipe_labels() {
+        use crate::err_parse::yocto::{
+            util::{failed_tasks, YoctoFailureKind},
+            YoctoError,
+        };
+
+        let log = "ERROR: Logfile of failure stored in: /build/tmp/work/x86_64-linux/sqlite3-native/3.43.2/temp/log.do_fetch.21616";
+        let yocto_error = YoctoError::new(
+            "ERROR: sqlite3-native-3_3.43.2-r0 do_fetch: Fetcher failure\n".to_owned(),
+            YoctoFailureKind::DoFetch,
+            None,
+        )
+        .with_failed_task(&failed_tasks(log)[0]);
+        let failed_job = FailedJob::new(
+            "Test template xilinx".to_string(),
+            "1".to_string(),
+            "https://github.com/luftkode/ci-manager/actions/runs/1/job/1".to_string(),
+            FirstFailedStep::StepName("📦 Build yocto image".to_owned()),
+            ErrorMessageSummary::Yocto(yocto_error),
+        );
+        assert!(failed_job.to_string().contains(
+            "**Log:** https://github.com/luftkode/ci-manager/actions/runs/1/job/1\n\
+            \n\
+            | Recipe | Version | Task |\n\
+            |--------|---------|------|\n\
+            | `sqlite3-native` | `3.43.2` | `do_fetch` |\n\
+            \n\
+            \\\n\
+            *Best effort error summary*:"
+        ));
+
+        let mut issue = Issue::new(
+            "Scheduled run failed".to_string(),
+            "1".to_string(),
+            "https://github.com/luftkode/ci-manager/actions/runs/1".to_string(),
+            vec![failed_job],
+            "CI".to_string(),
+        );
+        issue.add_recipe_labels();
+        assert_eq!(issue.labels, ["CI", "do_fetch", "recipe:sqlite3"]);
+    }
+
+    #[test]
+    fn test_issue_body_keeps_every_job_when_one_log_is_huge() {
+        let huge_log: String = (0..20_000).map(|i| format!("line {i}\n")).collect();
+        let failed_job = |name: &str, summary: String| {
+            FailedJob::new(
+                name.to_string(),
+                "1".to_string(),
+                "https://github.com/luftkode/ci-manager/actions/runs/1/job/1".to_string(),
+                FirstFailedStep::StepName("Build".to_owned()),
+                ErrorMessageSummary::Other(summary),
+            )
+        };
+        let mut issue_body = IssueBody::new(
+            "1".to_string(),
+            "https://github.com/luftkode/ci-manager/actions/runs/1".to_string(),
+            vec![
+                failed_job("huge", huge_log.clone()),
+                failed_job("small", "error: expected ';'\n".to_string()),
+                failed_job("huge too", huge_log),
+            ],
+        );
+
+        let body = issue_body.to_markdown_string();
+        assert!(body.len() <= ISSUE_BODY_MAX_LEN);
+        for name in ["huge", "small", "huge too"] {
+            assert!(body.contains(&format!("### `{name}` (ID 1)\n**Step failed:** `Build`")));
+        }
+        assert!(body.contains("```\nerror: expected ';'\n```"));
+        assert_eq!(body.matches("line 19999\n```").count(), 2);
+    }
+
+    #[test]
+    fn test_issue_body_within_custom_limits() {
+        let long_log: String = (0..1000).map(|i| format!("line {i}\n")).collect();
+        let mut issue_body = IssueBody::new(
+            "1".to_string(),
+            "https://github.com/luftkode/ci-manager/actions/runs/1".to_string(),
+            vec![FailedJob::new(
+                "long".to_string(),
+                "1".to_string(),
+                "https://github.com/luftkode/ci-manager/actions/runs/1/job/1".to_string(),
+                FirstFailedStep::StepName("Build".to_owned()),
+                ErrorMessageSummary::Other(long_log),
+            )],
+        );
+        assert!(issue_body.to_markdown_string().contains("line 0\n"));
+
+        issue_body.limits = BodyLimits {
+            max_len: BodyLimits::JIRA.max_len,
+            max_job_len: Some(500),
+        };
+        issue_body.reset_job_markdown();
+        let body = issue_body.to_markdown_string();
+        assert!(!body.contains("line 0\n"));
+        assert!(body.contains("line 999\n```"));
+        assert!(body.len() < 1000);
+    }
+
+    #[test]
+    fn test_issue_body_collapses_jobs_but_the_first() {
+        let failed_jobs = (1..=4)
+            .map(|i| {
+                FailedJob::new(
+                    format!("build {i}"),
+                    i.to_string(),
+                    format!("https://github.com/luftkode/ci-manager/actions/runs/1/job/{i}"),
+                    FirstFailedStep::StepName("Build".to_owned()),
+                    ErrorMessageSummary::Other("error: expected ';'\n".to_string()),
+                )
+            })
+            .collect();
+        let mut issue_body = IssueBody::new(
+            "1".to_string(),
+            "https://github.com/luftkode/ci-manager/actions/runs/1".to_string(),
+            failed_jobs,
+        );
+
+        let body = issue_body.to_markdown_string();
+        assert!(body.contains(
+            "<details open>\n<summary><code>build 1</code>: Build</summary>\n\n### `build 1` (ID 1)"
+        ));
+        assert_eq!(body.matches("<details>\n<summary><code>build").count(), 3);
+        assert_eq!(body.matches("\n\n</details>").count(), 4);
+    }
+
+    #[test]
+    fn test_overflow_comments_of_shortened_jobs() {
+        let huge_log: String = (0..20_000).map(|i| format!("line {i}\n")).collect();
+        let failed_job = |name: &str, summary: String| {
+            FailedJob::new(
+                name.to_string(),
+                "1".to_string(),
+                "https://github.com/luftkode/ci-manager/actions/runs/1/job/1".to_string(),
+                FirstFailedStep::StepName("Build".to_owned()),
+                ErrorMessageSummary::Other(summary),
+            )
+        };
+        let mut issue = Issue::new(
+            "Scheduled run failed".to_string(),
+            "1".to_string(),
+            "https://github.com/luftkode/ci-manager/actions/runs/1".to_string(),
+            vec![
+                failed_job("huge", huge_log),
+                failed_job("small", "error: expected ';'\n".to_string()),
+            ],
+            "CI".to_string(),
+        );
+
+        let comments = issue.overflow_comments();
+        assert_eq!(comments.len(), 1);
+        assert!(comments[0].starts_with(
+            "**Full error summary of `huge` (ID 1)**, shortened in the issue body:\n```\n"
+        ));
+        assert!(comments[0].len() <= COMMENT_MAX_LEN);
+        assert!(comments[0].ends_with("line 19999\n```"));
+    }
+
+    #[test]
+    fn test_link_to_full_log_of_shortened_job() {
+        let huge_log: String = (0..20_000).map(|i| format!("line {i}\n")).collect();
+        let mut issue = Issue::new(
+            "Scheduled run failed".to_string(),
+            "1".to_string(),
+            "https://github.com/luftkode/ci-manager/actions/runs/1".to_string(),
+            vec![FailedJob::new(
+                "huge".to_string(),
+                "1".to_string(),
+                "https://github.com/luftkode/ci-manager/actions/runs/1/job/1".to_string(),
+                FirstFailedStep::StepName("Build".to_owned()),
+                ErrorMessageSummary::Other(huge_log),
+            )],
+            "CI".to_string(),
+        );
+
+        for job in issue.shortened_jobs_mut() {
+            job.set_full_log_url("https://gist.github.com/octocat/aa5a315d61ae9438b18d".to_owned());
+        }
+        let body = issue.body();
+        assert!(body.contains(
+            "*Best effort error summary*:\n\
+            Too long for the issue, see the full summary and log: https://gist.github.com/octocat/aa5a315d61ae9438b18d"
+        ));
+        assert!(!body.contains("line 19999"));
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_run_details() {
+        let issue = |run_id: &str, job_id: &str, summary: &str| {
+            let run_url = format!("https://github.com/luftkode/ci-manager/actions/runs/{run_id}");
+            Issue::new(
+                "Scheduled run failed".to_string(),
+                run_id.to_string(),
+                run_url.clone(),
+                vec![FailedJob::new(
+                    "Build".to_string(),
+                    job_id.to_string(),
+                    format!("{run_url}/job/{job_id}"),
+                    FirstFailedStep::StepName("Compile".to_owned()),
+                    ErrorMessageSummary::Other(summary.to_owned()),
+                )],
+                "CI".to_string(),
+            )
+        };
+        let first = issue(
+            "7858139663",
+            "21442749267",
+            "/home/runner/work/app/app/src/main.c:12: error: `x` undeclared",
+        );
+        let mut second = issue(
+            "7945016152",
+            "21442749166",
+            "/srv/ci/_work/app/src/main.c:12: error: `x` undeclared",
+        );
+        let other = issue(
+            "7945016152",
+            "21442749166",
+            "/srv/ci/_work/app/src/main.c:14: error: `y` undeclared",
+        );
+
+        assert_eq!(first.fingerprint(), second.fingerprint());
+        assert_ne!(first.fingerprint(), other.fingerprint());
+        // The fingerprint is stored in the body for later runs to compare against
+        second.set_created_by("create-issue-from-run", "other");
+        assert_eq!(
+            fingerprint_from_issue_body(&second.body()),
+            Some(first.fingerprint())
+        );
+        assert_eq!(first.applied_normalizations(), ["paths"]);
+    }
+
+    #[test]
+    fn test_remove_failed_jobs_tracked_elsewhere() {
+        let job = |name: &str, summary: &str| {
+            FailedJob::new(
+                name.to_string(),
+                "2".to_string(),
+                "https://github.com/luftkode/ci-manager/actions/runs/1/job/2".to_string(),
+                FirstFailedStep::StepName("Build".to_owned()),
+                ErrorMessageSummary::Other(summary.to_owned()),
+            )
+        };
+        let mut issue = Issue::new(
+            "Scheduled run failed".to_string(),
+            "1".to_string(),
+            "https://github.com/luftkode/ci-manager/actions/runs/1".to_string(),
+            vec![job("old", "known failure"), job("new", "new failure")],
+            "CI".to_string(),
+        );
+        issue.set_created_by("create-issue-from-run", "other");
+        let job_fingerprints = issue.job_fingerprints();
+        let metadata = footer::IssueMetadata::from_issue_body(&issue.body()).unwrap();
+        assert_eq!(metadata.job_fingerprints, job_fingerprints);
+
+        let removed = issue.remove_failed_jobs(&job_fingerprints[..1]);
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].name(), "old");
+        assert_eq!(issue.failed_jobs().len(), 1);
+        assert_eq!(issue.failed_jobs()[0].name(), "new");
+
+        // The last job is kept
+        let removed = issue.remove_failed_jobs(&job_fingerprints);
+        assert!(removed.is_empty());
+        assert_eq!(issue.failed_jobs()[0].name(), "new");
+    }
 }
@@ -0,0 +1,124 @@
+//! Excerpts of error summaries that are too long for an issue, keeping the lines around the first
+//! error and the last lines of the log instead of cutting the summary at an arbitrary offset
+use crate::{Lazy, Regex};
+use std::fmt::Write;
+
+/// Number of lines kept of an error summary that is too long for the issue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContextExcerpt {
+    /// Lines before the first error line
+    pub before: usize,
+    /// Lines after the first error line
+    pub after: usize,
+    /// Last lines of the log
+    pub tail: usize,
+}
+
+impl ContextExcerpt {
+    /// The lines around the first error line and the last lines of `log` with the omitted lines
+    /// marked, shrunk until it is at most `max_len` bytes. `None` if there is no error line or the
+    /// excerpt does not fit.
+    ///
+    /// # Example
+    /// ```
+    /// # use ci_manager::issue::excerpt::ContextExcerpt;
+    /// let log = "configure\nmake\ncc -c main.c\nmain.c:3: error: expected ';'\nmake: *** Error 1\nclean up\n\
+    ///     upload cache\nsummary\ndone\n";
+    /// let excerpt = ContextExcerpt { before: 1, after: 1, tail: 2 };
+    ///
+    /// assert_eq!(
+    ///     excerpt.excerpt(log, 1000).unwrap(),
+    ///     "[... 2 lines omitted ...]\n\
+    ///     cc -c main.c\n\
+    ///     main.c:3: error: expected ';'\n\
+    ///     make: *** Error 1\n\
+    ///     [... 2 lines omitted ...]\n\
+    ///     summary\n\
+    ///     done\n"
+    /// );
+    /// ```
+    pub fn excerpt(&self, log: &str, max_len: usize) -> Option<String> {
+        let lines: Vec<&str> = log.lines().collect();
+        let error_idx = first_error_line(&lines)?;
+        let mut context = *self;
+        loop {
+            let excerpt = context.excerpt_of_lines(&lines, error_idx);
+            if excerpt.len() <= max_len {
+                return Some(excerpt);
+            }
+            // Shrink the least important part first: the tail, then what follows the error, then
+            // what precedes it
+            if context.tail > 0 {
+                context.tail /= 2;
+            } else if context.after > 0 {
+                context.after /= 2;
+            } else if context.before > 0 {
+                context.before /= 2;
+            } else {
+                return None;
+            }
+        }
+    }
+
+    fn excerpt_of_lines(&self, lines: &[&str], error_idx: usize) -> String {
+        let window_start = error_idx.saturating_sub(self.before);
+        let window_end = (error_idx + self.after + 1).min(lines.len());
+        let tail_start = lines.len().saturating_sub(self.tail).max(window_end);
+
+        let mut excerpt = String::new();
+        push_omitted(&mut excerpt, window_start);
+        push_lines(&mut excerpt, &lines[window_start..window_end]);
+        push_omitted(&mut excerpt, tail_start - window_end);
+        push_lines(&mut excerpt, &lines[tail_start..]);
+        excerpt
+    }
+}
+
+/// Index of the first line reporting an error
+pub(crate) fn first_error_line(lines: &[&str]) -> Option<usize> {
+    static ERROR_RE: Lazy<Regex> = Lazy::new(|| {
+        Regex::new(r"(?i)\berror\b|\bfatal\b|\bfailed\b|\bfailure\b|panicked at|\bexception\b")
+            .unwrap()
+    });
+    lines.iter().position(|line| ERROR_RE.is_match(line))
+}
+
+fn push_omitted(excerpt: &mut String, count: usize) {
+    if count > 0 {
+        let _ = writeln!(excerpt, "[... {count} lines omitted ...]");
+    }
+}
+
+fn push_lines(excerpt: &mut String, lines: &[&str]) {
+    for line in lines {
+        excerpt.push_str(line);
+        excerpt.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_excerpt_shrinks_to_fit() {
+        let mut log: String = (0..100).map(|i| format!("line {i}\n")).collect();
+        log.insert_str(0, "ERROR: first\n");
+        let excerpt = ContextExcerpt {
+            before: 5,
+            after: 2,
+            tail: 50,
+        };
+
+        assert_eq!(
+            excerpt.excerpt(&log, 70).unwrap(),
+            "ERROR: first\n\
+            line 0\n\
+            line 1\n\
+            [... 97 lines omitted ...]\n\
+            line 99\n"
+        );
+        assert_eq!(excerpt.excerpt("nothing wrong here\n", 70), None);
+    }
+}
@@ -0,0 +1,75 @@
+//! Footer of created issues saying what created them, with the details in a hidden HTML comment so
+//! later subcommands can find and interpret the issues, whatever template the body was rendered with
+use crate::{commands::BodyFormat, *};
+
+/// Details of how an issue was created, stored as JSON in a hidden comment in the footer of the issue
+///
+/// # Example
+/// ```
+/// # use ci_manager::issue::footer::IssueMetadata;
+/// # use ci_manager::config::commands::BodyFormat;
+/// let metadata = IssueMetadata {
+///     version: "0.5.0".to_owned(),
+///     command: "create-issue-from-run".to_owned(),
+///     kind: "yocto".to_owned(),
+///     run_id: 7858139663,
+///     fingerprint: "d0c5e0ac7e8c2a1b".to_owned(),
+///     job_fingerprints: Vec::new(),
+/// };
+/// let body = format!("**Run ID**: 7858139663\n\n{}", metadata.footer(BodyFormat::Markdown));
+/// assert!(body.ends_with(
+///     "<!-- ci-manager metadata {\"version\":\"0.5.0\",\"command\":\"create-issue-from-run\",\
+///     \"kind\":\"yocto\",\"run_id\":7858139663,\"fingerprint\":\"d0c5e0ac7e8c2a1b\"} -->"
+/// ));
+/// assert_eq!(IssueMetadata::from_issue_body(&body), Some(metadata));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IssueMetadata {
+    /// Version of ci-manager
+    pub version: String,
+    /// The subcommand that created the issue, e.g. `create-issue-from-run` or `serve`
+    pub command: String,
+    /// The kind of workflow the logs were parsed as
+    pub kind: String,
+    pub run_id: u64,
+    /// Fingerprint of the failure, see [Issue::fingerprint](super::Issue::fingerprint)
+    pub fingerprint: String,
+    /// Fingerprints of the failed jobs, see [Issue::job_fingerprints](super::Issue::job_fingerprints)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub job_fingerprints: Vec<String>,
+}
+
+static METADATA_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"<!-- ci-manager metadata (?<JSON>\{.*?\}) -->").unwrap());
+
+impl IssueMetadata {
+    /// Parse the metadata from an issue body, `None` if the body has none
+    pub fn from_issue_body(body: &str) -> Option<Self> {
+        let captures = METADATA_RE.captures(body)?;
+        serde_json::from_str(captures.name("JSON")?.as_str()).ok()
+    }
+
+    /// The footer of the issue, a line saying what created it and the hidden metadata. The
+    /// metadata is kept in a plain text body too, as later subcommands depend on it.
+    pub fn footer(&self, format: BodyFormat) -> String {
+        let json = serde_json::to_string(self).expect("the metadata serializes to JSON");
+        let (version, command) = (&self.version, &self.command);
+        match format {
+            BodyFormat::Markdown => format!(
+                "---\n\
+                <sub>Created by [ci-manager](https://github.com/luftkode/ci-manager) {version} with `{command}`</sub>\n\
+                <!-- ci-manager metadata {json} -->"
+            ),
+            BodyFormat::Plain => format!(
+                "-- Created by ci-manager {version} with {command}\n\
+                <!-- ci-manager metadata {json} -->"
+            ),
+            // Jira has no comments, the metadata is left as it is in a `noformat` block instead
+            BodyFormat::Jira => format!(
+                "----\n\
+                _Created by [ci-manager|https://github.com/luftkode/ci-manager] {version} with {command}_\n\
+                {{noformat}}<!-- ci-manager metadata {json} -->{{noformat}}"
+            ),
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! JUnit XML report of the failed jobs of a run, for dashboards that already ingest JUnit reports
+//! (e.g. Jenkins or GitLab), see `summarize-run --output junit`
+use super::FailedJob;
+use std::fmt::Write;
+
+/// A JUnit XML report with a test suite for the run and a failed test case for the failed step of
+/// every failed job. The first line of the parsed error summary is the message of the failure, the
+/// failure label its type, and the full summary and log its text.
+///
+/// # Example
+/// ```
+/// # use ci_manager::{err_parse::ErrorMessageSummary, issue::{junit::junit_xml, FailedJob, FirstFailedStep}};
+/// let job = FailedJob::new(
+///     "Build".to_owned(),
+///     "2".to_owned(),
+///     "https://github.com/luftkode/ci-manager/actions/runs/1/job/2".to_owned(),
+///     FirstFailedStep::StepName("cargo build".to_owned()),
+///     ErrorMessageSummary::Other("error[E0308]: mismatched types".to_owned()),
+/// );
+/// let xml = junit_xml("1", "https://github.com/luftkode/ci-manager/actions/runs/1", &[job]);
+/// assert!(xml.contains(r#"<testcase classname="Build" name="cargo build">"#));
+/// assert!(xml.contains(r#"<failure message="error[E0308]: mismatched types" type="error">"#));
+/// ```
+pub fn junit_xml(run_id: &str, run_url: &str, failed_jobs: &[FailedJob]) -> String {
+    let tests = failed_jobs.len();
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        xml,
+        r#"<testsuites name="ci-manager" tests="{tests}" failures="{tests}">"#
+    );
+    let _ = writeln!(
+        xml,
+        r#"  <testsuite name="Run {run_id}" tests="{tests}" failures="{tests}">"#,
+        run_id = escape(run_id)
+    );
+    let _ = writeln!(xml, "    <properties>");
+    let _ = writeln!(
+        xml,
+        r#"      <property name="run_url" value="{}"/>"#,
+        escape(run_url)
+    );
+    let _ = writeln!(xml, "    </properties>");
+    for job in failed_jobs {
+        let message = job
+            .summary()
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .unwrap_or_default();
+        let kind = job.failure_label().unwrap_or_else(|| "error".to_owned());
+        let _ = writeln!(
+            xml,
+            r#"    <testcase classname="{job}" name="{step}">"#,
+            job = escape(job.name()),
+            step = escape(&job.failed_step().to_string())
+        );
+        let _ = writeln!(
+            xml,
+            r#"      <failure message="{message}" type="{kind}">{url}&#10;&#10;{log}</failure>"#,
+            message = escape(message),
+            kind = escape(&kind),
+            url = escape(job.url()),
+            log = escape(&job.full_log())
+        );
+        let _ = writeln!(xml, "    </testcase>");
+    }
+    let _ = writeln!(xml, "  </testsuite>");
+    let _ = writeln!(xml, "</testsuites>");
+    xml
+}
+
+/// Escape text for XML attributes and text, and drop the characters XML 1.0 doesn't allow, e.g.
+/// the escape character of the ANSI codes in logs
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c < ' ' || c == '\u{FFFE}' || c == '\u{FFFF}' => (),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("\u{1b}[31merror\u{1b}[0m: expected `<T>` & \"U\""),
+            "[31merror[0m: expected `&lt;T&gt;` &amp; &quot;U&quot;"
+        );
+    }
+}
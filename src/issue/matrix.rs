@@ -0,0 +1,117 @@
+//! Matrix parameters of jobs, which GitHub only puts in the name of a job, e.g. `build (arm64, debug)`
+use crate::*;
+use std::collections::HashMap;
+
+/// A parameter of a matrix job. The key is only known if the workflow file was found, see
+/// [matrix_keys_of_workflow].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MatrixParam {
+    pub key: Option<String>,
+    pub value: String,
+}
+
+/// Split the name of a matrix job in the name of the job and the values of its matrix parameters
+///
+/// # Example
+/// ```
+/// # use ci_manager::issue::matrix::matrix_values;
+/// assert_eq!(matrix_values("build (arm64, debug)"), Some(("build", vec!["arm64", "debug"])));
+/// assert_eq!(matrix_values("Test template xilinx"), None);
+/// ```
+pub fn matrix_values(job_name: &str) -> Option<(&str, Vec<&str>)> {
+    let (name, values) = job_name.strip_suffix(')')?.rsplit_once(" (")?;
+    if values.is_empty() {
+        return None;
+    }
+    Some((name, values.split(", ").collect()))
+}
+
+/// The matrix parameters of a job by the name of the job, with their key if it is in `keys`
+pub(crate) fn matrix_params(
+    job_name: &str,
+    keys: &HashMap<String, Vec<String>>,
+) -> Vec<MatrixParam> {
+    let Some((name, values)) = matrix_values(job_name) else {
+        return Vec::new();
+    };
+    // The keys only apply if there is a key for every value, otherwise the name of the job just
+    // ends with something in parentheses
+    let keys = keys.get(name).filter(|keys| keys.len() == values.len());
+    values
+        .into_iter()
+        .enumerate()
+        .map(|(i, value)| MatrixParam {
+            key: keys.map(|keys| keys[i].clone()),
+            value: value.to_owned(),
+        })
+        .collect()
+}
+
+/// The keys of the matrix of every job in a workflow file by the name of the job, in the order
+/// GitHub lists their values in the names of the jobs
+///
+/// # Example
+/// ```
+/// # use ci_manager::issue::matrix::matrix_keys_of_workflow;
+/// let workflow = r#"
+/// jobs:
+///   build:
+///     strategy:
+///       matrix:
+///         arch: [x86_64, arm64]
+///         profile: [debug, release]
+///         exclude:
+///           - arch: arm64
+///             profile: debug
+///   lint:
+///     name: Lint
+///     runs-on: ubuntu-latest
+/// "#;
+/// let keys = matrix_keys_of_workflow(workflow).unwrap();
+/// assert_eq!(keys["build"], ["arch", "profile"]);
+/// assert!(!keys.contains_key("Lint"));
+/// ```
+pub fn matrix_keys_of_workflow(workflow: &str) -> Result<HashMap<String, Vec<String>>> {
+    let workflow: serde_norway::Value = serde_norway::from_str(workflow)?;
+    let Some(jobs) = workflow.get("jobs").and_then(|jobs| jobs.as_mapping()) else {
+        bail!("The workflow has no jobs");
+    };
+    let mut keys_by_job = HashMap::new();
+    for (id, job) in jobs {
+        let Some(matrix) = job
+            .get("strategy")
+            .and_then(|strategy| strategy.get("matrix"))
+            .and_then(|matrix| matrix.as_mapping())
+        else {
+            continue;
+        };
+        let mut keys: Vec<String> = matrix
+            .keys()
+            .filter_map(|key| key.as_str())
+            .filter(|key| !matches!(*key, "include" | "exclude"))
+            .map(str::to_owned)
+            .collect();
+        // Keys only added by `include` follow the keys of the matrix
+        let included = matrix
+            .get("include")
+            .and_then(|include| include.as_sequence());
+        for combination in included.into_iter().flatten() {
+            let Some(combination) = combination.as_mapping() else {
+                continue;
+            };
+            for key in combination.keys().filter_map(|key| key.as_str()) {
+                if !keys.iter().any(|k| k == key) {
+                    keys.push(key.to_owned());
+                }
+            }
+        }
+        let name = job
+            .get("name")
+            .and_then(|name| name.as_str())
+            .or_else(|| id.as_str());
+        if let Some(name) = name {
+            keys_by_job.insert(name.to_owned(), keys);
+        }
+    }
+    Ok(keys_by_job)
+}
@@ -0,0 +1,124 @@
+//! Tracking of how many times the same failure has been seen.
+//!
+//! The occurrence count is stored in the issue body as a hidden HTML comment, together with the
+//! fingerprint of the failure, so it survives without any external state.
+use crate::*;
+
+/// Label added to an issue once the failure has been seen at least `--recurring-threshold` times.
+pub const RECURRING_LABEL: &str = "recurring";
+
+/// Hidden marker in an issue body that records the fingerprint of the failure and how many times it has occurred.
+///
+/// # Example
+/// ```
+/// # use ci_manager::issue::occurrence::OccurrenceMarker;
+/// # use pretty_assertions::assert_eq;
+/// let mut marker = OccurrenceMarker::new("d0c5e0ac7e8c2a1b".to_string());
+/// assert_eq!(
+///     marker.to_string(),
+///     "<!-- ci-manager occurrence fingerprint=d0c5e0ac7e8c2a1b count=1 -->"
+/// );
+///
+/// let body = format!("Some issue body\n\n{marker}");
+/// marker.increment();
+/// let updated_body = marker.update_issue_body(&body);
+/// assert_eq!(
+///     updated_body,
+///     "Some issue body\n\n<!-- ci-manager occurrence fingerprint=d0c5e0ac7e8c2a1b count=2 -->"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OccurrenceMarker {
+    fingerprint: String,
+    count: u32,
+}
+
+static MARKER_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"<!-- ci-manager occurrence fingerprint=(?<FINGERPRINT>[0-9a-f]+) count=(?<COUNT>[0-9]+) -->")
+        .unwrap()
+});
+
+impl OccurrenceMarker {
+    /// Create a marker for the first occurrence of a failure
+    pub fn new(fingerprint: String) -> Self {
+        Self {
+            fingerprint,
+            count: 1,
+        }
+    }
+
+    /// Parse the marker from an issue body, returns `None` if the body has no marker
+    pub fn from_issue_body(body: &str) -> Option<Self> {
+        let captures = MARKER_RE.captures(body)?;
+        let fingerprint = captures.name("FINGERPRINT")?.as_str().to_owned();
+        let count = captures.name("COUNT")?.as_str().parse().ok()?;
+        Some(Self { fingerprint, count })
+    }
+
+    pub fn fingerprint(&self) -> &str {
+        &self.fingerprint
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// Register another occurrence of the failure
+    pub fn increment(&mut self) {
+        self.count = self.count.saturating_add(1);
+    }
+
+    /// Replace the marker in the issue body with this marker, or append it if the body has no marker
+    pub fn update_issue_body(&self, body: &str) -> String {
+        if MARKER_RE.is_match(body) {
+            MARKER_RE.replace(body, self.to_string()).into_owned()
+        } else {
+            format!("{body}\n\n{self}")
+        }
+    }
+}
+
+impl fmt::Display for OccurrenceMarker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "<!-- ci-manager occurrence fingerprint={fingerprint} count={count} -->",
+            fingerprint = self.fingerprint,
+            count = self.count
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_marker_roundtrip() {
+        let marker = OccurrenceMarker {
+            fingerprint: "00ff00ff00ff00ff".to_string(),
+            count: 42,
+        };
+        let body = format!("**Run ID**: 7858139663\n\n{marker}");
+        assert_eq!(OccurrenceMarker::from_issue_body(&body), Some(marker));
+    }
+
+    #[test]
+    fn test_no_marker_in_body() {
+        assert_eq!(
+            OccurrenceMarker::from_issue_body("**Run ID**: 7858139663"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_update_issue_body_without_marker_appends() {
+        let marker = OccurrenceMarker::new("abc123".to_string());
+        let updated = marker.update_issue_body("Old issue body");
+        assert_eq!(
+            updated,
+            "Old issue body\n\n<!-- ci-manager occurrence fingerprint=abc123 count=1 -->"
+        );
+    }
+}
@@ -1,7 +1,250 @@
 use crate::*;
+use std::collections::{HashMap, HashSet};
 
 /// The maximum Levenshtein distance for issues to be considered similar.
 pub const LEVENSHTEIN_THRESHOLD: usize = 100;
+/// The maximum Jaccard distance (the share of tokens not in both) for issues to be considered similar.
+pub const JACCARD_THRESHOLD: f64 = 0.1;
+/// The maximum cosine distance of the token counts for issues to be considered similar.
+pub const COSINE_THRESHOLD: f64 = 0.05;
+
+/// How the distance between issue bodies is measured
+#[derive(ValueEnum, Display, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SimilarityAlgo {
+    /// Edit distance in characters, precise but slow and sensitive to the length of the bodies
+    #[default]
+    #[value(name = "levenshtein")]
+    Levenshtein,
+    /// Share of the distinct words that are not in both bodies
+    #[value(name = "jaccard")]
+    Jaccard,
+    /// Cosine distance of the word counts of the bodies
+    #[value(name = "cosine")]
+    Cosine,
+}
+
+impl SimilarityAlgo {
+    /// The maximum distance for issues to be considered similar if no threshold is given
+    pub fn default_threshold(self) -> f64 {
+        match self {
+            SimilarityAlgo::Levenshtein => LEVENSHTEIN_THRESHOLD as f64,
+            SimilarityAlgo::Jaccard => JACCARD_THRESHOLD,
+            SimilarityAlgo::Cosine => COSINE_THRESHOLD,
+        }
+    }
+
+    /// The distance between two issue bodies, ignoring timestamps and IDs. The Levenshtein
+    /// distance is a number of characters, the token based distances are between 0 and 1.
+    ///
+    /// # Example
+    /// ```
+    /// # use ci_manager::issue::similarity::SimilarityAlgo;
+    /// # use pretty_assertions::assert_eq;
+    /// let a = "Task app failed";
+    /// let b = "Task lib failed";
+    /// assert_eq!(SimilarityAlgo::Levenshtein.distance(a, b), 3.0);
+    /// // 2 of the 4 distinct words are in both
+    /// assert_eq!(SimilarityAlgo::Jaccard.distance(a, b), 0.5);
+    /// assert!(SimilarityAlgo::Cosine.distance(a, a) < 1e-9);
+    /// ```
+    pub fn distance(self, issue_body: &str, other_issue_body: &str) -> f64 {
+        let issue_body = util::remove_timestamps_and_ids(issue_body);
+        let other_issue_body = util::remove_timestamps_and_ids(other_issue_body);
+        match self {
+            SimilarityAlgo::Levenshtein => {
+                distance::levenshtein(&issue_body, &other_issue_body) as f64
+            }
+            SimilarityAlgo::Jaccard => {
+                let words: HashSet<&str> = tokens(&issue_body).collect();
+                let other_words: HashSet<&str> = tokens(&other_issue_body).collect();
+                let union = words.union(&other_words).count();
+                if union == 0 {
+                    return 0.0;
+                }
+                let intersection = words.intersection(&other_words).count();
+                1.0 - intersection as f64 / union as f64
+            }
+            SimilarityAlgo::Cosine => {
+                let counts = token_counts(&issue_body);
+                let other_counts = token_counts(&other_issue_body);
+                let norm = |counts: &HashMap<&str, usize>| {
+                    counts.values().map(|c| (c * c) as f64).sum::<f64>().sqrt()
+                };
+                let (norm, other_norm) = (norm(&counts), norm(&other_counts));
+                if norm == 0.0 || other_norm == 0.0 {
+                    return if norm == other_norm { 0.0 } else { 1.0 };
+                }
+                let dot: usize = counts
+                    .iter()
+                    .filter_map(|(token, count)| other_counts.get(token).map(|other| count * other))
+                    .sum();
+                1.0 - dot as f64 / (norm * other_norm)
+            }
+        }
+    }
+}
+
+/// How issue bodies are compared to find an issue similar to a new issue
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Similarity {
+    pub algo: SimilarityAlgo,
+    /// The maximum distance for issues to be considered similar
+    pub threshold: f64,
+}
+
+impl Default for Similarity {
+    fn default() -> Self {
+        let algo = SimilarityAlgo::default();
+        Self {
+            algo,
+            threshold: algo.default_threshold(),
+        }
+    }
+}
+
+impl Similarity {
+    /// Compare issue bodies with `algo`, with its default threshold if `threshold` is `None`
+    pub fn new(algo: SimilarityAlgo, threshold: Option<f64>) -> Result<Self> {
+        let threshold = threshold.unwrap_or_else(|| algo.default_threshold());
+        match algo {
+            _ if threshold.is_nan() || threshold < 0.0 => {
+                bail!("The similarity threshold can't be negative, got {threshold}")
+            }
+            SimilarityAlgo::Jaccard | SimilarityAlgo::Cosine if threshold > 1.0 => bail!(
+                "The {algo} distance is between 0 and 1, a threshold of {threshold} makes every issue similar"
+            ),
+            _ => Ok(Self { algo, threshold }),
+        }
+    }
+
+    /// Whether issues at `distance` from each other are similar
+    pub fn is_similar(&self, distance: f64) -> bool {
+        distance < self.threshold
+    }
+
+    /// The distance between two issue bodies if they are similar, `None` otherwise
+    ///
+    /// Unlike [SimilarityAlgo::distance], the Levenshtein distance is only computed as far as the
+    /// threshold: bodies whose lengths or characters differ too much are skipped without computing
+    /// it, and otherwise the computation gives up once the distance exceeds the threshold, see
+    /// [bounded_levenshtein].
+    pub fn similar_distance(&self, issue_body: &str, other_issue_body: &str) -> Option<f64> {
+        if self.algo != SimilarityAlgo::Levenshtein {
+            let distance = self.algo.distance(issue_body, other_issue_body);
+            return self.is_similar(distance).then_some(distance);
+        }
+        // The largest whole number of edits below the threshold
+        let max_distance = (self.threshold.ceil() as usize).checked_sub(1)?;
+        let issue_body = util::remove_timestamps_and_ids(issue_body);
+        let other_issue_body = util::remove_timestamps_and_ids(other_issue_body);
+        let chars: Vec<char> = issue_body.chars().collect();
+        let other_chars: Vec<char> = other_issue_body.chars().collect();
+        if chars.len().abs_diff(other_chars.len()) > max_distance
+            || char_count_distance(&chars, &other_chars) > max_distance
+        {
+            return None;
+        }
+        bounded_levenshtein(&chars, &other_chars, max_distance).map(|d| d as f64)
+    }
+}
+
+/// A lower bound of the Levenshtein distance from how many of each character the texts have, every
+/// edit adds or removes at most one character of each text
+fn char_count_distance(chars: &[char], other_chars: &[char]) -> usize {
+    let mut surplus: HashMap<char, isize> = HashMap::new();
+    for c in chars {
+        *surplus.entry(*c).or_default() += 1;
+    }
+    for c in other_chars {
+        *surplus.entry(*c).or_default() -= 1;
+    }
+    let (more, fewer) = surplus.values().fold((0, 0), |(more, fewer), &n| {
+        if n > 0 {
+            (more + n.unsigned_abs(), fewer)
+        } else {
+            (more, fewer + n.unsigned_abs())
+        }
+    });
+    more.max(fewer)
+}
+
+/// The Levenshtein distance between two texts if it is at most `max_distance`
+///
+/// Only the band of `max_distance` cells around the diagonal of the edit matrix is computed, as a
+/// path outside it has more edits than that, and the computation gives up as soon as a whole row
+/// exceeds `max_distance`. This takes O(n·max_distance) instead of O(n·m).
+///
+/// # Example
+/// ```
+/// # use ci_manager::issue::similarity::bounded_levenshtein;
+/// let a: Vec<char> = "ERROR: Task do_compile failed".chars().collect();
+/// let b: Vec<char> = "ERROR: Task do_install failed".chars().collect();
+/// assert_eq!(bounded_levenshtein(&a, &b, 10), Some(6));
+/// assert_eq!(bounded_levenshtein(&a, &b, 5), None);
+/// ```
+pub fn bounded_levenshtein(a: &[char], b: &[char], max_distance: usize) -> Option<usize> {
+    if a.len().abs_diff(b.len()) > max_distance {
+        return None;
+    }
+    // A common prefix and suffix don't add edits
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let suffix = a
+        .iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a, b) = (&a[..a.len() - suffix], &b[..b.len() - suffix]);
+    if a.is_empty() || b.is_empty() {
+        return Some(a.len().max(b.len()));
+    }
+
+    // Cells outside the band are never read before they are written, except at the edges of the
+    // band, which are set to `out_of_bounds`
+    let out_of_bounds = max_distance + 1;
+    let mut prev: Vec<usize> = (0..=b.len()).map(|j| j.min(out_of_bounds)).collect();
+    let mut curr = vec![out_of_bounds; b.len() + 1];
+    for i in 1..=a.len() {
+        let lo = i.saturating_sub(max_distance).max(1);
+        let hi = (i + max_distance).min(b.len());
+        curr[0] = i.min(out_of_bounds);
+        curr[lo - 1] = if lo == 1 { curr[0] } else { out_of_bounds };
+        let mut row_min = curr[lo - 1];
+        for j in lo..=hi {
+            let substitution = prev[j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            let distance = substitution
+                .min(prev[j] + 1)
+                .min(curr[j - 1] + 1)
+                .min(out_of_bounds);
+            curr[j] = distance;
+            row_min = row_min.min(distance);
+        }
+        if hi < b.len() {
+            curr[hi + 1] = out_of_bounds;
+        }
+        if row_min > max_distance {
+            return None;
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    let distance = prev[b.len()];
+    (distance <= max_distance).then_some(distance)
+}
+
+/// The words of a text, for the token based distances
+fn tokens(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+}
+
+fn token_counts(text: &str) -> HashMap<&str, usize> {
+    let mut counts = HashMap::new();
+    for token in tokens(text) {
+        *counts.entry(token).or_default() += 1;
+    }
+    counts
+}
 
 /// Calculate the smallest levenshtein distance between the issue body and the other issues with the same label
 pub fn issue_text_similarity(issue_body: &str, other_issues: &[String]) -> usize {
@@ -139,6 +382,59 @@ Yocto error: ERROR: No recipes available for: ...
         assert_eq!(distance, 142);
     }
 
+    #[test]
+    fn test_token_based_distances() {
+        let issue_0 = EXAMPLE_ISSUE_BODY_0.to_string();
+        let different = EXAMPLE_ISSUE_BODY_1.replace(
+            "Yocto error: ERROR: No recipes available for: ...",
+            "ERROR: fetcher failure. malformed url. Attempting to fetch from ${SOURCE_MIRROR_URL}",
+        );
+        for algo in [SimilarityAlgo::Jaccard, SimilarityAlgo::Cosine] {
+            let similarity = Similarity::new(algo, None).unwrap();
+            let same = algo.distance(&issue_0, EXAMPLE_ISSUE_BODY_1);
+            assert!(similarity.is_similar(same), "{algo} distance: {same}");
+            let timestamps = algo.distance(
+                ISSUE_FREQUENT_TIMESTAMPS_TEXT1,
+                ISSUE_FREQUENT_TIMESTAMPS_TEXT2,
+            );
+            assert!(
+                similarity.is_similar(timestamps),
+                "{algo} distance: {timestamps}"
+            );
+            let other = algo.distance(&issue_0, &different);
+            assert!(!similarity.is_similar(other), "{algo} distance: {other}");
+        }
+        assert!(Similarity::new(SimilarityAlgo::Jaccard, Some(2.0)).is_err());
+    }
+
+    #[test]
+    fn test_bounded_levenshtein_agrees_with_full_distance() {
+        let issue_0 = EXAMPLE_ISSUE_BODY_0.to_string();
+        let different = EXAMPLE_ISSUE_BODY_1.replace(
+            "Yocto error: ERROR: No recipes available for: ...",
+            "ERROR: fetcher failure. malformed url. Attempting to fetch from ${SOURCE_MIRROR_URL}",
+        );
+        let full = issue_text_similarity(&issue_0, std::slice::from_ref(&different));
+        assert_eq!(full, 142);
+
+        let similarity = Similarity::default();
+        assert_eq!(similarity.similar_distance(&issue_0, &different), None);
+        let loose = Similarity::new(SimilarityAlgo::Levenshtein, Some(200.0)).unwrap();
+        assert_eq!(loose.similar_distance(&issue_0, &different), Some(142.0));
+        let exact = Similarity::new(SimilarityAlgo::Levenshtein, Some(142.0)).unwrap();
+        assert_eq!(exact.similar_distance(&issue_0, &different), None);
+        assert_eq!(
+            similarity.similar_distance(&issue_0, EXAMPLE_ISSUE_BODY_1),
+            Some(0.0)
+        );
+        assert!(similarity
+            .similar_distance(
+                ISSUE_FREQUENT_TIMESTAMPS_TEXT1,
+                ISSUE_FREQUENT_TIMESTAMPS_TEXT2
+            )
+            .is_some());
+    }
+
     // Regression test for https://github.com/luftkode/gh-workflow-parser/issues/9
     /// Large issue text with many timestamps doesn't make the issues dissimilar
     #[test]
@@ -151,6 +447,7 @@ Yocto error: ERROR: No recipes available for: ...
         assert!(distance < LEVENSHTEIN_THRESHOLD, "Distance: {distance}");
     }
 
+    #[allow(clippy::redundant_static_lifetimes)]
     const ISSUE_FREQUENT_TIMESTAMPS_TEXT1: &'static str = r#"**Run ID**: 8072883145 [LINK TO RUN](https://github.com/luftkode/distro-template/actions/runs/8072883145)
 
 **1 job failed:**
@@ -302,6 +599,7 @@ env:
 ##[error]Input required and not supplied: path
 ```"#;
 
+    #[allow(clippy::redundant_static_lifetimes)]
     const ISSUE_FREQUENT_TIMESTAMPS_TEXT2: &'static str = r#"**Run ID**: 8057183947 [LINK TO RUN](https://github.com/luftkode/distro-template/actions/runs/8057183947)
 
 **1 job failed:**
@@ -0,0 +1,526 @@
+//! Tera templates of the issue body, the embedded default can be replaced with `--template`
+//!
+//! The issue template is rendered with an [IssueContext]. Every failed job in it has the fields of
+//! a [JobContext], including `markdown`, the job rendered with the embedded `job` template and
+//! shortened to fit in the issue. A custom template can use it, `{% include "job" %}` in a loop
+//! over the jobs, or lay out the fields of the jobs itself.
+//!
+//! The same context is rendered as plain text with `--body-format plain`, by templates that use
+//! the raw fields instead of the ones preformatted as Markdown. The `plain` filter turns Markdown,
+//! e.g. of the sections, into plain text, see [plain_text]. Likewise for Jira wiki markup with
+//! `--body-format jira` and the `jira` filter, see [jira_wiki].
+use super::matrix::MatrixParam;
+use crate::{commands::BodyFormat, *};
+use std::collections::HashMap;
+use tera::{Context as TeraContext, Tera, Value};
+
+/// The default issue template
+pub const DEFAULT_ISSUE_TEMPLATE: &str = r##"**Run ID**: {{ run_id }} [LINK TO RUN]({{ run_url }})
+
+**{{ failed_jobs | length }} {% if failed_jobs | length == 1 %}job{% else %}jobs{% endif %} failed:**
+{% for job in failed_jobs %}- **`{{ job.name }}`**
+{% endfor %}{% for section in sections %}
+**{{ section.title }}**
+{{ section.content }}
+{% endfor %}{% for job in failed_jobs %}{% if collapse_jobs %}
+<details{% if loop.first %} open{% endif %}>
+<summary><code>{{ job.name }}</code>: {{ job.failed_step }}{% if job.failure_label %} ({{ job.failure_label }}){% endif %}</summary>
+{{ job.markdown }}
+
+</details>{% else %}{{ job.markdown }}{% endif %}{% endfor %}"##;
+
+/// The template of a failed job, rendered with a [JobContext] as `job`
+pub const JOB_TEMPLATE: &str = r##"
+### `{{ job.name }}` (ID {{ job.id }})
+**Step failed:** `{{ job.failed_step }}`{{ job.runner_line }}{% if job.matrix %}
+\
+**Matrix:** {% for param in job.matrix %}{% if not loop.first %}, {% endif %}`{% if param.key %}{{ param.key }}: {% endif %}{{ param.value }}`{% endfor %}{% endif %}
+\
+**Log:** {{ job.url }}{{ job.error_annotations_list }}{{ job.yocto_metadata_table }}{% if job.steps_executed %}
+\
+*Best effort error summary*:{% if job.full_log_url %}
+Too long for the issue, see the full summary and log: {{ job.full_log_url }}{% elif job.summary is string %}
+```
+{{ job.summary }}```{% if job.log is string %}
+<details>
+<summary>{{ job.log_name }}</summary>
+<br>
+
+```
+{{ job.log }}
+```
+
+</details>{% endif %}{% else %}(content > max len){% endif %}{% endif %}"##;
+
+/// The plain text issue template of `--body-format plain`
+pub const PLAIN_ISSUE_TEMPLATE: &str = r##"Run ID: {{ run_id }} ({{ run_url }})
+
+{{ failed_jobs | length }} {% if failed_jobs | length == 1 %}job{% else %}jobs{% endif %} failed:
+{% for job in failed_jobs %}- {{ job.name }}
+{% endfor %}{% for section in sections %}
+{{ section.title }}:
+{{ section.content | plain }}
+{%- endfor %}{% for job in failed_jobs %}{{ job.markdown }}{% endfor %}"##;
+
+/// The plain text template of a failed job of `--body-format plain`
+pub const PLAIN_JOB_TEMPLATE: &str = r##"
+
+== {{ job.name }} (ID {{ job.id }}) ==
+Step failed: {{ job.failed_step }}{% if job.runner_line %}
+Runner: {% if job.runner_name %}{{ job.runner_name }}{% else %}unknown{% endif %}{% if job.runner_labels %} (labels: {{ job.runner_labels | join(sep=", ") }}){% endif %}{% endif %}{% if job.matrix %}
+Matrix: {% for param in job.matrix %}{% if not loop.first %}, {% endif %}{% if param.key %}{{ param.key }}: {% endif %}{{ param.value }}{% endfor %}{% endif %}
+Log: {{ job.url }}{% if job.error_annotations %}
+Error annotations:{% for annotation in job.error_annotations %}
+- {{ annotation | plain }}{% endfor %}{% endif %}{% if job.recipe %}
+Recipe: {{ job.recipe }}{% if job.version %}, version: {{ job.version }}{% endif %}{% if job.task %}, task: {{ job.task }}{% endif %}{% endif %}{% if job.steps_executed %}
+
+Best effort error summary:{% if job.full_log_url %}
+Too long for the issue, see the full summary and log: {{ job.full_log_url }}{% elif job.summary is string %}
+{{ job.summary }}{% if job.log is string %}
+==== {{ job.log_name }} ====
+{{ job.log }}{% endif %}{% else %}(content > max len){% endif %}{% endif %}"##;
+
+/// The Jira wiki markup issue template of `--body-format jira`
+pub const JIRA_ISSUE_TEMPLATE: &str = r##"*Run ID*: {{ run_id }} [LINK TO RUN|{{ run_url }}]
+
+*{{ failed_jobs | length }} {% if failed_jobs | length == 1 %}job{% else %}jobs{% endif %} failed:*
+{% for job in failed_jobs %}* {{ job.name }}
+{% endfor %}{% for section in sections %}
+*{{ section.title }}*
+{{ section.content | jira }}
+{% endfor %}{% for job in failed_jobs %}{{ job.markdown }}{% endfor %}"##;
+
+/// The Jira wiki markup template of a failed job of `--body-format jira`
+pub const JIRA_JOB_TEMPLATE: &str = r##"
+
+h3. {{ job.name }} (ID {{ job.id }})
+*Step failed:* {{ job.failed_step }}{% if job.runner_line %}
+*Runner:* {% if job.runner_name %}{{ job.runner_name }}{% else %}unknown{% endif %}{% if job.runner_labels %} (labels: {{ job.runner_labels | join(sep=", ") }}){% endif %}{% endif %}{% if job.matrix %}
+*Matrix:* {% for param in job.matrix %}{% if not loop.first %}, {% endif %}{% if param.key %}{{ param.key }}: {% endif %}{{ param.value }}{% endfor %}{% endif %}
+*Log:* {{ job.url }}{% if job.error_annotations %}
+*Error annotations:*{% for annotation in job.error_annotations %}
+* {{ annotation | jira }}{% endfor %}{% endif %}{% if job.recipe %}
+||Recipe||Version||Task||
+|{{ job.recipe }}|{% if job.version %}{{ job.version }}{% else %} {% endif %}|{% if job.task %}{{ job.task }}{% else %} {% endif %}|{% endif %}{% if job.steps_executed %}
+
+_Best effort error summary_:{% if job.full_log_url %}
+Too long for the issue, see the full summary and log: {{ job.full_log_url }}{% elif job.summary is string %}
+{noformat}
+{{ job.summary }}{noformat}{% if job.log is string %}
+*{{ job.log_name }}*
+{noformat}
+{{ job.log }}
+{noformat}{% endif %}{% else %}(content > max len){% endif %}{% endif %}"##;
+
+const ISSUE: &str = "issue";
+const JOB: &str = "job";
+
+/// Everything the issue template can refer to
+#[derive(Debug, Serialize)]
+pub struct IssueContext<'a> {
+    pub run_id: &'a str,
+    pub run_url: &'a str,
+    pub failed_jobs: Vec<JobContext<'a>>,
+    /// Additional sections, e.g. the commits since the last successful run
+    pub sections: Vec<SectionContext<'a>>,
+    /// Whether there are so many failed jobs that every job but the first is collapsed
+    pub collapse_jobs: bool,
+}
+
+/// A failed job in the context of a template
+#[derive(Debug, Serialize)]
+pub struct JobContext<'a> {
+    pub name: &'a str,
+    pub id: &'a str,
+    pub url: &'a str,
+    pub failed_step: String,
+    /// `false` if the job failed before any step ran, e.g. waiting for a runner
+    pub steps_executed: bool,
+    pub runner_name: Option<&'a str>,
+    pub runner_labels: &'a [String],
+    /// The runner as a Markdown line, empty unless the runner caused the failure
+    pub runner_line: String,
+    /// Parameters of a matrix job, with a `key` if the workflow file was found
+    pub matrix: &'a [MatrixParam],
+    pub error_annotations: Vec<String>,
+    /// The error annotations as a Markdown list, empty if there are none
+    pub error_annotations_list: String,
+    pub failure_label: Option<String>,
+    /// The recipe, version and task of a failed Yocto task
+    pub recipe: Option<&'a str>,
+    pub version: Option<&'a str>,
+    pub task: Option<&'a str>,
+    /// The recipe, version and task as a Markdown table, empty if not a Yocto failure
+    pub yocto_metadata_table: String,
+    /// The error summary, shortened to fit in the issue. `None` if it does not fit at all or it is
+    /// replaced with `full_log_url`
+    pub summary: Option<String>,
+    /// The attached log, `None` if there is none or it does not fit in the issue
+    pub log_name: Option<&'a str>,
+    pub log: Option<&'a str>,
+    /// Link to the full summary and log, only set if they are too long for the issue
+    pub full_log_url: Option<&'a str>,
+    /// The job rendered with the `job` template, only set in the issue template. Plain text with
+    /// `--body-format plain` and Jira wiki markup with `--body-format jira`.
+    pub markdown: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SectionContext<'a> {
+    pub title: &'a str,
+    pub content: &'a str,
+}
+
+/// The templates of the issue body and of the failed jobs in it
+#[derive(Debug, Clone)]
+pub struct IssueTemplate {
+    tera: Tera,
+    format: BodyFormat,
+    /// Whether the issue template is the default, so it is not rendered twice if it fails
+    is_default: bool,
+}
+
+impl Default for IssueTemplate {
+    fn default() -> Self {
+        Self::default_for(BodyFormat::Markdown)
+    }
+}
+
+impl IssueTemplate {
+    /// The embedded templates of a body format
+    pub fn default_for(format: BodyFormat) -> Self {
+        let issue_template = match format {
+            BodyFormat::Markdown => DEFAULT_ISSUE_TEMPLATE,
+            BodyFormat::Plain => PLAIN_ISSUE_TEMPLATE,
+            BodyFormat::Jira => JIRA_ISSUE_TEMPLATE,
+        };
+        Self::with_issue_template(issue_template, format, true)
+            .expect("the default templates are valid")
+    }
+
+    /// Read a custom issue template, see the [module documentation](self) for what it can refer to.
+    /// The jobs in it are rendered with the embedded `job` template of `format`.
+    pub fn from_file(path: &Path, format: BodyFormat) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read issue template {}", path.display()))?;
+        Self::with_issue_template(&contents, format, false)
+            .with_context(|| format!("Invalid issue template {}", path.display()))
+    }
+
+    fn with_issue_template(
+        issue_template: &str,
+        format: BodyFormat,
+        is_default: bool,
+    ) -> Result<Self> {
+        let job_template = match format {
+            BodyFormat::Markdown => JOB_TEMPLATE,
+            BodyFormat::Plain => PLAIN_JOB_TEMPLATE,
+            BodyFormat::Jira => JIRA_JOB_TEMPLATE,
+        };
+        let mut tera = Tera::default();
+        // The issue body is Markdown, plain text or Jira wiki markup, not HTML
+        tera.autoescape_on(Vec::new());
+        tera.register_filter(
+            "plain",
+            |value: &Value, _: &HashMap<String, Value>| match value.as_str() {
+                Some(markdown) => Ok(Value::String(plain_text(markdown))),
+                None => Err(tera::Error::msg("the plain filter only applies to strings")),
+            },
+        );
+        tera.register_filter(
+            "jira",
+            |value: &Value, _: &HashMap<String, Value>| match value.as_str() {
+                Some(markdown) => Ok(Value::String(jira_wiki(markdown))),
+                None => Err(tera::Error::msg("the jira filter only applies to strings")),
+            },
+        );
+        tera.add_raw_templates([(JOB, job_template), (ISSUE, issue_template)])?;
+        Ok(Self {
+            tera,
+            format,
+            is_default,
+        })
+    }
+
+    pub fn format(&self) -> BodyFormat {
+        self.format
+    }
+
+    /// Render the issue body, with the default template if the custom template fails
+    pub fn render_issue(&self, context: &IssueContext) -> String {
+        let rendered = TeraContext::from_serialize(context)
+            .and_then(|context| self.tera.render(ISSUE, &context));
+        match rendered {
+            Ok(body) => body,
+            Err(e) if !self.is_default => {
+                log::error!("Failed to render the issue template, using the default: {e:?}");
+                Self::default_for(self.format).render_issue(context)
+            }
+            Err(e) => panic!("the default issue template failed to render: {e:?}"),
+        }
+    }
+
+    /// Render a failed job
+    pub fn render_job(&self, job: &JobContext) -> String {
+        let mut context = TeraContext::new();
+        context.insert("job", job);
+        self.tera
+            .render(JOB, &context)
+            .expect("the job template renders")
+    }
+}
+
+/// Turn Markdown into plain text: emphasis and code marks are removed, links are written as
+/// `text (url)` and every row of a table is written as `header: cell` pairs
+///
+/// # Example
+/// ```
+/// # use ci_manager::issue::template::plain_text;
+/// # use pretty_assertions::assert_eq;
+/// let markdown = "Last successful run: [run 42](https://github.com/luftkode/ci-manager/actions/runs/42)\n\
+///     | Event | Branch |\n\
+///     |-------|--------|\n\
+///     | `push` | **main** |\n";
+/// assert_eq!(
+///     plain_text(markdown),
+///     "Last successful run: run 42 (https://github.com/luftkode/ci-manager/actions/runs/42)\n\
+///     Event: push, Branch: main\n"
+/// );
+/// ```
+pub fn plain_text(markdown: &str) -> String {
+    static LINK_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"\[(?<TEXT>[^\]]*)\]\((?<URL>[^)\s]*)\)").unwrap());
+    static TABLE_SEPARATOR_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"^\|(?:\s*:?-+:?\s*\|)+$").unwrap());
+    let cells = |row: &str| -> Vec<String> {
+        row.trim()
+            .trim_matches('|')
+            .split('|')
+            .map(|cell| cell.trim().to_owned())
+            .collect()
+    };
+
+    let mut plain = String::with_capacity(markdown.len());
+    let mut table_header: Option<Vec<String>> = None;
+    for line in markdown.lines() {
+        let line = LINK_RE.replace_all(line, "$TEXT ($URL)");
+        let line = line
+            .replace("**", "")
+            .replace('`', "")
+            .replace("<br>", ", ");
+        if !line.trim_start().starts_with('|') {
+            table_header = None;
+            if line.trim() != "\\" {
+                plain.push_str(&line);
+                plain.push('\n');
+            }
+            continue;
+        }
+        if TABLE_SEPARATOR_RE.is_match(line.trim()) {
+            continue;
+        }
+        match &table_header {
+            None => table_header = Some(cells(&line)),
+            Some(header) => {
+                let pairs: Vec<String> = header
+                    .iter()
+                    .zip(cells(&line))
+                    .map(|(header, cell)| format!("{header}: {cell}"))
+                    .collect();
+                plain.push_str(&pairs.join(", "));
+                plain.push('\n');
+            }
+        }
+    }
+    plain
+}
+
+/// Turn Markdown into Jira wiki markup: emphasis, code and links are written the Jira way, code
+/// blocks become `{noformat}` blocks, headings `hN.` headings, list items `*` items and the header
+/// row of a table a `||` row
+///
+/// # Example
+/// ```
+/// # use ci_manager::issue::template::jira_wiki;
+/// # use pretty_assertions::assert_eq;
+/// let fence = "`".repeat(3);
+/// let markdown = format!(
+///     "### Commits\n\
+///     - [`d9a6c3e`](https://github.com/luftkode/ci-manager/commit/d9a6c3e) **Fix** the build\n\
+///     | Event | Branch |\n\
+///     |-------|--------|\n\
+///     | `push` | main |\n\
+///     {fence}\n\
+///     **not bold**\n\
+///     {fence}\n"
+/// );
+/// assert_eq!(
+///     jira_wiki(&markdown),
+///     "h3. Commits\n\
+///     * [{{d9a6c3e}}|https://github.com/luftkode/ci-manager/commit/d9a6c3e] *Fix* the build\n\
+///     ||Event||Branch||\n\
+///     |{{push}}|main|\n\
+///     {noformat}\n\
+///     **not bold**\n\
+///     {noformat}\n"
+/// );
+/// ```
+pub fn jira_wiki(markdown: &str) -> String {
+    static LINK_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"\[(?<TEXT>[^\]]*)\]\((?<URL>[^)\s]*)\)").unwrap());
+    static CODE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"`(?<CODE>[^`]+)`").unwrap());
+    static HEADING_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(?<LEVEL>#{1,6})\s+").unwrap());
+    static LIST_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(?<INDENT> *)[-+] ").unwrap());
+    static TABLE_SEPARATOR_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"^\|(?:\s*:?-+:?\s*\|)+$").unwrap());
+    let inline = |text: &str| -> String {
+        let text = LINK_RE.replace_all(text, "[$TEXT|$URL]");
+        let text = CODE_RE.replace_all(&text, "{{$CODE}}");
+        text.replace("**", "*").replace("<br>", r"\\")
+    };
+
+    let lines: Vec<&str> = markdown.lines().collect();
+    let mut wiki = String::with_capacity(markdown.len());
+    let mut in_code_block = false;
+    for (i, line) in lines.iter().enumerate() {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            wiki.push_str("{noformat}\n");
+            continue;
+        }
+        if in_code_block {
+            wiki.push_str(line);
+            wiki.push('\n');
+            continue;
+        }
+        let trimmed = line.trim();
+        if trimmed == "\\" || TABLE_SEPARATOR_RE.is_match(trimmed) {
+            continue;
+        }
+        if trimmed.starts_with('|') {
+            // The cells are converted one by one, as links have a `|` in Jira
+            let is_header = lines
+                .get(i + 1)
+                .is_some_and(|next| TABLE_SEPARATOR_RE.is_match(next.trim()));
+            let separator = if is_header { "||" } else { "|" };
+            let cells: Vec<String> = trimmed
+                .trim_matches('|')
+                .split('|')
+                .map(|cell| match inline(cell.trim()) {
+                    // Empty cells are read as separators of header cells
+                    cell if cell.is_empty() => " ".to_owned(),
+                    cell => cell,
+                })
+                .collect();
+            wiki.push_str(separator);
+            wiki.push_str(&cells.join(separator));
+            wiki.push_str(separator);
+            wiki.push('\n');
+            continue;
+        }
+        // Inline first, so the `*` of list items isn't mistaken for bold
+        let line = inline(line);
+        let line = HEADING_RE.replace(&line, |caps: &regex::Captures| {
+            format!("h{}. ", caps["LEVEL"].len())
+        });
+        let line = LIST_RE.replace(&line, |caps: &regex::Captures| {
+            format!("{} ", "*".repeat(caps["INDENT"].len() / 2 + 1))
+        });
+        wiki.push_str(&line);
+        wiki.push('\n');
+    }
+    wiki
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        err_parse::ErrorMessageSummary,
+        issue::{FailedJob, FirstFailedStep, Issue},
+    };
+    use pretty_assertions::assert_eq;
+    use temp_dir::TempDir;
+
+    #[test]
+    fn test_issue_body_from_custom_template() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.child("issue.md.tera");
+        fs::write(
+            &path,
+            "Run [{{ run_id }}]({{ run_url }})\n\
+            {% for job in failed_jobs %}* {{ job.name }} failed at `{{ job.failed_step }}`: {{ job.summary }}\n{% endfor %}",
+        )
+        .unwrap();
+        let mut issue = Issue::new(
+            "Scheduled run failed".to_string(),
+            "1".to_string(),
+            "https://github.com/luftkode/ci-manager/actions/runs/1".to_string(),
+            vec![FailedJob::new(
+                "Test template".to_string(),
+                "2".to_string(),
+                "https://github.com/luftkode/ci-manager/actions/runs/1/job/2".to_string(),
+                FirstFailedStep::StepName("Run tests".to_owned()),
+                ErrorMessageSummary::Other("assertion failed".to_owned()),
+            )],
+            "CI".to_string(),
+        );
+        issue.set_template(IssueTemplate::from_file(&path, BodyFormat::Markdown).unwrap());
+
+        assert_eq!(
+            issue.body.to_markdown_string(),
+            "Run [1](https://github.com/luftkode/ci-manager/actions/runs/1)\n\
+            * Test template failed at `Run tests`: assertion failed\n"
+        );
+    }
+
+    #[test]
+    fn test_plain_issue_body() {
+        let mut issue = Issue::new(
+            "Scheduled run failed".to_string(),
+            "1".to_string(),
+            "https://github.com/luftkode/ci-manager/actions/runs/1".to_string(),
+            vec![FailedJob::new(
+                "Test template".to_string(),
+                "2".to_string(),
+                "https://github.com/luftkode/ci-manager/actions/runs/1/job/2".to_string(),
+                FirstFailedStep::StepName("Run tests".to_owned()),
+                ErrorMessageSummary::Other("assertion failed".to_owned()),
+            )],
+            "CI".to_string(),
+        );
+        issue.add_section(
+            "Run context",
+            "| Event | Branch |\n|-------|--------|\n| `push` | `main` |\n",
+        );
+        issue.set_template(IssueTemplate::default_for(BodyFormat::Plain));
+
+        assert_eq!(
+            issue.body.to_markdown_string(),
+            "Run ID: 1 (https://github.com/luftkode/ci-manager/actions/runs/1)\n\
+            \n\
+            1 job failed:\n\
+            - Test template\n\
+            \n\
+            Run context:\n\
+            Event: push, Branch: main\n\
+            \n\
+            \n\
+            == Test template (ID 2) ==\n\
+            Step failed: Run tests\n\
+            Log: https://github.com/luftkode/ci-manager/actions/runs/1/job/2\n\
+            \n\
+            Best effort error summary:\n\
+            assertion failed"
+        );
+    }
+
+    #[test]
+    fn test_invalid_template_is_an_error() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.child("issue.md.tera");
+        fs::write(&path, "{% for job in failed_jobs %}").unwrap();
+
+        assert!(IssueTemplate::from_file(&path, BodyFormat::Markdown).is_err());
+    }
+}
@@ -70,7 +70,13 @@ pub mod macros {
 pub mod ci_provider;
 pub mod config;
 pub mod err_parse;
+pub mod exit_code;
+pub mod history;
 pub mod issue;
+pub mod notify;
+pub mod storage;
+pub mod telemetry;
+pub mod tracker;
 pub mod util;
 
 pub use crate::run::run;
@@ -1,11 +1,12 @@
+use ci_manager::exit_code::Exit;
 use std::process::ExitCode;
 
 #[tokio::main]
 async fn main() -> ExitCode {
     if let Err(e) = ci_manager::run().await {
         eprintln!("Error: {e}");
-        ExitCode::FAILURE
+        Exit::of_error(&e).into()
     } else {
-        ExitCode::SUCCESS
+        Exit::outcome().into()
     }
 }
@@ -0,0 +1,206 @@
+//! Notifications about what `create-issue-from-run` did about the failure of a run, see `--notify`
+use crate::{
+    issue::{FailedJob, Issue},
+    *,
+};
+
+pub mod alert;
+pub mod slack;
+pub mod webhook;
+
+/// The failure label of a failed job that the parser found no label for
+pub const UNCLASSIFIED_LABEL: &str = "unclassified";
+
+/// Where a notification is sent, parsed from `--notify KIND:TARGET`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotifyTarget {
+    /// A Slack incoming webhook URL, or a channel posted to with the bot token in `SLACK_BOT_TOKEN`
+    Slack(String),
+    /// A URL that the [RunReport] is POSTed to as JSON, see [webhook::Payload]
+    Webhook(String),
+}
+
+impl std::str::FromStr for NotifyTarget {
+    type Err = anyhow::Error;
+
+    /// Parse a target like `slack:#ci-failures`
+    ///
+    /// # Example
+    /// ```
+    /// # use ci_manager::notify::NotifyTarget;
+    /// let target: NotifyTarget = "slack:https://hooks.slack.com/services/T0/B0/XXXX".parse().unwrap();
+    /// assert_eq!(
+    ///     target,
+    ///     NotifyTarget::Slack("https://hooks.slack.com/services/T0/B0/XXXX".to_owned())
+    /// );
+    /// let target: NotifyTarget = "webhook:https://ci.example.com/hooks/ci-manager".parse().unwrap();
+    /// assert_eq!(
+    ///     target,
+    ///     NotifyTarget::Webhook("https://ci.example.com/hooks/ci-manager".to_owned())
+    /// );
+    /// assert!("slack:".parse::<NotifyTarget>().is_err());
+    /// assert!("email:ci@luftkode.dk".parse::<NotifyTarget>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self> {
+        let Some((kind, target)) = s.split_once(':') else {
+            bail!("Expected a notification target like KIND:TARGET, got: {s}");
+        };
+        if target.is_empty() {
+            bail!("Missing the target of the {kind} notification: {s}");
+        }
+        match kind {
+            "slack" => Ok(Self::Slack(target.to_owned())),
+            "webhook" => Ok(Self::Webhook(target.to_owned())),
+            _ => bail!("Unknown kind of notification {kind}, expected slack or webhook"),
+        }
+    }
+}
+
+impl fmt::Display for NotifyTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NotifyTarget::Slack(target) => write!(f, "slack:{target}"),
+            NotifyTarget::Webhook(url) => write!(f, "webhook:{url}"),
+        }
+    }
+}
+
+/// What was done about the failure of a run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "decision", rename_all = "snake_case")]
+pub enum IssueOutcome {
+    /// A new issue was created
+    Created { url: String },
+    /// Existing issues already track the failure
+    Duplicate { urls: Vec<String> },
+    /// A closed issue of the same failure was reopened
+    Reopened { url: String },
+    /// A new issue would have been created, but it is a dry run
+    DryRun,
+    /// Every failed job was an infrastructure failure that an alert was triggered for instead of
+    /// creating an issue, see `--alert-instead-of-issue`
+    Alerted { dedup_keys: Vec<String> },
+    /// A check run with the analysis was published on the head commit of the run instead of
+    /// creating an issue, see `--check-run`
+    CheckRun { url: String },
+    /// The failure was commented on the pull request of the run instead of creating an issue, see
+    /// `--pr-comment`
+    PrComment { url: String },
+}
+
+impl IssueOutcome {
+    /// The name of the decision, as in the serialized outcome
+    pub fn decision(&self) -> &'static str {
+        match self {
+            Self::Created { .. } => "created",
+            Self::Duplicate { .. } => "duplicate",
+            Self::Reopened { .. } => "reopened",
+            Self::DryRun => "dry_run",
+            Self::Alerted { .. } => "alerted",
+            Self::CheckRun { .. } => "check_run",
+            Self::PrComment { .. } => "pr_comment",
+        }
+    }
+
+    /// The URL of the issue that tracks the failure, the first one if several do
+    pub fn issue_url(&self) -> Option<&str> {
+        match self {
+            Self::Created { url } | Self::Reopened { url } => Some(url),
+            Self::Duplicate { urls } => urls.first().map(String::as_str),
+            Self::DryRun
+            | Self::Alerted { .. }
+            | Self::CheckRun { .. }
+            | Self::PrComment { .. } => None,
+        }
+    }
+
+    /// The URL of where the failure is tracked: the issue, the check run or the pull request comment
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            Self::CheckRun { url } | Self::PrComment { url } => Some(url),
+            outcome => outcome.issue_url(),
+        }
+    }
+}
+
+/// The failure of a run and what was done about it, the content of the notifications
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunReport {
+    pub run_id: String,
+    pub run_url: String,
+    /// Title of the issue
+    pub title: String,
+    /// Fingerprint of the failure of the run, see [Issue::fingerprint]
+    pub fingerprint: String,
+    /// Labels of the issue
+    pub labels: Vec<String>,
+    pub failed_jobs: Vec<JobReport>,
+    pub outcome: IssueOutcome,
+}
+
+/// A failed job in a [RunReport]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JobReport {
+    pub name: String,
+    pub url: String,
+    pub failed_step: String,
+    pub failure_label: Option<String>,
+    /// Fingerprint of the failure of the job, see [Issue::job_fingerprints]
+    pub fingerprint: String,
+    /// The parsed error summary
+    pub summary: String,
+}
+
+impl RunReport {
+    pub fn new(issue: &Issue, outcome: IssueOutcome) -> Self {
+        Self {
+            run_id: issue.run_id().to_owned(),
+            run_url: issue.run_url().to_owned(),
+            title: issue.title().to_owned(),
+            fingerprint: issue.fingerprint(),
+            labels: issue.labels().to_vec(),
+            failed_jobs: issue.failed_jobs().iter().map(JobReport::new).collect(),
+            outcome,
+        }
+    }
+
+    /// The distinct failure labels of the failed jobs, [UNCLASSIFIED_LABEL] for the jobs without one
+    pub fn failure_labels(&self) -> Vec<&str> {
+        let mut labels: Vec<&str> = Vec::new();
+        for job in &self.failed_jobs {
+            let label = job.failure_label.as_deref().unwrap_or(UNCLASSIFIED_LABEL);
+            if !labels.contains(&label) {
+                labels.push(label);
+            }
+        }
+        labels
+    }
+}
+
+impl JobReport {
+    pub fn new(job: &FailedJob) -> Self {
+        Self {
+            name: job.name().to_owned(),
+            url: job.url().to_owned(),
+            failed_step: job.failed_step().to_string(),
+            failure_label: job.failure_label(),
+            fingerprint: fingerprint(&job.error_key()),
+            summary: job.summary().to_owned(),
+        }
+    }
+}
+
+/// Send a notification about `report` to every target, or print them in a dry run. A notification
+/// that fails is logged, as the issue is already taken care of.
+#[tracing::instrument(skip_all)]
+pub async fn notify(targets: &[NotifyTarget], report: &RunReport) {
+    for target in targets {
+        let sent = match target {
+            NotifyTarget::Slack(target) => slack::notify(target, report).await,
+            NotifyTarget::Webhook(url) => webhook::notify(url, report).await,
+        };
+        if let Err(e) = sent {
+            log::warn!("Failed to send the notification to {target}: {e:#}");
+        }
+    }
+}
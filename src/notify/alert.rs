@@ -0,0 +1,196 @@
+//! Alerts in PagerDuty or Opsgenie about failed jobs classified as infrastructure failures, see
+//! `--alert`
+use super::JobReport;
+use crate::{commands::AlertService, issue::Issue, *};
+
+const PAGERDUTY_EVENTS_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+const OPSGENIE_ALERTS_URL: &str = "https://api.opsgenie.com/v2/alerts";
+/// Longest message of an Opsgenie alert
+const OPSGENIE_MAX_MESSAGE_LEN: usize = 130;
+
+/// An alert about a job that failed because of the CI infrastructure
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alert {
+    pub run_url: String,
+    pub job: JobReport,
+}
+
+impl Alert {
+    /// The alerts about the failed jobs of `issue` that are infrastructure failures
+    pub fn from_issue(issue: &Issue) -> Vec<Self> {
+        issue
+            .failed_jobs()
+            .iter()
+            .filter(|job| job.is_infra_failure())
+            .map(|job| Self {
+                run_url: issue.run_url().to_owned(),
+                job: JobReport::new(job),
+            })
+            .collect()
+    }
+
+    /// The key that alerts of the same failure are deduplicated by, in PagerDuty and Opsgenie alike,
+    /// from the fingerprint of the failure of the job
+    pub fn dedup_key(&self) -> String {
+        format!("ci-manager-{}", self.job.fingerprint)
+    }
+
+    /// One line about the failure, e.g. `Build image failed: runner-disk-full: No space left on device`
+    pub fn summary(&self) -> String {
+        let label = self
+            .job
+            .failure_label
+            .as_deref()
+            .unwrap_or("infrastructure failure");
+        let first_line = self
+            .job
+            .summary
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty());
+        match first_line {
+            Some(line) => format!("{} failed: {label}: {line}", self.job.name),
+            None => format!("{} failed: {label}", self.job.name),
+        }
+    }
+
+    /// The event that triggers the alert in PagerDuty
+    pub fn pagerduty_event(&self, routing_key: &str) -> serde_json::Value {
+        serde_json::json!({
+            "routing_key": routing_key,
+            "event_action": "trigger",
+            "dedup_key": self.dedup_key(),
+            "payload": {
+                "summary": self.summary(),
+                "source": self.run_url,
+                "severity": "error",
+                "component": self.job.name,
+                "class": self.job.failure_label,
+                "custom_details": self.job,
+            },
+            "links": [
+                { "href": self.job.url, "text": "Failed job" },
+                { "href": self.run_url, "text": "Workflow run" },
+            ],
+        })
+    }
+
+    /// The request body that creates the alert in Opsgenie
+    pub fn opsgenie_alert(&self) -> serde_json::Value {
+        let message: String = self
+            .summary()
+            .chars()
+            .take(OPSGENIE_MAX_MESSAGE_LEN)
+            .collect();
+        // Opsgenie only takes strings as the values of the details
+        let details = serde_json::json!({
+            "run_url": self.run_url,
+            "job_url": self.job.url,
+            "failed_step": self.job.failed_step,
+            "failure_label": self.job.failure_label.as_deref().unwrap_or_default(),
+        });
+        serde_json::json!({
+            "message": message,
+            "alias": self.dedup_key(),
+            "description": format!("{}\n\n{}", self.job.url, self.job.summary),
+            "source": "ci-manager",
+            "entity": self.job.name,
+            "tags": self.job.failure_label.iter().collect::<Vec<_>>(),
+            "details": details,
+        })
+    }
+}
+
+/// Trigger the alerts in `service`, or print them in a dry run, and return the fingerprints of the
+/// failures that were alerted about. An alert that fails is logged, so the failure still ends up in
+/// the issue.
+pub async fn trigger(service: AlertService, alerts: &[Alert]) -> Vec<String> {
+    let mut alerted = Vec::new();
+    for alert in alerts {
+        match send(service, alert).await {
+            Ok(()) => alerted.push(alert.job.fingerprint.clone()),
+            Err(e) => log::warn!(
+                "Failed to trigger the {service} alert {}: {e:#}",
+                alert.dedup_key()
+            ),
+        }
+    }
+    alerted
+}
+
+async fn send(service: AlertService, alert: &Alert) -> Result<()> {
+    if Config::global().dry_run() {
+        let body = match service {
+            AlertService::PagerDuty => alert.pagerduty_event("<PAGERDUTY_ROUTING_KEY>"),
+            AlertService::Opsgenie => alert.opsgenie_alert(),
+        };
+        println!(
+            "DRY RUN MODE! The following {service} alert would be triggered:\n{}",
+            serde_json::to_string_pretty(&body)?
+        );
+        return Ok(());
+    }
+    let client = reqwest::Client::new();
+    let request = match service {
+        AlertService::PagerDuty => {
+            let routing_key = env::var("PAGERDUTY_ROUTING_KEY")
+                .context("PAGERDUTY_ROUTING_KEY must be set to trigger PagerDuty alerts")?;
+            client
+                .post(PAGERDUTY_EVENTS_URL)
+                .json(&alert.pagerduty_event(&routing_key))
+        }
+        AlertService::Opsgenie => {
+            let api_key = env::var("OPSGENIE_API_KEY")
+                .context("OPSGENIE_API_KEY must be set to trigger Opsgenie alerts")?;
+            client
+                .post(OPSGENIE_ALERTS_URL)
+                .header("Authorization", format!("GenieKey {api_key}"))
+                .json(&alert.opsgenie_alert())
+        }
+    };
+    log::info!("Triggering the {service} alert {}", alert.dedup_key());
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        let error = response.text().await.unwrap_or_default();
+        bail!("{service} responded with {status}: {error}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_infra_alert() {
+        let alert = Alert {
+            run_url: "https://github.com/luftkode/ci-manager/actions/runs/1".to_owned(),
+            job: JobReport {
+                name: "Build image".to_owned(),
+                url: "https://github.com/luftkode/ci-manager/actions/runs/1/job/2".to_owned(),
+                failed_step: "Bake".to_owned(),
+                failure_label: Some("runner-disk-full".to_owned()),
+                fingerprint: "d0c5e0ac7e8c2a1b".to_owned(),
+                summary: format!("\nNo space left on device{}", " !".repeat(100)),
+            },
+        };
+
+        let event = alert.pagerduty_event("R0UT1NG");
+        assert_eq!(event["dedup_key"], "ci-manager-d0c5e0ac7e8c2a1b");
+        assert_eq!(event["payload"]["class"], "runner-disk-full");
+        assert!(event["payload"]["summary"]
+            .as_str()
+            .unwrap()
+            .starts_with("Build image failed: runner-disk-full: No space left on device !"));
+
+        let opsgenie = alert.opsgenie_alert();
+        assert_eq!(opsgenie["alias"], event["dedup_key"]);
+        assert_eq!(
+            opsgenie["message"].as_str().unwrap().chars().count(),
+            OPSGENIE_MAX_MESSAGE_LEN
+        );
+        assert_eq!(opsgenie["tags"], serde_json::json!(["runner-disk-full"]));
+    }
+}
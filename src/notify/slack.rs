@@ -0,0 +1,153 @@
+//! Slack messages in Block Kit, posted to an incoming webhook or with a bot token to a channel
+use super::{IssueOutcome, RunReport};
+use crate::*;
+
+const POST_MESSAGE_URL: &str = "https://slack.com/api/chat.postMessage";
+
+/// Post a message about `report` to `target`, an incoming webhook URL or a channel, or print it in
+/// a dry run
+pub async fn notify(target: &str, report: &RunReport) -> Result<()> {
+    let mut message = message(report);
+    if Config::global().dry_run() {
+        println!(
+            "DRY RUN MODE! The following Slack message would be posted to {target}:\n{}",
+            serde_json::to_string_pretty(&message)?
+        );
+        return Ok(());
+    }
+    let client = reqwest::Client::new();
+    if target.starts_with("https://") {
+        log::info!("Posting the Slack message to the incoming webhook");
+        let response = client.post(target).json(&message).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let error = response.text().await.unwrap_or_default();
+            bail!("Slack webhook responded with {status}: {error}");
+        }
+        return Ok(());
+    }
+    let token =
+        env::var("SLACK_BOT_TOKEN").context("SLACK_BOT_TOKEN must be set to post to a channel")?;
+    message["channel"] = target.into();
+    log::info!("Posting the Slack message to {target}");
+    let response: serde_json::Value = client
+        .post(POST_MESSAGE_URL)
+        .bearer_auth(token)
+        .json(&message)
+        .send()
+        .await?
+        .json()
+        .await?;
+    // The Web API responds with 200 OK and reports errors in the body
+    if response["ok"] != true {
+        bail!("Slack responded with error: {}", response["error"]);
+    }
+    Ok(())
+}
+
+/// The Block Kit message about `report`: the run, the failed jobs with their failure labels, and
+/// the issue
+pub fn message(report: &RunReport) -> serde_json::Value {
+    let headline = format!(
+        ":red_circle: *<{url}|{title}>*",
+        url = report.run_url,
+        title = escape(&report.title)
+    );
+    let jobs: Vec<String> = report
+        .failed_jobs
+        .iter()
+        .map(|job| match &job.failure_label {
+            Some(label) => format!("• <{}|{}> ({})", job.url, escape(&job.name), escape(label)),
+            None => format!("• <{}|{}>", job.url, escape(&job.name)),
+        })
+        .collect();
+    let issue = match &report.outcome {
+        IssueOutcome::Created { url } => format!("Issue created: {url}"),
+        IssueOutcome::Duplicate { urls } => format!("Already tracked in {}", urls.join(", ")),
+        IssueOutcome::Reopened { url } => format!("Issue reopened: {url}"),
+        IssueOutcome::DryRun => "No issue created (dry run)".to_owned(),
+        IssueOutcome::Alerted { dedup_keys } => {
+            format!(
+                "Alerted instead of creating an issue: {}",
+                dedup_keys.join(", ")
+            )
+        }
+        IssueOutcome::CheckRun { url } => format!("Check run published: {url}"),
+        IssueOutcome::PrComment { url } => format!("Commented on the pull request: {url}"),
+    };
+    let mut context = vec![issue];
+    if !report.labels.is_empty() {
+        context.push(format!("Labels: {}", escape(&report.labels.join(", "))));
+    }
+    serde_json::json!({
+        "text": format!("{}: {} failed job(s)", escape(&report.title), report.failed_jobs.len()),
+        "blocks": [
+            { "type": "section", "text": { "type": "mrkdwn", "text": headline } },
+            { "type": "section", "text": { "type": "mrkdwn", "text": jobs.join("\n") } },
+            {
+                "type": "context",
+                "elements": context
+                    .iter()
+                    .map(|text| serde_json::json!({ "type": "mrkdwn", "text": text }))
+                    .collect::<Vec<_>>(),
+            },
+        ],
+    })
+}
+
+/// Escape the characters that are control characters in Slack messages
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notify::JobReport;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_slack_message() {
+        let report = RunReport {
+            run_id: "1".to_owned(),
+            run_url: "https://github.com/luftkode/ci-manager/actions/runs/1".to_owned(),
+            title: "Nightly <main> failed".to_owned(),
+            fingerprint: "5f1d3a0c2b4e6a8d".to_owned(),
+            labels: vec!["CI".to_owned(), "yocto".to_owned()],
+            failed_jobs: vec![JobReport {
+                name: "Build image".to_owned(),
+                url: "https://github.com/luftkode/ci-manager/actions/runs/1/job/2".to_owned(),
+                failed_step: "Bake".to_owned(),
+                failure_label: Some("disk-full".to_owned()),
+                fingerprint: "d0c5e0ac7e8c2a1b".to_owned(),
+                summary: "No space left on device".to_owned(),
+            }],
+            outcome: IssueOutcome::Created {
+                url: "https://github.com/luftkode/ci-manager/issues/42".to_owned(),
+            },
+        };
+
+        let message = message(&report);
+        assert_eq!(
+            message["text"],
+            "Nightly &lt;main&gt; failed: 1 failed job(s)"
+        );
+        assert_eq!(
+            message["blocks"][0]["text"]["text"],
+            ":red_circle: *<https://github.com/luftkode/ci-manager/actions/runs/1|Nightly &lt;main&gt; failed>*"
+        );
+        assert_eq!(
+            message["blocks"][1]["text"]["text"],
+            "• <https://github.com/luftkode/ci-manager/actions/runs/1/job/2|Build image> (disk-full)"
+        );
+        assert_eq!(
+            message["blocks"][2]["elements"],
+            serde_json::json!([
+                { "type": "mrkdwn", "text": "Issue created: https://github.com/luftkode/ci-manager/issues/42" },
+                { "type": "mrkdwn", "text": "Labels: CI, yocto" },
+            ])
+        );
+    }
+}
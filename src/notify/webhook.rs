@@ -0,0 +1,102 @@
+//! A generic webhook, that receives the [RunReport] as JSON to wire ci-manager into other systems
+use super::RunReport;
+use crate::*;
+
+/// Version of the webhook payload, bumped when a field is removed or changes meaning. Fields may
+/// be added without bumping it.
+pub const PAYLOAD_VERSION: u32 = 1;
+
+/// The JSON body POSTed to the webhook
+#[derive(Debug, Serialize)]
+pub struct Payload<'r> {
+    pub version: u32,
+    #[serde(flatten)]
+    pub report: &'r RunReport,
+}
+
+impl<'r> Payload<'r> {
+    pub fn new(report: &'r RunReport) -> Self {
+        Self {
+            version: PAYLOAD_VERSION,
+            report,
+        }
+    }
+}
+
+/// POST the [Payload] of `report` to `url`, or print it in a dry run
+pub async fn notify(url: &str, report: &RunReport) -> Result<()> {
+    let payload = Payload::new(report);
+    if Config::global().dry_run() {
+        println!(
+            "DRY RUN MODE! The following payload would be POSTed to {url}:\n{}",
+            serde_json::to_string_pretty(&payload)?
+        );
+        return Ok(());
+    }
+    log::info!("POSTing the run report to {url}");
+    let response = reqwest::Client::new()
+        .post(url)
+        .json(&payload)
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let error = response.text().await.unwrap_or_default();
+        bail!("Webhook responded with {status}: {error}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notify::{IssueOutcome, JobReport};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_webhook_payload() {
+        let report = RunReport {
+            run_id: "1".to_owned(),
+            run_url: "https://github.com/luftkode/ci-manager/actions/runs/1".to_owned(),
+            title: "Nightly failed".to_owned(),
+            fingerprint: "5f1d3a0c2b4e6a8d".to_owned(),
+            labels: vec!["CI".to_owned()],
+            failed_jobs: vec![JobReport {
+                name: "Build image".to_owned(),
+                url: "https://github.com/luftkode/ci-manager/actions/runs/1/job/2".to_owned(),
+                failed_step: "Bake".to_owned(),
+                failure_label: None,
+                fingerprint: "d0c5e0ac7e8c2a1b".to_owned(),
+                summary: "No space left on device".to_owned(),
+            }],
+            outcome: IssueOutcome::Duplicate {
+                urls: vec!["https://github.com/luftkode/ci-manager/issues/42".to_owned()],
+            },
+        };
+
+        let payload = serde_json::to_value(Payload::new(&report)).unwrap();
+        assert_eq!(
+            payload,
+            serde_json::json!({
+                "version": 1,
+                "run_id": "1",
+                "run_url": "https://github.com/luftkode/ci-manager/actions/runs/1",
+                "title": "Nightly failed",
+                "fingerprint": "5f1d3a0c2b4e6a8d",
+                "labels": ["CI"],
+                "failed_jobs": [{
+                    "name": "Build image",
+                    "url": "https://github.com/luftkode/ci-manager/actions/runs/1/job/2",
+                    "failed_step": "Bake",
+                    "failure_label": null,
+                    "fingerprint": "d0c5e0ac7e8c2a1b",
+                    "summary": "No space left on device",
+                }],
+                "outcome": {
+                    "decision": "duplicate",
+                    "urls": ["https://github.com/luftkode/ci-manager/issues/42"],
+                },
+            })
+        );
+    }
+}
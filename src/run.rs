@@ -1,5 +1,6 @@
 #![allow(unused_imports, dead_code)]
 use super::*;
+use tracing::Instrument;
 
 #[derive(Debug, Deserialize)]
 struct Project {
@@ -30,6 +31,21 @@ pub async fn run() -> Result<()> {
         return Ok(());
     }
 
+    let telemetry = match Config::global().otel_endpoint() {
+        Some(endpoint) => Some(telemetry::Telemetry::init(endpoint)?),
+        None => None,
+    };
+    let command: &'static str = Config::global().subcmd().into();
+    let result = handle()
+        .instrument(tracing::info_span!("ci-manager", command))
+        .await;
+    if let Some(telemetry) = telemetry {
+        telemetry.shutdown();
+    }
+    result
+}
+
+async fn handle() -> Result<()> {
     let ci_provider = if let Some(ci_provider) = Config::global().no_ci() {
         ci_provider
     } else {
@@ -38,7 +54,5 @@ pub async fn run() -> Result<()> {
 
     log::info!("CI provider: {ci_provider}");
 
-    ci_provider.handle(Config::global().subcmd()).await?;
-
-    Ok(())
+    ci_provider.handle(Config::global().subcmd()).await
 }
@@ -0,0 +1,137 @@
+//! Upload of job logs that are too long for an issue to object storage, for when gists are too
+//! small or not allowed. The storage is configured in the `[storage]` section of the configuration
+//! file, see [StorageConfig], and used with `--long-logs storage`.
+use crate::{issue::Issue, *};
+
+pub mod azure;
+pub mod gcs;
+pub mod s3;
+
+/// Content type of the uploaded logs
+const CONTENT_TYPE: &str = "text/plain; charset=utf-8";
+
+/// Object storage service
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageProvider {
+    /// Amazon S3 or an S3-compatible service, see [s3]
+    S3,
+    /// Google Cloud Storage, see [gcs]
+    Gcs,
+    /// Azure Blob Storage, see [azure]
+    Azure,
+}
+
+/// The `[storage]` section of the configuration file
+///
+/// # Example
+/// ```toml
+/// [storage]
+/// provider = "s3"
+/// bucket = "ci-logs"
+/// region = "eu-north-1"
+/// prefix = "ci-manager/"
+/// public_url = "https://ci-logs.example.com"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StorageConfig {
+    pub provider: StorageProvider,
+    /// The bucket, or the container in Azure Blob Storage
+    pub bucket: String,
+    /// Prefix of the names of the uploaded objects, e.g. `ci-manager/`
+    #[serde(default)]
+    pub prefix: String,
+    /// Region of the S3 bucket
+    #[serde(default = "default_region")]
+    pub region: String,
+    /// Endpoint of an S3-compatible service used instead of AWS, e.g. `https://minio.example.com`,
+    /// where the bucket is addressed in the path
+    pub endpoint: Option<String>,
+    /// The storage account of Azure Blob Storage
+    pub account: Option<String>,
+    /// Base URL the uploaded logs are linked with instead of the URL of the object, e.g. a CDN or
+    /// a proxy that authenticates the readers
+    pub public_url: Option<String>,
+}
+
+fn default_region() -> String {
+    "us-east-1".to_owned()
+}
+
+impl StorageConfig {
+    /// Name of the object of the log of the job `job_name` of the run `run_id`
+    pub fn object_name(&self, run_id: &str, job_name: &str) -> String {
+        format!(
+            "{prefix}{run_id}/{job}.log",
+            prefix = self.prefix,
+            job = sanitize_filename(job_name)
+        )
+    }
+
+    /// Upload `contents` as the object `name` and return the URL it is linked with
+    pub async fn upload(&self, name: &str, contents: String) -> Result<String> {
+        let url = match self.provider {
+            StorageProvider::S3 => s3::upload(self, name, contents).await?,
+            StorageProvider::Gcs => gcs::upload(self, name, contents).await?,
+            StorageProvider::Azure => azure::upload(self, name, contents).await?,
+        };
+        Ok(match &self.public_url {
+            Some(base) => format!(
+                "{}/{}",
+                base.trim_end_matches('/'),
+                encode_object_name(name)
+            ),
+            None => url,
+        })
+    }
+}
+
+/// Upload the full error summary and log of every job that is shortened in the issue body to the
+/// object storage, and link them in the issue body instead
+pub async fn upload_shortened_logs(storage: &StorageConfig, issue: &mut Issue) -> Result<()> {
+    let run_id = issue.run_id().to_owned();
+    for job in issue.shortened_jobs_mut() {
+        let name = storage.object_name(&run_id, job.name());
+        if Config::global().dry_run() {
+            println!(
+                "DRY RUN MODE! The log of job {job} would be uploaded to {provider} as {name}",
+                job = job.name(),
+                provider = storage.provider
+            );
+            continue;
+        }
+        log::info!(
+            "Uploading the log of job {job} to {provider} as {name}",
+            job = job.name(),
+            provider = storage.provider
+        );
+        let url = storage
+            .upload(&name, job.full_log())
+            .await
+            .with_context(|| format!("Failed to upload the log of job {}", job.name()))?;
+        job.set_full_log_url(url);
+    }
+    Ok(())
+}
+
+/// Percent-encode an object name for the path of a URL, keeping the unreserved characters and `/`
+///
+/// # Example
+/// ```
+/// # use ci_manager::storage::encode_object_name;
+/// assert_eq!(
+///     encode_object_name("ci-manager/42/Build image (x86).log"),
+///     "ci-manager/42/Build%20image%20%28x86%29.log"
+/// );
+/// ```
+pub fn encode_object_name(name: &str) -> String {
+    name.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                char::from(b).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
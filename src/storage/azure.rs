@@ -0,0 +1,35 @@
+//! Upload to Azure Blob Storage, authorized with a shared access signature (SAS) read from
+//! `AZURE_STORAGE_SAS_TOKEN`
+use super::{encode_object_name, StorageConfig, CONTENT_TYPE};
+use crate::*;
+
+/// Version of the Blob service REST API
+const API_VERSION: &str = "2021-08-06";
+
+/// Upload `contents` as the block blob `name` in the container and return the URL of the blob
+pub async fn upload(storage: &StorageConfig, name: &str, contents: String) -> Result<String> {
+    let Some(account) = &storage.account else {
+        bail!("The storage account must be set as `account` in the [storage] section to upload to Azure");
+    };
+    let sas_token = env::var("AZURE_STORAGE_SAS_TOKEN")
+        .context("AZURE_STORAGE_SAS_TOKEN must be set to upload to Azure Blob Storage")?;
+    let url = format!(
+        "https://{account}.blob.core.windows.net/{container}/{blob}",
+        container = storage.bucket,
+        blob = encode_object_name(name)
+    );
+    let response = reqwest::Client::new()
+        .put(format!("{url}?{}", sas_token.trim_start_matches('?')))
+        .header("x-ms-blob-type", "BlockBlob")
+        .header("x-ms-version", API_VERSION)
+        .header("Content-Type", CONTENT_TYPE)
+        .body(contents)
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let error = response.text().await.unwrap_or_default();
+        bail!("Azure Blob Storage responded with {status}: {error}");
+    }
+    Ok(url)
+}
@@ -0,0 +1,31 @@
+//! Upload to Google Cloud Storage with the JSON API. The OAuth 2.0 access token is read from
+//! `GCS_ACCESS_TOKEN`, e.g. the output of `gcloud auth print-access-token`.
+use super::{encode_object_name, StorageConfig, CONTENT_TYPE};
+use crate::*;
+
+const UPLOAD_URL: &str = "https://storage.googleapis.com/upload/storage/v1/b";
+
+/// Upload `contents` as the object `name` in the bucket and return the URL that opens the object
+/// for signed-in users
+pub async fn upload(storage: &StorageConfig, name: &str, contents: String) -> Result<String> {
+    let token = env::var("GCS_ACCESS_TOKEN")
+        .context("GCS_ACCESS_TOKEN must be set to upload to Google Cloud Storage")?;
+    let response = reqwest::Client::new()
+        .post(format!("{UPLOAD_URL}/{}/o", storage.bucket))
+        .query(&[("uploadType", "media"), ("name", name)])
+        .bearer_auth(token)
+        .header("Content-Type", CONTENT_TYPE)
+        .body(contents)
+        .send()
+        .await?;
+    let status = response.status();
+    if !status.is_success() {
+        let error = response.text().await.unwrap_or_default();
+        bail!("Google Cloud Storage responded with {status}: {error}");
+    }
+    Ok(format!(
+        "https://storage.cloud.google.com/{}/{}",
+        storage.bucket,
+        encode_object_name(name)
+    ))
+}
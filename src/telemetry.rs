@@ -0,0 +1,93 @@
+//! OpenTelemetry traces and metrics of an invocation, exported over OTLP with `--otel-endpoint`
+//!
+//! The run pipeline is instrumented with [tracing] spans (API calls, log downloads, parsing and
+//! issue creation), that are only recorded once [Telemetry::init] installed the exporter.
+use crate::{
+    notify::{RunReport, UNCLASSIFIED_LABEL},
+    *,
+};
+use opentelemetry::{global, trace::TracerProvider as _, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::{metrics::SdkMeterProvider, runtime, trace, Resource};
+use tracing_subscriber::layer::SubscriberExt;
+
+const SERVICE_NAME: &str = env!("CARGO_PKG_NAME");
+
+/// The exporters of the traces and metrics, that have to be shut down to flush what they buffered
+pub struct Telemetry {
+    meter_provider: SdkMeterProvider,
+}
+
+impl Telemetry {
+    /// Export the spans and metrics to the OTLP collector at `endpoint` (gRPC), e.g.
+    /// `http://localhost:4317`
+    pub fn init(endpoint: &str) -> Result<Self> {
+        let resource = Resource::new([
+            KeyValue::new("service.name", SERVICE_NAME),
+            KeyValue::new("service.version", env!("CARGO_PKG_VERSION")),
+        ]);
+        let tracer_provider = opentelemetry_otlp::new_pipeline()
+            .tracing()
+            .with_exporter(
+                opentelemetry_otlp::new_exporter()
+                    .tonic()
+                    .with_endpoint(endpoint),
+            )
+            .with_trace_config(trace::Config::default().with_resource(resource.clone()))
+            .install_batch(runtime::Tokio)
+            .context("Failed to set up the OTLP trace exporter")?;
+        let meter_provider = opentelemetry_otlp::new_pipeline()
+            .metrics(runtime::Tokio)
+            .with_exporter(
+                opentelemetry_otlp::new_exporter()
+                    .tonic()
+                    .with_endpoint(endpoint),
+            )
+            .with_resource(resource)
+            .build()
+            .context("Failed to set up the OTLP metrics exporter")?;
+        let tracer = tracer_provider.tracer(SERVICE_NAME);
+        global::set_tracer_provider(tracer_provider);
+        global::set_meter_provider(meter_provider.clone());
+        // Not `try_init`, which would replace the logger of the log messages
+        tracing::subscriber::set_global_default(
+            tracing_subscriber::registry()
+                .with(tracing_opentelemetry::layer().with_tracer(tracer))
+                .with(config::json_log_layer()),
+        )?;
+        log::info!("Exporting traces and metrics to {endpoint}");
+        Ok(Self { meter_provider })
+    }
+
+    /// Export what is left of the traces and metrics
+    pub fn shutdown(self) {
+        global::shutdown_tracer_provider();
+        if let Err(e) = self.meter_provider.shutdown() {
+            log::warn!("Failed to export the metrics: {e}");
+        }
+    }
+}
+
+/// Count the failed jobs by the label of their failure, and what was done about the failure of the
+/// run, see [IssueOutcome::decision](notify::IssueOutcome::decision). Nothing is recorded unless
+/// [Telemetry::init] installed the exporter.
+pub fn record_report(report: &RunReport) {
+    let meter = global::meter(SERVICE_NAME);
+    let failed_jobs = meter
+        .u64_counter("ci_manager.failed_jobs")
+        .with_description("Failed jobs by the label of their failure")
+        .init();
+    for job in &report.failed_jobs {
+        let label = job
+            .failure_label
+            .as_deref()
+            .unwrap_or(UNCLASSIFIED_LABEL)
+            .to_owned();
+        failed_jobs.add(1, &[KeyValue::new("failure_label", label)]);
+    }
+    meter
+        .u64_counter("ci_manager.issue_decisions")
+        .with_description("Failed runs by what was done about them, e.g. created or duplicate")
+        .init()
+        .add(1, &[KeyValue::new("decision", report.outcome.decision())]);
+}
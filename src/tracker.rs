@@ -0,0 +1,113 @@
+//! Issue trackers that issues can be created in instead of the GitHub repository of the run, see
+//! [Tracker](crate::commands::Tracker)
+pub mod jira;
+pub mod linear;
+pub mod redmine;
+
+use crate::{
+    ci_provider::github::util::DuplicateReason,
+    issue::{fingerprint_from_issue_body, similarity::Similarity},
+};
+
+/// Find the issue in `other_issues` that tracks the same failure as an issue with `fingerprint`
+/// and `issue_body`, like [duplicate_issue](crate::ci_provider::github::util::duplicate_issue) does
+/// on GitHub: the issue with the same fingerprint, or else the issue with the most similar
+/// `description`
+pub fn duplicate_tracker_issue<'i, T>(
+    fingerprint: &str,
+    issue_body: &str,
+    other_issues: &'i [T],
+    description: impl Fn(&T) -> &str,
+    similarity: Similarity,
+) -> Option<(&'i T, DuplicateReason)> {
+    if let Some(issue) = other_issues.iter().find(|issue| {
+        fingerprint_from_issue_body(description(issue)).is_some_and(|f| f == fingerprint)
+    }) {
+        return Some((issue, DuplicateReason::Fingerprint(fingerprint.to_owned())));
+    }
+    other_issues
+        .iter()
+        .filter_map(|issue| {
+            let distance = similarity.similar_distance(issue_body, description(issue))?;
+            Some((issue, distance))
+        })
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map(|(issue, distance)| {
+            (
+                issue,
+                DuplicateReason::Similarity {
+                    similarity,
+                    distance,
+                },
+            )
+        })
+}
+
+/// A local HTTP server standing in for the API of an issue tracker in tests
+#[cfg(test)]
+pub(crate) mod mock {
+    use crate::config::{Config, CONFIG};
+    use clap::Parser;
+    use http_body_util::Full;
+    use hyper::{
+        body::{Bytes, Incoming},
+        server::conn::http1,
+        service::service_fn,
+        Request, Response,
+    };
+    use hyper_util::rt::TokioIo;
+    use std::{
+        convert::Infallible,
+        sync::{Arc, Mutex},
+    };
+
+    pub struct MockTracker {
+        pub url: String,
+        requests: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl MockTracker {
+        /// Answer every request `"<METHOD> <path>"` in `routes` with its JSON body, and any other
+        /// request with an empty JSON object
+        pub async fn start(routes: Vec<(&'static str, serde_json::Value)>) -> Self {
+            // The trackers read the dry run flag from the global config
+            CONFIG.get_or_init(|| Config::parse_from(["ci-manager"]));
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let requests = Arc::new(Mutex::new(Vec::new()));
+            let routes = Arc::new(routes);
+            let recorded = Arc::clone(&requests);
+            tokio::spawn(async move {
+                loop {
+                    let Ok((stream, _)) = listener.accept().await else {
+                        return;
+                    };
+                    let routes = Arc::clone(&routes);
+                    let recorded = Arc::clone(&recorded);
+                    let service = service_fn(move |req: Request<Incoming>| {
+                        let request = format!("{} {}", req.method(), req.uri().path());
+                        let body = routes
+                            .iter()
+                            .find(|(route, _)| *route == request)
+                            .map_or_else(|| serde_json::json!({}), |(_, body)| body.clone());
+                        recorded.lock().unwrap().push(request);
+                        async move {
+                            Ok::<_, Infallible>(Response::new(Full::new(Bytes::from(
+                                body.to_string(),
+                            ))))
+                        }
+                    });
+                    tokio::spawn(
+                        http1::Builder::new().serve_connection(TokioIo::new(stream), service),
+                    );
+                }
+            });
+            Self { url, requests }
+        }
+
+        /// The `"<METHOD> <path>"` of every request so far
+        pub fn requests(&self) -> Vec<String> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+}
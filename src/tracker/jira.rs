@@ -0,0 +1,471 @@
+//! Jira Cloud as the issue tracker of `--tracker jira`, through the REST API v2, which takes the
+//! description of an issue in Jira wiki markup
+use crate::{
+    ci_provider::github::util::DuplicateReason,
+    commands::JiraArgs,
+    issue::{similarity::Similarity, Issue},
+    notify::IssueOutcome,
+    *,
+};
+
+/// Maximum length of the summary (title) of a Jira issue
+const SUMMARY_MAX_LEN: usize = 255;
+
+/// A Jira Cloud site, authenticated with the email and API token of a user
+#[derive(Debug)]
+pub struct Jira {
+    client: reqwest::Client,
+    url: String,
+    user: String,
+    token: String,
+}
+
+/// An issue to create, see
+/// <https://developer.atlassian.com/cloud/jira/platform/rest/v2/api-group-issues/#api-rest-api-2-issue-post>
+#[derive(Debug, PartialEq, Serialize)]
+pub struct NewIssue {
+    pub fields: NewIssueFields,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct NewIssueFields {
+    pub project: Key,
+    pub issuetype: Name,
+    pub summary: String,
+    pub description: String,
+    pub labels: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<Name>,
+}
+
+/// A reference to a Jira object by its key
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Key {
+    pub key: String,
+}
+
+/// A reference to a Jira object by its name
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Name {
+    pub name: String,
+}
+
+/// The response to creating an issue
+#[derive(Debug, Deserialize)]
+pub struct CreatedIssue {
+    pub id: String,
+    pub key: String,
+}
+
+/// An issue found by a search, with only the fields needed to find duplicates
+#[derive(Debug, Deserialize)]
+pub struct JiraIssue {
+    pub key: String,
+    pub fields: JiraIssueFields,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JiraIssueFields {
+    pub description: Option<String>,
+}
+
+/// The response to a JQL search
+#[derive(Debug, Deserialize)]
+struct SearchResults {
+    issues: Vec<JiraIssue>,
+}
+
+impl NewIssue {
+    /// The Jira issue of `issue` in `project`, with the type and components of `args`. Jira labels
+    /// can't contain spaces, so they are replaced with `-`.
+    pub fn new(project: &str, args: &JiraArgs, issue: &mut Issue) -> Self {
+        let summary = match issue.title().char_indices().nth(SUMMARY_MAX_LEN - 3) {
+            Some((end, _)) if issue.title().chars().count() > SUMMARY_MAX_LEN => {
+                format!("{}...", &issue.title()[..end])
+            }
+            _ => issue.title().to_owned(),
+        };
+        let labels = issue
+            .labels()
+            .iter()
+            .map(|label| label.split_whitespace().collect::<Vec<_>>().join("-"))
+            .collect();
+        Self {
+            fields: NewIssueFields {
+                project: Key {
+                    key: project.to_owned(),
+                },
+                issuetype: Name {
+                    name: args.jira_issue_type.clone(),
+                },
+                summary,
+                description: issue.body(),
+                labels,
+                components: args
+                    .jira_components
+                    .iter()
+                    .map(|name| Name { name: name.clone() })
+                    .collect(),
+            },
+        }
+    }
+}
+
+impl Jira {
+    /// The Jira site at `JIRA_URL`, authenticated with `JIRA_USER` and `JIRA_API_TOKEN`
+    pub fn from_env() -> Result<Self> {
+        let var = |name: &str| {
+            env::var(name).with_context(|| format!("{name} must be set to create Jira issues"))
+        };
+        let mut url = var("JIRA_URL")?.trim_end_matches('/').to_owned();
+        ensure_https_prefix(&mut url);
+        Ok(Self {
+            client: reqwest::Client::new(),
+            url,
+            user: var("JIRA_USER")?,
+            token: var("JIRA_API_TOKEN")?,
+        })
+    }
+
+    /// Create an issue
+    pub async fn create_issue(&self, new_issue: &NewIssue) -> Result<CreatedIssue> {
+        let response = self
+            .client
+            .post(format!("{}/rest/api/2/issue", self.url))
+            .basic_auth(&self.user, Some(&self.token))
+            .json(new_issue)
+            .send()
+            .await?;
+        let response = error_for_status(response, "create the Jira issue").await?;
+        Ok(response.json().await?)
+    }
+
+    /// The issues of the project created by ci-manager that are not done
+    pub async fn open_issues(&self, project: &str) -> Result<Vec<JiraIssue>> {
+        let jql = format!(
+            "project = \"{project}\" AND statusCategory != Done AND description ~ \"ci-manager\""
+        );
+        let response = self
+            .client
+            .post(format!("{}/rest/api/2/search", self.url))
+            .basic_auth(&self.user, Some(&self.token))
+            .json(&serde_json::json!({
+                "jql": jql,
+                "fields": ["description"],
+                "maxResults": 100,
+            }))
+            .send()
+            .await?;
+        let response = error_for_status(response, "get the open Jira issues").await?;
+        let results: SearchResults = response.json().await?;
+        Ok(results.issues)
+    }
+
+    /// Comment on an issue
+    pub async fn comment(&self, key: &str, body: &str) -> Result<()> {
+        let response = self
+            .client
+            .post(format!("{}/rest/api/2/issue/{key}/comment", self.url))
+            .basic_auth(&self.user, Some(&self.token))
+            .json(&serde_json::json!({ "body": body }))
+            .send()
+            .await?;
+        error_for_status(response, &format!("comment on Jira issue {key}")).await?;
+        Ok(())
+    }
+
+    /// Comment with `run_url` on the open issue of `project` that already tracks the failure of
+    /// `issue`, or print the comment in a dry run. Returns the outcome if there is one.
+    pub async fn comment_on_duplicate(
+        &self,
+        project: &str,
+        issue: &mut Issue,
+        similarity: Similarity,
+        run_url: &str,
+    ) -> Result<Option<IssueOutcome>> {
+        let open_issues = self.open_issues(project).await?;
+        log::info!(
+            "Found {num_issues} open Jira issue(s) created by ci-manager in project {project}",
+            num_issues = open_issues.len()
+        );
+        let fingerprint = issue.fingerprint();
+        let body = issue.body();
+        let Some((duplicate, reason)) =
+            duplicate_jira_issue(&fingerprint, &body, &open_issues, similarity)
+        else {
+            log::info!("No similar Jira issue found. Continuing...");
+            return Ok(None);
+        };
+        log::warn!(
+            "Jira issue {key} already tracks this failure ({reason}). Exiting...",
+            key = duplicate.key
+        );
+        let comment = format!("Seen again in {run_url}");
+        if Config::global().dry_run() {
+            println!(
+                "DRY RUN MODE! Jira issue {key} would be commented on: {comment}",
+                key = duplicate.key
+            );
+        } else {
+            self.comment(&duplicate.key, &comment).await?;
+        }
+        Ok(Some(IssueOutcome::Duplicate {
+            urls: vec![self.browse_url(&duplicate.key)],
+        }))
+    }
+
+    /// The URL of the page of an issue
+    pub fn browse_url(&self, key: &str) -> String {
+        format!("{}/browse/{key}", self.url)
+    }
+}
+
+/// The response if its status is a success, or else an error with the body of the response
+async fn error_for_status(response: reqwest::Response, action: &str) -> Result<reqwest::Response> {
+    let status = response.status();
+    if !status.is_success() {
+        let error = response.text().await.unwrap_or_default();
+        bail!("Failed to {action}: {status}: {error}");
+    }
+    Ok(response)
+}
+
+/// Find a Jira issue that tracks the same failure as an issue with `fingerprint` and
+/// `issue_body`, like [duplicate_issue](crate::ci_provider::github::util::duplicate_issue) does on
+/// GitHub
+pub fn duplicate_jira_issue<'i>(
+    fingerprint: &str,
+    issue_body: &str,
+    other_issues: &'i [JiraIssue],
+    similarity: Similarity,
+) -> Option<(&'i JiraIssue, DuplicateReason)> {
+    super::duplicate_tracker_issue(
+        fingerprint,
+        issue_body,
+        other_issues,
+        |issue| issue.fields.description.as_deref().unwrap_or_default(),
+        similarity,
+    )
+}
+
+/// Create `issue` in the Jira project of `args`, or print it in a dry run
+///
+/// With `no_duplicate`, the issue is not created if an open issue already tracks the failure, the
+/// open issue is commented on with `run_url` instead
+#[tracing::instrument(skip_all)]
+pub async fn file_issue(
+    args: &JiraArgs,
+    issue: &mut Issue,
+    no_duplicate: bool,
+    similarity: Similarity,
+    run_url: &str,
+) -> Result<IssueOutcome> {
+    let jira = Jira::from_env()?;
+    file_issue_on(&jira, args, issue, no_duplicate, similarity, run_url).await
+}
+
+/// [file_issue] on the Jira site `jira`
+async fn file_issue_on(
+    jira: &Jira,
+    args: &JiraArgs,
+    issue: &mut Issue,
+    no_duplicate: bool,
+    similarity: Similarity,
+    run_url: &str,
+) -> Result<IssueOutcome> {
+    let Some(project) = &args.jira_project else {
+        bail!("--jira-project is required to create Jira issues");
+    };
+    if no_duplicate {
+        if let Some(outcome) = jira
+            .comment_on_duplicate(project, issue, similarity, run_url)
+            .await?
+        {
+            return Ok(outcome);
+        }
+    }
+    let new_issue = NewIssue::new(project, args, issue);
+    let fields = &new_issue.fields;
+    if Config::global().dry_run() {
+        println!("####################################");
+        println!("DRY RUN MODE! The following Jira issue would be created in project {project}:");
+        println!("==== ISSUE TYPE ==== \n{}", fields.issuetype.name);
+        println!("==== ISSUE SUMMARY ==== \n{}", fields.summary);
+        println!("==== ISSUE LABEL(S) ==== \n{}", fields.labels.join(","));
+        if !fields.components.is_empty() {
+            let components: Vec<&str> = fields.components.iter().map(|c| c.name.as_str()).collect();
+            println!("==== ISSUE COMPONENT(S) ==== \n{}", components.join(","));
+        }
+        println!(
+            "==== START OF ISSUE DESCRIPTION ==== \n{}",
+            fields.description
+        );
+        println!("==== END OF ISSUE DESCRIPTION ====");
+        return Ok(IssueOutcome::DryRun);
+    }
+    log::info!("Creating a Jira issue in project {project}");
+    let created_issue = jira.create_issue(&new_issue).await?;
+    let url = jira.browse_url(&created_issue.key);
+    log::info!("Created Jira issue {key}: {url}", key = created_issue.key);
+    Ok(IssueOutcome::Created { url })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        commands::BodyFormat,
+        err_parse::ErrorMessageSummary,
+        issue::{similarity::SimilarityAlgo, template::IssueTemplate, FailedJob, FirstFailedStep},
+        tracker::mock::MockTracker,
+    };
+    use pretty_assertions::assert_eq;
+
+    /// An issue of a run where the Build job failed, with the body in Jira wiki markup
+    fn build_failure_issue() -> Issue {
+        let mut issue = Issue::new(
+            "Scheduled run failed".to_string(),
+            "1".to_string(),
+            "https://github.com/luftkode/ci-manager/actions/runs/1".to_string(),
+            vec![FailedJob::new(
+                "Build".to_string(),
+                "2".to_string(),
+                "https://github.com/luftkode/ci-manager/actions/runs/1/job/2".to_string(),
+                FirstFailedStep::StepName("Compile".to_owned()),
+                ErrorMessageSummary::Other("error: `x` undeclared".to_owned()),
+            )],
+            "CI".to_string(),
+        );
+        issue.add_labels(&["build failure".to_owned()]);
+        issue.set_template(IssueTemplate::default_for(BodyFormat::Jira));
+        issue
+    }
+
+    #[test]
+    fn test_new_jira_issue() {
+        let mut issue = build_failure_issue();
+        let args = JiraArgs {
+            jira_project: Some("CI".to_owned()),
+            jira_issue_type: "Bug".to_owned(),
+            jira_components: vec!["Firmware".to_owned()],
+        };
+
+        let new_issue = NewIssue::new("CI", &args, &mut issue);
+        let json = serde_json::to_value(&new_issue).unwrap();
+        assert_eq!(json["fields"]["project"]["key"], "CI");
+        assert_eq!(json["fields"]["issuetype"]["name"], "Bug");
+        assert_eq!(json["fields"]["summary"], "Scheduled run failed");
+        assert_eq!(
+            json["fields"]["labels"],
+            serde_json::json!(["CI", "build-failure"])
+        );
+        assert_eq!(
+            json["fields"]["components"],
+            serde_json::json!([{"name": "Firmware"}])
+        );
+        assert!(new_issue.fields.description.starts_with(
+            "*Run ID*: 1 [LINK TO RUN|https://github.com/luftkode/ci-manager/actions/runs/1]\n\
+            \n\
+            *1 job failed:*\n\
+            * Build\n\
+            \n\
+            \n\
+            h3. Build (ID 2)\n\
+            *Step failed:* Compile\n\
+            *Log:* https://github.com/luftkode/ci-manager/actions/runs/1/job/2\n\
+            \n\
+            _Best effort error summary_:\n\
+            {noformat}\n\
+            error: `x` undeclared"
+        ));
+    }
+
+    #[test]
+    fn test_duplicate_jira_issue() {
+        let jira_issue = |key: &str, description: Option<&str>| JiraIssue {
+            key: key.to_owned(),
+            fields: JiraIssueFields {
+                description: description.map(str::to_owned),
+            },
+        };
+        let issues = [
+            jira_issue("CI-1", None),
+            jira_issue("CI-2", Some("ERROR: Task do_compile failed")),
+            jira_issue(
+                "CI-3",
+                Some(
+                    "ERROR: Task do_fetch failed\n\n\
+                    {noformat}<!-- ci-manager occurrence fingerprint=d0c5e0ac7e8c2a1b count=2 -->{noformat}",
+                ),
+            ),
+        ];
+        let similarity = Similarity::new(SimilarityAlgo::Levenshtein, Some(10.0)).unwrap();
+
+        let (duplicate, reason) = duplicate_jira_issue(
+            "d0c5e0ac7e8c2a1b",
+            "ERROR: Task do_compile failed",
+            &issues,
+            similarity,
+        )
+        .unwrap();
+        assert_eq!(duplicate.key, "CI-3");
+        assert_eq!(
+            reason,
+            DuplicateReason::Fingerprint("d0c5e0ac7e8c2a1b".to_owned())
+        );
+        let (duplicate, _) = duplicate_jira_issue(
+            "0000000000000000",
+            "ERROR: Task do_install failed",
+            &issues,
+            similarity,
+        )
+        .unwrap();
+        assert_eq!(duplicate.key, "CI-2");
+    }
+
+    #[tokio::test]
+    async fn test_no_duplicate_comments_on_existing_issue() {
+        let mut issue = build_failure_issue();
+        let description = format!(
+            "{{noformat}}<!-- ci-manager occurrence fingerprint={} count=1 -->{{noformat}}",
+            issue.fingerprint()
+        );
+        let server = MockTracker::start(vec![(
+            "POST /rest/api/2/search",
+            serde_json::json!({
+                "issues": [{"key": "CI-7", "fields": {"description": description}}]
+            }),
+        )])
+        .await;
+        let jira = Jira {
+            client: reqwest::Client::new(),
+            url: server.url.clone(),
+            user: "ci@example.com".to_owned(),
+            token: "token".to_owned(),
+        };
+        let args = JiraArgs {
+            jira_project: Some("CI".to_owned()),
+            jira_issue_type: "Bug".to_owned(),
+            jira_components: vec![],
+        };
+        let similarity = Similarity::new(SimilarityAlgo::Levenshtein, None).unwrap();
+
+        file_issue_on(
+            &jira,
+            &args,
+            &mut issue,
+            true,
+            similarity,
+            "https://github.com/luftkode/ci-manager/actions/runs/3",
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            server.requests(),
+            [
+                "POST /rest/api/2/search",
+                "POST /rest/api/2/issue/CI-7/comment"
+            ]
+        );
+    }
+}
@@ -0,0 +1,325 @@
+//! Linear as the issue tracker of `--tracker linear`, through its GraphQL API. Linear renders the
+//! Markdown issue body, and open issues created by ci-manager are checked for duplicates like on
+//! GitHub.
+use crate::{
+    ci_provider::github::{projects::graphql_data, util::DuplicateReason},
+    commands::LinearArgs,
+    issue::{similarity::Similarity, Issue},
+    notify::IssueOutcome,
+    *,
+};
+
+const API_URL: &str = "https://api.linear.app/graphql";
+
+/// A Linear workspace, authenticated with a personal API key
+#[derive(Debug)]
+pub struct Linear {
+    client: reqwest::Client,
+    api_key: String,
+}
+
+/// An issue in Linear
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct LinearIssue {
+    pub id: String,
+    /// The key of the issue, e.g. `ENG-42`
+    pub identifier: String,
+    pub url: String,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+impl Linear {
+    /// The Linear workspace of the API key in `LINEAR_API_KEY`
+    pub fn from_env() -> Result<Self> {
+        let api_key =
+            env::var("LINEAR_API_KEY").context("LINEAR_API_KEY must be set to use Linear")?;
+        Ok(Self {
+            client: reqwest::Client::new(),
+            api_key,
+        })
+    }
+
+    /// Send a GraphQL query and return the `field` of its data
+    async fn graphql(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+        field: &str,
+    ) -> Result<serde_json::Value> {
+        let response: serde_json::Value = self
+            .client
+            .post(API_URL)
+            .header("Authorization", &self.api_key)
+            .json(&serde_json::json!({ "query": query, "variables": variables }))
+            .send()
+            .await?
+            .json()
+            .await?;
+        graphql_data(response, field)
+    }
+
+    /// The ID of the team with `key`
+    pub async fn team_id(&self, key: &str) -> Result<String> {
+        let teams = self
+            .graphql(
+                "query($key: String!) { teams(filter: {key: {eq: $key}}) { nodes { id } } }",
+                serde_json::json!({ "key": key }),
+                "teams",
+            )
+            .await
+            .with_context(|| format!("Failed to look up Linear team {key}"))?;
+        match teams["nodes"][0]["id"].as_str() {
+            Some(id) => Ok(id.to_owned()),
+            None => bail!("Linear team {key} not found"),
+        }
+    }
+
+    /// The IDs of the labels named `names` that issues of the team can have, the labels that
+    /// don't exist yet are created in the team
+    pub async fn label_ids(&self, team_id: &str, names: &[String]) -> Result<Vec<String>> {
+        let labels = self
+            .graphql(
+                "query($team: ID!, $names: [String!]) {
+                    issueLabels(filter: {and: [
+                        {name: {in: $names}},
+                        {or: [{team: {id: {eq: $team}}}, {team: {null: true}}]}
+                    ]}) { nodes { id name } }
+                }",
+                serde_json::json!({ "team": team_id, "names": names }),
+                "issueLabels",
+            )
+            .await
+            .context("Failed to look up the Linear labels")?;
+        let existing: Vec<(&str, &str)> = labels["nodes"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|label| Some((label["name"].as_str()?, label["id"].as_str()?)))
+            .collect();
+        let mut ids = Vec::with_capacity(names.len());
+        for name in names {
+            if let Some((_, id)) = existing.iter().find(|(n, _)| n == name) {
+                ids.push((*id).to_owned());
+                continue;
+            }
+            log::info!("Creating Linear label: {name}");
+            let created = self
+                .graphql(
+                    "mutation($input: IssueLabelCreateInput!) {
+                        issueLabelCreate(input: $input) { issueLabel { id } }
+                    }",
+                    serde_json::json!({ "input": { "name": name, "teamId": team_id } }),
+                    "issueLabelCreate",
+                )
+                .await
+                .with_context(|| format!("Failed to create Linear label {name}"))?;
+            match created["issueLabel"]["id"].as_str() {
+                Some(id) => ids.push(id.to_owned()),
+                None => bail!("Linear returned no ID for the created label {name}"),
+            }
+        }
+        Ok(ids)
+    }
+
+    /// The issues of the team created by ci-manager that are neither completed nor canceled
+    pub async fn open_issues(&self, team_id: &str) -> Result<Vec<LinearIssue>> {
+        let issues = self
+            .graphql(
+                "query($team: ID!) {
+                    issues(first: 250, filter: {
+                        team: {id: {eq: $team}},
+                        state: {type: {nin: [\"completed\", \"canceled\"]}},
+                        description: {contains: \"ci-manager\"}
+                    }) { nodes { id identifier url description } }
+                }",
+                serde_json::json!({ "team": team_id }),
+                "issues",
+            )
+            .await
+            .context("Failed to get the open Linear issues")?;
+        Ok(serde_json::from_value(issues["nodes"].clone())?)
+    }
+
+    /// Create an issue in the team
+    pub async fn create_issue(
+        &self,
+        team_id: &str,
+        issue: &mut Issue,
+        label_ids: &[String],
+        priority: u8,
+    ) -> Result<LinearIssue> {
+        let created = self
+            .graphql(
+                "mutation($input: IssueCreateInput!) {
+                    issueCreate(input: $input) { issue { id identifier url } }
+                }",
+                serde_json::json!({ "input": {
+                    "teamId": team_id,
+                    "title": issue.title(),
+                    "description": issue.body(),
+                    "labelIds": label_ids,
+                    "priority": priority,
+                } }),
+                "issueCreate",
+            )
+            .await
+            .context("Failed to create the Linear issue")?;
+        Ok(serde_json::from_value(created["issue"].clone())?)
+    }
+
+    /// Comment on an issue
+    pub async fn comment(&self, issue: &LinearIssue, body: &str) -> Result<()> {
+        self.graphql(
+            "mutation($input: CommentCreateInput!) { commentCreate(input: $input) { success } }",
+            serde_json::json!({ "input": { "issueId": issue.id, "body": body } }),
+            "commentCreate",
+        )
+        .await
+        .with_context(|| format!("Failed to comment on Linear issue {}", issue.identifier))?;
+        Ok(())
+    }
+}
+
+/// Find a Linear issue that tracks the same failure as an issue with `fingerprint` and
+/// `issue_body`, like [duplicate_issue](crate::ci_provider::github::util::duplicate_issue) does on
+/// GitHub
+pub fn duplicate_linear_issue<'i>(
+    fingerprint: &str,
+    issue_body: &str,
+    other_issues: &'i [LinearIssue],
+    similarity: Similarity,
+) -> Option<(&'i LinearIssue, DuplicateReason)> {
+    super::duplicate_tracker_issue(
+        fingerprint,
+        issue_body,
+        other_issues,
+        |issue| issue.description.as_deref().unwrap_or_default(),
+        similarity,
+    )
+}
+
+/// Create `issue` in the Linear team of `args`, or print it in a dry run. With `no_duplicate`, an
+/// open issue of the same failure is commented on with `run_url` instead.
+#[tracing::instrument(skip_all)]
+pub async fn file_issue(
+    args: &LinearArgs,
+    issue: &mut Issue,
+    no_duplicate: bool,
+    similarity: Similarity,
+    run_url: &str,
+) -> Result<IssueOutcome> {
+    let Some(team) = &args.linear_team else {
+        bail!("--linear-team is required to create Linear issues");
+    };
+    let linear = Linear::from_env()?;
+    let team_id = linear.team_id(team).await?;
+    if no_duplicate {
+        let open_issues = linear.open_issues(&team_id).await?;
+        log::info!(
+            "Found {num_issues} open Linear issue(s) created by ci-manager in team {team}",
+            num_issues = open_issues.len()
+        );
+        let fingerprint = issue.fingerprint();
+        let body = issue.body();
+        if let Some((duplicate, reason)) =
+            duplicate_linear_issue(&fingerprint, &body, &open_issues, similarity)
+        {
+            log::warn!(
+                "Linear issue {identifier} already tracks this failure ({reason}). Exiting...",
+                identifier = duplicate.identifier
+            );
+            let comment = format!("Seen again in {run_url}");
+            if Config::global().dry_run() {
+                println!(
+                    "DRY RUN MODE! Linear issue {identifier} would be commented on: {comment}",
+                    identifier = duplicate.identifier
+                );
+            } else {
+                linear.comment(duplicate, &comment).await?;
+            }
+            return Ok(IssueOutcome::Duplicate {
+                urls: vec![duplicate.url.clone()],
+            });
+        }
+        log::info!("No similar Linear issue found. Continuing...");
+    }
+
+    if Config::global().dry_run() {
+        println!("####################################");
+        println!("DRY RUN MODE! The following Linear issue would be created in team {team}:");
+        println!("==== ISSUE TITLE ==== \n{}", issue.title());
+        println!("==== ISSUE LABEL(S) ==== \n{}", issue.labels().join(","));
+        println!("==== ISSUE PRIORITY ==== \n{}", args.linear_priority);
+        println!("==== START OF ISSUE BODY ==== \n{}", issue.body());
+        println!("==== END OF ISSUE BODY ====");
+        return Ok(IssueOutcome::DryRun);
+    }
+    let label_ids = linear.label_ids(&team_id, issue.labels()).await?;
+    let created_issue = linear
+        .create_issue(&team_id, issue, &label_ids, args.linear_priority.value())
+        .await?;
+    log::info!(
+        "Created Linear issue {identifier}: {url}",
+        identifier = created_issue.identifier,
+        url = created_issue.url
+    );
+    Ok(IssueOutcome::Created {
+        url: created_issue.url,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::issue::similarity::SimilarityAlgo;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_duplicate_linear_issue_by_fingerprint_then_similarity() {
+        let linear_issue = |identifier: &str, description: &str| LinearIssue {
+            id: identifier.to_lowercase(),
+            identifier: identifier.to_owned(),
+            url: format!("https://linear.app/luftkode/issue/{identifier}"),
+            description: Some(description.to_owned()),
+        };
+        let issues = [
+            linear_issue("ENG-1", "ERROR: Task do_compile failed"),
+            linear_issue(
+                "ENG-2",
+                "ERROR: Task do_fetch failed\n\n\
+                <!-- ci-manager occurrence fingerprint=d0c5e0ac7e8c2a1b count=2 -->",
+            ),
+        ];
+        let similarity = Similarity::new(SimilarityAlgo::Levenshtein, Some(10.0)).unwrap();
+
+        let (duplicate, reason) = duplicate_linear_issue(
+            "d0c5e0ac7e8c2a1b",
+            "ERROR: Task do_compile failed",
+            &issues,
+            similarity,
+        )
+        .unwrap();
+        assert_eq!(duplicate.identifier, "ENG-2");
+        assert_eq!(
+            reason,
+            DuplicateReason::Fingerprint("d0c5e0ac7e8c2a1b".to_owned())
+        );
+
+        let (duplicate, _) = duplicate_linear_issue(
+            "0000000000000000",
+            "ERROR: Task do_install failed",
+            &issues,
+            similarity,
+        )
+        .unwrap();
+        assert_eq!(duplicate.identifier, "ENG-1");
+        assert!(duplicate_linear_issue(
+            "0000000000000000",
+            "No space left on device",
+            &issues,
+            similarity
+        )
+        .is_none());
+    }
+}
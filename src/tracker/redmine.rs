@@ -0,0 +1,444 @@
+//! Redmine as the issue tracker of `--tracker redmine`, through its REST API
+use crate::{
+    ci_provider::github::util::DuplicateReason,
+    commands::RedmineArgs,
+    issue::{similarity::Similarity, Issue},
+    notify::IssueOutcome,
+    *,
+};
+
+/// A Redmine server, authenticated with the API key of a user
+#[derive(Debug)]
+pub struct Redmine {
+    client: reqwest::Client,
+    url: String,
+    api_key: String,
+}
+
+/// The value of a custom field of an issue, by the ID of the field
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CustomFieldValue {
+    pub id: u64,
+    pub value: String,
+}
+
+impl std::str::FromStr for CustomFieldValue {
+    type Err = anyhow::Error;
+
+    /// Parse a custom field value like `ID=VALUE`
+    ///
+    /// # Example
+    /// ```
+    /// # use ci_manager::tracker::redmine::CustomFieldValue;
+    /// let field: CustomFieldValue = "4=Rev B".parse().unwrap();
+    /// assert_eq!(field.id, 4);
+    /// assert_eq!(field.value, "Rev B");
+    /// assert!("board=Rev B".parse::<CustomFieldValue>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self> {
+        let Some((id, value)) = s.split_once('=') else {
+            bail!("Expected a custom field like ID=VALUE, got: {s}");
+        };
+        let Ok(id) = id.trim().parse() else {
+            bail!("Expected the numeric ID of a custom field in ID=VALUE, got: {s}");
+        };
+        Ok(Self {
+            id,
+            value: value.to_owned(),
+        })
+    }
+}
+
+/// An issue to create, see <https://www.redmine.org/projects/redmine/wiki/Rest_Issues>
+#[derive(Debug, PartialEq, Serialize)]
+pub struct NewIssue {
+    pub issue: NewIssueFields,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct NewIssueFields {
+    /// Identifier or ID of the project
+    pub project_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tracker_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category_id: Option<u64>,
+    pub subject: String,
+    pub description: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub custom_fields: Vec<CustomFieldValue>,
+}
+
+/// The response to creating an issue
+#[derive(Debug, Deserialize)]
+struct CreatedIssue {
+    issue: IssueId,
+}
+
+#[derive(Debug, Deserialize)]
+struct IssueId {
+    id: u64,
+}
+
+/// An issue in a list of issues, with only the fields needed to find duplicates
+#[derive(Debug, Deserialize)]
+pub struct RedmineIssue {
+    pub id: u64,
+    pub description: Option<String>,
+}
+
+impl Redmine {
+    /// The Redmine server at `REDMINE_URL`, authenticated with `REDMINE_API_KEY`
+    pub fn from_env() -> Result<Self> {
+        let var = |name: &str| {
+            env::var(name).with_context(|| format!("{name} must be set to create Redmine issues"))
+        };
+        let mut url = var("REDMINE_URL")?.trim_end_matches('/').to_owned();
+        ensure_https_prefix(&mut url);
+        Ok(Self {
+            client: reqwest::Client::new(),
+            url,
+            api_key: var("REDMINE_API_KEY")?,
+        })
+    }
+
+    async fn get(&self, path: &str) -> Result<serde_json::Value> {
+        let response = self
+            .client
+            .get(format!("{}/{path}", self.url))
+            .header("X-Redmine-API-Key", &self.api_key)
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            bail!("Failed to get {path} from Redmine: {status}");
+        }
+        Ok(response.json().await?)
+    }
+
+    /// The ID of `name_or_id` in the list of `key` at `path`, e.g. the ID of a tracker by its name
+    async fn id_of(&self, path: &str, key: &str, name_or_id: &str) -> Result<u64> {
+        if let Ok(id) = name_or_id.parse() {
+            return Ok(id);
+        }
+        let list = self.get(path).await?;
+        let entries = list[key].as_array().map(Vec::as_slice).unwrap_or_default();
+        match entries.iter().find(|entry| entry["name"] == name_or_id) {
+            Some(entry) => entry["id"]
+                .as_u64()
+                .with_context(|| format!("Redmine returned no ID for {name_or_id}")),
+            None => {
+                let names: Vec<&str> = entries.iter().filter_map(|e| e["name"].as_str()).collect();
+                bail!("{name_or_id} not found in Redmine, expected one of: {names:?}")
+            }
+        }
+    }
+
+    /// Create an issue, returns its ID
+    pub async fn create_issue(&self, new_issue: &NewIssue) -> Result<u64> {
+        let response = self
+            .client
+            .post(format!("{}/issues.json", self.url))
+            .header("X-Redmine-API-Key", &self.api_key)
+            .json(new_issue)
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            let error = response.text().await.unwrap_or_default();
+            bail!("Failed to create the Redmine issue: {status}: {error}");
+        }
+        let created: CreatedIssue = response.json().await?;
+        Ok(created.issue.id)
+    }
+
+    /// The open issues of the project created by ci-manager
+    pub async fn open_issues(&self, project: &str) -> Result<Vec<RedmineIssue>> {
+        let list = self
+            .get(&format!(
+                "issues.json?project_id={project}&status_id=open&limit=100"
+            ))
+            .await?;
+        let issues: Vec<RedmineIssue> = serde_json::from_value(list["issues"].clone())?;
+        Ok(issues
+            .into_iter()
+            .filter(|issue| {
+                issue
+                    .description
+                    .as_deref()
+                    .is_some_and(|description| description.contains("ci-manager"))
+            })
+            .collect())
+    }
+
+    /// Add a note (comment) to an issue
+    pub async fn comment(&self, id: u64, notes: &str) -> Result<()> {
+        let response = self
+            .client
+            .put(format!("{}/issues/{id}.json", self.url))
+            .header("X-Redmine-API-Key", &self.api_key)
+            .json(&serde_json::json!({ "issue": { "notes": notes } }))
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            let error = response.text().await.unwrap_or_default();
+            bail!("Failed to comment on Redmine issue #{id}: {status}: {error}");
+        }
+        Ok(())
+    }
+
+    /// Add a note with `run_url` to the open issue of `project` that already tracks the failure of
+    /// `issue`, or print the note in a dry run. Returns the outcome if there is one.
+    pub async fn comment_on_duplicate(
+        &self,
+        project: &str,
+        issue: &mut Issue,
+        similarity: Similarity,
+        run_url: &str,
+    ) -> Result<Option<IssueOutcome>> {
+        let open_issues = self.open_issues(project).await?;
+        log::info!(
+            "Found {num_issues} open Redmine issue(s) created by ci-manager in project {project}",
+            num_issues = open_issues.len()
+        );
+        let fingerprint = issue.fingerprint();
+        let body = issue.body();
+        let Some((duplicate, reason)) =
+            duplicate_redmine_issue(&fingerprint, &body, &open_issues, similarity)
+        else {
+            log::info!("No similar Redmine issue found. Continuing...");
+            return Ok(None);
+        };
+        log::warn!(
+            "Redmine issue #{id} already tracks this failure ({reason}). Exiting...",
+            id = duplicate.id
+        );
+        let comment = format!("Seen again in {run_url}");
+        if Config::global().dry_run() {
+            println!(
+                "DRY RUN MODE! Redmine issue #{id} would be commented on: {comment}",
+                id = duplicate.id
+            );
+        } else {
+            self.comment(duplicate.id, &comment).await?;
+        }
+        Ok(Some(IssueOutcome::Duplicate {
+            urls: vec![self.issue_url(duplicate.id)],
+        }))
+    }
+
+    /// The URL of the page of an issue
+    pub fn issue_url(&self, id: u64) -> String {
+        format!("{}/issues/{id}", self.url)
+    }
+}
+
+/// Find a Redmine issue that tracks the same failure as an issue with `fingerprint` and
+/// `issue_body`, like [duplicate_issue](crate::ci_provider::github::util::duplicate_issue) does on
+/// GitHub
+pub fn duplicate_redmine_issue<'i>(
+    fingerprint: &str,
+    issue_body: &str,
+    other_issues: &'i [RedmineIssue],
+    similarity: Similarity,
+) -> Option<(&'i RedmineIssue, DuplicateReason)> {
+    super::duplicate_tracker_issue(
+        fingerprint,
+        issue_body,
+        other_issues,
+        |issue| issue.description.as_deref().unwrap_or_default(),
+        similarity,
+    )
+}
+
+/// Create `issue` in the Redmine project of `args`, or print it in a dry run
+///
+/// With `no_duplicate`, the issue is not created if an open issue already tracks the failure, a
+/// note with `run_url` is added to the open issue instead
+#[tracing::instrument(skip_all)]
+pub async fn file_issue(
+    args: &RedmineArgs,
+    issue: &mut Issue,
+    no_duplicate: bool,
+    similarity: Similarity,
+    run_url: &str,
+) -> Result<IssueOutcome> {
+    let redmine = Redmine::from_env()?;
+    file_issue_on(&redmine, args, issue, no_duplicate, similarity, run_url).await
+}
+
+/// [file_issue] on the Redmine server `redmine`
+async fn file_issue_on(
+    redmine: &Redmine,
+    args: &RedmineArgs,
+    issue: &mut Issue,
+    no_duplicate: bool,
+    similarity: Similarity,
+    run_url: &str,
+) -> Result<IssueOutcome> {
+    let Some(project) = &args.redmine_project else {
+        bail!("--redmine-project is required to create Redmine issues");
+    };
+    if no_duplicate {
+        if let Some(outcome) = redmine
+            .comment_on_duplicate(project, issue, similarity, run_url)
+            .await?
+        {
+            return Ok(outcome);
+        }
+    }
+    if Config::global().dry_run() {
+        println!("####################################");
+        println!(
+            "DRY RUN MODE! The following Redmine issue would be created in project {project}:"
+        );
+        if let Some(tracker) = &args.redmine_tracker {
+            println!("==== ISSUE TRACKER ==== \n{tracker}");
+        }
+        if let Some(category) = &args.redmine_category {
+            println!("==== ISSUE CATEGORY ==== \n{category}");
+        }
+        for field in &args.redmine_custom_fields {
+            println!("==== CUSTOM FIELD {} ==== \n{}", field.id, field.value);
+        }
+        println!("==== ISSUE SUBJECT ==== \n{}", issue.title());
+        println!("==== START OF ISSUE DESCRIPTION ==== \n{}", issue.body());
+        println!("==== END OF ISSUE DESCRIPTION ====");
+        return Ok(IssueOutcome::DryRun);
+    }
+    let tracker_id = match &args.redmine_tracker {
+        Some(tracker) => Some(redmine.id_of("trackers.json", "trackers", tracker).await?),
+        None => None,
+    };
+    let category_id = match &args.redmine_category {
+        Some(category) => Some(
+            redmine
+                .id_of(
+                    &format!("projects/{project}/issue_categories.json"),
+                    "issue_categories",
+                    category,
+                )
+                .await?,
+        ),
+        None => None,
+    };
+    let new_issue = NewIssue {
+        issue: NewIssueFields {
+            project_id: project.to_owned(),
+            tracker_id,
+            category_id,
+            subject: issue.title().to_owned(),
+            description: issue.body(),
+            custom_fields: args.redmine_custom_fields.clone(),
+        },
+    };
+    log::info!("Creating a Redmine issue in project {project}");
+    let id = redmine.create_issue(&new_issue).await?;
+    let url = redmine.issue_url(id);
+    log::info!("Created Redmine issue #{id}: {url}");
+    Ok(IssueOutcome::Created { url })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        err_parse::ErrorMessageSummary,
+        issue::{similarity::SimilarityAlgo, FailedJob, FirstFailedStep},
+        tracker::mock::MockTracker,
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_duplicate_redmine_issue() {
+        let redmine_issue = |id: u64, description: &str| RedmineIssue {
+            id,
+            description: Some(description.to_owned()),
+        };
+        let issues = [
+            redmine_issue(1, "ERROR: Task do_compile failed"),
+            redmine_issue(
+                2,
+                "ERROR: Task do_fetch failed\n\n\
+                <!-- ci-manager occurrence fingerprint=d0c5e0ac7e8c2a1b count=2 -->",
+            ),
+        ];
+        let similarity = Similarity::new(SimilarityAlgo::Levenshtein, Some(10.0)).unwrap();
+
+        let (duplicate, reason) = duplicate_redmine_issue(
+            "d0c5e0ac7e8c2a1b",
+            "ERROR: Task do_compile failed",
+            &issues,
+            similarity,
+        )
+        .unwrap();
+        assert_eq!(duplicate.id, 2);
+        assert_eq!(
+            reason,
+            DuplicateReason::Fingerprint("d0c5e0ac7e8c2a1b".to_owned())
+        );
+        assert!(duplicate_redmine_issue(
+            "0000000000000000",
+            "No space left on device",
+            &issues,
+            similarity
+        )
+        .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_no_duplicate_comments_on_existing_issue() {
+        let mut issue = Issue::new(
+            "Scheduled run failed".to_string(),
+            "1".to_string(),
+            "https://github.com/luftkode/ci-manager/actions/runs/1".to_string(),
+            vec![FailedJob::new(
+                "Build".to_string(),
+                "2".to_string(),
+                "https://github.com/luftkode/ci-manager/actions/runs/1/job/2".to_string(),
+                FirstFailedStep::StepName("Compile".to_owned()),
+                ErrorMessageSummary::Other("error: `x` undeclared".to_owned()),
+            )],
+            "CI".to_string(),
+        );
+        let description = format!(
+            "<!-- ci-manager occurrence fingerprint={} count=1 -->",
+            issue.fingerprint()
+        );
+        let server = MockTracker::start(vec![(
+            "GET /issues.json",
+            serde_json::json!({
+                "issues": [{"id": 7, "description": description}]
+            }),
+        )])
+        .await;
+        let redmine = Redmine {
+            client: reqwest::Client::new(),
+            url: server.url.clone(),
+            api_key: "key".to_owned(),
+        };
+        let args = RedmineArgs {
+            redmine_project: Some("firmware".to_owned()),
+            redmine_tracker: None,
+            redmine_category: None,
+            redmine_custom_fields: vec![],
+        };
+        let similarity = Similarity::new(SimilarityAlgo::Levenshtein, None).unwrap();
+
+        file_issue_on(
+            &redmine,
+            &args,
+            &mut issue,
+            true,
+            similarity,
+            "https://github.com/luftkode/ci-manager/actions/runs/3",
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            server.requests(),
+            ["GET /issues.json", "PUT /issues/7.json"]
+        );
+    }
+}
@@ -89,7 +89,7 @@ pub fn id_from_job_lines(lines: &[String]) -> Vec<String> {
 /// let modified = remove_timestamps_and_ids(test_str);
 /// assert_eq!(modified, "IDdate: \nother text");
 /// ```
-pub fn remove_timestamps_and_ids(text: &str) -> borrow::Cow<str> {
+pub fn remove_timestamps_and_ids(text: &str) -> borrow::Cow<'_, str> {
     static RE: Lazy<Regex> = Lazy::new(|| {
         Regex::new(
             r"(?x)
@@ -106,6 +106,237 @@ pub fn remove_timestamps_and_ids(text: &str) -> borrow::Cow<str> {
     RE.replace_all(text, "")
 }
 
+/// Compute a stable fingerprint of a text, ignoring timestamps and IDs (see [remove_timestamps_and_ids]).
+///
+/// The fingerprint is a 64-bit FNV-1a hash formatted as 16 hexadecimal characters. Unlike the
+/// standard library hashers it is stable across runs and versions, so it can be stored in issues.
+///
+/// # Example
+/// ```
+/// # use ci_manager::util::fingerprint;
+/// # use pretty_assertions::{assert_eq, assert_ne};
+/// let a = fingerprint("Job ID 21442749267 failed at 2024-02-28 00:03:46");
+/// let b = fingerprint("Job ID 21442749166 failed at 2024-03-01 12:00:00");
+/// assert_eq!(a, b);
+/// assert_eq!(a.len(), 16);
+///
+/// let c = fingerprint("Job ID 21442749166 failed at do_fetch");
+/// assert_ne!(a, c);
+/// ```
+pub fn fingerprint(text: &str) -> String {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+    let hash = remove_timestamps_and_ids(text)
+        .bytes()
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME)
+        });
+    format!("{hash:016x}")
+}
+
+/// Normalize an error message so the same error in different runs reads the same: timestamps, IDs
+/// and hashes are removed and paths are shortened to their file name, as the paths differ between
+/// runners and checkouts.
+///
+/// # Example
+/// ```
+/// # use ci_manager::util::normalize_error;
+/// # use pretty_assertions::assert_eq;
+/// let a = normalize_error(
+///     "2024-02-28T00:03:46.0000000Z /home/runner/work/app/app/src/main.c:12: error: `x` undeclared\n\
+///     ERROR: Task (/build/tmp/work/app-1.0-r0/temp/log.do_compile.4242) failed, commit 8c7f4e2b1",
+/// );
+/// let b = normalize_error(
+///     "2024-03-15T20:35:48.9824182Z /srv/ci/_work/app/src/main.c:12: error: `x` undeclared\n\
+///     ERROR: Task (/build/tmp/work/app-1.0-r0/temp/log.do_compile.1337) failed, commit d9a6c3e5f",
+/// );
+/// assert_eq!(a, b);
+/// assert_eq!(
+///     a,
+///     "main.c:12: error: `x` undeclared\nERROR: Task (log.do_compile.<num>) failed, commit <hash>"
+/// );
+/// ```
+pub fn normalize_error(text: &str) -> String {
+    normalize_error_with_rules(text).0
+}
+
+/// [normalize_error] and the names of the normalization rules that changed the text, to explain
+/// why two errors are considered the same
+///
+/// # Example
+/// ```
+/// # use ci_manager::util::normalize_error_with_rules;
+/// # use pretty_assertions::assert_eq;
+/// let (normalized, rules) = normalize_error_with_rules("/home/runner/work/app/app/src/main.c:12: error");
+/// assert_eq!(normalized, "main.c:12: error");
+/// assert_eq!(rules, ["paths"]);
+/// ```
+pub fn normalize_error_with_rules(text: &str) -> (String, Vec<&'static str>) {
+    static PATH_RE: Lazy<Regex> =
+        Lazy::new(|| Regex::new(r"(?:[\w.+-]*/)+(?<FILE>[\w.+-]+)").unwrap());
+    static HASH_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b[0-9a-f]{7,64}\b").unwrap());
+    static NUMBER_SUFFIX_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\.[0-9]+\b").unwrap());
+
+    /// Record `rule` if it changed the text
+    fn apply(
+        rules: &mut Vec<&'static str>,
+        rule: &'static str,
+        before: &str,
+        after: borrow::Cow<str>,
+    ) -> String {
+        if after != before {
+            rules.push(rule);
+        }
+        after.into_owned()
+    }
+
+    let mut rules = Vec::new();
+    let text = apply(
+        &mut rules,
+        "timestamp prefixes",
+        text,
+        remove_timestamp_prefixes(text),
+    );
+    let text = apply(
+        &mut rules,
+        "timestamps and IDs",
+        &text,
+        remove_timestamps_and_ids(&text),
+    );
+    let text = apply(
+        &mut rules,
+        "paths",
+        &text,
+        PATH_RE.replace_all(&text, "$FILE"),
+    );
+    // Hashes are told apart from words made of the letters a-f by their digits
+    let hashes = HASH_RE.replace_all(&text, |caps: &regex::Captures| {
+        let hash = &caps[0];
+        if hash.bytes().any(|b| b.is_ascii_digit()) && hash.bytes().any(|b| b.is_ascii_alphabetic())
+        {
+            "<hash>".to_owned()
+        } else {
+            hash.to_owned()
+        }
+    });
+    let text = apply(&mut rules, "hashes", &text, hashes);
+    // Numeric suffixes of file names are PIDs, e.g. `log.do_compile.4242`
+    let suffixes = NUMBER_SUFFIX_RE.replace_all(&text, |caps: &regex::Captures| {
+        let start = caps.get(0).unwrap().start();
+        if text[..start].ends_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+            ".<num>".to_owned()
+        } else {
+            caps[0].to_owned()
+        }
+    });
+    let text = apply(&mut rules, "numeric file suffixes", &text, suffixes);
+    let text = text
+        .lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n");
+    (text, rules)
+}
+
+/// A line in the difference between two texts, see [diff_lines]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineDiff<'s> {
+    Unchanged(&'s str),
+    Removed(&'s str),
+    Added(&'s str),
+}
+
+impl fmt::Display for LineDiff<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LineDiff::Unchanged(line) => write!(f, " {line}"),
+            LineDiff::Removed(line) => write!(f, "-{line}"),
+            LineDiff::Added(line) => write!(f, "+{line}"),
+        }
+    }
+}
+
+/// Compute the line by line difference between two texts from their longest common subsequence.
+///
+/// Timestamp prefixes are ignored when comparing lines (see [remove_timestamp_prefixes]).
+///
+/// # Example
+/// ```
+/// # use ci_manager::util::{diff_lines, LineDiff};
+/// # use pretty_assertions::assert_eq;
+/// let old = "2024-02-28T00:03:46.0000000Z checkout\nbuild\nfailed: foo";
+/// let new = "2024-03-15T20:35:48.9824182Z checkout\nfailed: foo\nfailed: bar";
+/// assert_eq!(
+///     diff_lines(old, new),
+///     [
+///         LineDiff::Unchanged("2024-03-15T20:35:48.9824182Z checkout"),
+///         LineDiff::Removed("build"),
+///         LineDiff::Unchanged("failed: foo"),
+///         LineDiff::Added("failed: bar"),
+///     ]
+/// );
+/// ```
+pub fn diff_lines<'s>(old: &'s str, new: &'s str) -> Vec<LineDiff<'s>> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let old_keys: Vec<_> = old.iter().map(|l| remove_timestamp_prefixes(l)).collect();
+    let new_keys: Vec<_> = new.iter().map(|l| remove_timestamp_prefixes(l)).collect();
+
+    // lcs[i * width + j] is the length of the longest common subsequence of old[i..] and new[j..]
+    let width = new.len() + 1;
+    let mut lcs = vec![0usize; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i * width + j] = if old_keys[i] == new_keys[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut diff = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old_keys[i] == new_keys[j] {
+            diff.push(LineDiff::Unchanged(new[j]));
+            i += 1;
+            j += 1;
+        } else if lcs[(i + 1) * width + j] >= lcs[i * width + j + 1] {
+            diff.push(LineDiff::Removed(old[i]));
+            i += 1;
+        } else {
+            diff.push(LineDiff::Added(new[j]));
+            j += 1;
+        }
+    }
+    diff.extend(old[i..].iter().copied().map(LineDiff::Removed));
+    diff.extend(new[j..].iter().copied().map(LineDiff::Added));
+    diff
+}
+
+/// Keep only the lines of `log` that do not appear anywhere in `baseline`.
+///
+/// Timestamp prefixes are ignored when comparing lines (see [remove_timestamp_prefixes]).
+///
+/// # Example
+/// ```
+/// # use ci_manager::util::lines_not_in;
+/// # use pretty_assertions::assert_eq;
+/// let baseline = "2024-02-28T00:03:46.0000000Z Fetching sources\nBuilding foo\nDone";
+/// let log = "2024-03-15T20:35:48.9824182Z Fetching sources\nBuilding foo\nERROR: foo failed";
+/// assert_eq!(lines_not_in(log, baseline), "ERROR: foo failed");
+/// ```
+pub fn lines_not_in(log: &str, baseline: &str) -> String {
+    let baseline_lines: std::collections::HashSet<borrow::Cow<str>> =
+        baseline.lines().map(remove_timestamp_prefixes).collect();
+    log.lines()
+        .filter(|line| !baseline_lines.contains(&remove_timestamp_prefixes(line)))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 /// Remove non-ASCII characters from a string
 /// # Example
 /// ```
@@ -115,7 +346,7 @@ pub fn remove_timestamps_and_ids(text: &str) -> borrow::Cow<str> {
 /// let modified = remove_non_ascii(test_str);
 /// assert_eq!(modified, "strng wth nn-scii chrcters");
 /// ```
-pub fn remove_non_ascii(text: &str) -> borrow::Cow<str> {
+pub fn remove_non_ascii(text: &str) -> borrow::Cow<'_, str> {
     static RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"[^\x00-\x7F]+").unwrap());
 
     RE.replace_all(text, "")
@@ -130,7 +361,7 @@ pub fn remove_non_ascii(text: &str) -> borrow::Cow<str> {
 /// let modified = remove_ansi_codes(test_str);
 /// assert_eq!(modified, "ERROR: Logfile of failure stored in");
 /// ```
-pub fn remove_ansi_codes(text: &str) -> borrow::Cow<str> {
+pub fn remove_ansi_codes(text: &str) -> borrow::Cow<'_, str> {
     static RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\x1b\[[;\d]*[A-Za-z]").unwrap());
 
     RE.replace_all(text, "")
@@ -157,7 +388,7 @@ pub fn remove_ansi_codes(text: &str) -> borrow::Cow<str> {
 /// [INFO] This is a log message
 /// [ERROR] This is another log message");
 ///
-pub fn remove_timestamp_prefixes(log: &str) -> borrow::Cow<str> {
+pub fn remove_timestamp_prefixes(log: &str) -> borrow::Cow<'_, str> {
     // The fist group matches 0 or more newlines, and uses that group to replace the timestamp
     // this way the newlines are preserved (making it agnostic to the type of newline used in the log)
     static RE: Lazy<Regex> =
@@ -212,6 +443,104 @@ pub fn ensure_https_prefix(url: &mut String) {
     url.insert_str(0, "https://");
 }
 
+/// Make a string safe to use as a file name by replacing every character that is not an ASCII
+/// alphanumeric, `.`, `-` or `_` with `_`
+///
+/// # Example
+/// ```
+/// # use ci_manager::util::sanitize_filename;
+/// # use pretty_assertions::assert_eq;
+/// assert_eq!(sanitize_filename("3_Build image: x86/64.txt"), "3_Build_image__x86_64.txt");
+///
+/// // Names made up of only dots are not allowed, as they refer to a directory
+/// assert_eq!(sanitize_filename(".."), "__");
+/// ```
+pub fn sanitize_filename(name: &str) -> String {
+    let sanitized: String = name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' | '_' => c,
+            _ => '_',
+        })
+        .collect();
+    if sanitized.chars().all(|c| c == '.') {
+        sanitized.replace('.', "_")
+    } else {
+        sanitized
+    }
+}
+
+/// Compile a pattern where `*` matches any characters (and nothing else is special) to a regex
+/// matching the whole string
+///
+/// # Example
+/// ```
+/// # use ci_manager::util::wildcard_regex;
+/// let re = wildcard_regex("yocto-*-logs");
+/// assert!(re.is_match("yocto-x86-logs"));
+/// assert!(!re.is_match("yocto-x86-logs.zip"));
+/// assert!(wildcard_regex("build (1.0)").is_match("build (1.0)"));
+/// ```
+pub fn wildcard_regex(pattern: &str) -> Regex {
+    let regex = format!(
+        "^{}$",
+        pattern
+            .split('*')
+            .map(regex::escape)
+            .collect::<Vec<_>>()
+            .join(".*")
+    );
+    // Everything but the wildcards is escaped, so the regex is always valid
+    Regex::new(&regex).unwrap()
+}
+
+/// Format a number of bytes in human readable binary units
+///
+/// # Example
+/// ```
+/// # use ci_manager::util::format_bytes;
+/// # use pretty_assertions::assert_eq;
+/// assert_eq!(format_bytes(512), "512 B");
+/// assert_eq!(format_bytes(1536), "1.5 KiB");
+/// assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GiB");
+/// ```
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+/// Format a duration in seconds, e.g. `1h 02m 03s` or `12.3s`
+///
+/// # Example
+/// ```
+/// # use ci_manager::util::format_duration;
+/// assert_eq!(format_duration(12.34), "12.3s");
+/// assert_eq!(format_duration(723.0), "12m 03s");
+/// assert_eq!(format_duration(3723.0), "1h 02m 03s");
+/// ```
+pub fn format_duration(seconds: f64) -> String {
+    if seconds < 60.0 {
+        return format!("{seconds:.1}s");
+    }
+    let seconds = seconds.round() as u64;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{hours}h {minutes:02}m {seconds:02}s")
+    } else {
+        format!("{minutes}m {seconds:02}s")
+    }
+}
+
 /// Canonicalize a repository URL to the form `https://{host}/{repo}`
 ///
 /// # Arguments
@@ -344,7 +673,7 @@ mod tests {
 
     #[test]
     pub fn test_remove_timestamps_and_ids_log_text() {
-        const LOG_TEXT: &'static str = r#"**Run ID**: 8072883145 [LINK TO RUN](https://github.com/luftkode/distro-template/actions/runs/8072883145)
+        const LOG_TEXT: &str = r#"**Run ID**: 8072883145 [LINK TO RUN](https://github.com/luftkode/distro-template/actions/runs/8072883145)
 
         **1 job failed:**
         - **`Test template xilinx`**
@@ -355,7 +684,7 @@ mod tests {
         **Log:** https://github.com/luftkode/distro-template/actions/runs/8072883145/job/22055505284
         "#;
 
-        const EXPECTED_MODIFIED: &'static str = r#"**Run ID**:[LINK TO RUN](https://github.com/luftkode/distro-template/actions/runs
+        const EXPECTED_MODIFIED: &str = r#"**Run ID**:[LINK TO RUN](https://github.com/luftkode/distro-template/actions/runs
 
         **1 job failed:**
         - **`Test template xilinx`**
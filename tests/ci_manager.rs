@@ -142,3 +142,52 @@ other contents",
 
     Ok(())
 }
+
+#[test]
+fn locate_failure_log_options_from_env() -> Result<(), Box<dyn Error>> {
+    // Create a temporary directory with a long path down to a text file
+    let dir = TempDir::new()?;
+    // Create the whole path in the temp dir
+    let path_to_log = dir.path().join(REL_PATH_TO_FAILURE_LOG);
+    fs::create_dir_all(path_to_log.parent().unwrap())?;
+    // Create the file with the test string
+    fs::write(&path_to_log, EXPECT_FAILURE_LOG_CONTENTS)?;
+
+    // Now create the yocto build failure log string that should contain the path to the file
+    // The test log string is formatted with the path to the temporary file
+    let test_log_str = format!(
+        r"other contents
+ERROR: Logfile of failure stored in: /app{real_location} other contents
+other contents",
+        real_location = &path_to_log.to_string_lossy()
+    );
+    let test_log_file = dir.child("test.log");
+    test_log_file.write_str(&test_log_str)?;
+
+    // Now we should be able to retrieve the `foobar` string from the file by locating it through the log string
+    let mut cmd = Command::cargo_bin("ci-manager")?;
+    cmd.pipe_stdin(test_log_file)?
+        .env("CI_MANAGER_CI", "github")
+        .env("CI_MANAGER_KIND", "yocto")
+        .arg("locate-failure-log");
+
+    let Output {
+        status,
+        stdout,
+        stderr,
+    } = cmd.output()?;
+
+    let stdout = String::from_utf8(stdout)?;
+    let stderr = String::from_utf8(stderr)?;
+
+    assert!(
+        status.success(),
+        "Command failed with status: {status}\n - stdout: {stdout}\n - stderr: {stderr}"
+    );
+    pretty_assert_eq!(stdout, path_to_log.to_str().unwrap());
+    // Read the file and check that the contents are as expected
+    let contents = fs::read_to_string(&stdout)?;
+    pretty_assert_eq!(contents, EXPECT_FAILURE_LOG_CONTENTS);
+
+    Ok(())
+}